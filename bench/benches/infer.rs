@@ -0,0 +1,20 @@
+use bench::{generate::PROGRAMS, report, sample, selected, with_stack};
+use parser::{expr_parser, EasyParser};
+use type_system::test_infer;
+
+fn main() {
+    with_stack(|| {
+        for (name, generate, sizes) in PROGRAMS {
+            let name = format!("infer/{}", name);
+            if !selected(&name) {
+                continue;
+            }
+            for size in sizes {
+                let src = generate(size);
+                let (expr, _) = expr_parser().easy_parse(src.as_str()).unwrap();
+                let result = sample(&expr, |expr| test_infer(expr).unwrap());
+                report(&name, size, src.len(), &result);
+            }
+        }
+    });
+}
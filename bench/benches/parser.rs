@@ -0,0 +1,22 @@
+use bench::{generate::PROGRAMS, report, sample, selected, with_stack};
+use hir::expr::Expr;
+use parser::{expr_parser, EasyParser};
+
+fn main() {
+    with_stack(|| {
+        for (name, generate, sizes) in PROGRAMS {
+            let name = format!("parse/{}", name);
+            if !selected(&name) {
+                continue;
+            }
+            for size in sizes {
+                let src = generate(size);
+                let result = sample(&src.as_str(), |src| {
+                    let (expr, _): (Expr<()>, _) = expr_parser().easy_parse(src).unwrap();
+                    expr
+                });
+                report(&name, size, src.len(), &result);
+            }
+        }
+    });
+}
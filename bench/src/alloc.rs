@@ -0,0 +1,58 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+};
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+// forwards to the system allocator while counting the allocated bytes
+struct Counting;
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Relaxed);
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            CURRENT.fetch_sub(layout.size(), Relaxed);
+            allocated(new_size);
+        }
+        new_ptr
+    }
+}
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocated(size: usize) {
+    TOTAL.fetch_add(size, Relaxed);
+    let current = CURRENT.fetch_add(size, Relaxed) + size;
+    PEAK.fetch_max(current, Relaxed);
+}
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Usage {
+    // bytes allocated during the run including those already freed
+    pub total: usize,
+    // highest amount of bytes in use at once above what was in use before
+    pub peak: usize,
+}
+pub(super) fn measure<T>(run: impl FnOnce() -> T) -> (T, Usage) {
+    let before = CURRENT.load(Relaxed);
+    let total = TOTAL.load(Relaxed);
+    PEAK.store(before, Relaxed);
+    let output = run();
+    let usage = Usage {
+        total: TOTAL.load(Relaxed) - total,
+        peak: PEAK.load(Relaxed) - before,
+    };
+    (output, usage)
+}
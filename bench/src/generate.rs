@@ -0,0 +1,74 @@
+use std::fmt::Write;
+
+// Generators of synthetic Butter programs, every program is a single
+// expression that type checks so it can be fed to both the parser and the
+// type checker
+
+// if expressions nested within blocks `depth` times
+pub fn deep_nesting(depth: usize) -> String {
+    let mut src = String::new();
+    for i in 1..=depth {
+        write!(src, "{{ x{} = {}; if x{} == {} {{ ", i, i, i, i).unwrap();
+    }
+    src.push('0');
+    for i in (1..=depth).rev() {
+        write!(src, " }} else {{ x{} }} }}", i).unwrap();
+    }
+    src
+}
+// a record of `width` fields that is then extended `width` times through
+// splats
+pub fn wide_record(width: usize) -> String {
+    let mut src = "{\n    r0 = (".to_string();
+    for i in 0..width {
+        write!(src, "f{} = {}, ", i, i).unwrap();
+    }
+    src.push_str(");\n");
+    for i in 1..=width {
+        writeln!(src, "    r{} = (g{} = [{}], *r{});", i, i, i, i - 1).unwrap();
+    }
+    writeln!(src, "    r{}\n}}", width).unwrap();
+    src
+}
+// arrays concatenated `len` times in a single expression
+pub fn concat_chain(len: usize) -> String {
+    let mut src = "[0]".to_string();
+    for i in 1..len {
+        write!(src, " ++ [{}]", i).unwrap();
+    }
+    src
+}
+// chained function declarations, each one calls the previous one so the
+// environment grows along the program
+pub fn fun_declares(count: usize) -> String {
+    let mut src = "{\n    f0(x) => x;\n    v0 = f0([0]);\n".to_string();
+    for i in 1..count {
+        writeln!(src, "    f{}(x) => [f{}(x)[0] + {}];", i, i - 1, i).unwrap();
+        writeln!(src, "    v{} = f{}(v{}) ++ [{}];", i, i, i - 1, i).unwrap();
+    }
+    writeln!(src, "    v{}\n}}", count - 1).unwrap();
+    src
+}
+pub type Generator = fn(usize) -> String;
+
+pub const PROGRAMS: [(&str, Generator, [usize; 4]); 4] = [
+    ("deep_nesting", deep_nesting, [25, 50, 100, 200]),
+    ("wide_record", wide_record, [50, 100, 200, 400]),
+    ("concat_chain", concat_chain, [500, 1000, 2000, 4000]),
+    ("fun_declares", fun_declares, [250, 500, 1000, 2000]),
+];
+#[cfg(test)]
+mod test {
+    use super::PROGRAMS;
+    use parser::{expr_parser, EasyParser};
+    use type_system::test_infer;
+
+    #[test]
+    fn programs_type_check() {
+        for (name, generate, _) in PROGRAMS {
+            let src = generate(3);
+            let (expr, _) = expr_parser().easy_parse(src.as_str()).unwrap();
+            assert!(test_infer(expr).is_ok(), "{} doesn't type check", name);
+        }
+    }
+}
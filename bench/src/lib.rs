@@ -0,0 +1,79 @@
+#![warn(clippy::all)]
+#![deny(clippy::correctness)]
+
+use std::{
+    env::args,
+    thread,
+    time::{Duration, Instant},
+};
+
+pub use alloc::Usage;
+
+mod alloc;
+pub mod generate;
+
+const ITERATIONS: u32 = 5;
+// the parser and the type checker are both recursive, deeply nested programs
+// need more than the default stack
+const STACK_SIZE: usize = 1 << 30;
+
+pub struct Sample {
+    pub time: Duration,
+    pub usage: Usage,
+}
+// runs `run` several times with a fresh copy of the input, the fastest time
+// is taken as it's the least disturbed by everything else running
+pub fn sample<I, T>(input: &I, run: impl Fn(I) -> T) -> Sample
+where
+    I: Clone,
+{
+    let mut time = Duration::MAX;
+    let mut usage = Usage::default();
+    for _ in 0..ITERATIONS {
+        let input = input.clone();
+        let start = Instant::now();
+        let (output, new_usage) = alloc::measure(|| run(input));
+        time = time.min(start.elapsed());
+        usage = new_usage;
+        drop(output);
+    }
+    Sample { time, usage }
+}
+fn human_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut bytes = bytes;
+    let mut unit = 0;
+    while bytes >= 1024.0 && unit < UNITS.len() - 1 {
+        bytes /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", bytes, UNITS[unit])
+}
+pub fn report(name: &str, size: usize, src_len: usize, sample: &Sample) {
+    let throughput = src_len as f64 / sample.time.as_secs_f64();
+    println!(
+        "{:<24} {:>6} {:>12.3?} {:>14}/s  alloc {:>10}  peak {:>10}",
+        name,
+        size,
+        sample.time,
+        human_bytes(throughput),
+        human_bytes(sample.usage.total as f64),
+        human_bytes(sample.usage.peak as f64),
+    );
+}
+// `cargo bench -- <filter>` only runs benchmarks whose name contains the
+// filter, flags passed by cargo such as `--bench` are ignored
+pub fn selected(name: &str) -> bool {
+    match args().skip(1).find(|arg| !arg.starts_with('-')) {
+        Some(filter) => name.contains(&filter),
+        None => true,
+    }
+}
+pub fn with_stack(run: impl FnOnce() + Send + 'static) {
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -1,22 +1,75 @@
 use clap::Parser;
 use hir::expr::Expr;
 use parser::{expr_parser, EasyParser};
-use std::io::{self, Write};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::exit,
+};
 use type_system::test_infer;
 
 /// Butter compiler
-#[derive(Parser, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Parser, Debug, Clone, PartialEq, Eq)]
 enum Command {
     /// Start a repl for testing type inference
     TypeRepl,
     /// Start a repl for testing parser
     ParserRepl,
+    /// Format source files in place, or the standard input when no file is given
+    Fmt {
+        /// Only check whether the files are formatted
+        #[clap(long)]
+        check: bool,
+        files: Vec<PathBuf>,
+    },
 }
 fn main() {
     match Command::parse() {
         Command::TypeRepl => type_repl().unwrap(),
         Command::ParserRepl => parser_repl().unwrap(),
+        Command::Fmt { check, files } => {
+            if !fmt(check, files).unwrap() {
+                exit(1);
+            }
+        }
+    }
+}
+// returns false when some file couldn't be formatted or, with `check`, isn't
+// formatted yet
+fn fmt(check: bool, files: Vec<PathBuf>) -> io::Result<bool> {
+    if files.is_empty() {
+        let mut src = String::new();
+        io::stdin().read_to_string(&mut src)?;
+        return match formatter::format(&src) {
+            Ok(formatted) if check => Ok(formatted == src),
+            Ok(formatted) => {
+                print!("{}", formatted);
+                Ok(true)
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                Ok(false)
+            }
+        };
+    }
+    let mut success = true;
+    for file in files {
+        let src = fs::read_to_string(&file)?;
+        match formatter::format(&src) {
+            Ok(formatted) if formatted == src => (),
+            Ok(_) if check => {
+                println!("{}", file.display());
+                success = false;
+            }
+            Ok(formatted) => fs::write(&file, formatted)?,
+            Err(err) => {
+                eprintln!("{}: {}", file.display(), err);
+                success = false;
+            }
+        }
     }
+    Ok(success)
 }
 fn type_repl() -> io::Result<()> {
     let mut stdout = io::stdout();
@@ -0,0 +1,140 @@
+// a small Wadler-style document, groups are laid out on a single line when
+// they fit and broken at every line otherwise
+#[derive(Debug, Clone)]
+pub enum Doc {
+    Text(String),
+    // a space when flat
+    Line,
+    // nothing when flat
+    SoftLine,
+    // always breaks, and forces the enclosing groups to break
+    HardLine,
+    // only printed when broken
+    IfBreak(&'static str),
+    Indent(Vec<Doc>),
+    Group(Vec<Doc>),
+}
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Mode {
+    Flat,
+    Break,
+}
+pub const INDENT: usize = 4;
+
+fn fits<'a>(mut width: isize, group: &'a [Doc], rest: &[(usize, Mode, &'a Doc)]) -> bool {
+    let mut stack: Vec<(Mode, &Doc)> = group.iter().rev().map(|doc| (Mode::Flat, doc)).collect();
+    let mut rest = rest.iter().rev();
+    // whether the group is done and the rest of the line is being measured
+    let mut in_rest = false;
+    loop {
+        let (mode, doc) = match stack.pop() {
+            Some(item) => item,
+            None => match rest.next() {
+                Some((_, mode, doc)) => {
+                    in_rest = true;
+                    (*mode, *doc)
+                }
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(text) => width -= text.chars().count() as isize,
+            Doc::Line | Doc::SoftLine if mode == Mode::Break => return true,
+            Doc::Line => width -= 1,
+            Doc::SoftLine => (),
+            Doc::HardLine => return in_rest,
+            Doc::IfBreak(text) => {
+                if mode == Mode::Break {
+                    width -= text.chars().count() as isize;
+                }
+            }
+            Doc::Indent(docs) | Doc::Group(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (mode, doc)));
+            }
+        }
+        if width < 0 {
+            return false;
+        }
+    }
+}
+fn newline(out: &mut String, indent: usize) {
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+    out.push('\n');
+    out.extend(std::iter::repeat_n(' ', indent));
+}
+pub fn print(docs: &[Doc], width: usize) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    let mut commands: Vec<(usize, Mode, &Doc)> =
+        docs.iter().rev().map(|doc| (0, Mode::Break, doc)).collect();
+    while let Some((indent, mode, doc)) = commands.pop() {
+        match doc {
+            Doc::Text(text) => {
+                out.push_str(text);
+                column += text.chars().count();
+            }
+            Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                if let Doc::Line = doc {
+                    out.push(' ');
+                    column += 1;
+                }
+            }
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                newline(&mut out, indent);
+                column = indent;
+            }
+            Doc::IfBreak(text) => {
+                if mode == Mode::Break {
+                    out.push_str(text);
+                    column += text.chars().count();
+                }
+            }
+            Doc::Indent(docs) => {
+                commands.extend(docs.iter().rev().map(|doc| (indent + INDENT, mode, doc)));
+            }
+            Doc::Group(docs) => {
+                let mode = if mode == Mode::Flat
+                    || fits(width as isize - column as isize, docs, &commands)
+                {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                commands.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+            }
+        }
+    }
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+    out
+}
+#[cfg(test)]
+mod test {
+    use super::{print, Doc};
+
+    fn list(items: &[&str]) -> Vec<Doc> {
+        let mut inner = vec![Doc::SoftLine];
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                inner.extend([Doc::Text(",".to_string()), Doc::Line]);
+            }
+            inner.push(Doc::Text(item.to_string()));
+        }
+        inner.push(Doc::IfBreak(","));
+        vec![Doc::Group(vec![
+            Doc::Text("[".to_string()),
+            Doc::Indent(inner),
+            Doc::SoftLine,
+            Doc::Text("]".to_string()),
+        ])]
+    }
+    #[test]
+    fn group() {
+        assert_eq!(print(&list(&["foo", "bar"]), 20), "[foo, bar]");
+        assert_eq!(
+            print(&list(&["foo", "bar", "baz", "qux"]), 16),
+            "[\n    foo,\n    bar,\n    baz,\n    qux,\n]",
+        );
+    }
+}
@@ -0,0 +1,405 @@
+use crate::{
+    doc::Doc,
+    lex::{joins, Kind, Token},
+};
+use hir::Atom;
+use parser::is_keyword;
+use std::mem::take;
+
+// tokens nested by their brackets
+enum Node<'a> {
+    Token(Token<'a>),
+    Group {
+        open: Token<'a>,
+        body: Vec<Node<'a>>,
+        close: Option<Token<'a>>,
+    },
+}
+impl<'a> Node<'a> {
+    fn first(&self) -> &Token<'a> {
+        match self {
+            Self::Token(token) => token,
+            Self::Group { open, .. } => open,
+        }
+    }
+    fn comment(&self) -> Option<&Token<'a>> {
+        match self {
+            Self::Token(token) if token.kind == Kind::Comment => Some(token),
+            _ => None,
+        }
+    }
+    fn is(&self, text: &str) -> bool {
+        matches!(self, Self::Token(token) if token.is(text) || token.is_keyword(text))
+    }
+    fn is_group(&self, open_text: &str) -> bool {
+        matches!(self, Self::Group { open, .. } if open.is(open_text))
+    }
+}
+fn is_opening(token: &Token) -> bool {
+    ["(", "[", "{", "#(", "#["]
+        .iter()
+        .any(|text| token.is(text))
+}
+fn is_closing(token: &Token) -> bool {
+    token.is(")") || token.is("]") || token.is("}")
+}
+fn nodes<'a>(tokens: &[Token<'a>], i: &mut usize) -> Vec<Node<'a>> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.get(*i) {
+        if is_closing(token) {
+            break;
+        }
+        *i += 1;
+        if is_opening(token) {
+            let body = self::nodes(tokens, i);
+            let close = tokens.get(*i).copied();
+            if close.is_some() {
+                *i += 1;
+            }
+            nodes.push(Node::Group {
+                open: *token,
+                body,
+                close,
+            });
+        } else {
+            nodes.push(Node::Token(*token));
+        }
+    }
+    nodes
+}
+fn tree<'a>(tokens: &[Token<'a>]) -> Vec<Node<'a>> {
+    let mut i = 0;
+    let mut tree = nodes(tokens, &mut i);
+    // unbalanced closing brackets are kept as is
+    while i < tokens.len() {
+        tree.push(Node::Token(tokens[i]));
+        i += 1;
+        tree.extend(nodes(tokens, &mut i));
+    }
+    tree
+}
+fn is_word(token: &Token) -> bool {
+    token.kind == Kind::Word && !is_keyword(&Atom::from(token.text))
+}
+// whether the token can end an operand, so that `(` and `[` after it are a
+// call, an index or a slice
+fn is_operand_end(token: &Token) -> bool {
+    match token.kind {
+        Kind::Word => is_word(token) || token.text == "true" || token.text == "false",
+        Kind::Number | Kind::Str => true,
+        Kind::Comment => false,
+        Kind::Punct => token.is(")") || token.is("]") || token.is("^"),
+    }
+}
+fn is_range(token: &Token) -> bool {
+    ["..", ".<", ">.", "><"].iter().any(|range| token.is(range))
+}
+fn is_control_flow(node: &Node) -> bool {
+    ["if", "for", "while", "loop", "match"]
+        .iter()
+        .any(|keyword| node.is(keyword))
+        || node.is_group("{")
+}
+struct Element<'b, 'a> {
+    nodes: Vec<&'b Node<'a>>,
+    comments: Vec<&'b Token<'a>>,
+}
+struct Statement<'b, 'a> {
+    nodes: Vec<&'b Node<'a>>,
+    blank: bool,
+}
+#[derive(Default)]
+struct Layout<'a> {
+    // previous token apart from comments, and whether it's a prefix operator
+    prev: Option<(Token<'a>, bool)>,
+    line_start: bool,
+    statement_start: bool,
+    // the last thing written is an inline block comment
+    after_comment: bool,
+}
+impl<'a> Layout<'a> {
+    fn is_prefix(&self, token: &Token) -> bool {
+        if token.is("@") {
+            true
+        } else if ["-", "&", "!", ">", "*"].iter().any(|op| token.is(op)) {
+            match &self.prev {
+                _ if self.statement_start => true,
+                None => true,
+                Some((prev, _)) => !is_operand_end(prev) && !prev.is("}"),
+            }
+        } else {
+            false
+        }
+    }
+    fn space(&self, token: &Token) -> bool {
+        let Some((prev, prev_prefix)) = &self.prev else {
+            return false;
+        };
+        let tight = *prev_prefix
+            || prev.is("(")
+            || prev.is("[")
+            || prev.is("#(")
+            || prev.is("#[")
+            || prev.is(".")
+            || [",", ";", ":", ")", "]", ".", "^"]
+                .iter()
+                .any(|text| token.is(text))
+            // an open ended range pattern still leaves space before `=>` and `|`
+            || (is_range(prev) && !token.is("=>") && !token.is("|"))
+            || is_range(token)
+            || ((token.is("(") || token.is("[")) && is_operand_end(prev))
+            || (token.is("(") && prev.is_keyword("pub"));
+        !tight || joins(prev.text, token.text)
+    }
+    fn comment(&mut self, docs: &mut Vec<Doc>, token: &Token) {
+        if !self.line_start {
+            if token.newlines > 0 {
+                docs.push(Doc::HardLine);
+            } else {
+                docs.push(Doc::Text(" ".to_string()));
+            }
+        }
+        docs.push(Doc::Text(token.text.to_string()));
+        if token.inline {
+            self.line_start = false;
+            self.after_comment = true;
+        } else {
+            docs.push(Doc::HardLine);
+            self.line_start = true;
+        }
+    }
+    fn token(&mut self, docs: &mut Vec<Doc>, token: &Token<'a>) {
+        if token.kind == Kind::Comment {
+            self.comment(docs, token);
+            return;
+        }
+        let prefix = self.is_prefix(token);
+        let after_comment = take(&mut self.after_comment);
+        if !self.line_start && (after_comment || self.space(token)) {
+            docs.push(Doc::Text(" ".to_string()));
+        }
+        docs.push(Doc::Text(token.text.to_string()));
+        self.prev = Some((*token, prefix));
+        self.line_start = false;
+        self.statement_start = false;
+    }
+    fn inline(&mut self, docs: &mut Vec<Doc>, nodes: &[&Node<'a>]) {
+        // the first block after `match` holds its arms, as does the one after
+        // `handling` with its handlers
+        let mut pending_match = false;
+        for node in nodes {
+            match node {
+                Node::Token(token) => {
+                    if token.is_keyword("match") || token.is_keyword("handling") {
+                        pending_match = true;
+                    }
+                    self.token(docs, token);
+                }
+                Node::Group { open, body, close } => {
+                    let is_match = pending_match && open.is("{");
+                    if open.is("{") {
+                        pending_match = false;
+                    }
+                    self.token(docs, open);
+                    if open.is("{") {
+                        self.block_group(docs, body, is_match);
+                    } else {
+                        self.list_group(docs, open, body);
+                    }
+                    if let Some(close) = close {
+                        docs.push(Doc::Text(close.text.to_string()));
+                        self.prev = Some((*close, false));
+                        self.line_start = false;
+                        self.after_comment = false;
+                    }
+                }
+            }
+        }
+    }
+    fn block_group(&mut self, docs: &mut Vec<Doc>, body: &[Node<'a>], is_match: bool) {
+        if body.is_empty() {
+            return;
+        }
+        let mut inner = vec![Doc::HardLine];
+        self.line_start = true;
+        self.block(&mut inner, body, is_match);
+        if let Some(Doc::HardLine) = inner.last() {
+            inner.pop();
+        }
+        docs.push(Doc::Indent(inner));
+        docs.push(Doc::HardLine);
+    }
+    fn list_group(&mut self, docs: &mut Vec<Doc>, open: &Token, body: &[Node<'a>]) {
+        let mut elements = vec![Element {
+            nodes: Vec::new(),
+            comments: Vec::new(),
+        }];
+        let mut commas = 0;
+        let mut after_comma = false;
+        for node in body {
+            if node.is(",") {
+                commas += 1;
+                after_comma = true;
+                elements.push(Element {
+                    nodes: Vec::new(),
+                    comments: Vec::new(),
+                });
+                continue;
+            }
+            match node.comment() {
+                // comments right after a comma stay with the previous element
+                Some(comment) if after_comma && comment.newlines == 0 && !comment.inline => {
+                    let len = elements.len();
+                    elements[len - 2].comments.push(comment);
+                }
+                _ => {
+                    after_comma = false;
+                    elements.last_mut().unwrap().nodes.push(node);
+                }
+            }
+        }
+        // comments ending an element are moved after its comma
+        for element in &mut elements {
+            while let Some(comment) = element.nodes.last().and_then(|node| node.comment()) {
+                element.comments.insert(0, comment);
+                element.nodes.pop();
+            }
+        }
+        if let [.., previous, last] = &mut elements[..] {
+            if last.nodes.is_empty() {
+                previous.comments.append(&mut last.comments);
+            }
+        }
+        if elements.last().unwrap().nodes.is_empty() && elements.len() > 1 {
+            elements.pop();
+        }
+        if let [element] = &elements[..] {
+            if element.nodes.is_empty() && element.comments.is_empty() {
+                return;
+            }
+        }
+        self.elements(docs, open, elements, commas)
+    }
+    fn elements(
+        &mut self,
+        docs: &mut Vec<Doc>,
+        open: &Token,
+        elements: Vec<Element<'_, 'a>>,
+        commas: usize,
+    ) {
+        let count = elements.len();
+        let mut inner = vec![Doc::SoftLine];
+        self.line_start = true;
+        for (i, element) in elements.into_iter().enumerate() {
+            if i > 0 && !self.line_start {
+                inner.push(Doc::Line);
+            }
+            self.line_start = true;
+            self.statement_start = true;
+            self.inline(&mut inner, &element.nodes);
+            if i + 1 < count {
+                inner.push(Doc::Text(",".to_string()));
+            } else if commas > 0 && count == 1 && open.is("(") {
+                // a single element tuple needs its comma
+                inner.push(Doc::Text(",".to_string()));
+            } else if commas > 0 {
+                inner.push(Doc::IfBreak(","));
+            }
+            for comment in element.comments {
+                self.comment(&mut inner, comment);
+            }
+        }
+        // a comment ending the list breaks the line at the outer indentation
+        let line = if let Some(Doc::HardLine) = inner.last() {
+            inner.pop();
+            Doc::HardLine
+        } else {
+            Doc::SoftLine
+        };
+        docs.push(Doc::Group(vec![Doc::Indent(inner), line]));
+    }
+    fn statements<'b>(nodes: &'b [Node<'a>], is_match: bool) -> Vec<Statement<'b, 'a>> {
+        let separator = if is_match { "," } else { ";" };
+        let mut statements: Vec<Statement> = Vec::new();
+        let mut current = Vec::new();
+        for (i, node) in nodes.iter().enumerate() {
+            // an inline comment goes with the statement after it
+            if current.is_empty() {
+                if let Some(comment) = node.comment().filter(|comment| !comment.inline) {
+                    match statements.last_mut() {
+                        Some(statement) if comment.newlines == 0 => statement.nodes.push(node),
+                        _ => statements.push(Statement {
+                            nodes: vec![node],
+                            blank: comment.newlines > 1,
+                        }),
+                    }
+                    continue;
+                }
+            }
+            current.push(node);
+            let end = if node.is(separator) {
+                true
+            } else if node.is_group("{") {
+                // statements made of control flow may end without separator
+                let start = current
+                    .iter()
+                    .rposition(|node| node.is("=>"))
+                    .map_or(0, |i| i + 1);
+                let continues = nodes[i + 1..]
+                    .iter()
+                    .find(|node| node.comment().is_none())
+                    .is_some_and(|node| node.is("else") || node.is(separator));
+                // as do module declarations, after their visibility if any
+                let declaration = match current[..] {
+                    [visibility, path, ..] if visibility.is("pub") && path.is_group("(") => 2,
+                    [visibility, ..] if visibility.is("pub") => 1,
+                    _ => 0,
+                };
+                let module = current.get(declaration).is_some_and(|node| node.is("mod"));
+                (module || current.get(start).is_some_and(|node| is_control_flow(node)))
+                    && !continues
+            } else {
+                false
+            };
+            if end {
+                let nodes = std::mem::take(&mut current);
+                statements.push(Statement {
+                    blank: nodes[0].first().newlines > 1,
+                    nodes,
+                });
+            }
+        }
+        if !current.is_empty() {
+            statements.push(Statement {
+                blank: current[0].first().newlines > 1,
+                nodes: current,
+            });
+        }
+        statements
+    }
+    fn block(&mut self, docs: &mut Vec<Doc>, nodes: &[Node<'a>], is_match: bool) {
+        for (i, statement) in Self::statements(nodes, is_match).into_iter().enumerate() {
+            if i > 0 {
+                if !self.line_start {
+                    docs.push(Doc::HardLine);
+                }
+                if statement.blank {
+                    docs.push(Doc::HardLine);
+                }
+            }
+            self.line_start = true;
+            self.statement_start = true;
+            self.inline(docs, &statement.nodes);
+        }
+    }
+}
+pub fn layout(tokens: &[Token]) -> Vec<Doc> {
+    let tree = tree(tokens);
+    let mut docs = Vec::new();
+    let mut layout = Layout {
+        line_start: true,
+        ..Layout::default()
+    };
+    layout.block(&mut docs, &tree, false);
+    docs
+}
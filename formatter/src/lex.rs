@@ -0,0 +1,110 @@
+use syntax::SyntaxKind;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Kind {
+    Word,
+    Number,
+    Str,
+    Comment,
+    Punct,
+}
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Token<'a> {
+    pub kind: Kind,
+    pub text: &'a str,
+    // line breaks between this and the previous token
+    pub newlines: usize,
+    // a block comment with more code after it on the same line
+    pub inline: bool,
+}
+impl<'a> Token<'a> {
+    pub fn is(&self, text: &str) -> bool {
+        self.kind == Kind::Punct && self.text == text
+    }
+    pub fn is_keyword(&self, text: &str) -> bool {
+        self.kind == Kind::Word && self.text == text
+    }
+}
+// tokens of the lossless lexer with whitespaces folded into line break
+// counts
+pub fn lex(src: &str) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut newlines = 0;
+    for (kind, text) in syntax::lex(src) {
+        let kind = match kind {
+            SyntaxKind::Whitespace => {
+                newlines += text.matches('\n').count();
+                continue;
+            }
+            SyntaxKind::Comment | SyntaxKind::Shebang => Kind::Comment,
+            SyntaxKind::Ident | SyntaxKind::PrivateIdent | SyntaxKind::Label => Kind::Word,
+            SyntaxKind::Number => Kind::Number,
+            SyntaxKind::Char | SyntaxKind::Str => Kind::Str,
+            kind if kind.is_keyword() => Kind::Word,
+            _ => Kind::Punct,
+        };
+        let text = if kind == Kind::Comment {
+            text.trim_end()
+        } else {
+            text
+        };
+        if let Some(prev) = tokens.last_mut() {
+            prev.inline =
+                prev.kind == Kind::Comment && !prev.text.starts_with("--") && newlines == 0;
+        }
+        tokens.push(Token {
+            kind,
+            text,
+            newlines,
+            inline: false,
+        });
+        newlines = 0;
+    }
+    tokens
+}
+// whether writing the two tokens without space in between lexes differently
+pub fn joins(left: &str, right: &str) -> bool {
+    let src = format!("{}{}", left, right);
+    let tokens = lex(&src);
+    !matches!(&tokens[..], [a, b] if a.text == left && b.text == right)
+}
+#[cfg(test)]
+mod test {
+    use super::{joins, lex, Kind};
+
+    #[test]
+    fn tokens() {
+        let src = "foo <- [1..2.5e-3, .5] -- comment\n\"a\\\"b\" 0x_1e-2";
+        let tokens: Vec<_> = lex(src)
+            .into_iter()
+            .map(|token| (token.kind, token.text))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (Kind::Word, "foo"),
+                (Kind::Punct, "<-"),
+                (Kind::Punct, "["),
+                (Kind::Number, "1"),
+                (Kind::Punct, ".."),
+                (Kind::Number, "2.5e-3"),
+                (Kind::Punct, ","),
+                (Kind::Number, ".5"),
+                (Kind::Punct, "]"),
+                (Kind::Comment, "-- comment"),
+                (Kind::Str, "\"a\\\"b\""),
+                (Kind::Number, "0x_1e"),
+                (Kind::Punct, "-"),
+                (Kind::Number, "2"),
+            ],
+        );
+    }
+    #[test]
+    fn join() {
+        assert!(joins("-", "-"));
+        assert!(joins("&", "&"));
+        assert!(joins("=", ">"));
+        assert!(!joins("(", "-"));
+        assert!(!joins(">", ">"));
+    }
+}
@@ -0,0 +1,251 @@
+#![warn(clippy::all)]
+#![deny(clippy::correctness)]
+#![forbid(unsafe_code)]
+
+use hir::statement::Statement;
+use parser::{ast, EasyParser};
+use std::fmt::{self, Display, Formatter};
+
+mod doc;
+mod layout;
+mod lex;
+
+pub const WIDTH: usize = 100;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Error {
+    Parse(String),
+    // the formatted code parses differently, this is a bug of the formatter
+    Changed,
+}
+impl Display for Error {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Parse(message) => write!(fmt, "{}", message),
+            Self::Changed => write!(fmt, "formatting would change the meaning of the code"),
+        }
+    }
+}
+impl std::error::Error for Error {}
+
+fn parse(src: &str) -> Result<Vec<Statement<()>>, Error> {
+    ast()
+        .easy_parse(src)
+        .map(|(statements, _)| statements)
+        .map_err(|err| {
+            let err = err.map_position(|position| position.translate_position(src));
+            Error::Parse(err.to_string())
+        })
+}
+// formats the source keeping every comment and token as is, only the
+// whitespace in between and trailing commas are changed
+pub fn format(src: &str) -> Result<String, Error> {
+    let statements = parse(src)?;
+    let docs = layout::layout(&lex::lex(src));
+    let mut formatted = doc::print(&docs, WIDTH).trim().to_string();
+    if !formatted.is_empty() {
+        formatted.push('\n');
+    }
+    if parse(&formatted).ok() == Some(statements) {
+        Ok(formatted)
+    } else {
+        Err(Error::Changed)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::{format, parse, Error};
+
+    const SOURCES: [&str; 18] = [
+        "id(x)=>x;foo=id( 10 );",
+        "-- leading comment
+        first(arr) => &arr^[0]; -- trailing comment
+
+        mut foo = [10,20 ,30,];
+        elem <- first( & foo );",
+        "rec = (foo = 1, bar = true, = baz, *rest);
+        (foo = a, *rest) = rec;
+        tuple = (1,);
+        [first, *rest, last] = [1, 2, 3, 4];",
+        "foo = if a { 1 } else if b { 2 } else { 3 };
+        if a { foo() } else { bar(); }
+        while !done { done <- next(&state) ; }
+        loop { break; }",
+        "result = match value { @some x => x, @none => { 0 }, _ => -1 };",
+        "a, b <- b, a; foo <- bar <- baz;
+        text = \"hello -- not a comment\" ++ 'c';
+        slice = arr[1..end] ++ arr[.<2] ++ [a >. b];
+        x = - -1; y = a - -b; z = >x; w = clone w;",
+        "long = [first_element_with_long_name, second_element_with_long_name, third_element_name, fourth_element_name];",
+        "call(
+            -- explains the first
+            first, -- and the second
+            second
+        );",
+        "pub newtype Point(x:Num, pub #y: &mut Box( Num ), tags : [_]);
+        p = Point(x = 1, #y = 2, tags = []); Point(x = x, *_) = p; p.#y;",
+        "kind = match code { 1|2 => @small, 3 .< 9 as n => @big n, -9 .. => @negative, _ => @none };",
+        "same = match x { ==y+1 | == -z => true, _ => false };",
+        "if o=:@some x { x } else { 0 } while pop(&q) =: @val item { push(item); }",
+        "f(o) => { @some x = o else { return 0 }; x }",
+        "n = 'outer:loop { 'inner : while b { break 'outer 1; continue 'inner } };",
+        "r = xs|>map(?, (x) => x * 2)|>fold(init = 0, f = ?);",
+        "d = #( \"a\"=1, b = [2], ); s = #[ #[] ,x]; d[k] <- s[x];",
+        "n = do gen() handling {item(x)=>print(x),ask()=>{2} fail(ref e)=>return 0};",
+        "`loop`=#\"raw\n  \\string\"#; /- block\n  comment -/ f(`in`)=>{- {- ignored -} -} `in`;",
+    ];
+    #[test]
+    fn round_trip() {
+        for src in SOURCES {
+            let formatted = format(src).unwrap();
+            assert_eq!(parse(&formatted), parse(src), "{}", formatted);
+        }
+    }
+    #[test]
+    fn idempotent() {
+        for src in SOURCES {
+            let formatted = format(src).unwrap();
+            assert_eq!(format(&formatted).unwrap(), formatted, "{}", formatted);
+        }
+    }
+    #[test]
+    fn spacing() {
+        assert_eq!(
+            format("id(x)=>x;foo=id( 10 );"),
+            Ok("id(x) => x;\nfoo = id(10);\n".to_string())
+        );
+        assert_eq!(
+            format("foo<-bar++[1,2,];x=-a*-b;"),
+            Ok("foo <- bar ++ [1, 2];\nx = -a * -b;\n".to_string()),
+        );
+        assert_eq!(format("x = - -1;"), Ok("x = - -1;\n".to_string()));
+        assert_eq!(format("t = ( 1 , );"), Ok("t = (1,);\n".to_string()));
+        assert_eq!(
+            format("[a, *rest] = (1, * rest);"),
+            Ok("[a, *rest] = (1, *rest);\n".to_string()),
+        );
+        assert_eq!(
+            format("newtype P(x : Num, #y:&[_]);"),
+            Ok("newtype P(x: Num, #y: &[_]);\n".to_string()),
+        );
+        assert_eq!(
+            format("x = match y { 1|-2 => 0, 3 .. => 1 };"),
+            Ok("x = match y {\n    1 | -2 => 0,\n    3.. => 1\n};\n".to_string()),
+        );
+        assert_eq!(
+            format("x = match y { ==a*2 => 0 };"),
+            Ok("x = match y {\n    == a * 2 => 0\n};\n".to_string()),
+        );
+        assert_eq!(
+            format("while q.next=:@val x {}"),
+            Ok("while q.next =: @val x {}\n".to_string()),
+        );
+        assert_eq!(
+            format("@some x=o else{return 0};"),
+            Ok("@some x = o else {\n    return 0\n};\n".to_string()),
+        );
+        assert_eq!(
+            format("'a :loop{break 'a 'c'}"),
+            Ok("'a: loop {\n    break 'a 'c'\n}\n".to_string()),
+        );
+        assert_eq!(
+            format("r=40|>add( ?,2 );"),
+            Ok("r = 40 |> add(?, 2);\n".to_string()),
+        );
+        assert_eq!(
+            format("d=#( a=1 ,b=2, );s=#[ x ];"),
+            Ok("d = #(a = 1, b = 2);\ns = #[x];\n".to_string()),
+        );
+        assert_eq!(
+            format("x=do f() handling{a(y)=>yield b( y ),c()=>1};"),
+            Ok("x = do f() handling {\n    a(y) => yield b(y),\n    c() => 1\n};\n".to_string()),
+        );
+    }
+    #[test]
+    fn blocks() {
+        let src = "foo = if a {1} else {2}; if b { bar(); }  baz;";
+        let expected = "\
+foo = if a {
+    1
+} else {
+    2
+};
+if b {
+    bar();
+}
+baz;
+";
+        assert_eq!(format(src), Ok(expected.to_string()));
+        let src = "mod math; pub ( inner ) mod inner { pub pi = 3; } x = inner.pi;";
+        let expected = "\
+mod math;
+pub(inner) mod inner {
+    pub pi = 3;
+}
+x = inner.pi;
+";
+        assert_eq!(format(src), Ok(expected.to_string()));
+        let src = "r = match v { @a x => x, @b => { 0 } _ => 1 };";
+        let expected = "\
+r = match v {
+    @a x => x,
+    @b => {
+        0
+    }
+    _ => 1
+};
+";
+        assert_eq!(format(src), Ok(expected.to_string()));
+    }
+    #[test]
+    fn wrapping() {
+        let src = "long = [first_element_with_long_name, second_element_with_long_name, third_element_name, fourth_element_name];";
+        let expected = "\
+long = [
+    first_element_with_long_name,
+    second_element_with_long_name,
+    third_element_name,
+    fourth_element_name,
+];
+";
+        assert_eq!(format(src), Ok(expected.to_string()));
+    }
+    #[test]
+    fn comments() {
+        let src = "-- leading\n\n\nfoo = 1; -- trailing\n  -- own line\nbar = [1, -- one\n2];";
+        let expected = "\
+-- leading
+
+foo = 1; -- trailing
+-- own line
+bar = [
+    1, -- one
+    2,
+];
+";
+        assert_eq!(format(src), Ok(expected.to_string()));
+        // block comments stay inline when code follows them on their line,
+        // their content is kept as is
+        let src = "foo = /- one\n    two -/ 1; {-\n  bar = \"-}\";\n-}";
+        let expected = "\
+foo = /- one
+    two -/ 1; {-
+  bar = \"-}\";
+-}
+";
+        assert_eq!(format(src), Ok(expected.to_string()));
+        assert_eq!(format(expected), Ok(expected.to_string()));
+        let src = "/- a -/ foo = [1, /- b -/ 2, 3 /- c -/];\nbar(/- d -/ 1); /- e -/\nbaz = 1;";
+        let expected = "\
+/- a -/ foo = [1, /- b -/ 2, 3 /- c -/];
+bar(/- d -/ 1); /- e -/
+baz = 1;
+";
+        assert_eq!(format(src), Ok(expected.to_string()));
+        assert_eq!(format(expected), Ok(expected.to_string()));
+    }
+    #[test]
+    fn parse_error() {
+        assert!(matches!(format("foo = ;"), Err(Error::Parse(_))));
+    }
+}
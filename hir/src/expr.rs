@@ -24,6 +24,10 @@ pub enum Expr<T> {
     Array(Box<[Element<T>]>),
     ArrayRange(Range<T>),
 
+    // `#(key = value)` and `#[elem]`
+    Dict(Box<[Entry<T>]>),
+    Set(Box<[Expr<T>]>),
+
     Unit,
     Splat(Box<Expr<T>>),
     Record(Record<T>),
@@ -38,6 +42,11 @@ pub enum Expr<T> {
     ControlFlow(ControlFlow<T>),
     Fun(Fun<T>),
     Jump(Jump<T>),
+
+    Yield(Yield<T>),
+    Handle(Handle<T>),
+
+    Module(Module<T>),
 }
 impl<T> Expr<T> {
     pub fn field_name(&self) -> Option<Atom> {
@@ -90,12 +99,47 @@ pub struct Fun<T> {
     pub param: Box<[Var<T>]>,
     pub body: Box<Expr<T>>,
 }
+// `yield effect(arg)`, evaluates into the value the handler resumes it with
+#[derive(Debug, PartialEq, Clone)]
+pub struct Yield<T> {
+    pub effect: Atom,
+    pub arg: Arg<T>,
+}
+// `do expr handling { effect(param) => body }`, evaluates into the value of
+// the expression
+#[derive(Debug, PartialEq, Clone)]
+pub struct Handle<T> {
+    pub expr: Box<Expr<T>>,
+    pub handler: Box<[Handler<T>]>,
+}
+// the value of the body resumes the `yield`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Handler<T> {
+    pub effect: Atom,
+    pub param: Box<[Var<T>]>,
+    pub body: Expr<T>,
+}
+// a module evaluates into a record of its top-level bindings
+#[derive(Debug, PartialEq, Clone)]
+pub enum Module<T> {
+    // `mod name`, stays until the file `name.butter` beside the current file
+    // is loaded in place of it
+    File(Atom),
+    Inline(Box<[Statement<T>]>),
+}
 #[derive(Debug, PartialEq, Clone)]
 pub enum Jump<T> {
-    Break(Option<Box<Expr<T>>>),
-    Continue,
+    Break(Break<T>),
+    // the label of the loop to continue, the innermost one without it
+    Continue(Option<Atom>),
     Return(Option<Box<Expr<T>>>),
 }
+// `break 'label value`, both are optional
+#[derive(Debug, PartialEq, Clone)]
+pub struct Break<T> {
+    pub label: Option<Atom>,
+    pub expr: Option<Box<Expr<T>>>,
+}
 #[derive(Debug, PartialEq, Clone)]
 pub struct Unary<T> {
     pub kind: UnaryType,
@@ -134,6 +178,8 @@ pub enum BinaryType {
     Less,
     LessEqual,
     Concatenate,
+    // `value |> fun`, calls the right side with the left
+    Pipe,
 }
 #[derive(Debug, PartialEq, Clone)]
 pub struct Index<T> {
@@ -141,6 +187,11 @@ pub struct Index<T> {
     pub index: Box<Expr<T>>,
 }
 #[derive(Debug, PartialEq, Clone)]
+pub struct Entry<T> {
+    pub key: Expr<T>,
+    pub value: Expr<T>,
+}
+#[derive(Debug, PartialEq, Clone)]
 pub struct Element<T> {
     pub expr: Expr<T>,
     pub kind: ElementKind,
@@ -197,7 +248,7 @@ pub enum ControlFlow<T> {
     If(If<T>),
     For(For<T>),
     While(While<T>),
-    Loop(Block<T>),
+    Loop(Loop<T>),
     Match(Match<T>),
 }
 #[derive(Debug, PartialEq, Clone)]
@@ -205,21 +256,62 @@ pub struct Block<T> {
     pub statement: Box<[Statement<T>]>,
     pub expr: Option<Box<Expr<T>>>,
 }
+// the condition of `if` and `while`
+#[derive(Debug, PartialEq, Clone)]
+pub enum Condition<T> {
+    Bool(Box<Expr<T>>),
+    Match(MatchCondition<T>),
+}
+impl<T> Condition<T> {
+    // the evaluated expression, before matching if there's a pattern
+    pub fn expr(&self) -> &Expr<T> {
+        match self {
+            Self::Bool(expr) => expr,
+            Self::Match(condition) => &condition.expr,
+        }
+    }
+}
+// `expr =: pattern`, holds when the value matches the pattern, its bindings
+// are only in scope of the body
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchCondition<T> {
+    pub expr: Box<Expr<T>>,
+    pub pattern: Pattern<T>,
+}
 #[derive(Debug, PartialEq, Clone)]
 pub struct If<T> {
-    pub condition: Box<Expr<T>>,
+    pub condition: Condition<T>,
     pub body: Block<T>,
     pub else_part: Option<Box<ControlFlow<T>>>,
 }
+// loops may be labelled with `'label:` so jumps of nested loops can target
+// them, labels are kept without `'`
 #[derive(Debug, PartialEq, Clone)]
 pub struct For<T> {
+    pub label: Option<Atom>,
     pub pattern: Pattern<T>,
     pub expr: Box<Expr<T>>,
     pub body: Block<T>,
 }
+impl<T> For<T> {
+    // the range when the loop goes over one written in place with a start,
+    // it's counted through without building the array
+    pub fn counted(&self) -> Option<&Range<T>> {
+        match &*self.expr {
+            Expr::ArrayRange(range) if range.left.is_some() => Some(range),
+            _ => None,
+        }
+    }
+}
 #[derive(Debug, PartialEq, Clone)]
 pub struct While<T> {
-    pub condition: Box<Expr<T>>,
+    pub label: Option<Atom>,
+    pub condition: Condition<T>,
+    pub body: Block<T>,
+}
+#[derive(Debug, PartialEq, Clone)]
+pub struct Loop<T> {
+    pub label: Option<Atom>,
     pub body: Block<T>,
 }
 #[derive(Debug, PartialEq, Clone)]
@@ -257,6 +349,55 @@ pub struct Call<T> {
     pub expr: Box<Expr<T>>,
     pub arg: Arg<T>,
 }
+impl<T> Call<T>
+where
+    T: Default,
+{
+    // `f(?, x)` where the `?` placeholders are variables named `?`, turns it
+    // into `(?0) => f(?0, x)`, the names can't clash with identifiers
+    pub fn partially_applied(mut self) -> Expr<T> {
+        let exprs: Vec<_> = match &mut self.arg {
+            Arg::Unit | Arg::Splat(_) => Vec::new(),
+            Arg::Tuple(Tuple::Tuple(exprs)) => exprs.iter_mut().collect(),
+            Arg::Tuple(Tuple::TupleWithSplat(tuple)) => tuple
+                .left
+                .iter_mut()
+                .chain(tuple.right.iter_mut())
+                .collect(),
+            Arg::Record(Record::Record(fields)) => {
+                fields.iter_mut().map(|field| &mut field.expr).collect()
+            }
+            Arg::Record(Record::RecordWithSplat(record)) => record
+                .left
+                .iter_mut()
+                .chain(record.right.iter_mut())
+                .map(|field| &mut field.expr)
+                .collect(),
+        };
+        let placeholder = Atom::from("?");
+        let mut param = Vec::new();
+        for expr in exprs {
+            if matches!(expr, Expr::Place(PlaceExpr::Var(name)) if *name == placeholder) {
+                let ident = Atom::from(format!("?{}", param.len()));
+                *expr = Expr::Place(PlaceExpr::Var(ident.clone()));
+                param.push(Var {
+                    ident,
+                    mutable: false,
+                    bind_to_ref: false,
+                    ty: T::default(),
+                });
+            }
+        }
+        if param.is_empty() {
+            Expr::Call(self)
+        } else {
+            Expr::Fun(Fun {
+                param: param.into(),
+                body: Box::new(Expr::Call(self)),
+            })
+        }
+    }
+}
 #[derive(Debug, PartialEq, Clone)]
 pub enum Arg<T> {
     Unit,
@@ -1,7 +1,10 @@
-use crate::Atom;
-use std::collections::HashMap;
+use crate::{
+    expr::{BoundType, Expr},
+    Atom,
+};
+use std::collections::BTreeMap;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Pattern<T> {
     True,
     False,
@@ -14,6 +17,12 @@ pub enum Pattern<T> {
     Array(ListPattern<T>),
     Tag(TaggedPattern<T>),
     Ref(Box<Pattern<T>>),
+    NewType(NewTypePattern<T>),
+    Or(Box<[Pattern<T>]>),
+    Range(RangePattern<T>),
+    As(AsPattern<T>),
+    // `== expr`, compares against the value of an expression
+    Equal(Box<Expr<T>>),
 }
 impl<T> Pattern<T> {
     pub fn field_name(&self) -> Option<Atom> {
@@ -23,31 +32,55 @@ impl<T> Pattern<T> {
         }
     }
 }
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Var<T> {
     pub ident: Atom,
     pub mutable: bool,
     pub bind_to_ref: bool,
     pub ty: T,
 }
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum ListPattern<T> {
     List(Box<[Pattern<T>]>),
     ListWithRest(ListWithRest<T>),
 }
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct ListWithRest<T> {
     pub left: Box<[Pattern<T>]>,
     pub rest: Box<Pattern<T>>,
     pub right: Box<[Pattern<T>]>,
 }
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct RecordPattern<T> {
-    pub fields: HashMap<Atom, Pattern<T>>,
+    pub fields: BTreeMap<Atom, Pattern<T>>,
     pub rest: Option<Box<Pattern<T>>>,
 }
-#[derive(Debug, PartialEq, Eq, Clone)]
+// `Name(field = pattern)`, the fields of a newtype
+#[derive(Debug, PartialEq, Clone)]
+pub struct NewTypePattern<T> {
+    pub name: Atom,
+    pub fields: RecordPattern<T>,
+}
+#[derive(Debug, PartialEq, Clone)]
 pub struct TaggedPattern<T> {
     pub tag: Atom,
     pub pattern: Option<Box<Pattern<T>>>,
 }
+// `1..3`, at least one of the bounds is present and they are integer
+// patterns
+#[derive(Debug, PartialEq, Clone)]
+pub struct RangePattern<T> {
+    pub left: Option<PatternBound<T>>,
+    pub right: Option<PatternBound<T>>,
+}
+#[derive(Debug, PartialEq, Clone)]
+pub struct PatternBound<T> {
+    pub kind: BoundType,
+    pub pattern: Box<Pattern<T>>,
+}
+// `pattern as name`, binds the whole value as well
+#[derive(Debug, PartialEq, Clone)]
+pub struct AsPattern<T> {
+    pub pattern: Box<Pattern<T>>,
+    pub var: Var<T>,
+}
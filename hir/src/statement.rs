@@ -1,5 +1,5 @@
 use crate::{
-    expr::{Expr, Fun},
+    expr::{Block, Expr, Fun},
     pattern::Pattern,
     Atom,
 };
@@ -8,16 +8,70 @@ use crate::{
 pub enum Statement<T> {
     Declare(Declare<T>),
     FunDeclare(FunDeclare<T>),
+    NewType(NewType<T>),
     Expr(Expr<T>),
 }
 #[derive(Debug, PartialEq, Clone)]
 pub struct Declare<T> {
+    pub visibility: Visibility,
     pub pattern: Pattern<T>,
     pub expr: Expr<T>,
+    // `pattern = expr else { ... };`, executed when the pattern doesn't
+    // match, it must not finish
+    pub else_part: Option<Block<T>>,
 }
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunDeclare<T> {
+    pub visibility: Visibility,
     pub ident: Atom,
     pub fun: Fun<T>,
     pub ty: T,
 }
+// `newtype Name(field: Type, #private)`, declares a nominal type along with
+// its constructor, a function of the same name taking the fields
+#[derive(Debug, PartialEq, Clone)]
+pub struct NewType<T> {
+    pub visibility: Visibility,
+    pub ident: Atom,
+    pub fields: Box<[NewTypeField]>,
+    // type of the constructor
+    pub ty: T,
+}
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NewTypeField {
+    // public unless the name starts with `#`, which `pub` may override
+    pub visibility: Visibility,
+    pub name: Atom,
+    pub ty: TypeAnnotation,
+}
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TypeAnnotation {
+    // `_` or a missing annotation, each one is a parameter of the newtype
+    Infer,
+    Array(Box<TypeAnnotation>),
+    Ref(bool, Box<TypeAnnotation>),
+    // builtin types and newtypes along with their arguments
+    Named(Atom, Box<[TypeAnnotation]>),
+}
+// where a top-level binding of a module can be accessed from outside of it,
+// it's only meaningful for modules
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub enum Visibility {
+    #[default]
+    Private,
+    Public,
+    // `pub(path.to.module)`, public to the module at the path and the modules
+    // within it, the path starts at the top of the file
+    Restricted(Box<[Atom]>),
+}
+impl Visibility {
+    // whether a module at the path can access it, private bindings are only
+    // accessed by name within their own module
+    pub fn is_visible_from(&self, module: &[Atom]) -> bool {
+        match self {
+            Self::Private => false,
+            Self::Public => true,
+            Self::Restricted(path) => module.starts_with(path),
+        }
+    }
+}
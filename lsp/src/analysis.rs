@@ -0,0 +1,156 @@
+use crate::{
+    line_index::LineIndex,
+    resolve::{resolve, Symbols},
+};
+use hir::{statement::Statement, Atom};
+use lsp_types::{Diagnostic, DiagnosticSeverity};
+use query::Database;
+use syntax::{SyntaxNode, TextRange};
+use type_system::{Cons, Keyed, Type};
+
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub symbols: Symbols,
+    // whether the source parsed and type checked
+    pub typed: bool,
+}
+fn diagnostic(index: &LineIndex, span: std::ops::Range<usize>, message: String) -> Diagnostic {
+    Diagnostic {
+        range: index.range(span),
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("butter".to_string()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+fn span(range: TextRange) -> std::ops::Range<usize> {
+    range.start().into()..range.end().into()
+}
+// byte spans of each top-level statement
+fn statement_spans(root: &SyntaxNode) -> Vec<std::ops::Range<usize>> {
+    root.children()
+        .map(|node| span(node.text_range()))
+        .collect()
+}
+pub fn analyze(db: &mut Database) -> Analysis {
+    let src = db.text();
+    let index = LineIndex::new(&src);
+    let parse = db.parse();
+    let inferred = match db.infer() {
+        Ok(inferred) => inferred,
+        Err(err) => {
+            // every syntax error is reported, lowering errors come alone
+            let errors = if parse.errors().is_empty() {
+                vec![err]
+            } else {
+                parse.errors().to_vec()
+            };
+            return Analysis {
+                diagnostics: errors
+                    .into_iter()
+                    .map(|err| diagnostic(&index, span(err.range), err.message))
+                    .collect(),
+                symbols: Symbols::default(),
+                typed: false,
+            };
+        }
+    };
+    match inferred.as_ref() {
+        Ok(typed) => Analysis {
+            diagnostics: Vec::new(),
+            symbols: resolve(&parse.syntax(), typed),
+            typed: true,
+        },
+        // the error comes with the statement that caused it, names are still
+        // resolved but without types
+        Err((statement, err)) => {
+            let span = statement_spans(&parse.syntax())
+                .get(*statement)
+                .cloned()
+                .unwrap_or(0..0);
+            let statements: Vec<Statement<()>> = db
+                .items()
+                .unwrap_or_default()
+                .iter()
+                .map(|item| Statement::clone(&item.statement))
+                .collect();
+            Analysis {
+                diagnostics: vec![diagnostic(&index, span, err.to_string())],
+                symbols: resolve(&parse.syntax(), &statements),
+                typed: false,
+            }
+        }
+    }
+}
+// fields of the record bound to the identifier directly before a `.` at the
+// offset, as inferred by the given analysis
+pub fn field_completions(src: &str, offset: usize, symbols: &Symbols) -> Vec<(Atom, Type)> {
+    let before = src[..offset].trim_end_matches(|ch: char| ch.is_alphanumeric() || ch == '_');
+    let Some(before) = before.strip_suffix('.') else {
+        return Vec::new();
+    };
+    let start = before
+        .trim_end_matches(|ch: char| ch.is_alphanumeric() || ch == '_')
+        .len();
+    let name = &before[start..];
+    if name.is_empty() {
+        return Vec::new();
+    }
+    let definition = symbols
+        .definitions
+        .iter()
+        .rev()
+        .find(|definition| &*definition.name == name && definition.span.start < start);
+    let mut ty = definition.and_then(|definition| definition.ty.as_ref());
+    while let Some(Type::Cons(Cons::Ref(_, inner))) = ty {
+        ty = Some(inner);
+    }
+    match ty {
+        Some(Type::Cons(Cons::Record(Keyed { fields, rest: _ }))) => fields
+            .iter()
+            .map(|(name, ty)| (name.clone(), ty.clone()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::{analyze, field_completions};
+    use query::Database;
+
+    #[test]
+    fn parse_error() {
+        let analysis = analyze(&mut Database::new("foo = 1;\nbar = ;"));
+        let [diagnostic] = &analysis.diagnostics[..] else {
+            panic!("expected a single diagnostic");
+        };
+        assert_eq!(diagnostic.range.start.line, 1);
+        assert!(!analysis.typed);
+    }
+    #[test]
+    fn type_error() {
+        let analysis = analyze(&mut Database::new("foo = 1;\nbar = foo + true;\nbaz = 2;"));
+        let [diagnostic] = &analysis.diagnostics[..] else {
+            panic!("expected a single diagnostic");
+        };
+        assert_eq!(diagnostic.range.start.line, 1);
+        assert_eq!(diagnostic.range.end.line, 1);
+        assert_eq!(diagnostic.message, "mismatching types");
+    }
+    #[test]
+    fn row_tuples() {
+        let analysis = analyze(&mut Database::new("pair(a, b) => [(1, *a), (1, *b)];"));
+        assert!(analysis.diagnostics.is_empty());
+        assert!(analysis.typed);
+    }
+    #[test]
+    fn completion() {
+        let src = "rec = (foo = 1, bar = true); rec.";
+        let analysis = analyze(&mut Database::new("rec = (foo = 1, bar = true);"));
+        let fields: Vec<_> = field_completions(src, src.len(), &analysis.symbols)
+            .into_iter()
+            .map(|(name, ty)| format!("{}: {}", name, ty))
+            .collect();
+        assert_eq!(fields, ["bar: Bool", "foo: Num"]);
+    }
+}
@@ -0,0 +1,53 @@
+use lsp_types::{Position, Range};
+
+// converts between byte offsets and LSP positions, which count UTF-16 code
+// units within a line
+pub struct LineIndex<'a> {
+    src: &'a str,
+    line_starts: Vec<usize>,
+}
+impl<'a> LineIndex<'a> {
+    pub fn new(src: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { src, line_starts }
+    }
+    pub fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.src[start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+    pub fn range(&self, range: std::ops::Range<usize>) -> Range {
+        Range::new(self.position(range.start), self.position(range.end))
+    }
+    pub fn offset(&self, position: Position) -> usize {
+        let Some(start) = self.line_starts.get(position.line as usize) else {
+            return self.src.len();
+        };
+        let mut units = 0;
+        for (i, ch) in self.src[*start..].char_indices() {
+            if units >= position.character as usize || ch == '\n' {
+                return start + i;
+            }
+            units += ch.len_utf16();
+        }
+        self.src.len()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::LineIndex;
+    use lsp_types::Position;
+
+    #[test]
+    fn utf16() {
+        let src = "a\n\u{1F600}b = 1;\n";
+        let index = LineIndex::new(src);
+        let offset = src.find('b').unwrap();
+        assert_eq!(index.position(offset), Position::new(1, 2));
+        assert_eq!(index.offset(Position::new(1, 2)), offset);
+        assert_eq!(index.offset(Position::new(0, 10)), 1);
+    }
+}
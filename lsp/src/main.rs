@@ -0,0 +1,214 @@
+#![warn(clippy::all)]
+#![deny(clippy::correctness)]
+#![forbid(unsafe_code)]
+
+use analysis::{analyze, field_completions, Analysis};
+use line_index::LineIndex;
+use lsp_server::{Connection, ExtractError, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{Completion, GotoDefinition, HoverRequest, Request as _},
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use query::Database;
+use resolve::Symbols;
+use std::{collections::HashMap, error::Error};
+
+mod analysis;
+mod line_index;
+mod resolve;
+
+struct Document {
+    // kept across changes so that unaffected statements aren't checked again
+    db: Database,
+    analysis: Analysis,
+    // symbols of the last version that type checked, completion is usually
+    // requested while the source is incomplete
+    typed_symbols: Symbols,
+}
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Url, Document>,
+}
+impl Server {
+    fn update(
+        &mut self,
+        connection: &Connection,
+        uri: Url,
+        text: String,
+    ) -> Result<(), Box<dyn Error>> {
+        let (mut db, previous_symbols) = match self.documents.remove(&uri) {
+            Some(mut document) => {
+                document.db.set_text(&text);
+                (document.db, document.typed_symbols)
+            }
+            None => {
+                let mut db = Database::new(&text);
+                // file modules are loaded beside the document
+                db.set_path(uri.to_file_path().ok());
+                (db, Symbols::default())
+            }
+        };
+        let analysis = analyze(&mut db);
+        let typed_symbols = if analysis.typed {
+            analysis.symbols.clone()
+        } else {
+            previous_symbols
+        };
+        let params = PublishDiagnosticsParams::new(uri.clone(), analysis.diagnostics.clone(), None);
+        connection
+            .sender
+            .send(Message::Notification(Notification::new(
+                PublishDiagnostics::METHOD.to_string(),
+                params,
+            )))?;
+        self.documents.insert(
+            uri,
+            Document {
+                db,
+                analysis,
+                typed_symbols,
+            },
+        );
+        Ok(())
+    }
+    fn notification(
+        &mut self,
+        connection: &Connection,
+        notification: Notification,
+    ) -> Result<(), Box<dyn Error>> {
+        match &notification.method[..] {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.update(
+                    connection,
+                    params.text_document.uri,
+                    params.text_document.text,
+                )
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // full sync, the last change holds the whole text
+                match params.content_changes.into_iter().last() {
+                    Some(change) => self.update(connection, params.text_document.uri, change.text),
+                    None => Ok(()),
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let params = params.text_document_position_params;
+        let document = self.documents.get(&params.text_document.uri)?;
+        let text = document.db.text();
+        let index = LineIndex::new(&text);
+        let offset = index.offset(params.position);
+        let (symbol, definition) = document.analysis.symbols.at(offset)?;
+        let ty = definition.ty.as_ref()?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::PlainText,
+                value: format!("{}: {}", definition.name, ty),
+            }),
+            range: Some(index.range(symbol.span.clone())),
+        })
+    }
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let params = params.text_document_position_params;
+        let document = self.documents.get(&params.text_document.uri)?;
+        let text = document.db.text();
+        let index = LineIndex::new(&text);
+        let offset = index.offset(params.position);
+        let (_, definition) = document.analysis.symbols.at(offset)?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            params.text_document.uri,
+            index.range(definition.span.clone()),
+        )))
+    }
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let params = params.text_document_position;
+        let document = self.documents.get(&params.text_document.uri)?;
+        let text = document.db.text();
+        let offset = LineIndex::new(&text).offset(params.position);
+        let items = field_completions(&text, offset, &document.typed_symbols)
+            .into_iter()
+            .map(|(name, ty)| CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::FIELD),
+                detail: Some(ty.to_string()),
+                ..CompletionItem::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+    fn request(&mut self, request: Request) -> Result<Response, Box<dyn Error>> {
+        let request = match request.extract(HoverRequest::METHOD) {
+            Ok((id, params)) => return Ok(Response::new_ok(id, self.hover(params))),
+            Err(ExtractError::MethodMismatch(request)) => request,
+            Err(err) => return Err(err.into()),
+        };
+        let request = match request.extract(GotoDefinition::METHOD) {
+            Ok((id, params)) => return Ok(Response::new_ok(id, self.definition(params))),
+            Err(ExtractError::MethodMismatch(request)) => request,
+            Err(err) => return Err(err.into()),
+        };
+        let request = match request.extract(Completion::METHOD) {
+            Ok((id, params)) => return Ok(Response::new_ok(id, self.completion(params))),
+            Err(ExtractError::MethodMismatch(request)) => request,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Response::new_err(
+            request.id,
+            lsp_server::ErrorCode::MethodNotFound as i32,
+            format!("unknown method {}", request.method),
+        ))
+    }
+}
+fn main() -> Result<(), Box<dyn Error>> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![".".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    let mut server = Server::default();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                let response = server.request(request)?;
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                server.notification(&connection, notification)?;
+            }
+            Message::Response(_) => (),
+        }
+    }
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
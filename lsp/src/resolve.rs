@@ -0,0 +1,784 @@
+use hir::{
+    expr::{
+        Arg, Assign, Binary, Block, Bound, Break, Call, Condition, ControlFlow, Element, Entry,
+        Expr, Field, FieldAccess, For, Fun, Handle, Handler, If, Index, Jump, Loop, Match,
+        MatchArm, MatchCondition, Module, PlaceExpr, Range, Record, RecordWithSplat, Slice, Tag,
+        Tuple, TupleWithSplat, Unary, While, Yield,
+    },
+    pattern::{
+        AsPattern, ListPattern, ListWithRest, NewTypePattern, Pattern, RecordPattern,
+        TaggedPattern, Var,
+    },
+    statement::{Declare, FunDeclare, NewType, Statement, TypeAnnotation},
+    Atom,
+};
+use std::{collections::HashMap, mem::replace};
+use syntax::{SyntaxKind, SyntaxNode, SyntaxToken};
+use type_system::Type;
+
+// the HIR doesn't carry spans, so names are aligned with the identifiers of
+// the syntax tree by walking the HIR in source order
+
+pub trait Annotation {
+    fn ty(&self) -> Option<&Type>;
+}
+impl Annotation for () {
+    fn ty(&self) -> Option<&Type> {
+        None
+    }
+}
+impl Annotation for Type {
+    fn ty(&self) -> Option<&Type> {
+        Some(self)
+    }
+}
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub name: Atom,
+    pub span: std::ops::Range<usize>,
+    pub ty: Option<Type>,
+}
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub span: std::ops::Range<usize>,
+    pub definition: usize,
+}
+#[derive(Debug, Clone, Default)]
+pub struct Symbols {
+    pub definitions: Vec<Definition>,
+    pub symbols: Vec<Symbol>,
+}
+impl Symbols {
+    pub fn at(&self, offset: usize) -> Option<(&Symbol, &Definition)> {
+        self.symbols
+            .iter()
+            .find(|symbol| symbol.span.start <= offset && offset <= symbol.span.end)
+            .map(|symbol| (symbol, &self.definitions[symbol.definition]))
+    }
+}
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum NameKind {
+    // a variable being bound or used
+    Var,
+    // name after `mod`, the module being declared or loaded
+    Module,
+}
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Name {
+    name: Atom,
+    kind: NameKind,
+    span: std::ops::Range<usize>,
+}
+// raw identifiers span their name only
+fn name(token: &SyntaxToken) -> Name {
+    let text = token.text();
+    let start = usize::from(token.text_range().start());
+    let name = text.trim_matches('`');
+    let offset = start + text.len() - text.trim_start_matches('`').len();
+    let kind = match token.parent().map(|node| node.kind()) {
+        Some(SyntaxKind::ModuleDeclare | SyntaxKind::ModuleExpr) => NameKind::Module,
+        _ => NameKind::Var,
+    };
+    Name {
+        name: Atom::from(name),
+        kind,
+        span: offset..offset + name.len(),
+    }
+}
+// identifiers naming variables, newtypes, effects or modules in source order,
+// record labels, fields, tags and module paths aren't
+fn names(root: &SyntaxNode) -> Vec<Name> {
+    root.descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| {
+            token.kind() == SyntaxKind::Ident
+                && token.parent().is_some_and(|node| {
+                    !matches!(
+                        node.kind(),
+                        SyntaxKind::Field
+                            | SyntaxKind::FieldPattern
+                            | SyntaxKind::FieldAccess
+                            | SyntaxKind::TagExpr
+                            | SyntaxKind::TagPattern
+                            | SyntaxKind::Visibility
+                    )
+                })
+        })
+        .map(|token| name(&token))
+        .collect()
+}
+pub fn resolve<T>(root: &SyntaxNode, statements: &[Statement<T>]) -> Symbols
+where
+    T: Annotation,
+{
+    let names = names(root);
+    let mut resolver = Resolver {
+        claimed: vec![false; names.len()],
+        names,
+        cursor: 0,
+        scopes: vec![HashMap::new()],
+        symbols: Symbols::default(),
+    };
+    for statement in statements {
+        resolver.statement(statement);
+    }
+    resolver.symbols
+}
+struct Resolver {
+    names: Vec<Name>,
+    claimed: Vec<bool>,
+    cursor: usize,
+    scopes: Vec<HashMap<Atom, usize>>,
+    symbols: Symbols,
+}
+impl Resolver {
+    // module names are claimed as declared variables too as these are
+    // declared with `mod name;`
+    fn claim(&mut self, from: usize, name: &Atom, allow_module: bool) -> Option<usize> {
+        let i = (from..self.names.len()).find(|i| {
+            let found = &self.names[*i];
+            !self.claimed[*i]
+                && found.name == *name
+                && (found.kind == NameKind::Var || (allow_module && found.kind == NameKind::Module))
+        })?;
+        self.claimed[i] = true;
+        Some(i)
+    }
+    // effect names aren't variables, they're passed over so a variable of
+    // the same name isn't aligned with them
+    fn skip(&mut self, name: &Atom) {
+        if let Some(i) = self.claim(self.cursor, name, false) {
+            self.cursor = i + 1;
+        }
+    }
+    fn define(&mut self, from: usize, name: &Atom, ty: Option<&Type>, allow_module: bool) {
+        let Some(i) = self.claim(from, name, allow_module) else {
+            return;
+        };
+        let span = self.names[i].span.clone();
+        let definition = self.symbols.definitions.len();
+        self.symbols.definitions.push(Definition {
+            name: name.clone(),
+            span: span.clone(),
+            ty: ty.cloned(),
+        });
+        self.symbols.symbols.push(Symbol { span, definition });
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.clone(), definition);
+        self.cursor = self.cursor.max(i + 1);
+    }
+    fn use_var(&mut self, name: &Atom) {
+        let Some(i) = self.claim(self.cursor, name, false) else {
+            return;
+        };
+        self.cursor = i + 1;
+        let definition = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied());
+        if let Some(definition) = definition {
+            let span = self.names[i].span.clone();
+            self.symbols.symbols.push(Symbol { span, definition });
+        }
+    }
+    fn scoped(&mut self, body: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        body(self);
+        self.scopes.pop();
+    }
+    // names of patterns are searched from where the pattern starts as the
+    // fields of record patterns are not kept in source order, the bindings
+    // are brought into scope only once `insert` is called
+    fn pattern<T>(&mut self, pattern: &Pattern<T>) -> Vec<(Atom, usize)>
+    where
+        T: Annotation,
+    {
+        let mut vars = Vec::new();
+        let mut pinned = Vec::new();
+        collect_vars(pattern, &mut vars, &mut pinned);
+        // the newtype pattern names its constructor before its fields
+        if let Pattern::NewType(NewTypePattern { name, fields: _ }) = pattern {
+            self.use_var(name);
+        }
+        let allow_module = matches!(pattern, Pattern::Var(_));
+        let start = self.cursor;
+        // the bindings of the pattern aren't in scope of its expressions
+        for expr in pinned {
+            self.expr(expr);
+        }
+        let mut bindings = Vec::new();
+        self.scopes.push(HashMap::new());
+        for var in vars {
+            self.define(start, &var.ident, var.ty.ty(), allow_module);
+        }
+        bindings.extend(self.scopes.pop().unwrap());
+        bindings
+    }
+    fn insert(&mut self, bindings: Vec<(Atom, usize)>) {
+        self.scopes.last_mut().unwrap().extend(bindings);
+    }
+    fn statement<T>(&mut self, statement: &Statement<T>)
+    where
+        T: Annotation,
+    {
+        match statement {
+            Statement::Declare(Declare {
+                visibility: _,
+                pattern,
+                expr,
+                else_part,
+            }) => {
+                let bindings = self.pattern(pattern);
+                self.expr(expr);
+                if let Some(else_part) = else_part {
+                    self.block(else_part);
+                }
+                self.insert(bindings);
+            }
+            Statement::FunDeclare(FunDeclare {
+                visibility: _,
+                ident,
+                fun,
+                ty,
+            }) => {
+                let start = self.cursor;
+                self.define(start, ident, ty.ty(), false);
+                self.fun(fun);
+            }
+            Statement::NewType(NewType {
+                visibility: _,
+                ident,
+                fields,
+                ty,
+            }) => {
+                let start = self.cursor;
+                self.define(start, ident, ty.ty(), false);
+                for field in fields.iter() {
+                    // private fields are `#name` tokens, these aren't collected
+                    if !field.name.starts_with('#') {
+                        if let Some(i) = self.claim(self.cursor, &field.name, false) {
+                            self.cursor = i + 1;
+                        }
+                    }
+                    self.annotation(&field.ty);
+                }
+            }
+            Statement::Expr(expr) => self.expr(expr),
+        }
+    }
+    // names of newtypes within a type annotation refer to their declaration
+    fn annotation(&mut self, annotation: &TypeAnnotation) {
+        match annotation {
+            TypeAnnotation::Infer => (),
+            TypeAnnotation::Array(ty) | TypeAnnotation::Ref(_, ty) => self.annotation(ty),
+            TypeAnnotation::Named(name, args) => {
+                self.use_var(name);
+                for arg in args.iter() {
+                    self.annotation(arg);
+                }
+            }
+        }
+    }
+    fn fun<T>(&mut self, fun: &Fun<T>)
+    where
+        T: Annotation,
+    {
+        self.scoped(|resolver| {
+            let start = resolver.cursor;
+            for param in fun.param.iter() {
+                resolver.define(start, &param.ident, param.ty.ty(), false);
+            }
+            resolver.expr(&fun.body);
+        });
+    }
+    fn arg<T>(&mut self, arg: &Arg<T>)
+    where
+        T: Annotation,
+    {
+        match arg {
+            Arg::Unit => (),
+            Arg::Splat(expr) => self.expr(expr),
+            Arg::Record(record) => self.record(record),
+            Arg::Tuple(tuple) => self.tuple(tuple),
+        }
+    }
+    fn block<T>(&mut self, block: &Block<T>)
+    where
+        T: Annotation,
+    {
+        self.scoped(|resolver| {
+            for statement in block.statement.iter() {
+                resolver.statement(statement);
+            }
+            if let Some(expr) = &block.expr {
+                resolver.expr(expr);
+            }
+        });
+    }
+    fn exprs<T>(&mut self, exprs: &[Expr<T>])
+    where
+        T: Annotation,
+    {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+    fn fields<T>(&mut self, fields: &[Field<T>])
+    where
+        T: Annotation,
+    {
+        for field in fields {
+            self.expr(&field.expr);
+        }
+    }
+    fn range<T>(&mut self, range: &Range<T>)
+    where
+        T: Annotation,
+    {
+        for Bound { kind: _, expr } in [&range.left, &range.right].into_iter().flatten() {
+            self.expr(expr);
+        }
+    }
+    fn record<T>(&mut self, record: &Record<T>)
+    where
+        T: Annotation,
+    {
+        match record {
+            Record::Record(fields) => self.fields(fields),
+            Record::RecordWithSplat(RecordWithSplat { left, splat, right }) => {
+                self.fields(left);
+                self.expr(splat);
+                self.fields(right);
+            }
+        }
+    }
+    fn tuple<T>(&mut self, tuple: &Tuple<T>)
+    where
+        T: Annotation,
+    {
+        match tuple {
+            Tuple::Tuple(exprs) => self.exprs(exprs),
+            Tuple::TupleWithSplat(TupleWithSplat { left, splat, right }) => {
+                self.exprs(left);
+                self.expr(splat);
+                self.exprs(right);
+            }
+        }
+    }
+    fn place<T>(&mut self, place: &PlaceExpr<T>)
+    where
+        T: Annotation,
+    {
+        match place {
+            PlaceExpr::Var(var) => self.use_var(var),
+            PlaceExpr::FieldAccess(FieldAccess { expr, name: _ }) => self.expr(expr),
+            PlaceExpr::Index(Index { expr, index }) => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            PlaceExpr::Slice(Slice { expr, range }) => {
+                self.expr(expr);
+                self.range(range);
+            }
+            PlaceExpr::Deref(expr) | PlaceExpr::Len(expr) => self.expr(expr),
+        }
+    }
+    // the bindings of `expr =: pattern` are only in scope of the body
+    fn conditional_block<T>(&mut self, condition: &Condition<T>, body: &Block<T>)
+    where
+        T: Annotation,
+    {
+        match condition {
+            Condition::Bool(expr) => {
+                self.expr(expr);
+                self.block(body);
+            }
+            Condition::Match(MatchCondition { expr, pattern }) => {
+                self.expr(expr);
+                let bindings = self.pattern(pattern);
+                self.scoped(|resolver| {
+                    resolver.insert(bindings);
+                    resolver.block(body);
+                });
+            }
+        }
+    }
+    fn control_flow<T>(&mut self, control_flow: &ControlFlow<T>)
+    where
+        T: Annotation,
+    {
+        match control_flow {
+            ControlFlow::Block(block)
+            | ControlFlow::Loop(Loop {
+                label: _,
+                body: block,
+            }) => self.block(block),
+            ControlFlow::If(If {
+                condition,
+                body,
+                else_part,
+            }) => {
+                self.conditional_block(condition, body);
+                if let Some(else_part) = else_part {
+                    self.control_flow(else_part);
+                }
+            }
+            ControlFlow::For(For {
+                label: _,
+                pattern,
+                expr,
+                body,
+            }) => {
+                let bindings = self.pattern(pattern);
+                self.expr(expr);
+                self.scoped(|resolver| {
+                    resolver.insert(bindings);
+                    resolver.block(body);
+                });
+            }
+            ControlFlow::While(While {
+                label: _,
+                condition,
+                body,
+            }) => self.conditional_block(condition, body),
+            ControlFlow::Match(Match { expr, arm }) => {
+                self.expr(expr);
+                for MatchArm { pattern, expr } in arm.iter() {
+                    self.scoped(|resolver| {
+                        let bindings = resolver.pattern(pattern);
+                        resolver.insert(bindings);
+                        resolver.expr(expr);
+                    });
+                }
+            }
+        }
+    }
+    fn expr<T>(&mut self, expr: &Expr<T>)
+    where
+        T: Annotation,
+    {
+        match expr {
+            Expr::Literal(_) | Expr::Unit | Expr::Jump(Jump::Continue(_)) => (),
+            Expr::Tag(Tag { tag: _, expr }) => {
+                if let Some(expr) = expr {
+                    self.expr(expr);
+                }
+            }
+            // places of parallel assignments are all written before the values
+            Expr::Assign(assigns) => {
+                for Assign { place, expr: _ } in assigns.iter() {
+                    self.place(place);
+                }
+                for Assign { place: _, expr } in assigns.iter() {
+                    self.expr(expr);
+                }
+            }
+            Expr::Array(elements) => {
+                for Element { expr, kind: _ } in elements.iter() {
+                    self.expr(expr);
+                }
+            }
+            Expr::ArrayRange(range) => self.range(range),
+            Expr::Dict(entries) => {
+                for Entry { key, value } in entries.iter() {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Set(elements) => {
+                for expr in elements.iter() {
+                    self.expr(expr);
+                }
+            }
+            Expr::Splat(expr) => self.expr(expr),
+            Expr::Record(record) => self.record(record),
+            Expr::Tuple(tuple) => self.tuple(tuple),
+            Expr::Unary(Unary { kind: _, expr }) => self.expr(expr),
+            Expr::Binary(Binary {
+                kind: _,
+                left,
+                right,
+            }) => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Place(place) => self.place(place),
+            Expr::Call(Call { expr, arg }) => {
+                self.expr(expr);
+                self.arg(arg);
+            }
+            Expr::Yield(Yield { effect, arg }) => {
+                self.skip(effect);
+                self.arg(arg);
+            }
+            Expr::Handle(Handle { expr, handler }) => {
+                self.expr(expr);
+                for Handler {
+                    effect,
+                    param,
+                    body,
+                } in handler.iter()
+                {
+                    self.skip(effect);
+                    self.scoped(|resolver| {
+                        let start = resolver.cursor;
+                        for param in param.iter() {
+                            resolver.define(start, &param.ident, param.ty.ty(), false);
+                        }
+                        resolver.expr(body);
+                    });
+                }
+            }
+            Expr::ControlFlow(control_flow) => self.control_flow(control_flow),
+            Expr::Fun(fun) => self.fun(fun),
+            Expr::Jump(Jump::Break(Break { label: _, expr }) | Jump::Return(expr)) => {
+                if let Some(expr) = expr {
+                    self.expr(expr);
+                }
+            }
+            // the name in `math = mod math`, it's already claimed with
+            // `mod math;` as the declared variable
+            Expr::Module(Module::File(name)) => {
+                let token = self.names.get(self.cursor);
+                if token.is_some_and(|token| token.kind == NameKind::Module && token.name == *name)
+                    && !self.claimed[self.cursor]
+                {
+                    self.claimed[self.cursor] = true;
+                    self.cursor += 1;
+                }
+            }
+            // modules don't see the scope they're in
+            Expr::Module(Module::Inline(statements)) => {
+                let scopes = replace(&mut self.scopes, vec![HashMap::new()]);
+                for statement in statements.iter() {
+                    self.statement(statement);
+                }
+                self.scopes = scopes;
+            }
+        }
+    }
+}
+// the expressions of `==` patterns are collected too, these are uses
+fn collect_vars<'a, T>(
+    pattern: &'a Pattern<T>,
+    vars: &mut Vec<&'a Var<T>>,
+    pinned: &mut Vec<&'a Expr<T>>,
+) {
+    match pattern {
+        Pattern::True | Pattern::False | Pattern::UInt(_) | Pattern::Int(_) | Pattern::Ignore => (),
+        Pattern::Var(var) => vars.push(var),
+        Pattern::Record(RecordPattern { fields, rest }) => {
+            for pattern in fields.values() {
+                collect_vars(pattern, vars, pinned);
+            }
+            if let Some(rest) = rest {
+                collect_vars(rest, vars, pinned);
+            }
+        }
+        Pattern::Tuple(list) | Pattern::Array(list) => match list {
+            ListPattern::List(list) => {
+                for pattern in list.iter() {
+                    collect_vars(pattern, vars, pinned);
+                }
+            }
+            ListPattern::ListWithRest(ListWithRest { left, rest, right }) => {
+                for pattern in left.iter() {
+                    collect_vars(pattern, vars, pinned);
+                }
+                collect_vars(rest, vars, pinned);
+                for pattern in right.iter() {
+                    collect_vars(pattern, vars, pinned);
+                }
+            }
+        },
+        Pattern::Tag(TaggedPattern { tag: _, pattern }) => {
+            if let Some(pattern) = pattern {
+                collect_vars(pattern, vars, pinned);
+            }
+        }
+        Pattern::Ref(pattern) => collect_vars(pattern, vars, pinned),
+        Pattern::NewType(NewTypePattern { name: _, fields }) => {
+            for pattern in fields.fields.values() {
+                collect_vars(pattern, vars, pinned);
+            }
+        }
+        // every alternative binds the same names
+        Pattern::Or(patterns) => {
+            collect_vars(&patterns[0], vars, pinned);
+            for pattern in patterns[1..].iter() {
+                collect_vars(pattern, &mut Vec::new(), pinned);
+            }
+        }
+        Pattern::Range(_) => (),
+        Pattern::As(AsPattern { pattern, var }) => {
+            collect_vars(pattern, vars, pinned);
+            vars.push(var);
+        }
+        Pattern::Equal(expr) => pinned.push(expr),
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::{names, resolve, NameKind};
+    use hir::statement::Statement;
+
+    fn definitions(src: &str) -> Vec<(&str, &str)> {
+        let parse = syntax::parse(src);
+        let statements: Vec<Statement<()>> = parse.lower().unwrap();
+        let symbols = resolve(&parse.syntax(), &statements);
+        symbols
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let definition = &symbols.definitions[symbol.definition];
+                (&src[symbol.span.clone()], &src[definition.span.start..])
+            })
+            .collect()
+    }
+    #[test]
+    fn name_kinds() {
+        let src =
+            "foo = (bar = @baz rec.qux, = 'x'); mod math; pub(a.b) c = p.#d; `loop` = mod io;";
+        let found: Vec<_> = names(&syntax::parse(src).syntax())
+            .into_iter()
+            .map(|name| (&src[name.span], name.kind))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("foo", NameKind::Var),
+                ("rec", NameKind::Var),
+                ("math", NameKind::Module),
+                ("c", NameKind::Var),
+                ("p", NameKind::Var),
+                ("loop", NameKind::Var),
+                ("io", NameKind::Module),
+            ],
+        );
+    }
+    #[test]
+    fn shadowing() {
+        let src = "x = 1; x = x + 1; x;";
+        let found: Vec<_> = definitions(src)
+            .into_iter()
+            .map(|(name, definition)| (name, src.len() - definition.len()))
+            .collect();
+        assert_eq!(found, [("x", 0), ("x", 7), ("x", 0), ("x", 7)]);
+    }
+    #[test]
+    fn labels_and_fields() {
+        let src = "(foo = bar) = (foo = 1); baz(foo) => bar.foo; baz(= bar);";
+        let found: Vec<_> = definitions(src)
+            .into_iter()
+            .map(|(name, definition)| (name, src.len() - definition.len()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("bar", 7),
+                ("baz", 25),
+                ("foo", 29),
+                ("bar", 7),
+                ("baz", 25),
+                ("bar", 7),
+            ],
+        );
+    }
+    #[test]
+    fn modules() {
+        let src = "mod math { pi = 3; double(x) => x * pi; } pi = math.pi; mod io; io = mod io;";
+        let found: Vec<_> = definitions(src)
+            .into_iter()
+            .map(|(name, definition)| (name, src.len() - definition.len()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("math", 4),
+                ("pi", 11),
+                ("double", 19),
+                ("x", 26),
+                ("x", 26),
+                ("pi", 11),
+                ("pi", 42),
+                ("math", 4),
+                ("io", 60),
+                ("io", 64),
+            ],
+        );
+    }
+    #[test]
+    fn newtypes() {
+        let src =
+            "newtype Meters(value); newtype Run(value: Meters); Run(value = run) = Run(value = 1);";
+        let found: Vec<_> = definitions(src)
+            .into_iter()
+            .map(|(name, definition)| (name, src.len() - definition.len()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Meters", 8),
+                ("Run", 31),
+                ("Meters", 8),
+                ("Run", 31),
+                ("run", 63),
+                ("Run", 31),
+            ],
+        );
+    }
+    #[test]
+    fn or_as_patterns() {
+        let src = "y = match x { @a n | @b n => n, 1..3 as m => m };";
+        let found: Vec<_> = definitions(src)
+            .into_iter()
+            .map(|(name, definition)| (name, src.len() - definition.len()))
+            .collect();
+        assert_eq!(
+            found,
+            [("y", 0), ("n", 17), ("n", 17), ("m", 40), ("m", 40)]
+        );
+    }
+    #[test]
+    fn equal_patterns() {
+        let src = "z = 1; y = match x { (a = == z, b = z) => z };";
+        let found: Vec<_> = definitions(src)
+            .into_iter()
+            .map(|(name, definition)| (name, src.len() - definition.len()))
+            .collect();
+        assert_eq!(found, [("z", 0), ("y", 7), ("z", 0), ("z", 36), ("z", 36)]);
+    }
+    #[test]
+    fn match_conditions() {
+        let src = "x = 1; while f(x) =: @val x { g(x); } h(x);";
+        let found: Vec<_> = definitions(src)
+            .into_iter()
+            .map(|(name, definition)| (name, src.len() - definition.len()))
+            .collect();
+        assert_eq!(found, [("x", 0), ("x", 0), ("x", 26), ("x", 26), ("x", 0)]);
+    }
+    #[test]
+    fn else_parts() {
+        let src = "x = 1; [x] = f(x) else { g(x) }; x;";
+        let found: Vec<_> = definitions(src)
+            .into_iter()
+            .map(|(name, definition)| (name, src.len() - definition.len()))
+            .collect();
+        assert_eq!(found, [("x", 0), ("x", 8), ("x", 0), ("x", 0), ("x", 8)]);
+    }
+    #[test]
+    fn raw_idents() {
+        let src = "`loop` = 1; /- loop -/ {- `loop` -} f(`in`) => `in` + `loop`;";
+        let found: Vec<_> = definitions(src)
+            .into_iter()
+            .map(|(name, definition)| (name, src.len() - definition.len()))
+            .collect();
+        assert_eq!(
+            found,
+            [("loop", 1), ("f", 36), ("in", 39), ("in", 39), ("loop", 1)]
+        );
+    }
+}
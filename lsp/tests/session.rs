@@ -0,0 +1,172 @@
+use lsp_server::{Message, Notification, Request, RequestId, Response};
+use serde_json::{json, Value};
+use std::{
+    io::BufReader,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+// scripted client talking to the server binary over stdio
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    id: i32,
+}
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_butter-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Self {
+            child,
+            stdin,
+            stdout,
+            id: 0,
+        };
+        let response = client.request("initialize", json!({ "capabilities": {} }));
+        assert!(response["capabilities"]["hoverProvider"].as_bool().unwrap());
+        client.notify("initialized", json!({}));
+        client
+    }
+    fn send(&mut self, message: Message) {
+        message.write(&mut self.stdin).unwrap();
+    }
+    fn receive(&mut self) -> Message {
+        Message::read(&mut self.stdout).unwrap().unwrap()
+    }
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(Message::Notification(Notification::new(
+            method.to_string(),
+            params,
+        )));
+    }
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.id += 1;
+        let id = RequestId::from(self.id);
+        self.send(Message::Request(Request::new(
+            id.clone(),
+            method.to_string(),
+            params,
+        )));
+        loop {
+            if let Message::Response(Response {
+                id: response_id,
+                result,
+                error,
+            }) = self.receive()
+            {
+                assert_eq!(response_id, id);
+                assert!(error.is_none(), "{:?}", error);
+                return result.unwrap_or(Value::Null);
+            }
+        }
+    }
+    fn diagnostics(&mut self) -> Value {
+        loop {
+            if let Message::Notification(notification) = self.receive() {
+                if notification.method == "textDocument/publishDiagnostics" {
+                    return notification.params["diagnostics"].clone();
+                }
+            }
+        }
+    }
+    fn open(&mut self, text: &str) -> Value {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": URI,
+                    "languageId": "butter",
+                    "version": 0,
+                    "text": text,
+                },
+            }),
+        );
+        self.diagnostics()
+    }
+    fn change(&mut self, version: i32, text: &str) -> Value {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": URI, "version": version },
+                "contentChanges": [{ "text": text }],
+            }),
+        );
+        self.diagnostics()
+    }
+    fn at(&mut self, method: &str, line: u32, character: u32) -> Value {
+        self.request(
+            method,
+            json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character },
+            }),
+        )
+    }
+    fn shutdown(mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+const URI: &str = "file:///main.butter";
+
+#[test]
+fn session() {
+    let mut client = Client::start();
+    let src = "\
+id(x) => x;
+rec = (foo = id(1), bar = true);
+num = rec.foo;
+";
+    assert_eq!(client.open(src), json!([]));
+
+    let hover = client.at("textDocument/hover", 0, 0);
+    assert_eq!(hover["contents"]["value"], "id: (x: a) -> a");
+    let hover = client.at("textDocument/hover", 2, 7);
+    assert_eq!(hover["contents"]["value"], "rec: {bar: Bool, foo: Num}");
+    let hover = client.at("textDocument/hover", 2, 0);
+    assert_eq!(hover["contents"]["value"], "num: Num");
+    assert_eq!(client.at("textDocument/hover", 1, 9), Value::Null);
+
+    let definition = client.at("textDocument/definition", 2, 7);
+    assert_eq!(
+        definition["range"],
+        json!({
+            "start": { "line": 1, "character": 0 },
+            "end": { "line": 1, "character": 3 },
+        }),
+    );
+    let definition = client.at("textDocument/definition", 1, 13);
+    assert_eq!(
+        definition["range"]["start"],
+        json!({ "line": 0, "character": 0 })
+    );
+
+    let diagnostics = client.change(1, &format!("{}num.", src));
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 3);
+    let completion = client.at("textDocument/completion", 2, 10);
+    let labels: Vec<_> = completion
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert_eq!(labels, ["bar", "foo"]);
+
+    let diagnostics = client.change(2, "foo = 1;\nbar = foo + true;\n");
+    assert_eq!(diagnostics[0]["message"], "mismatching types");
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({
+            "start": { "line": 1, "character": 0 },
+            "end": { "line": 1, "character": 17 },
+        }),
+    );
+    client.shutdown();
+}
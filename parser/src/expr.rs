@@ -1,13 +1,14 @@
 use crate::{
     expr::{
         array::{array, range},
-        infix::{expr_0, expr_6, infix_expr_op},
+        dict::{dict, set},
+        infix::{expr_0, expr_7, infix_expr_op},
         integer::integer_u64,
         record::record,
         string::{char_literal, string_literal},
         tuple::tuple,
     },
-    ident_keyword::{ident, keyword},
+    ident_keyword::{ident, keyword, label},
     lex,
     pattern::parameter,
 };
@@ -16,15 +17,23 @@ use combine::{
     parser::char::{char, string},
     value, ParseError, Parser, Stream,
 };
-use hir::expr::{Element, ElementKind, Expr, Fun, Jump, Literal, PlaceExpr, Tag, Unary, UnaryType};
+use hir::{
+    expr::{
+        Break, Element, ElementKind, Expr, Fun, Jump, Literal, Module, PlaceExpr, Tag, Unary,
+        UnaryType,
+    },
+    keyword,
+};
 
-mod array;
+pub(crate) mod array;
 pub(crate) mod control_flow;
+mod dict;
+mod effect;
 mod float;
 mod infix;
 pub(crate) mod integer;
 mod record;
-mod string;
+pub(crate) mod string;
 mod tuple;
 
 fn literal<I>() -> impl Parser<I, Output = Literal>
@@ -49,9 +58,16 @@ where
 {
     choice((
         lex(keyword("break"))
-            .with(optional(expr(0)))
-            .map(|expr| Jump::Break(expr.map(Box::new))),
-        lex(keyword("continue")).with(value(Jump::Continue)),
+            .with((optional(lex(label())), optional(expr(0))))
+            .map(|(label, expr)| {
+                Jump::Break(Break {
+                    label,
+                    expr: expr.map(Box::new),
+                })
+            }),
+        lex(keyword("continue"))
+            .with(optional(lex(label())))
+            .map(Jump::Continue),
         lex(keyword("return"))
             .with(optional(expr(0)))
             .map(|expr| Jump::Return(expr.map(Box::new))),
@@ -72,7 +88,7 @@ where
             attempt(keyword("clone")).with(value(UnaryType::Clone)),
         ))
     };
-    (lex(kind()), expr(6)).map(|(kind, expr)| Unary {
+    (lex(kind()), expr(7)).map(|(kind, expr)| Unary {
         kind,
         expr: Box::new(expr),
     })
@@ -84,7 +100,7 @@ where
     T: Default + Clone,
 {
     lex(char('@'))
-        .with((lex(ident()), optional(expr(6))))
+        .with((lex(ident()), optional(expr(7))))
         .map(|(tag, expr)| Tag {
             tag,
             expr: expr.map(Box::new),
@@ -134,6 +150,24 @@ where
         record().map(Expr::Record),
     ))
 }
+// an element of a tuple or a record, call arguments may also be `?`
+// placeholders of a partial application, parsed as variables named `?`
+pub(crate) fn element<T, I, const PLACEHOLDER: bool>() -> impl Parser<I, Output = Expr<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    if PLACEHOLDER {
+        choice((
+            lex(char('?')).map(|_| Expr::Place(PlaceExpr::Var(keyword!("?")))),
+            expr(0),
+        ))
+        .left()
+    } else {
+        expr(0).right()
+    }
+}
 fn prefix_expr_<T, I>() -> impl Parser<I, Output = Expr<T>>
 where
     I: Stream<Token = char>,
@@ -144,6 +178,8 @@ where
         fun().map(Expr::Fun),
         tuple_record_group(),
         array_range(),
+        dict().map(Expr::Dict),
+        set().map(Expr::Set),
         lex(string_literal()).map(|vec| {
             let vec = vec
                 .into_iter()
@@ -156,10 +192,14 @@ where
         }),
         unary().map(Expr::Unary),
         tag().map(Expr::Tag),
+        attempt(lex(keyword("mod")))
+            .with(lex(ident()))
+            .map(|name| Expr::Module(Module::File(name))),
         attempt(lex(ident())).map(|ident| Expr::Place(PlaceExpr::Var(ident))),
         control_flow::control_flow().map(Expr::ControlFlow),
         lex(literal()).map(Expr::Literal),
         jump().map(Expr::Jump),
+        effect::effect(),
     ))
 }
 combine::parser! {
@@ -180,11 +220,11 @@ where
 {
     match precedence {
         0 => expr_0().left().left(),
-        1..=5 => chainl1(expr(precedence + 1), lex(infix_expr_op(precedence)))
+        1..=6 => chainl1(expr(precedence + 1), lex(infix_expr_op(precedence)))
             .right()
             .left(),
-        6 => expr_6().left().right(),
-        7.. => prefix_expr().right().right(),
+        7 => expr_7().left().right(),
+        8.. => prefix_expr().right().right(),
     }
 }
 combine::parser! {
@@ -204,7 +244,14 @@ mod test {
         test::{var_expr, var_place},
     };
     use combine::EasyParser;
-    use hir::expr::{Assign, Binary, BinaryType};
+    use hir::{
+        expr::{
+            Arg, Assign, Binary, BinaryType, Call, Entry, Field, Fun, Handle, Handler, Literal,
+            Record, Tuple, Yield,
+        },
+        pattern::Var,
+        Atom,
+    };
 
     #[test]
     fn group() {
@@ -260,7 +307,7 @@ mod test {
         let src = "foo + bar";
         let expected: Expr<()> = var_expr("foo");
         let left = "+ bar";
-        assert_eq!(expr(6).easy_parse(src), Ok((expected, left)));
+        assert_eq!(expr(7).easy_parse(src), Ok((expected, left)));
     }
     #[test]
     fn ignore_range() {
@@ -269,4 +316,133 @@ mod test {
         let left = "..";
         assert_eq!(expr(0).easy_parse(src), Ok((expected, left)));
     }
+    #[test]
+    fn pipe() {
+        let src = "foo | bar |> baz";
+        let expected = Expr::Binary(Binary {
+            kind: BinaryType::Pipe,
+            left: Box::new(Expr::Binary(Binary {
+                kind: BinaryType::Or,
+                left: Box::new(var_expr("foo")),
+                right: Box::new(var_expr("bar")),
+            })),
+            right: Box::new(var_expr("baz")),
+        });
+        assert_eq!(expr(0).easy_parse(src), Ok((expected, "")));
+    }
+    #[test]
+    fn partial_application() {
+        let param = |ident: &str| Var {
+            ident: Atom::from(ident),
+            mutable: false,
+            bind_to_ref: false,
+            ty: (),
+        };
+        let src = "foo(?, bar, ?)";
+        let expected = Expr::Fun(Fun {
+            param: vec![param("?0"), param("?1")].into(),
+            body: Box::new(Expr::Call(Call {
+                expr: Box::new(var_expr("foo")),
+                arg: Arg::Tuple(Tuple::Tuple(
+                    vec![var_expr("?0"), var_expr("bar"), var_expr("?1")].into(),
+                )),
+            })),
+        });
+        assert_eq!(expr(0).easy_parse(src), Ok((expected, "")));
+        let src = "foo(a = bar(?))";
+        let expected = Expr::Call(Call {
+            expr: Box::new(var_expr("foo")),
+            arg: Arg::Record(Record::Record(
+                vec![Field {
+                    name: Atom::from("a"),
+                    expr: Expr::Fun(Fun {
+                        param: vec![param("?0")].into(),
+                        body: Box::new(Expr::Call(Call {
+                            expr: Box::new(var_expr("bar")),
+                            arg: Arg::Tuple(Tuple::Tuple(vec![var_expr("?0")].into())),
+                        })),
+                    }),
+                }]
+                .into(),
+            )),
+        });
+        assert_eq!(expr(0).easy_parse(src), Ok((expected, "")));
+        assert!(expr::<(), _>(0).easy_parse("(?, foo)").is_err());
+        assert!(expr::<(), _>(0).easy_parse("foo(= ?)").is_err());
+        assert!(expr::<(), _>(0).easy_parse("foo(*?)").is_err());
+    }
+    #[test]
+    fn dict_set() {
+        let num = |num| Expr::Literal(Literal::UInt(num));
+        let src = "#(1 = foo, bar = 2,)";
+        let expected = Expr::Dict(
+            vec![
+                Entry {
+                    key: num(1),
+                    value: var_expr("foo"),
+                },
+                Entry {
+                    key: var_expr("bar"),
+                    value: num(2),
+                },
+            ]
+            .into(),
+        );
+        assert_eq!(expr(0).easy_parse(src), Ok((expected, "")));
+        let src = "#[foo, 1]";
+        let expected = Expr::Set(vec![var_expr("foo"), num(1)].into());
+        assert_eq!(expr(0).easy_parse(src), Ok((expected, "")));
+        assert_eq!(
+            expr::<(), _>(0).easy_parse("#()"),
+            Ok((Expr::Dict(Box::default()), ""))
+        );
+        assert!(expr::<(), _>(0).easy_parse("#(foo)").is_err());
+    }
+    #[test]
+    fn effect() {
+        let src = "do message() handling { item(text) => print(text), done() => { 1 } }";
+        let expected = Expr::Handle(Handle {
+            expr: Box::new(Expr::Call(Call {
+                expr: Box::new(var_expr("message")),
+                arg: Arg::Unit,
+            })),
+            handler: vec![
+                Handler {
+                    effect: Atom::from("item"),
+                    param: vec![Var {
+                        ident: Atom::from("text"),
+                        mutable: false,
+                        bind_to_ref: false,
+                        ty: (),
+                    }]
+                    .into(),
+                    body: Expr::Call(Call {
+                        expr: Box::new(var_expr("print")),
+                        arg: Arg::Tuple(Tuple::Tuple(vec![var_expr("text")].into())),
+                    }),
+                },
+                Handler {
+                    effect: Atom::from("done"),
+                    param: Box::default(),
+                    body: expr(0).easy_parse("{ 1 }").unwrap().0,
+                },
+            ]
+            .into(),
+        });
+        assert_eq!(expr(0).easy_parse(src), Ok((expected, "")));
+        let src = "yield item(text = foo)";
+        let expected = Expr::Yield(Yield {
+            effect: Atom::from("item"),
+            arg: Arg::Record(Record::Record(
+                vec![Field {
+                    name: Atom::from("text"),
+                    expr: var_expr("foo"),
+                }]
+                .into(),
+            )),
+        });
+        assert_eq!(expr(0).easy_parse(src), Ok((expected, "")));
+        assert!(expr::<(), _>(0).easy_parse("yield item(?)").is_err());
+        assert!(expr::<(), _>(0).easy_parse("yield item").is_err());
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
     expr::expr,
-    ident_keyword::keyword,
+    ident_keyword::{keyword, label},
     lex,
     pattern::pattern,
     statement::{statement_return, StatementReturn},
@@ -11,7 +11,9 @@ use combine::{
     value, ParseError, Parser, Stream,
 };
 use hir::{
-    expr::{Block, ControlFlow, Expr, For, If, Match, MatchArm, While},
+    expr::{
+        Block, Condition, ControlFlow, Expr, For, If, Loop, Match, MatchArm, MatchCondition, While,
+    },
     statement::Statement,
 };
 
@@ -46,7 +48,7 @@ impl<T> Extend<StatementReturn<T>> for StatementExpr<T> {
         }
     }
 }
-pub(crate) fn block<T, I>() -> impl Parser<I, Output = Block<T>>
+fn block_<T, I>() -> impl Parser<I, Output = Block<T>>
 where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
@@ -66,6 +68,34 @@ where
     })
     .expected("block")
 }
+combine::parser! {
+    pub(crate) fn block[T, I]()(I) -> Block<T>
+    where [
+        I: Stream<Token = char>,
+        I::Error: ParseError<I::Token, I::Range, I::Position>,
+        T: Default + Clone,
+    ] {
+        block_()
+    }
+}
+fn condition<T, I>() -> impl Parser<I, Output = Condition<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    (
+        expr(0),
+        optional(attempt(lex(string("=:"))).with(pattern())),
+    )
+        .map(|(expr, pattern)| match pattern {
+            Some(pattern) => Condition::Match(MatchCondition {
+                expr: Box::new(expr),
+                pattern,
+            }),
+            None => Condition::Bool(Box::new(expr)),
+        })
+}
 fn if_<T, I>() -> impl Parser<I, Output = If<T>>
 where
     I: Stream<Token = char>,
@@ -79,9 +109,9 @@ where
         )))
     };
     attempt(lex(keyword("if")))
-        .with((expr(0), block(), optional(else_part())))
+        .with((condition(), block(), optional(else_part())))
         .map(|(condition, body, else_part)| If {
-            condition: Box::new(condition),
+            condition,
             body,
             else_part: else_part.map(Box::new),
         })
@@ -105,6 +135,7 @@ where
     attempt(lex(keyword("for")))
         .with((pattern(), lex(keyword("in")), expr(0), block()))
         .map(|(pattern, _, expr, body)| For {
+            label: None,
             pattern,
             expr: Box::new(expr),
             body,
@@ -117,19 +148,40 @@ where
     T: Default + Clone,
 {
     attempt(lex(keyword("while")))
-        .with((expr(0), block()))
+        .with((condition(), block()))
         .map(|(condition, body)| While {
-            condition: Box::new(condition),
+            label: None,
+            condition,
             body,
         })
 }
-fn loop_expression<T, I>() -> impl Parser<I, Output = Block<T>>
+fn loop_expression<T, I>() -> impl Parser<I, Output = Loop<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    attempt(lex(keyword("loop")))
+        .with(block())
+        .map(|body| Loop { label: None, body })
+}
+// the expression of an arm after `=>`, the comma is optional after control
+// flow and before `}`
+pub(crate) fn arm_expr<T, I>() -> impl Parser<I, Output = Expr<T>>
 where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
     T: Default + Clone,
 {
-    attempt(lex(keyword("loop"))).with(block())
+    choice((
+        attempt(control_flow())
+            .skip(optional(lex(char(','))))
+            .map(Expr::ControlFlow),
+        expr(0).skip(choice((
+            lex(char(',')).with(value(())),
+            look_ahead(char('}')).with(value(())),
+        ))),
+    ))
 }
 fn match_expression<T, I>() -> impl Parser<I, Output = Match<T>>
 where
@@ -137,17 +189,6 @@ where
     I::Error: ParseError<I::Token, I::Range, I::Position>,
     T: Default + Clone,
 {
-    let arm_expr = || {
-        choice((
-            attempt(control_flow())
-                .skip(optional(lex(char(','))))
-                .map(Expr::ControlFlow),
-            expr(0).skip(choice((
-                lex(char(',')).with(value(())),
-                look_ahead(char('}')).with(value(())),
-            ))),
-        ))
-    };
     let arm = || {
         (pattern().skip(lex(string("=>"))), arm_expr())
             .map(|(pattern, expr)| MatchArm { pattern, expr })
@@ -170,7 +211,25 @@ where
     I::Error: ParseError<I::Token, I::Range, I::Position>,
     T: Default + Clone,
 {
+    let labelled = (
+        attempt(lex(label()).skip(lex(char(':')))),
+        choice((
+            for_expression().map(ControlFlow::For),
+            while_expression().map(ControlFlow::While),
+            loop_expression().map(ControlFlow::Loop),
+        )),
+    )
+        .map(|(label, mut control_flow)| {
+            match &mut control_flow {
+                ControlFlow::For(For { label: target, .. })
+                | ControlFlow::While(While { label: target, .. })
+                | ControlFlow::Loop(Loop { label: target, .. }) => *target = Some(label),
+                _ => unreachable!(),
+            }
+            control_flow
+        });
     choice((
+        labelled,
         block().map(ControlFlow::Block),
         if_expression().map(ControlFlow::If),
         for_expression().map(ControlFlow::For),
@@ -189,3 +248,105 @@ combine::parser! {
         control_flow_()
     }
 }
+#[cfg(test)]
+mod test {
+    use crate::{
+        expr::control_flow::control_flow,
+        test::{var_expr, var_place},
+    };
+    use combine::EasyParser;
+    use hir::{
+        expr::{
+            Block, Break, Condition, ControlFlow, Expr, FieldAccess, If, Jump, Literal, Loop,
+            MatchCondition, PlaceExpr, While,
+        },
+        pattern::{Pattern, TaggedPattern, Var},
+        Atom,
+    };
+
+    fn empty() -> Block<()> {
+        Block {
+            statement: Vec::new().into(),
+            expr: None,
+        }
+    }
+    #[test]
+    fn match_condition() {
+        let pattern = Pattern::Tag(TaggedPattern {
+            tag: Atom::from("val"),
+            pattern: Some(Box::new(Pattern::Var(Var {
+                ident: Atom::from("item"),
+                mutable: false,
+                bind_to_ref: false,
+                ty: (),
+            }))),
+        });
+        let expected = ControlFlow::While(While {
+            label: None,
+            condition: Condition::Match(MatchCondition {
+                expr: Box::new(Expr::Place(PlaceExpr::FieldAccess(FieldAccess {
+                    expr: Box::new(Expr::Place(var_place("queue"))),
+                    name: Atom::from("next"),
+                }))),
+                pattern,
+            }),
+            body: empty(),
+        });
+        assert_eq!(
+            control_flow().easy_parse("while queue.next =: @val item {}"),
+            Ok((expected, ""))
+        );
+        let expected = ControlFlow::If(If {
+            condition: Condition::Bool(Box::new(var_expr("done"))),
+            body: empty(),
+            else_part: None,
+        });
+        assert_eq!(control_flow().easy_parse("if done {}"), Ok((expected, "")));
+    }
+    #[test]
+    fn labels() {
+        let expected: ControlFlow<()> = ControlFlow::Loop(Loop {
+            label: Some(Atom::from("outer")),
+            body: Block {
+                statement: Vec::new().into(),
+                expr: Some(Box::new(Expr::Jump(Jump::Continue(Some(Atom::from(
+                    "outer",
+                )))))),
+            },
+        });
+        assert_eq!(
+            control_flow().easy_parse("'outer: loop { continue 'outer }"),
+            Ok((expected, ""))
+        );
+        let jump = |src| match control_flow().easy_parse(src) {
+            Ok((ControlFlow::Loop(Loop { label: None, body }), "")) => body.expr.map(|expr| *expr),
+            result => panic!("expected a loop, found {:?}", result),
+        };
+        let expected = Expr::Jump(Jump::Break(Break {
+            label: Some(Atom::from("a")),
+            expr: Some(Box::new(var_expr("b"))),
+        }));
+        assert_eq!(jump("loop { break 'a b }"), Some(expected));
+        // a char literal rather than a label
+        let expected = Expr::Jump(Jump::Break(Break {
+            label: None,
+            expr: Some(Box::new(Expr::Literal(Literal::UInt(97)))),
+        }));
+        assert_eq!(jump("loop { break 'a' }"), Some(expected));
+        assert!(control_flow::<(), _>()
+            .easy_parse("'a: if true {}")
+            .is_err());
+    }
+    #[test]
+    fn counted() {
+        let counted = |src| match control_flow::<(), _>().easy_parse(src) {
+            Ok((ControlFlow::For(for_loop), "")) => for_loop.counted().is_some(),
+            result => panic!("expected a for loop, found {:?}", result),
+        };
+        assert!(counted("for i in [0.<n] {}"));
+        assert!(counted("for i in [1..] { break; }"));
+        assert!(!counted("for i in [.<n] {}"));
+        assert!(!counted("for i in range {}"));
+        assert!(!counted("for i in [0, 1, 2] {}"));
+    }
+}
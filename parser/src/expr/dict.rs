@@ -0,0 +1,36 @@
+use crate::{expr::expr, lex};
+use combine::{
+    attempt, between, parser::char::char, parser::char::string, sep_end_by, ParseError, Parser,
+    Stream,
+};
+use hir::expr::{Entry, Expr};
+
+pub(crate) fn dict<T, I>() -> impl Parser<I, Output = Box<[Entry<T>]>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    let entry = || (expr(0), lex(char('=')).with(expr(0))).map(|(key, value)| Entry { key, value });
+    between(
+        lex(attempt(string("#("))),
+        lex(char(')')),
+        sep_end_by(entry(), lex(char(','))),
+    )
+    .map(Vec::into)
+    .expected("dict")
+}
+pub(crate) fn set<T, I>() -> impl Parser<I, Output = Box<[Expr<T>]>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    between(
+        lex(attempt(string("#["))),
+        lex(char(']')),
+        sep_end_by(expr(0), lex(char(','))),
+    )
+    .map(Vec::into)
+    .expected("set")
+}
@@ -0,0 +1,91 @@
+use crate::{
+    expr::{control_flow::arm_expr, expr, record::record, tuple::tuple},
+    ident_keyword::{ident, keyword},
+    lex,
+    pattern::parameter,
+};
+use combine::{
+    attempt, between, choice, many, optional,
+    parser::char::{char, string},
+    value, ParseError, Parser, Stream,
+};
+use hir::expr::{Arg, Expr, Handle, Handler, Yield};
+
+// the argument of an effect, like that of a call without placeholders
+fn arg<T, I>() -> impl Parser<I, Output = Arg<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    choice((
+        attempt((lex(char('(')), lex(char(')')))).with(value(Arg::Unit)),
+        attempt(between(
+            (lex(char('(')), lex(char('*'))),
+            (optional(lex(char(','))), lex(char(')'))),
+            expr(0),
+        ))
+        .map(|expr| Arg::Splat(Box::new(expr))),
+        attempt(tuple()).map(Arg::Tuple),
+        record().map(Arg::Record),
+    ))
+    .expected("argument")
+}
+fn yield_expr<T, I>() -> impl Parser<I, Output = Yield<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    attempt(lex(keyword("yield")))
+        .with((lex(ident()), arg()))
+        .map(|(effect, arg)| Yield { effect, arg })
+}
+fn handle<T, I>() -> impl Parser<I, Output = Handle<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    let handler = || {
+        (
+            lex(ident()),
+            parameter().skip(lex(string("=>"))),
+            arm_expr(),
+        )
+            .map(|(effect, param, body)| Handler {
+                effect,
+                param,
+                body,
+            })
+    };
+    let body = || {
+        between(lex(char('{')), lex(char('}')), many(handler()))
+            .map(Vec::into)
+            .expected("handlers")
+    };
+    attempt(lex(keyword("do")))
+        .with((expr(0), lex(keyword("handling")).with(body())))
+        .map(|(expr, handler)| Handle {
+            expr: Box::new(expr),
+            handler,
+        })
+}
+fn effect_<T, I>() -> impl Parser<I, Output = Expr<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    choice((yield_expr().map(Expr::Yield), handle().map(Expr::Handle)))
+}
+combine::parser! {
+    pub(crate) fn effect[T, I]()(I) -> Expr<T>
+    where [
+        I: Stream<Token = char>,
+        I::Error: ParseError<I::Token, I::Range, I::Position>,
+        T: Default + Clone,
+    ] {
+        effect_()
+    }
+}
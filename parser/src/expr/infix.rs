@@ -1,6 +1,6 @@
 use crate::{
-    expr::{array::range, expr, record::record, tuple::tuple},
-    ident_keyword::ident,
+    expr::{array::range, expr, record::arg_record, tuple::arg_tuple},
+    ident_keyword::field_name,
     lex,
 };
 use combine::{
@@ -31,7 +31,10 @@ pub(crate) enum PartialAst<T> {
     Deref,
     Len,
 }
-impl<T> PartialAst<T> {
+impl<T> PartialAst<T>
+where
+    T: Default,
+{
     pub(crate) fn combine_from(self, left: Expr<T>) -> Expr<T> {
         match self {
             Self::Property(name) => Expr::Place(PlaceExpr::FieldAccess(FieldAccess {
@@ -54,20 +57,22 @@ impl<T> PartialAst<T> {
                 expr: Box::new(left),
                 arg: Arg::Splat(Box::new(arg)),
             }),
-            Self::RecordCall(arg) => Expr::Call(Call {
+            Self::RecordCall(arg) => Call {
                 expr: Box::new(left),
                 arg: Arg::Record(arg),
-            }),
-            Self::TupleCall(arg) => Expr::Call(Call {
+            }
+            .partially_applied(),
+            Self::TupleCall(arg) => Call {
                 expr: Box::new(left),
                 arg: Arg::Tuple(arg),
-            }),
+            }
+            .partially_applied(),
             Self::Deref => Expr::Place(PlaceExpr::Deref(Box::new(left))),
             Self::Len => Expr::Place(PlaceExpr::Len(Box::new(left))),
         }
     }
 }
-fn infix_6<T, I>() -> impl Parser<I, Output = PartialAst<T>>
+fn infix_7<T, I>() -> impl Parser<I, Output = PartialAst<T>>
 where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
@@ -79,7 +84,7 @@ where
                 .skip(not_followed_by(char('<')))
                 .skip(not_followed_by(char('.'))),
         ))
-        .with(lex(ident()))
+        .with(lex(field_name()))
         .map(|prop| {
             if prop == keyword!("len") {
                 PartialAst::Len
@@ -102,8 +107,8 @@ where
                 expr(0),
             ))
             .map(PartialAst::SplatCall),
-            attempt(tuple()).map(PartialAst::TupleCall),
-            record().map(PartialAst::RecordCall),
+            attempt(arg_tuple()).map(PartialAst::TupleCall),
+            arg_record().map(PartialAst::RecordCall),
         ))
         .expected("argument")
     };
@@ -115,13 +120,13 @@ where
         lex(char('^')).with(value(PartialAst::Deref)),
     ))
 }
-pub(crate) fn expr_6<T, I>() -> impl Parser<I, Output = Expr<T>>
+pub(crate) fn expr_7<T, I>() -> impl Parser<I, Output = Expr<T>>
 where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
     T: Default + Clone,
 {
-    (expr(7), many(infix_6())).map(|(prefix, infixes)| {
+    (expr(8), many(infix_7())).map(|(prefix, infixes)| {
         let infixes: Vec<_> = infixes;
         let mut expr = prefix;
         for infix in infixes {
@@ -159,7 +164,7 @@ where
     I::Error: ParseError<I::Token, I::Range, I::Position>,
 {
     let op = match precedence {
-        5 => choice((
+        6 => choice((
             attempt(string("//")).with(value(BinaryType::FloorDiv)),
             char('*').with(value(BinaryType::Multiply)),
             char('/').with(value(BinaryType::Div)),
@@ -167,14 +172,14 @@ where
         ))
         .left()
         .left(),
-        4 => choice((
+        5 => choice((
             attempt(string("++")).with(value(BinaryType::Concatenate)),
             char('+').with(value(BinaryType::Add)),
             char('-').with(value(BinaryType::Sub)),
         ))
         .right()
         .left(),
-        3 => choice((
+        4 => choice((
             attempt(string("==")).with(value(BinaryType::Equal)),
             attempt(string("!=")).with(value(BinaryType::NotEqual)),
             attempt(string("<=")).with(value(BinaryType::LessEqual)),
@@ -185,18 +190,27 @@ where
         ))
         .left()
         .right(),
-        2 => choice((
+        3 => choice((
             attempt(string("&&")).with(value(BinaryType::LazyAnd)),
             char('&').with(value(BinaryType::And)),
         ))
+        .left()
         .right()
         .right(),
-        1 => choice((
+        2 => choice((
             attempt(string("||")).with(value(BinaryType::LazyOr)),
-            char('|').with(value(BinaryType::Or)),
+            attempt(char('|').skip(not_followed_by(char('>')))).with(value(BinaryType::Or)),
         ))
+        .left()
+        .right()
         .right()
         .right(),
+        1 => attempt(string("|>"))
+            .with(value(BinaryType::Pipe))
+            .right()
+            .right()
+            .right()
+            .right(),
         precedence => panic!("invalid precedence {}", precedence),
     };
     op.map(|op| {
@@ -1,22 +1,41 @@
-use crate::{expr::expr, ident_keyword::ident, lex, sep_optional_between};
+use crate::{expr::element, ident_keyword::field_name, lex, sep_optional_between};
 use combine::{
     between, error::StreamError, optional, parser::char::char, stream::StreamErrorFor, ParseError,
     Parser, Stream,
 };
 use hir::{
     expr::{Field, Record, RecordWithSplat},
-    Atom,
+    keyword,
 };
 
 pub(crate) fn record<T, I>() -> impl Parser<I, Output = Record<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    record_::<T, I, false>()
+}
+// call arguments, these may be `?` placeholders
+pub(crate) fn arg_record<T, I>() -> impl Parser<I, Output = Record<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    record_::<T, I, true>()
+}
+fn record_<T, I, const PLACEHOLDER: bool>() -> impl Parser<I, Output = Record<T>>
 where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
     T: Default + Clone,
 {
     let field = || {
-        (optional(lex(ident())), lex(char('=')).with(expr(0))).and_then(|(name, expr)| {
-            match name.map(Atom::from).or_else(|| expr.field_name()) {
+        let value = lex(char('=')).with(element::<T, I, PLACEHOLDER>());
+        (optional(lex(field_name())), value).and_then(|(name, expr)| {
+            let name = name.or_else(|| expr.field_name().filter(|name| *name != keyword!("?")));
+            match name {
                 Some(name) => Ok(Field { name, expr }),
                 None => Err(<StreamErrorFor<I>>::message_static_message(
                     "couldn't infer field name",
@@ -25,27 +44,29 @@ where
         })
     };
     let fields = || {
-        sep_optional_between(field, lex(char('*')).with(expr(0)), || lex(char(',')))
-            .map(|(left, rest_right)| {
-                let left: Vec<_> = left;
-                match rest_right {
-                    Some((rest, right)) => Record::RecordWithSplat(RecordWithSplat {
-                        left: left.into(),
-                        splat: Box::new(rest),
-                        right: right.into(),
-                    }),
-                    None => Record::Record(left.into()),
-                }
-            })
-            .and_then(|record| {
-                if record.all_name_unique() {
-                    Ok(record)
-                } else {
-                    Err(<StreamErrorFor<I>>::message_static_message(
-                        "duplicate field name",
-                    ))
-                }
-            })
+        sep_optional_between(field, lex(char('*')).with(element::<T, I, false>()), || {
+            lex(char(','))
+        })
+        .map(|(left, rest_right)| {
+            let left: Vec<_> = left;
+            match rest_right {
+                Some((rest, right)) => Record::RecordWithSplat(RecordWithSplat {
+                    left: left.into(),
+                    splat: Box::new(rest),
+                    right: right.into(),
+                }),
+                None => Record::Record(left.into()),
+            }
+        })
+        .and_then(|record| {
+            if record.all_name_unique() {
+                Ok(record)
+            } else {
+                Err(<StreamErrorFor<I>>::message_static_message(
+                    "duplicate field name",
+                ))
+            }
+        })
     };
     between(lex(char('(')), lex(char(')')), fields()).expected("record")
 }
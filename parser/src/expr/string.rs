@@ -1,9 +1,9 @@
 use crate::expr::integer::parse_digit;
 use combine::{
-    between, choice,
+    any, attempt, between, choice,
     error::StreamError,
-    many,
-    parser::char::{char, hex_digit},
+    many, not_followed_by,
+    parser::char::{char, hex_digit, string},
     satisfy,
     stream::StreamErrorFor,
     value, ParseError, Parser, Stream,
@@ -47,20 +47,24 @@ where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
 {
-    between(char('\''), char('\''), char_inside('\''))
-        .and_then(|ch| match ch {
-            Char::Byte(byte) => Ok(byte as u64),
-            Char::Char(ch) => {
-                if ch.len_utf8() == 1 {
-                    Ok(ch as u8 as u64)
-                } else {
-                    Err(<StreamErrorFor<I>>::message_static_message(
-                        "multiple character in char literal",
-                    ))
-                }
+    between(
+        char('\''),
+        char('\'').message("unterminated char"),
+        char_inside('\''),
+    )
+    .and_then(|ch| match ch {
+        Char::Byte(byte) => Ok(byte as u64),
+        Char::Char(ch) => {
+            if ch.len_utf8() == 1 {
+                Ok(ch as u8 as u64)
+            } else {
+                Err(<StreamErrorFor<I>>::message_static_message(
+                    "multiple character in char literal",
+                ))
             }
-        })
-        .expected("char")
+        }
+    })
+    .expected("char")
 }
 #[derive(Clone, PartialEq, Debug, Default)]
 struct StringLiteral(Vec<u8>);
@@ -85,19 +89,36 @@ impl Extend<Char> for StringLiteral {
         }
     }
 }
+// `#"..."#` is taken as is, without escapes and possibly spanning lines
+pub(crate) fn raw_string_literal<I>() -> impl Parser<I, Output = String>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    attempt(string("#\""))
+        .with(many(not_followed_by(attempt(string("\"#"))).with(any())))
+        .skip(string("\"#").message("unterminated raw string"))
+}
 pub(crate) fn string_literal<I>() -> impl Parser<I, Output = Vec<u8>>
 where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
 {
-    between(char('"'), char('"'), many(char_inside('"')))
-        .map(|StringLiteral(vec)| vec)
-        .expected("string")
+    choice((
+        raw_string_literal().map(String::into_bytes),
+        between(
+            char('"'),
+            char('"').message("unterminated string"),
+            many(char_inside('"')),
+        )
+        .map(|StringLiteral(vec)| vec),
+    ))
+    .expected("string")
 }
 #[cfg(test)]
 mod test {
     use crate::expr::string_literal;
-    use combine::EasyParser;
+    use combine::{easy, EasyParser};
 
     #[test]
     fn string() {
@@ -105,4 +126,24 @@ mod test {
         let expected: Vec<u8> = "\x41Aßℝ💣\n".into();
         assert_eq!(string_literal().easy_parse(src), Ok((expected, "")));
     }
+    #[test]
+    fn raw_string() {
+        let src = "#\"\\n \"quoted\"\nnext line\"#";
+        let expected: Vec<u8> = "\\n \"quoted\"\nnext line".into();
+        assert_eq!(string_literal().easy_parse(src), Ok((expected, "")));
+    }
+    #[test]
+    fn unterminated() {
+        for (src, message) in [
+            ("\"foo\nbar\"", "unterminated string"),
+            ("#\"foo\"", "unterminated raw string"),
+        ] {
+            let errors = string_literal().easy_parse(src).unwrap_err().errors;
+            assert!(
+                errors.contains(&easy::Error::Message(message.into())),
+                "{}",
+                src
+            );
+        }
+    }
 }
@@ -1,7 +1,11 @@
 use combine::{
+    attempt,
     error::StreamError,
-    not_followed_by,
-    parser::{char::string, combinator::recognize},
+    not_followed_by, optional,
+    parser::{
+        char::{char, string},
+        combinator::recognize,
+    },
     satisfy, skip_many,
     stream::StreamErrorFor,
     value, ParseError, Parser, Stream,
@@ -16,7 +20,7 @@ where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
 {
-    let start = move |ch: char| rest(ch) && !('0'..='9').contains(&ch);
+    let start = move |ch: char| rest(ch) && !ch.is_ascii_digit();
     recognize::<String, _, _>((satisfy(start), skip_many(satisfy(rest))))
         .map(Atom::from)
         .expected("identifier")
@@ -30,35 +34,92 @@ where
         .skip(not_followed_by(satisfy(rest)))
         .with(value(()))
 }
-pub(crate) fn ident<I>() -> impl Parser<I, Output = Atom>
+pub fn is_keyword(ident: &Atom) -> bool {
+    matches!(
+        *ident,
+        keyword!("_")
+            | keyword!("as")
+            | keyword!("break")
+            | keyword!("clone")
+            | keyword!("continue")
+            | keyword!("do")
+            | keyword!("else")
+            | keyword!("false")
+            | keyword!("for")
+            | keyword!("handling")
+            | keyword!("if")
+            | keyword!("in")
+            | keyword!("loop")
+            | keyword!("match")
+            | keyword!("mod")
+            | keyword!("mut")
+            | keyword!("newtype")
+            | keyword!("pub")
+            | keyword!("ref")
+            | keyword!("return")
+            | keyword!("true")
+            | keyword!("while")
+            | keyword!("yield")
+    )
+}
+// `` `name` ``, which may also be a keyword
+pub(crate) fn raw_ident<I>() -> impl Parser<I, Output = Atom>
 where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
 {
-    ident_or_keyword().and_then(|ident| match ident {
-        keyword!("_")
-        | keyword!("break")
-        | keyword!("clone")
-        | keyword!("continue")
-        | keyword!("else")
-        | keyword!("false")
-        | keyword!("for")
-        | keyword!("if")
-        | keyword!("in")
-        | keyword!("loop")
-        | keyword!("match")
-        | keyword!("mut")
-        | keyword!("ref")
-        | keyword!("return")
-        | keyword!("true")
-        | keyword!("while") => Err(<StreamErrorFor<I>>::unexpected_static_message("keyword")),
-        ident => Ok(ident),
+    char('`')
+        .with(ident_or_keyword())
+        .skip(char('`').message("unterminated raw identifier"))
+}
+fn ident_<I>() -> impl Parser<I, Output = Atom>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    let plain = ident_or_keyword().and_then(|ident| {
+        if is_keyword(&ident) {
+            Err(<StreamErrorFor<I>>::unexpected_static_message("keyword"))
+        } else {
+            Ok(ident)
+        }
+    });
+    raw_ident().or(plain)
+}
+combine::parser! {
+    pub(crate) fn ident[I]()(I) -> Atom
+    where [
+        I: Stream<Token = char>,
+        I::Error: ParseError<I::Token, I::Range, I::Position>,
+    ] {
+        ident_()
+    }
+}
+// name of a field, a leading `#` marks a private field of a newtype
+pub(crate) fn field_name<I>() -> impl Parser<I, Output = Atom>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    // not a raw string
+    let private = attempt(char('#').skip(not_followed_by(char('"'))));
+    (optional(private), ident()).map(|(private, name)| match private {
+        Some(_) => Atom::from(format!("#{}", name)),
+        None => name,
     })
 }
+// `'name` of a loop, unlike a char literal there's no closing `'`
+pub(crate) fn label<I>() -> impl Parser<I, Output = Atom>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    attempt(char('\'').with(ident()).skip(not_followed_by(char('\''))))
+}
 #[cfg(test)]
 mod test {
     use crate::ident_keyword::{ident, ident_or_keyword, keyword};
-    use combine::EasyParser;
+    use combine::{easy, EasyParser};
     use hir::Atom;
 
     #[test]
@@ -80,4 +141,11 @@ mod test {
     fn non_ident() {
         assert!(ident().easy_parse("12").is_err());
     }
+    #[test]
+    fn raw_ident() {
+        assert_eq!(ident().easy_parse("`loop`"), Ok((Atom::from("loop"), "")));
+        assert_eq!(ident().easy_parse("`foo`"), Ok((Atom::from("foo"), "")));
+        let errors = ident().easy_parse("`loop").unwrap_err().errors;
+        assert!(errors.contains(&easy::Error::Message("unterminated raw identifier".into())));
+    }
 }
@@ -3,13 +3,16 @@
 #![forbid(unsafe_code)]
 
 use combine::{
-    attempt, choice, eof, many, none_of, optional,
-    parser::char::{space, string},
-    sep_end_by, skip_many, skip_many1, value, ParseError, Stream,
+    any, attempt, choice, eof, many, none_of, not_followed_by, optional,
+    parser::char::{char, space, string},
+    satisfy, sep_end_by, skip_many, skip_many1, value, ParseError, Stream,
 };
+use expr::string::raw_string_literal;
 use hir::{expr::Expr, statement::Statement};
+use ident_keyword::ident_or_keyword;
 
 pub use combine::{EasyParser, Parser};
+pub use ident_keyword::is_keyword;
 
 mod expr;
 mod ident_keyword;
@@ -39,19 +42,72 @@ combine::parser! {
         insignificants().with(expr::expr(0)).skip(eof())
     }
 }
-fn comment<I>() -> impl Parser<I, Output = ()>
+// `/- -/` doesn't nest, the first `-/` closes it
+fn block_comment<I>() -> impl Parser<I, Output = ()>
 where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
 {
-    (attempt(string("--")), skip_many(none_of(['\n']))).with(value(()))
+    attempt(string("/-"))
+        .with(skip_many(
+            not_followed_by(attempt(string("-/"))).with(any()),
+        ))
+        .skip(string("-/").message("unterminated comment"))
+}
+combine::parser! {
+    // `{- -}` nests, what's inside is still made of tokens so a `-}` within a
+    // string doesn't close it
+    fn ignore_block[I]()(I) -> ()
+    where [
+        I: Stream<Token = char>,
+        I::Error: ParseError<I::Token, I::Range, I::Position>,
+    ] {
+        let quoted = |delimiter: char, message: &'static str| {
+            let escape = char('\\').with(any());
+            let inside = satisfy(move |ch: char| ch != delimiter && ch != '\n');
+            (
+                char(delimiter),
+                skip_many(escape.or(inside).with(value(()))),
+                char(delimiter).message(message),
+            )
+                .with(value(()))
+        };
+        let label = (char('\''), ident_or_keyword(), not_followed_by(char('\'')));
+        let raw_ident = attempt((char('`'), ident_or_keyword()))
+            .skip(char('`').message("unterminated raw identifier"));
+        let token = choice((
+            comment(),
+            raw_string_literal().with(value(())),
+            quoted('"', "unterminated string"),
+            attempt(label).with(value(())),
+            quoted('\'', "unterminated char"),
+            raw_ident.with(value(())),
+            not_followed_by(attempt(string("-}"))).with(any()).with(value(())),
+        ));
+        attempt(string("{-"))
+            .with(skip_many(token))
+            .skip(string("-}").message("unterminated ignore block"))
+    }
 }
-pub fn insignificants<I>() -> impl Parser<I, Output = ()>
+fn comment<I>() -> impl Parser<I, Output = ()>
 where
     I: Stream<Token = char>,
     I::Error: ParseError<I::Token, I::Range, I::Position>,
 {
-    skip_many(skip_many1(space()).or(comment())).silent()
+    choice((
+        (attempt(string("--")), skip_many(none_of(['\n']))).with(value(())),
+        block_comment(),
+        ignore_block(),
+    ))
+}
+combine::parser! {
+    pub fn insignificants[I]()(I) -> ()
+    where [
+        I: Stream<Token = char>,
+        I::Error: ParseError<I::Token, I::Range, I::Position>,
+    ] {
+        skip_many(skip_many1(space()).or(comment())).silent()
+    }
 }
 fn lex<I, P>(parser: P) -> impl Parser<I, Output = P::Output>
 where
@@ -88,8 +144,8 @@ where
 }
 #[cfg(test)]
 mod test {
-    use crate::insignificants;
-    use combine::Parser;
+    use crate::{ast, insignificants};
+    use combine::{easy, EasyParser, Parser};
     use hir::{
         expr::{Expr, PlaceExpr},
         Atom,
@@ -110,4 +166,43 @@ mod test {
             ((), "")
         )
     }
+    #[test]
+    fn block_comment() {
+        assert_eq!(
+            insignificants()
+                .parse("/- not {- nested -/ -- comment\n/-\nmore\n-/")
+                .unwrap(),
+            ((), "")
+        )
+    }
+    #[test]
+    fn ignore_block() {
+        let src = "{- outer {- inner -} \"-}\" '-' 'a `loop` #\"-}\"# /- -} -/ -- -}\n-}";
+        assert_eq!(insignificants().parse(src).unwrap(), ((), ""));
+    }
+    #[test]
+    fn unterminated() {
+        for (src, message) in [
+            ("/- comment", "unterminated comment"),
+            ("{- {- -}", "unterminated ignore block"),
+            ("{- \"-}\n-}", "unterminated string"),
+            ("{- #\"-} -}", "unterminated raw string"),
+            ("{- `loop -}", "unterminated raw identifier"),
+        ] {
+            let errors = insignificants().easy_parse(src).unwrap_err().errors;
+            assert!(
+                errors.contains(&easy::Error::Message(message.into())),
+                "{}",
+                src
+            );
+        }
+    }
+    #[test]
+    fn hashbang() {
+        let src = "#!/usr/bin/env butter {- /-\n{- -} /- -/ x = 1;";
+        assert_eq!(ast::<(), _>().easy_parse(src).unwrap().0.len(), 1);
+        assert!(ast::<(), _>()
+            .easy_parse("{- -}\n#!butter\nx = 1;")
+            .is_err());
+    }
 }
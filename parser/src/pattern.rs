@@ -1,17 +1,22 @@
 use crate::{
-    expr::integer::integer_u64,
-    ident_keyword::{ident, keyword},
+    expr::{array::range_operator, expr, integer::integer_u64},
+    ident_keyword::{field_name, ident, keyword},
     lex, sep_optional_between,
 };
 use combine::{
-    attempt, between, choice, error::StreamError, optional, parser::char::char, sep_end_by,
-    stream::StreamErrorFor, value, ParseError, Parser, Stream,
+    attempt, between, choice,
+    error::StreamError,
+    look_ahead, not_followed_by, optional,
+    parser::char::{char, string},
+    sep_by1, sep_end_by,
+    stream::StreamErrorFor,
+    value, ParseError, Parser, Stream,
 };
-use hir::{
-    pattern::{ListPattern, ListWithRest, Pattern, RecordPattern, TaggedPattern, Var},
-    Atom,
+use hir::pattern::{
+    AsPattern, ListPattern, ListWithRest, NewTypePattern, Pattern, PatternBound, RangePattern,
+    RecordPattern, TaggedPattern, Var,
 };
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 fn var<T, I>() -> impl Parser<I, Output = Var<T>>
 where
@@ -88,8 +93,8 @@ where
     T: Default + Clone,
 {
     let field = || {
-        (optional(lex(ident())), lex(char('=')).with(pattern())).and_then(|(name, pattern)| {
-            match name.map(Atom::from).or_else(|| pattern.field_name()) {
+        (optional(lex(field_name())), lex(char('=')).with(pattern())).and_then(|(name, pattern)| {
+            match name.or_else(|| pattern.field_name()) {
                 Some(name) => Ok((name, pattern)),
                 None => Err(<StreamErrorFor<I>>::message_static_message(
                     "couldn't infer field name",
@@ -105,7 +110,7 @@ where
     )
     .map(|(left, rest_right)| match rest_right {
         Some((rest, right)) => {
-            let mut fields: HashMap<_, _> = left;
+            let mut fields: BTreeMap<_, _> = left;
             fields.extend(right);
             RecordPattern {
                 fields,
@@ -119,6 +124,54 @@ where
     })
     .expected("record pattern")
 }
+fn integer<T, I>() -> impl Parser<I, Output = Pattern<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    choice((
+        lex(char('-')).with(integer_u64()).and_then(|uint| {
+            match 0_i64.checked_sub_unsigned(uint) {
+                Some(int) => Ok(Pattern::Int(int)),
+                None => Err(<StreamErrorFor<I>>::message_static_message(
+                    "integer overflow",
+                )),
+            }
+        }),
+        integer_u64().map(Pattern::UInt),
+    ))
+}
+// an integer or a range of integers, `1..3`, `..3`, or `1..`
+fn number<T, I>() -> impl Parser<I, Output = Pattern<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    let bound = |kind, pattern| PatternBound {
+        kind,
+        pattern: Box::new(pattern),
+    };
+    choice((
+        (
+            lex(integer()),
+            optional((lex(range_operator()), optional(lex(integer())))),
+        )
+            .map(move |(left, range)| match range {
+                Some(((left_kind, right_kind), right)) => Pattern::Range(RangePattern {
+                    left: Some(bound(left_kind, left)),
+                    right: right.map(|right| bound(right_kind, right)),
+                }),
+                None => left,
+            }),
+        (lex(range_operator()), lex(integer())).map(move |((_, kind), right)| {
+            Pattern::Range(RangePattern {
+                left: None,
+                right: Some(bound(kind, right)),
+            })
+        }),
+    ))
+    .expected("integer")
+}
 fn pattern_<T, I>() -> impl Parser<I, Output = Pattern<T>>
 where
     I: Stream<Token = char>,
@@ -127,7 +180,11 @@ where
 {
     choice((
         lex(char('@'))
-            .with((lex(ident()), optional(pattern())))
+            .with((
+                lex(ident()),
+                // `@tag as name` has no payload
+                optional(not_followed_by(keyword("as").map(|_| "as")).with(primary_pattern())),
+            ))
             .map(|(tag, pattern)| {
                 Pattern::Tag(TaggedPattern {
                     tag,
@@ -135,10 +192,13 @@ where
                 })
             }),
         lex(char('&'))
-            .with(pattern())
+            .with(primary_pattern())
             .map(|pattern| Pattern::Ref(Box::new(pattern))),
-        // TODO: minus integer
-        integer_u64().map(Pattern::UInt),
+        // binds tighter than `|` and comparisons
+        attempt(lex(string("==")))
+            .with(expr(5))
+            .map(|expr| Pattern::Equal(Box::new(expr))),
+        number(),
         attempt(between(lex(char('(')), lex(char(')')), pattern())).expected("group"),
         record().map(Pattern::Record),
         tuple().map(Pattern::Tuple),
@@ -146,11 +206,13 @@ where
         attempt(lex(keyword("_"))).with(value(Pattern::Ignore)),
         attempt(lex(keyword("true"))).with(value(Pattern::True)),
         attempt(lex(keyword("false"))).with(value(Pattern::False)),
+        (attempt(ident().skip(look_ahead(char('(')))), record())
+            .map(|(name, fields)| Pattern::NewType(NewTypePattern { name, fields })),
         var().map(Pattern::Var),
     ))
 }
 combine::parser! {
-    pub(crate) fn pattern[T, I]()(I) -> Pattern<T>
+    fn primary_pattern[T, I]()(I) -> Pattern<T>
     where [
         I: Stream<Token = char>,
         I::Error: ParseError<I::Token, I::Range, I::Position>,
@@ -159,3 +221,149 @@ combine::parser! {
         pattern_()
     }
 }
+// `|` has the lowest precedence, `1 | 2 as num` only binds `num` on the
+// second alternative
+fn or_pattern<T, I>() -> impl Parser<I, Output = Pattern<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default + Clone,
+{
+    let as_pattern = || {
+        (
+            primary_pattern(),
+            optional(attempt(lex(keyword("as"))).with(var())),
+        )
+            .map(|(pattern, var)| match var {
+                Some(var) => Pattern::As(AsPattern {
+                    pattern: Box::new(pattern),
+                    var,
+                }),
+                None => pattern,
+            })
+    };
+    sep_by1(as_pattern(), lex(char('|'))).map(|patterns: Vec<_>| {
+        if patterns.len() == 1 {
+            patterns.into_iter().next().unwrap()
+        } else {
+            Pattern::Or(patterns.into())
+        }
+    })
+}
+combine::parser! {
+    pub(crate) fn pattern[T, I]()(I) -> Pattern<T>
+    where [
+        I: Stream<Token = char>,
+        I::Error: ParseError<I::Token, I::Range, I::Position>,
+        T: Default + Clone,
+    ] {
+        or_pattern()
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::pattern::pattern;
+    use crate::test::var_expr;
+    use combine::EasyParser;
+    use hir::{
+        expr::{Binary, BinaryType, BoundType, Expr, Literal},
+        pattern::{AsPattern, Pattern, PatternBound, RangePattern, TaggedPattern, Var},
+        Atom,
+    };
+
+    fn var(name: &str) -> Var<()> {
+        Var {
+            ident: Atom::from(name),
+            mutable: false,
+            bind_to_ref: false,
+            ty: (),
+        }
+    }
+    fn bound(kind: BoundType, pattern: Pattern<()>) -> Option<PatternBound<()>> {
+        Some(PatternBound {
+            kind,
+            pattern: Box::new(pattern),
+        })
+    }
+    #[test]
+    fn integer() {
+        assert_eq!(
+            pattern().easy_parse("12 "),
+            Ok((Pattern::<()>::UInt(12), ""))
+        );
+        assert_eq!(
+            pattern().easy_parse("- 12"),
+            Ok((Pattern::<()>::Int(-12), ""))
+        );
+        assert!(pattern::<(), _>()
+            .easy_parse("-9223372036854775809")
+            .is_err());
+    }
+    #[test]
+    fn range() {
+        let expected = Pattern::Range(RangePattern {
+            left: bound(BoundType::Inclusive, Pattern::Int(-1)),
+            right: bound(BoundType::Exclusive, Pattern::UInt(10)),
+        });
+        assert_eq!(pattern().easy_parse("-1 .< 10"), Ok((expected, "")));
+        let expected = Pattern::Range(RangePattern {
+            left: bound(BoundType::Inclusive, Pattern::UInt(1)),
+            right: None,
+        });
+        assert_eq!(pattern().easy_parse("1.."), Ok((expected, "")));
+        let expected = Pattern::Range(RangePattern {
+            left: None,
+            right: bound(BoundType::Inclusive, Pattern::UInt(3)),
+        });
+        assert_eq!(pattern().easy_parse("..3"), Ok((expected, "")));
+    }
+    #[test]
+    fn or_as() {
+        let tag = |name: &str, pattern| {
+            Pattern::Tag(TaggedPattern {
+                tag: Atom::from(name),
+                pattern,
+            })
+        };
+        let src = "@none | @some 0 as zero";
+        let expected = Pattern::Or(
+            vec![
+                tag("none", None),
+                Pattern::As(AsPattern {
+                    pattern: Box::new(tag("some", Some(Box::new(Pattern::UInt(0))))),
+                    var: var("zero"),
+                }),
+            ]
+            .into(),
+        );
+        assert_eq!(pattern().easy_parse(src), Ok((expected, "")));
+        let src = "@some (1 | 2)";
+        let expected = tag(
+            "some",
+            Some(Box::new(Pattern::Or(
+                vec![Pattern::UInt(1), Pattern::UInt(2)].into(),
+            ))),
+        );
+        assert_eq!(pattern().easy_parse(src), Ok((expected, "")));
+        assert!(pattern::<(), _>().easy_parse("as").is_err());
+    }
+    #[test]
+    fn equal() {
+        let src = "== a + 1 | == b";
+        let expected = Pattern::Or(
+            vec![
+                Pattern::Equal(Box::new(Expr::Binary(Binary {
+                    kind: BinaryType::Add,
+                    left: Box::new(var_expr("a")),
+                    right: Box::new(Expr::Literal(Literal::UInt(1))),
+                }))),
+                Pattern::Equal(Box::new(var_expr("b"))),
+            ]
+            .into(),
+        );
+        assert_eq!(pattern().easy_parse(src), Ok((expected, "")));
+        assert!(pattern::<(), _>()
+            .easy_parse("== a == b")
+            .is_ok_and(|(_, rest)| rest == "== b"));
+    }
+}
@@ -1,21 +1,27 @@
 use crate::{
-    expr::{control_flow::control_flow, expr},
-    ident_keyword::ident,
+    expr::{
+        control_flow::{block, control_flow},
+        expr,
+    },
+    ident_keyword::{field_name, ident, keyword},
     lex,
     pattern::{parameter, pattern},
 };
 use combine::{
-    attempt, choice,
+    attempt, between, choice,
     error::StreamError,
-    look_ahead, optional,
+    look_ahead, many, not_followed_by, one_of, optional,
     parser::char::{char, string},
-    sep_by1,
+    sep_by1, sep_end_by,
     stream::StreamErrorFor,
     value, ParseError, Parser, Stream,
 };
 use hir::{
-    expr::{Assign, Expr, Fun},
-    statement::{Declare, FunDeclare, Statement},
+    expr::{Assign, Expr, Fun, Module},
+    pattern::{Pattern, Var},
+    statement::{
+        Declare, FunDeclare, NewType, NewTypeField, Statement, TypeAnnotation, Visibility,
+    },
 };
 
 pub(crate) enum StatementReturn<T> {
@@ -55,6 +61,7 @@ where
         )
             .map(|((ident, param), body)| {
                 Statement::FunDeclare(FunDeclare {
+                    visibility: Visibility::Private,
                     ident,
                     fun: Fun {
                         param,
@@ -76,10 +83,21 @@ where
         })
     };
     let declare = || {
-        (attempt(pattern().skip(lex(char('=')))), expr(0))
+        // `=` followed by `>` or `=` isn't a declaration, `f(x) => x` would
+        // otherwise be a newtype pattern
+        (
+            attempt(pattern().skip(lex(char('=').skip(not_followed_by(one_of(['>', '='])))))),
+            expr(0),
+            optional(attempt(lex(keyword("else"))).with(block())),
+        )
             .skip(lex(char(';')))
-            .map(|(pattern, expr)| {
-                StatementReturn::Statement(Statement::Declare(Declare { pattern, expr }))
+            .map(|(pattern, expr, else_part)| {
+                Statement::Declare(Declare {
+                    visibility: Visibility::Private,
+                    pattern,
+                    expr,
+                    else_part,
+                })
             })
     };
     let parallel_assign = || {
@@ -97,7 +115,7 @@ where
                 }
                 let assign = place
                     .into_iter()
-                    .zip(expr.into_iter())
+                    .zip(expr)
                     .map(|(place, expr)| Assign { place, expr })
                     .collect();
                 Ok(Expr::Assign(assign))
@@ -119,13 +137,165 @@ where
                 }
             })
     };
+    // `mod name;` and `mod name { ... }` declare a module of the same name
+    let module_declare = || {
+        (
+            attempt((lex(keyword("mod")), lex(ident())).skip(look_ahead(one_of(['{', ';'])))),
+            choice((lex(char(';')).map(|_| None), module_body().map(Some))),
+        )
+            .map(|((_, ident), statement)| {
+                let module = match statement {
+                    Some(statement) => Module::Inline(statement.into()),
+                    None => Module::File(ident.clone()),
+                };
+                Statement::Declare(Declare {
+                    visibility: Visibility::Private,
+                    pattern: Pattern::Var(Var {
+                        ident,
+                        mutable: false,
+                        bind_to_ref: false,
+                        ty: T::default(),
+                    }),
+                    expr: Expr::Module(module),
+                    else_part: None,
+                })
+            })
+    };
+    let public_declare = || {
+        (
+            visibility(),
+            choice((module_declare(), newtype(), declare(), fun_declare())),
+        )
+            .map(|(visibility, mut statement)| {
+                match &mut statement {
+                    Statement::Declare(declare) => declare.visibility = visibility,
+                    Statement::FunDeclare(fun) => fun.visibility = visibility,
+                    Statement::NewType(newtype) => newtype.visibility = visibility,
+                    Statement::Expr(_) => unreachable!(),
+                }
+                statement
+            })
+    };
     choice((
         control_flow_statement(),
-        declare(),
+        public_declare().map(StatementReturn::Statement),
+        module_declare().map(StatementReturn::Statement),
+        newtype().map(StatementReturn::Statement),
+        declare().map(StatementReturn::Statement),
         fun_declare().map(StatementReturn::Statement),
         expr(),
     ))
 }
+// `pub` or `pub(path.to.module)` before a declaration or a newtype field
+fn visibility<I>() -> impl Parser<I, Output = Visibility>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+{
+    attempt(lex(keyword("pub")))
+        .with(optional(between(
+            lex(char('(')),
+            lex(char(')')),
+            sep_by1(lex(ident()), lex(char('.'))),
+        )))
+        .map(|path: Option<Vec<_>>| match path {
+            Some(path) => Visibility::Restricted(path.into()),
+            None => Visibility::Public,
+        })
+}
+combine::parser! {
+    fn type_annotation[I]()(I) -> TypeAnnotation
+    where [
+        I: Stream<Token = char>,
+        I::Error: ParseError<I::Token, I::Range, I::Position>,
+    ] {
+        choice((
+            attempt(lex(keyword("_"))).with(value(TypeAnnotation::Infer)),
+            between(lex(char('[')), lex(char(']')), type_annotation())
+                .map(|ty| TypeAnnotation::Array(Box::new(ty))),
+            (
+                lex(char('&')),
+                optional(attempt(lex(keyword("mut")))),
+                type_annotation(),
+            )
+                .map(|(_, mutable, ty)| TypeAnnotation::Ref(mutable.is_some(), Box::new(ty))),
+            (
+                lex(ident()),
+                optional(between(
+                    lex(char('(')),
+                    lex(char(')')),
+                    sep_end_by(type_annotation(), lex(char(','))),
+                )),
+            )
+                .map(|(name, args): (_, Option<Vec<_>>)| {
+                    TypeAnnotation::Named(name, args.unwrap_or_default().into())
+                }),
+        ))
+        .expected("type")
+    }
+}
+// `newtype Name(field: Type, #private)`
+fn newtype<T, I>() -> impl Parser<I, Output = Statement<T>>
+where
+    I: Stream<Token = char>,
+    I::Error: ParseError<I::Token, I::Range, I::Position>,
+    T: Default,
+{
+    let field = (
+        optional(visibility()),
+        lex(field_name()),
+        optional(lex(char(':')).with(type_annotation())),
+    )
+        .map(|(visibility, name, ty)| NewTypeField {
+            visibility: visibility.unwrap_or_else(|| {
+                if name.starts_with('#') {
+                    Visibility::Private
+                } else {
+                    Visibility::Public
+                }
+            }),
+            name,
+            ty: ty.unwrap_or(TypeAnnotation::Infer),
+        });
+    (
+        attempt(lex(keyword("newtype"))),
+        lex(ident()),
+        between(
+            lex(char('(')),
+            lex(char(')')),
+            sep_end_by(field, lex(char(','))),
+        ),
+        lex(char(';')),
+    )
+        .and_then(|(_, ident, fields, _): (_, _, Vec<NewTypeField>, _)| {
+            let unique = fields
+                .iter()
+                .enumerate()
+                .all(|(i, field)| fields[..i].iter().all(|other| other.name != field.name));
+            if unique {
+                Ok(Statement::NewType(NewType {
+                    visibility: Visibility::Private,
+                    ident,
+                    fields: fields.into(),
+                    ty: T::default(),
+                }))
+            } else {
+                Err(<StreamErrorFor<I>>::message_static_message(
+                    "duplicate field name",
+                ))
+            }
+        })
+}
+combine::parser! {
+    fn module_body[T, I]()(I) -> Vec<Statement<T>>
+    where [
+        I: Stream<Token = char>,
+        I::Error: ParseError<I::Token, I::Range, I::Position>,
+        T: Default + Clone,
+    ] {
+        between(lex(char('{')), lex(char('}')), many(statement()))
+    }
+}
 pub(crate) fn statement<T, I>() -> impl Parser<I, Output = Statement<T>>
 where
     I: Stream<Token = char>,
@@ -146,9 +316,9 @@ mod test {
     };
     use combine::EasyParser;
     use hir::{
-        expr::Literal,
-        pattern::{Pattern, Var},
-        statement::Declare,
+        expr::{Block, Jump, Literal, Module},
+        pattern::{NewTypePattern, Pattern, TaggedPattern, Var},
+        statement::{Declare, FunDeclare, NewType, NewTypeField, TypeAnnotation, Visibility},
         Atom,
     };
 
@@ -192,6 +362,7 @@ mod test {
     fn var() {
         let src = "foo = 10;";
         let expected: Statement<()> = Statement::Declare(Declare {
+            visibility: Visibility::Private,
             pattern: Pattern::Var(Var {
                 ident: Atom::from("foo"),
                 mutable: false,
@@ -199,7 +370,159 @@ mod test {
                 ty: (),
             }),
             expr: Expr::Literal(Literal::UInt(10)),
+            else_part: None,
+        });
+        assert_eq!(statement().easy_parse(src), Ok((expected, "")));
+    }
+    #[test]
+    fn module() {
+        let declare = |name, module| {
+            Statement::Declare(Declare {
+                visibility: Visibility::Private,
+                pattern: Pattern::Var(Var {
+                    ident: Atom::from(name),
+                    mutable: false,
+                    bind_to_ref: false,
+                    ty: (),
+                }),
+                expr: Expr::Module(module),
+                else_part: None,
+            })
+        };
+        let src = "math = mod math;";
+        let expected: Statement<()> = declare("math", Module::File(Atom::from("math")));
+        assert_eq!(statement().easy_parse(src), Ok((expected.clone(), "")));
+        assert_eq!(statement().easy_parse("mod math;"), Ok((expected, "")));
+        let src = "mod math { pi = 3; }";
+        let expected: Statement<()> = declare(
+            "math",
+            Module::Inline(
+                vec![Statement::Declare(Declare {
+                    visibility: Visibility::Private,
+                    pattern: Pattern::Var(Var {
+                        ident: Atom::from("pi"),
+                        mutable: false,
+                        bind_to_ref: false,
+                        ty: (),
+                    }),
+                    expr: Expr::Literal(Literal::UInt(3)),
+                    else_part: None,
+                })]
+                .into(),
+            ),
+        );
+        assert_eq!(statement().easy_parse(src), Ok((expected, "")));
+    }
+    #[test]
+    fn visibility() {
+        let declare = |visibility| {
+            Statement::Declare(Declare {
+                visibility,
+                pattern: Pattern::Var(Var {
+                    ident: Atom::from("pi"),
+                    mutable: false,
+                    bind_to_ref: false,
+                    ty: (),
+                }),
+                expr: Expr::Literal(Literal::UInt(3)),
+                else_part: None,
+            })
+        };
+        let expected: Statement<()> = declare(Visibility::Public);
+        assert_eq!(statement().easy_parse("pub pi = 3;"), Ok((expected, "")));
+        let expected: Statement<()> = declare(Visibility::Restricted(
+            vec![Atom::from("path"), Atom::from("to")].into(),
+        ));
+        assert_eq!(
+            statement().easy_parse("pub(path.to) pi = 3;"),
+            Ok((expected, "")),
+        );
+        let (fun, _) = statement::<(), _>().easy_parse("pub id(x) => x;").unwrap();
+        assert!(matches!(
+            fun,
+            Statement::FunDeclare(FunDeclare {
+                visibility: Visibility::Public,
+                ..
+            }),
+        ));
+        let (module, _) = statement::<(), _>().easy_parse("pub mod math;").unwrap();
+        assert!(matches!(
+            module,
+            Statement::Declare(Declare {
+                visibility: Visibility::Public,
+                ..
+            }),
+        ));
+    }
+    #[test]
+    fn newtype() {
+        let src = "pub newtype Point(x: Num, #y: [_], pub(geo) #z);";
+        let named = |name| TypeAnnotation::Named(Atom::from(name), vec![].into());
+        let expected: Statement<()> = Statement::NewType(NewType {
+            visibility: Visibility::Public,
+            ident: Atom::from("Point"),
+            fields: vec![
+                NewTypeField {
+                    visibility: Visibility::Public,
+                    name: Atom::from("x"),
+                    ty: named("Num"),
+                },
+                NewTypeField {
+                    visibility: Visibility::Private,
+                    name: Atom::from("#y"),
+                    ty: TypeAnnotation::Array(Box::new(TypeAnnotation::Infer)),
+                },
+                NewTypeField {
+                    visibility: Visibility::Restricted(vec![Atom::from("geo")].into()),
+                    name: Atom::from("#z"),
+                    ty: TypeAnnotation::Infer,
+                },
+            ]
+            .into(),
+            ty: (),
+        });
+        assert_eq!(statement().easy_parse(src), Ok((expected, "")));
+        assert!(statement::<(), _>()
+            .easy_parse("newtype Point(x, x);")
+            .is_err());
+        let (declare, _) = statement::<(), _>()
+            .easy_parse("Point(#y = y) = point;")
+            .unwrap();
+        let Statement::Declare(Declare {
+            pattern: Pattern::NewType(NewTypePattern { name, fields }),
+            ..
+        }) = declare
+        else {
+            panic!("expected a newtype pattern, found {:?}", declare);
+        };
+        assert_eq!(name, Atom::from("Point"));
+        assert!(fields.fields.contains_key(&Atom::from("#y")));
+        let (fun, _) = statement::<(), _>().easy_parse("f(x) => x;").unwrap();
+        assert!(matches!(fun, Statement::FunDeclare(_)));
+    }
+    #[test]
+    fn else_part() {
+        let src = "@val val = val else { return 0 };";
+        let expected: Statement<()> = Statement::Declare(Declare {
+            visibility: Visibility::Private,
+            pattern: Pattern::Tag(TaggedPattern {
+                tag: Atom::from("val"),
+                pattern: Some(Box::new(Pattern::Var(Var {
+                    ident: Atom::from("val"),
+                    mutable: false,
+                    bind_to_ref: false,
+                    ty: (),
+                }))),
+            }),
+            expr: var_expr("val"),
+            else_part: Some(Block {
+                statement: vec![].into(),
+                expr: Some(Box::new(Expr::Jump(Jump::Return(Some(Box::new(
+                    Expr::Literal(Literal::UInt(0)),
+                )))))),
+            }),
         });
         assert_eq!(statement().easy_parse(src), Ok((expected, "")));
+        assert!(statement::<(), _>().easy_parse("x = y else 0;").is_err());
     }
 }
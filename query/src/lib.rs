@@ -0,0 +1,250 @@
+#![warn(clippy::all)]
+#![deny(clippy::correctness)]
+#![forbid(unsafe_code)]
+
+use hir::statement::Statement;
+use module::Loader;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+use syntax::{Parse, SyntaxError, SyntaxNode};
+use type_system::{Incremental, Item, Type, TypeError};
+
+mod module;
+
+pub use crate::module::{load, ModuleError};
+
+pub type Inferred = Result<Vec<Statement<Type>>, (usize, TypeError)>;
+
+// a derived value along with the revision it's computed at
+struct Memo<T> {
+    revision: u64,
+    value: T,
+}
+impl<T: Clone> Memo<T> {
+    fn get(memo: &Option<Self>, revision: u64) -> Option<T> {
+        memo.as_ref()
+            .filter(|memo| memo.revision == revision)
+            .map(|memo| memo.value.clone())
+    }
+}
+// Queries over the source of a single file. The text and the path of the file
+// are the inputs, changing either starts a new revision and derived queries are recomputed lazily
+// as they're requested. Below the file, lowering is memoized on the text of
+// each top-level statement and inference on the statements along with what
+// they use, so these survive across revisions. File modules are loaded
+// relative to the path, without one they're left unloaded.
+pub struct Database {
+    revision: u64,
+    text: Rc<str>,
+    path: Option<PathBuf>,
+    parse: Option<Memo<Rc<Parse>>>,
+    items: Option<Memo<Result<Rc<[Item]>, SyntaxError>>>,
+    lowered: HashMap<Rc<str>, Rc<Statement<()>>>,
+    infer: Option<Memo<Rc<Inferred>>>,
+    incremental: Incremental,
+}
+impl Database {
+    pub fn new(text: &str) -> Self {
+        Self {
+            revision: 0,
+            text: Rc::from(text),
+            path: None,
+            parse: None,
+            items: None,
+            lowered: HashMap::new(),
+            infer: None,
+            incremental: Incremental::new(),
+        }
+    }
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+    pub fn text(&self) -> Rc<str> {
+        self.text.clone()
+    }
+    // setting the same text keeps everything as is
+    pub fn set_text(&mut self, text: &str) {
+        if *self.text != *text {
+            self.text = Rc::from(text);
+            self.revision += 1;
+        }
+    }
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        if self.path != path {
+            self.path = path;
+            self.revision += 1;
+        }
+    }
+    pub fn parse(&mut self) -> Rc<Parse> {
+        if let Some(parse) = Memo::get(&self.parse, self.revision) {
+            return parse;
+        }
+        let parse = Rc::new(syntax::parse(&self.text));
+        self.parse = Some(Memo {
+            revision: self.revision,
+            value: parse.clone(),
+        });
+        parse
+    }
+    // the top-level statements lowered into HIR, fails with the first syntax
+    // error if there's some
+    pub fn items(&mut self) -> Result<Rc<[Item]>, SyntaxError> {
+        if let Some(items) = Memo::get(&self.items, self.revision) {
+            return items;
+        }
+        let parse = self.parse();
+        let items = match parse.errors().first() {
+            Some(error) => Err(error.clone()),
+            None => self.lower(&parse.syntax()),
+        };
+        self.items = Some(Memo {
+            revision: self.revision,
+            value: items.clone(),
+        });
+        items
+    }
+    fn lower(&mut self, root: &SyntaxNode) -> Result<Rc<[Item]>, SyntaxError> {
+        let mut lowered = HashMap::new();
+        let path = self.path.clone();
+        let mut loader = Loader::new(path.as_deref());
+        let items = root
+            .children()
+            .map(|node| {
+                let key: Rc<str> = Rc::from(node.text().to_string());
+                // errors aren't memoized as their ranges depend on where the
+                // statement is
+                let statement = match self.lowered.get(&key) {
+                    Some(statement) => statement.clone(),
+                    None => Rc::new(syntax::lower_statement(&node)?),
+                };
+                lowered.insert(key.clone(), statement.clone());
+                let Some(dir) = path.as_deref().and_then(Path::parent) else {
+                    return Ok(Item { key, statement });
+                };
+                // loaded modules aren't memoized as the files may change under
+                // the same text, their sources are part of the key instead
+                let read = loader.sources.len();
+                let mut loaded = (*statement).clone();
+                loader
+                    .statement(&mut loaded, dir)
+                    .map_err(|error| SyntaxError {
+                        message: error.to_string(),
+                        range: node.text_range(),
+                    })?;
+                if loader.sources.len() == read {
+                    return Ok(Item { key, statement });
+                }
+                let mut key = key.to_string();
+                for (path, src) in &loader.sources[read..] {
+                    key.push_str(&format!("\0{}\0{}", path.display(), src));
+                }
+                Ok(Item {
+                    key: Rc::from(key),
+                    statement: Rc::new(loaded),
+                })
+            })
+            .collect::<Result<_, _>>();
+        // only the statements of the latest text are kept
+        if items.is_ok() {
+            self.lowered = lowered;
+        }
+        items
+    }
+    // type checks the file, the error comes with the index of the top-level
+    // statement that caused it
+    pub fn infer(&mut self) -> Result<Rc<Inferred>, SyntaxError> {
+        if let Some(inferred) = Memo::get(&self.infer, self.revision) {
+            return Ok(inferred);
+        }
+        let items = self.items()?;
+        let inferred = Rc::new(self.incremental.infer(&items));
+        self.infer = Some(Memo {
+            revision: self.revision,
+            value: inferred.clone(),
+        });
+        Ok(inferred)
+    }
+    // number of top-level statements inferred in the last type check
+    pub fn inferred(&self) -> usize {
+        self.incremental.inferred()
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::Database;
+    use parser::{ast, EasyParser};
+    use std::rc::Rc;
+    use type_system::TypeError;
+
+    // type variables are renumbered in order of appearance, a statement
+    // inferred apart from the rest draws them from its own counter
+    fn renumbered(debug: String) -> String {
+        let mut vars = Vec::new();
+        let mut out = String::new();
+        let mut rest = debug.as_str();
+        while let Some(start) = rest.find("Var { name: ") {
+            let len = rest[start..].find(" }").unwrap();
+            let (before, after) = rest.split_at(start);
+            let (var, after) = after.split_at(len);
+            let (name, _) = var.rsplit_once(", id: ").unwrap();
+            let id = match vars.iter().position(|seen| *seen == var) {
+                Some(id) => id,
+                None => {
+                    vars.push(var);
+                    vars.len() - 1
+                }
+            };
+            out.push_str(before);
+            out.push_str(&format!("{name}, id: {id}"));
+            rest = after;
+        }
+        out.push_str(rest);
+        out
+    }
+    #[test]
+    fn same_as_whole() {
+        let src = "id(x) => x; foo = id(10); bar(x) => foo + x; baz = bar(id(1));";
+        let mut db = Database::new(src);
+        let (statements, _) = ast().easy_parse(src).unwrap();
+        let whole: Vec<_> = type_system::infer(statements)
+            .unwrap()
+            .into_iter()
+            .map(|statement| renumbered(format!("{:?}", statement)))
+            .collect();
+        let inferred = db.infer().unwrap();
+        let incremental: Vec<_> = inferred
+            .as_ref()
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|statement| renumbered(format!("{:?}", statement)))
+            .collect();
+        assert_eq!(incremental, whole);
+    }
+    #[test]
+    fn edit() {
+        let mut db = Database::new("double(x) => x * 2;\nfoo = double(2);\nbar = 10;");
+        db.infer().unwrap().as_ref().as_ref().unwrap();
+        assert_eq!(db.inferred(), 3);
+        let parse = db.parse();
+        db.set_text("double(x) => x * 2;\nfoo = double(2);\nbar = 10;");
+        assert!(Rc::ptr_eq(&parse, &db.parse()));
+        db.set_text("double(x) => x + x;\nfoo = double(2);\nbar = 10;");
+        db.infer().unwrap().as_ref().as_ref().unwrap();
+        assert_eq!(db.inferred(), 1);
+        db.set_text("double(x) => x ++ x;\nfoo = double(2);\nbar = 10;");
+        assert_eq!(*db.infer().unwrap(), Err((1, TypeError::MismatchCons)));
+        assert_eq!(db.inferred(), 2);
+        db.set_text("double(x) => x + x;\n\nfoo = double(2);  bar = 10;");
+        db.infer().unwrap().as_ref().as_ref().unwrap();
+        assert_eq!(db.inferred(), 0);
+        db.set_text("double(x) => x + x;\nfoo = double(2);\nbar = ;");
+        assert!(db.infer().is_err());
+    }
+}
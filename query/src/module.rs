@@ -0,0 +1,249 @@
+use hir::{
+    expr::{Expr, Module},
+    statement::{Declare, Statement},
+};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+use syntax::SyntaxError;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ModuleError {
+    Read { path: PathBuf, message: String },
+    Syntax { path: PathBuf, error: SyntaxError },
+    // the files of the cycle, starting and ending with the same file
+    Circular(Vec<PathBuf>),
+}
+impl Display for ModuleError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Read { path, message } => {
+                write!(fmt, "couldn't read {}: {}", path.display(), message)
+            }
+            Self::Syntax { path, error } => write!(fmt, "{}: {}", path.display(), error),
+            Self::Circular(paths) => {
+                let paths: Vec<_> = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                write!(fmt, "circular import: {}", paths.join(" -> "))
+            }
+        }
+    }
+}
+impl std::error::Error for ModuleError {}
+
+type Statements = Rc<[Statement<()>]>;
+
+fn module_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.butter", name))
+}
+// Replaces `mod name` of declarations with the content of `name.butter`
+// beside the file it's written in. Only top-level declarations, including the
+// ones within inline modules, are loaded.
+#[derive(Default)]
+pub(crate) struct Loader {
+    // files currently being loaded, a file found here again is a cycle
+    stack: Vec<PathBuf>,
+    // along with the range of sources read for them
+    loaded: HashMap<PathBuf, (Statements, Range<usize>)>,
+    // every file used, in the order they're used, a file used more than once
+    // is repeated
+    pub(crate) sources: Vec<(PathBuf, Rc<str>)>,
+}
+impl Loader {
+    pub(crate) fn new(file: Option<&Path>) -> Self {
+        Self {
+            stack: file.map(canonical).into_iter().collect(),
+            ..Self::default()
+        }
+    }
+    pub(crate) fn statement(
+        &mut self,
+        statement: &mut Statement<()>,
+        dir: &Path,
+    ) -> Result<(), ModuleError> {
+        if let Statement::Declare(Declare {
+            visibility: _,
+            pattern: _,
+            expr: Expr::Module(module),
+            else_part: _,
+        }) = statement
+        {
+            match module {
+                Module::File(name) => {
+                    let path = module_path(dir, name);
+                    *module = Module::Inline(self.file(&path)?.to_vec().into());
+                }
+                Module::Inline(statements) => {
+                    for statement in statements.iter_mut() {
+                        self.statement(statement, dir)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    fn file(&mut self, path: &Path) -> Result<Statements, ModuleError> {
+        let canonical = canonical(path);
+        if let Some(start) = self.stack.iter().position(|file| *file == canonical) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(canonical);
+            return Err(ModuleError::Circular(cycle));
+        }
+        if let Some((statements, sources)) = self.loaded.get(&canonical) {
+            let sources = self.sources[sources.clone()].to_vec();
+            let statements = statements.clone();
+            self.sources.extend(sources);
+            return Ok(statements);
+        }
+        let src = fs::read_to_string(path).map_err(|err| ModuleError::Read {
+            path: path.to_owned(),
+            message: err.to_string(),
+        })?;
+        let mut statements = syntax::parse(&src)
+            .lower()
+            .map_err(|error| ModuleError::Syntax {
+                path: path.to_owned(),
+                error,
+            })?;
+        let start = self.sources.len();
+        self.sources.push((path.to_owned(), Rc::from(src)));
+        let dir = path.parent().unwrap_or(Path::new(""));
+        self.stack.push(canonical.clone());
+        let result = statements
+            .iter_mut()
+            .try_for_each(|statement| self.statement(statement, dir));
+        self.stack.pop();
+        result?;
+        let statements: Rc<[_]> = statements.into();
+        let sources = start..self.sources.len();
+        self.loaded.insert(canonical, (statements.clone(), sources));
+        Ok(statements)
+    }
+}
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+// parses the file along with every module it loads
+pub fn load(path: &Path) -> Result<Vec<Statement<()>>, ModuleError> {
+    let mut loader = Loader::default();
+    Ok(loader.file(path)?.to_vec())
+}
+#[cfg(test)]
+mod test {
+    use super::{load, ModuleError};
+    use crate::Database;
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process,
+    };
+
+    // a fresh directory with the given files
+    fn files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("butter-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, src) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, src).unwrap();
+        }
+        dir
+    }
+    fn check(path: &Path) -> Result<Vec<String>, String> {
+        let statements = load(path).map_err(|err| err.to_string())?;
+        let typed = type_system::infer(statements).map_err(|err| err.to_string())?;
+        Ok(typed
+            .into_iter()
+            .filter_map(|statement| match statement {
+                hir::statement::Statement::Declare(declare) => match declare.pattern {
+                    hir::pattern::Pattern::Var(var) => Some(var.ty.to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect())
+    }
+    #[test]
+    fn qualified_access() {
+        let dir = files(
+            "qualified",
+            &[
+                (
+                    "main.butter",
+                    "math = mod math; pi = math.pi; two = math.double(1); id = math.id(true);",
+                ),
+                (
+                    "math.butter",
+                    "pub pi = 3; pub double(x) => x * 2; pub id(x) => x; pub mod inner;",
+                ),
+                ("inner.butter", "pub e = 2;"),
+            ],
+        );
+        let typed = check(&dir.join("main.butter")).unwrap();
+        assert_eq!(typed[1..], ["Num", "Num", "Bool"]);
+        assert!(typed[0].contains("inner: {e: Num}"), "{}", typed[0]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn circular() {
+        let dir = files(
+            "circular",
+            &[
+                ("main.butter", "mod a;"),
+                ("a.butter", "mod b;"),
+                ("b.butter", "x = 1; mod a;"),
+            ],
+        );
+        let err = load(&dir.join("main.butter")).unwrap_err();
+        let ModuleError::Circular(cycle) = &err else {
+            panic!("expected a circular import, found {}", err);
+        };
+        let names: Vec<_> = cycle
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["a.butter", "b.butter", "a.butter"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn missing() {
+        let dir = files("missing", &[("main.butter", "mod nothing;")]);
+        let err = load(&dir.join("main.butter")).unwrap_err();
+        assert!(matches!(err, ModuleError::Read { .. }));
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn database() {
+        let dir = files(
+            "database",
+            &[
+                ("math.butter", "pub pi = 3;"),
+                ("cycle.butter", "mod cycle;"),
+            ],
+        );
+        let src = "x = 1;\nmath = mod math;\npi = math.pi;";
+        let mut db = Database::new(src);
+        assert_eq!(
+            *db.infer().unwrap(),
+            Err((1, type_system::TypeError::UnloadedModule))
+        );
+        db.set_path(Some(dir.join("main.butter")));
+        db.infer().unwrap().as_ref().as_ref().unwrap();
+        fs::write(dir.join("math.butter"), "pub pi = true;").unwrap();
+        db.set_text(&format!("{}\n", src));
+        db.infer().unwrap().as_ref().as_ref().unwrap();
+        assert_eq!(db.inferred(), 2);
+        db.set_text("x = 1;\nmod cycle;");
+        let err = db.items().unwrap_err();
+        assert!(err.message.starts_with("circular import"), "{}", err);
+        assert_eq!(u32::from(err.range.start()), 7);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
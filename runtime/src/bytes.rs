@@ -0,0 +1,52 @@
+use crate::string::find;
+
+// The string helpers at the top of `std`, they only know about ASCII and
+// leave every other byte as it is. Their UTF-8 aware counterparts are in
+// `string`.
+
+pub fn to_upper(bytes: &[u8]) -> Vec<u8> {
+    bytes.to_ascii_uppercase()
+}
+pub fn to_lower(bytes: &[u8]) -> Vec<u8> {
+    bytes.to_ascii_lowercase()
+}
+// removes ASCII whitespace from both ends
+pub fn trim(bytes: &[u8]) -> &[u8] {
+    bytes.trim_ascii()
+}
+// an empty pattern is contained in anything
+pub fn contains(bytes: &[u8], pattern: &[u8]) -> bool {
+    find(bytes, pattern).is_some()
+}
+pub fn starts_with(bytes: &[u8], pattern: &[u8]) -> bool {
+    bytes.starts_with(pattern)
+}
+pub fn ends_with(bytes: &[u8], pattern: &[u8]) -> bool {
+    bytes.ends_with(pattern)
+}
+#[cfg(test)]
+mod test {
+    use super::{contains, ends_with, starts_with, to_lower, to_upper, trim};
+
+    #[test]
+    fn case() {
+        assert_eq!(to_upper("aZ1ß".as_bytes()), "AZ1ß".as_bytes());
+        assert_eq!(to_lower(b"Hello \xFF"), b"hello \xFF");
+    }
+    #[test]
+    fn trimming() {
+        assert_eq!(trim(b" \t a b\r\n"), b"a b");
+        assert_eq!(trim("\u{3000}a".as_bytes()), "\u{3000}a".as_bytes());
+        assert_eq!(trim(b"  "), b"");
+    }
+    #[test]
+    fn search() {
+        assert!(contains(b"butter", b"tt"));
+        assert!(contains(b"butter", b""));
+        assert!(!contains(b"butter", b"tt "));
+        assert!(starts_with(b"butter", b"but"));
+        assert!(!starts_with(b"but", b"butter"));
+        assert!(ends_with(b"butter", b"er"));
+        assert!(ends_with(b"", b""));
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+// Dicts and sets hash their keys, keys are compared by value so anything
+// that can be a key can be hashed. Neither keeps any order, iterating gives
+// the entries in whatever order they're stored.
+
+#[derive(Debug, Clone)]
+pub struct Dict<K, V>(HashMap<K, V>);
+impl<K: Eq + Hash, V> Dict<K, V> {
+    // `#(key = value, ...)`, a later entry replaces an earlier one with the
+    // same key
+    pub fn new(entries: Vec<(K, V)>) -> Self {
+        Self(entries.into_iter().collect())
+    }
+    // `dict[key]`, `None` when the key isn't there
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.0.get(key)
+    }
+    // `dict[key] <- value`, gives back the value it replaces
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.0.insert(key, value)
+    }
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.0.remove(key)
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    // what `for` over the dict goes through, the entries are the `key` and
+    // `value` fields
+    pub fn entries(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.0.iter()
+    }
+}
+#[derive(Debug, Clone)]
+pub struct Set<T>(HashSet<T>);
+impl<T: Eq + Hash> Set<T> {
+    // `#[elem, ...]`, duplicates are kept once
+    pub fn new(elems: Vec<T>) -> Self {
+        Self(elems.into_iter().collect())
+    }
+    // `set[elem]`
+    pub fn contains(&self, elem: &T) -> bool {
+        self.0.contains(elem)
+    }
+    // `set[elem] <- contained`, `true` inserts and `false` removes, gives
+    // back whether it was contained before
+    pub fn assign(&mut self, elem: T, contained: bool) -> bool {
+        if contained {
+            !self.0.insert(elem)
+        } else {
+            self.0.remove(&elem)
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn elems(&self) -> impl Iterator<Item = &T> {
+        self.0.iter()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::{Dict, Set};
+
+    #[test]
+    fn dict() {
+        let mut prices = Dict::new(vec![("apple", 3), ("banana", 2), ("apple", 4)]);
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices.get(&"apple"), Some(&4));
+        assert_eq!(prices.get(&"cherry"), None);
+        assert_eq!(prices.insert("cherry", 5), None);
+        assert_eq!(prices.insert("banana", 1), Some(2));
+        assert_eq!(prices.remove(&"apple"), Some(4));
+        let mut entries: Vec<_> = prices.entries().collect();
+        entries.sort();
+        assert_eq!(entries, [(&"banana", &1), (&"cherry", &5)]);
+        assert!(Dict::<(), ()>::new(Vec::new()).is_empty());
+    }
+    #[test]
+    fn set() {
+        let mut seen = Set::new(vec![10, 20, 30, 20]);
+        assert_eq!(seen.len(), 3);
+        assert!(seen.contains(&20));
+        assert!(!seen.contains(&40));
+        assert!(!seen.assign(40, true));
+        assert!(seen.assign(40, true));
+        assert!(seen.assign(10, false));
+        assert!(!seen.assign(10, false));
+        let mut elems: Vec<_> = seen.elems().copied().collect();
+        elems.sort();
+        assert_eq!(elems, [20, 30, 40]);
+        assert!(Set::<()>::new(Vec::new()).is_empty());
+    }
+}
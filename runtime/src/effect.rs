@@ -0,0 +1,170 @@
+use std::{collections::HashMap, rc::Rc};
+
+// Effects are delimited continuations. A computation runs until it finishes
+// or performs an effect, it's then suspended along with the rest of it up to
+// the nearest `do` handling the effect. The handler decides whether it's
+// resumed with the value of the handler or whether the whole `do` is left, as
+// with `break` or `return` in it. Continuations are one-shot, a `yield` is
+// resumed at most once.
+
+pub enum Step<'a, V, T> {
+    Done(T),
+    Yield {
+        effect: String,
+        arg: V,
+        resume: Box<dyn FnOnce(V) -> Step<'a, V, T> + 'a>,
+    },
+}
+impl<'a, V: 'a, T: 'a> Step<'a, V, T> {
+    // runs `next` with the value once it's done, effects performed before
+    // that suspend both
+    pub fn then<U>(self, next: impl FnOnce(T) -> Step<'a, V, U> + 'a) -> Step<'a, V, U> {
+        match self {
+            Self::Done(value) => next(value),
+            Self::Yield {
+                effect,
+                arg,
+                resume,
+            } => Step::Yield {
+                effect,
+                arg,
+                resume: Box::new(move |value| resume(value).then(next)),
+            },
+        }
+    }
+}
+// `yield effect(arg)`, evaluates into what it's resumed with
+pub fn perform<'a, V: 'a>(effect: &str, arg: V) -> Step<'a, V, V> {
+    Step::Yield {
+        effect: effect.to_string(),
+        arg,
+        resume: Box::new(Step::Done),
+    }
+}
+pub enum Handled<V, T> {
+    Resume(V),
+    // leaves the `do` with the value
+    Abort(T),
+}
+// the bodies of `handling { effect(param) => body }` by their effect, they
+// may perform effects of their own which are left to the surrounding code
+pub type Handlers<'a, V, T> = HashMap<String, Box<dyn Fn(V) -> Step<'a, V, Handled<V, T>> + 'a>>;
+
+// `do step handling { .. }`, the handlers stay in place once resumed so
+// every effect the computation performs goes through them
+pub fn handle<'a, V: 'a, T: 'a>(
+    step: Step<'a, V, T>,
+    handlers: Rc<Handlers<'a, V, T>>,
+) -> Step<'a, V, T> {
+    match step {
+        Step::Done(value) => Step::Done(value),
+        Step::Yield {
+            effect,
+            arg,
+            resume,
+        } => match handlers.get(&effect) {
+            Some(handler) => handler(arg).then(move |handled| match handled {
+                Handled::Resume(value) => handle(resume(value), handlers),
+                Handled::Abort(value) => Step::Done(value),
+            }),
+            // what an outer handler resumes with still goes through these
+            None => Step::Yield {
+                effect,
+                arg,
+                resume: Box::new(move |value| handle(resume(value), handlers)),
+            },
+        },
+    }
+}
+// the value of a computation with every effect handled, otherwise the
+// effect that isn't, which type checking rules out
+pub fn run<V, T>(step: Step<'_, V, T>) -> Result<T, String> {
+    match step {
+        Step::Done(value) => Ok(value),
+        Step::Yield { effect, .. } => Err(effect),
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::{handle, perform, run, Handled, Handlers, Step};
+    use std::{cell::RefCell, rc::Rc};
+
+    fn count<'a>(from: i64, to: i64) -> Step<'a, i64, ()> {
+        if from == to {
+            return Step::Done(());
+        }
+        perform("item", from).then(move |_| count(from + 1, to))
+    }
+    #[test]
+    fn generator() {
+        let items = RefCell::new(Vec::new());
+        let mut handlers: Handlers<i64, ()> = Handlers::new();
+        handlers.insert(
+            "item".to_string(),
+            Box::new(|item| {
+                items.borrow_mut().push(item);
+                Step::Done(Handled::Resume(0))
+            }),
+        );
+        assert_eq!(run(handle(count(0, 3), Rc::new(handlers))), Ok(()));
+        assert_eq!(items.into_inner(), [0, 1, 2]);
+    }
+    #[test]
+    fn exception() {
+        let divide = |a: i64, b: i64| -> Step<i64, i64> {
+            if b == 0 {
+                perform("fail", a).then(|_| unreachable!())
+            } else {
+                Step::Done(a / b)
+            }
+        };
+        let handlers = || {
+            let mut handlers: Handlers<i64, i64> = Handlers::new();
+            handlers.insert(
+                "fail".to_string(),
+                Box::new(|_| Step::Done(Handled::Abort(-1))),
+            );
+            Rc::new(handlers)
+        };
+        assert_eq!(run(handle(divide(6, 3), handlers())), Ok(2));
+        assert_eq!(run(handle(divide(6, 0), handlers())), Ok(-1));
+    }
+    #[test]
+    fn forwarded() {
+        let logged = RefCell::new(Vec::new());
+        let body = perform("ask", 0).then(|x| perform("item", x).then(move |y| Step::Done(x + y)));
+        let mut inner: Handlers<i64, i64> = Handlers::new();
+        inner.insert(
+            "item".to_string(),
+            Box::new(|item| {
+                perform("log", item).then(move |_| Step::Done(Handled::Resume(item * 2)))
+            }),
+        );
+        let mut outer: Handlers<i64, i64> = Handlers::new();
+        outer.insert(
+            "ask".to_string(),
+            Box::new(|_| Step::Done(Handled::Resume(10))),
+        );
+        outer.insert(
+            "log".to_string(),
+            Box::new(|item| {
+                logged.borrow_mut().push(item);
+                Step::Done(Handled::Resume(0))
+            }),
+        );
+        let step = handle(handle(body, Rc::new(inner)), Rc::new(outer));
+        assert_eq!(run(step), Ok(30));
+        assert_eq!(logged.into_inner(), [10]);
+    }
+    #[test]
+    fn unhandled() {
+        assert_eq!(run(perform::<i64>("ask", 0)), Err("ask".to_string()));
+        let mut handlers: Handlers<i64, i64> = Handlers::new();
+        handlers.insert(
+            "item".to_string(),
+            Box::new(|_| Step::Done(Handled::Resume(0))),
+        );
+        let step = handle(perform("ask", 0), Rc::new(handlers));
+        assert_eq!(run(step), Err("ask".to_string()));
+    }
+}
@@ -0,0 +1,69 @@
+use std::io::{self, BufRead, Write};
+
+// Strings are written and read as their bytes, nothing checks they're UTF-8.
+// Backends pass the standard output and input.
+
+pub fn print(out: &mut impl Write, text: &[u8]) -> io::Result<()> {
+    out.write_all(text)?;
+    out.flush()
+}
+pub fn print_line(out: &mut impl Write, text: &[u8]) -> io::Result<()> {
+    out.write_all(text)?;
+    out.write_all(b"\n")?;
+    out.flush()
+}
+// without the `\n` that ends it, or `\r\n`. Empty once the input ends
+pub fn get_line(input: &mut impl BufRead) -> io::Result<Vec<u8>> {
+    let mut line = Vec::new();
+    input.read_until(b'\n', &mut line)?;
+    if line.ends_with(b"\n") {
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+    }
+    Ok(line)
+}
+// Panics end the program, the message is shown as text with ill-formed parts
+// replaced
+pub fn assert(condition: bool) {
+    if !condition {
+        panic(b"assertion failed");
+    }
+}
+pub fn panic(message: &[u8]) -> ! {
+    panic!("{}", String::from_utf8_lossy(message))
+}
+#[cfg(test)]
+mod test {
+    use super::{assert, get_line, panic, print, print_line};
+
+    #[test]
+    fn output() {
+        let mut out = Vec::new();
+        print(&mut out, b"a").unwrap();
+        print_line(&mut out, b"b").unwrap();
+        print_line(&mut out, b"").unwrap();
+        assert_eq!(out, b"ab\n\n");
+    }
+    #[test]
+    fn input() {
+        let mut input = &b"first\r\nsecond\n\nlast"[..];
+        assert_eq!(get_line(&mut input).unwrap(), b"first");
+        assert_eq!(get_line(&mut input).unwrap(), b"second");
+        assert_eq!(get_line(&mut input).unwrap(), b"");
+        assert_eq!(get_line(&mut input).unwrap(), b"last");
+        assert_eq!(get_line(&mut input).unwrap(), b"");
+    }
+    #[test]
+    #[should_panic(expected = "assertion failed")]
+    fn failed_assert() {
+        assert(true);
+        assert(false);
+    }
+    #[test]
+    #[should_panic(expected = "bad \u{FFFD}")]
+    fn panicked() {
+        panic(b"bad \xFF");
+    }
+}
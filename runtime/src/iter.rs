@@ -0,0 +1,128 @@
+use std::ops::Bound;
+
+// Iterators are first-class values, every adaptor boxes the one it wraps so
+// they all share a single type however they're built. `for` over an array, a
+// set or a dict goes through it directly instead.
+
+pub struct Iter<'a, T>(Box<dyn Iterator<Item = T> + 'a>);
+impl<'a, T> Iter<'a, T> {
+    pub fn new(iter: impl Iterator<Item = T> + 'a) -> Self {
+        Self(Box::new(iter))
+    }
+}
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.next()
+    }
+}
+pub fn from<'a, T: 'a>(items: Vec<T>) -> Iter<'a, T> {
+    Iter::new(items.into_iter())
+}
+// `next` gives a value along with the state it's called with next time,
+// `None` ends the iterator
+pub fn unfold<'a, S, T>(state: S, mut next: impl FnMut(S) -> Option<(T, S)> + 'a) -> Iter<'a, T>
+where
+    S: 'a,
+    T: 'a,
+{
+    let mut state = Some(state);
+    Iter::new(std::iter::from_fn(move || {
+        let (value, new_state) = next(state.take()?)?;
+        state = Some(new_state);
+        Some(value)
+    }))
+}
+pub fn map<'a, T: 'a, U>(iter: Iter<'a, T>, f: impl FnMut(T) -> U + 'a) -> Iter<'a, U> {
+    Iter::new(iter.map(f))
+}
+pub fn filter<'a, T: 'a>(iter: Iter<'a, T>, mut f: impl FnMut(&T) -> bool + 'a) -> Iter<'a, T> {
+    Iter::new(iter.filter(move |item| f(item)))
+}
+// stops as soon as either one does
+pub fn zip<'a, T: 'a, U: 'a>(left: Iter<'a, T>, right: Iter<'a, U>) -> Iter<'a, (T, U)> {
+    Iter::new(left.zip(right))
+}
+pub fn enumerate<'a, T: 'a>(iter: Iter<'a, T>) -> Iter<'a, (usize, T)> {
+    Iter::new(iter.enumerate())
+}
+pub fn collect<T>(iter: Iter<'_, T>) -> Vec<T> {
+    iter.collect()
+}
+// What `for` over a range written in place counts with, see `For::counted`,
+// it never allocates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter {
+    next: i64,
+    // exclusive, `None` counts forever
+    end: Option<i64>,
+}
+impl Counter {
+    // `None` when there's no start to count from
+    pub fn new(start: Bound<i64>, end: Bound<i64>) -> Option<Self> {
+        let next = match start {
+            Bound::Included(start) => start,
+            Bound::Excluded(start) => start + 1,
+            Bound::Unbounded => return None,
+        };
+        let end = match end {
+            Bound::Included(end) => Some(end + 1),
+            Bound::Excluded(end) => Some(end),
+            Bound::Unbounded => None,
+        };
+        Some(Self { next, end })
+    }
+}
+impl Iterator for Counter {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.end.is_some_and(|end| self.next >= end) {
+            return None;
+        }
+        let next = self.next;
+        self.next += 1;
+        Some(next)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::{collect, enumerate, filter, from, map, unfold, zip, Counter};
+    use std::ops::Bound;
+
+    #[test]
+    fn adaptors() {
+        let evens = filter(from((0..10).collect()), |num| num % 2 == 0);
+        let squares = map(evens, |num| num * num);
+        let letters = from(vec!['a', 'b', 'c']);
+        let pairs = collect(zip(enumerate(squares), letters));
+        assert_eq!(pairs, [((0, 0), 'a'), ((1, 4), 'b'), ((2, 16), 'c')]);
+    }
+    #[test]
+    fn unfold_state() {
+        let powers = unfold(1, |num| (num < 100).then_some((num, num * 2)));
+        assert_eq!(collect(powers), [1, 2, 4, 8, 16, 32, 64]);
+        let empty = unfold((), |()| None::<((), ())>);
+        assert_eq!(collect(empty), []);
+    }
+    #[test]
+    fn counter() {
+        let counted = |start, end| Counter::new(start, end).map(Iterator::collect::<Vec<_>>);
+        assert_eq!(
+            counted(Bound::Included(0), Bound::Excluded(3)),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(
+            counted(Bound::Excluded(0), Bound::Included(3)),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(
+            counted(Bound::Included(3), Bound::Excluded(3)),
+            Some(vec![])
+        );
+        assert_eq!(counted(Bound::Unbounded, Bound::Excluded(3)), None);
+        let endless = Counter::new(Bound::Included(-2), Bound::Unbounded).unwrap();
+        assert_eq!(endless.take(3).collect::<Vec<_>>(), [-2, -1, 0]);
+    }
+}
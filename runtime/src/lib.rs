@@ -0,0 +1,14 @@
+#![warn(clippy::all)]
+#![deny(clippy::correctness)]
+#![forbid(unsafe_code)]
+
+// Reference implementations of the builtins in `std`, the semantics every
+// backend has to agree with
+
+pub mod bytes;
+pub mod dict;
+pub mod effect;
+pub mod io;
+pub mod iter;
+pub mod number;
+pub mod string;
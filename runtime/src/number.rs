@@ -0,0 +1,142 @@
+use hir::expr::Literal;
+
+// Numbers are floats, integers are those without a fractional part. The
+// text of a number is what a number literal would be, so `parse_num` reads
+// back whatever `to_str` writes.
+
+pub fn abs(number: f64) -> f64 {
+    number.abs()
+}
+// NaN for negative numbers
+pub fn sqrt(number: f64) -> f64 {
+    number.sqrt()
+}
+pub fn floor(number: f64) -> f64 {
+    number.floor()
+}
+pub fn ceil(number: f64) -> f64 {
+    number.ceil()
+}
+// halves are rounded away from zero
+pub fn round(number: f64) -> f64 {
+    number.round()
+}
+// a NaN is only given back when both are
+pub fn min(left: f64, right: f64) -> f64 {
+    left.min(right)
+}
+pub fn max(left: f64, right: f64) -> f64 {
+    left.max(right)
+}
+// The fewest digits that still read back as the same number. Numbers from
+// 1e-6 up to 1e19 are written in full, the rest in E-notation as integer
+// literals can't go much further. Neither NaN
+// nor the infinities have a literal, they're written `NaN`, `inf` and `-inf`.
+pub fn to_str(number: f64) -> Vec<u8> {
+    if number.is_nan() {
+        return b"NaN".to_vec();
+    }
+    let sign = if number.is_sign_negative() { "-" } else { "" };
+    if number.is_infinite() {
+        return format!("{}inf", sign).into_bytes();
+    }
+    // `{:e}` already gives the shortest digits
+    let scientific = format!("{:e}", number.abs());
+    let (mantissa, exp) = scientific.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    let digits: String = mantissa.chars().filter(|ch| *ch != '.').collect();
+    let text = if !(-6..19).contains(&exp) {
+        scientific
+    } else if exp < 0 {
+        format!("0.{}{}", "0".repeat((-exp - 1) as usize), digits)
+    } else {
+        let whole = exp as usize + 1;
+        if digits.len() <= whole {
+            format!("{}{}", digits, "0".repeat(whole - digits.len()))
+        } else {
+            format!("{}.{}", &digits[..whole], &digits[whole..])
+        }
+    };
+    format!("{}{}", sign, text).into_bytes()
+}
+// a number literal optionally preceded by `-`, `None` when it's anything
+// else, surrounding whitespace included
+pub fn parse_num(text: &[u8]) -> Option<f64> {
+    let text = std::str::from_utf8(text).ok()?;
+    let (negative, literal) = match text.strip_prefix('-') {
+        Some(literal) => (true, literal),
+        None => (false, text),
+    };
+    let number = match syntax::number_literal(literal)? {
+        Literal::UInt(number) => number as f64,
+        Literal::Float(number) => number,
+        Literal::True | Literal::False => return None,
+    };
+    Some(if negative { -number } else { number })
+}
+#[cfg(test)]
+mod test {
+    use super::{max, min, parse_num, round, to_str};
+
+    #[test]
+    fn rounding() {
+        assert_eq!(round(2.5), 3.0);
+        assert_eq!(round(-2.5), -3.0);
+        assert_eq!(round(2.4), 2.0);
+        assert_eq!(round(-0.4), -0.0);
+        assert_eq!(min(1.0, f64::NAN), 1.0);
+        assert_eq!(max(f64::NAN, 1.0), 1.0);
+        assert!(min(f64::NAN, f64::NAN).is_nan());
+    }
+    #[test]
+    fn text() {
+        let text = |number| String::from_utf8(to_str(number)).unwrap();
+        assert_eq!(text(0.0), "0");
+        assert_eq!(text(-0.0), "-0");
+        assert_eq!(text(42.0), "42");
+        assert_eq!(text(-1.5), "-1.5");
+        assert_eq!(text(0.1), "0.1");
+        assert_eq!(text(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(text(0.000001), "0.000001");
+        assert_eq!(text(0.0000001), "1e-7");
+        assert_eq!(text(1e18), "1000000000000000000");
+        assert_eq!(text(1e19), "1e19");
+        assert_eq!(text(1.5e300), "1.5e300");
+        assert_eq!(text(f64::NAN), "NaN");
+        assert_eq!(text(f64::NEG_INFINITY), "-inf");
+    }
+    #[test]
+    fn parse() {
+        assert_eq!(parse_num(b"42"), Some(42.0));
+        assert_eq!(parse_num(b"-1_000"), Some(-1000.0));
+        assert_eq!(parse_num(b"0xff"), Some(255.0));
+        assert_eq!(parse_num(b".5"), Some(0.5));
+        assert_eq!(parse_num(b"4_e-7"), Some(4e-7));
+        assert_eq!(parse_num(b""), None);
+        assert_eq!(parse_num(b" 1"), None);
+        assert_eq!(parse_num(b"1."), None);
+        assert_eq!(parse_num(b"1 + 2"), None);
+        assert_eq!(parse_num(b"--1"), None);
+        assert_eq!(parse_num(b"true"), None);
+        assert_eq!(parse_num(b"NaN"), None);
+        assert_eq!(parse_num(b"\xFF"), None);
+    }
+    #[test]
+    fn round_trip() {
+        let mut number: f64 = 1.0;
+        for _ in 0..2000 {
+            for number in [number, -number, 1.0 / number, number.sqrt()] {
+                let parsed = parse_num(&to_str(number)).unwrap();
+                assert_eq!(parsed.to_bits(), number.to_bits(), "{}", number);
+            }
+            number = number * 1.7 + 0.3;
+            if number.is_infinite() {
+                number = 0.1;
+            }
+        }
+        for number in [0.0, -0.0, f64::MIN_POSITIVE, 5e-324, f64::MAX, f64::EPSILON] {
+            let parsed = parse_num(&to_str(number)).unwrap();
+            assert_eq!(parsed.to_bits(), number.to_bits(), "{}", number);
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::ops::RangeInclusive;
+
+// Strings are plain arrays of bytes meant to be UTF-8, nothing guarantees
+// they are so everything here copes with ill-formed input. Indices and
+// lengths are always counted in bytes.
+
+pub const REPLACEMENT: char = '\u{FFFD}';
+const CONTINUATION: RangeInclusive<u8> = 0x80..=0xBF;
+
+// the second byte is narrower after some leading bytes, this rules out
+// overlong encodings, surrogates and code points past U+10FFFF
+fn second_byte(first: u8) -> RangeInclusive<u8> {
+    match first {
+        0xE0 => 0xA0..=0xBF,
+        0xED => 0x80..=0x9F,
+        0xF0 => 0x90..=0xBF,
+        0xF4 => 0x80..=0x8F,
+        _ => CONTINUATION,
+    }
+}
+// the length of the well-formed sequence starting at the index, otherwise
+// the length of its longest prefix, which is replaced as a whole when decoded
+// lossily
+fn sequence(bytes: &[u8], index: usize) -> Result<usize, usize> {
+    let first = bytes[index];
+    let len = match first {
+        0x00..=0x7F => 1,
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return Err(1),
+    };
+    let mut valid = 1;
+    while valid < len {
+        let range = if valid == 1 {
+            second_byte(first)
+        } else {
+            CONTINUATION
+        };
+        match bytes.get(index + valid) {
+            Some(byte) if range.contains(byte) => valid += 1,
+            _ => return Err(valid),
+        }
+    }
+    Ok(len)
+}
+fn decode(bytes: &[u8], index: usize, len: usize) -> char {
+    let first = bytes[index];
+    let lead = match len {
+        1 => first,
+        2 => first & 0x1F,
+        3 => first & 0x0F,
+        _ => first & 0x07,
+    };
+    let code = bytes[index + 1..index + len]
+        .iter()
+        .fold(lead as u32, |code, byte| code << 6 | (byte & 0x3F) as u32);
+    // well-formed sequences only ever encode scalar values
+    char::from_u32(code).unwrap()
+}
+// the character starting at the index along with its length, `None` when
+// there's no well-formed sequence there
+pub fn decode_char(bytes: &[u8], index: usize) -> Option<(char, usize)> {
+    if index >= bytes.len() {
+        return None;
+    }
+    let len = sequence(bytes, index).ok()?;
+    Some((decode(bytes, index, len), len))
+}
+// `None` for surrogates and numbers past U+10FFFF
+pub fn encode_char(code: u32) -> Option<Vec<u8>> {
+    if (0xD800..=0xDFFF).contains(&code) {
+        return None;
+    }
+    let continuation = |shift: u32| 0x80 | (code >> shift & 0x3F) as u8;
+    let bytes = match code {
+        0..=0x7F => vec![code as u8],
+        0x80..=0x7FF => vec![0xC0 | (code >> 6) as u8, continuation(0)],
+        0x800..=0xFFFF => vec![0xE0 | (code >> 12) as u8, continuation(6), continuation(0)],
+        0x10000..=0x10FFFF => vec![
+            0xF0 | (code >> 18) as u8,
+            continuation(12),
+            continuation(6),
+            continuation(0),
+        ],
+        _ => return None,
+    };
+    Some(bytes)
+}
+// the length of the longest well-formed prefix
+pub fn valid_up_to(bytes: &[u8]) -> usize {
+    let mut index = 0;
+    while index < bytes.len() {
+        match sequence(bytes, index) {
+            Ok(len) => index += len,
+            Err(_) => break,
+        }
+    }
+    index
+}
+pub fn is_utf8(bytes: &[u8]) -> bool {
+    valid_up_to(bytes) == bytes.len()
+}
+// Iterates over the characters, each ill-formed part becomes a single
+// replacement character
+#[derive(Debug, Clone)]
+pub struct Chars<'a> {
+    bytes: &'a [u8],
+    index: usize,
+}
+impl Iterator for Chars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.index >= self.bytes.len() {
+            return None;
+        }
+        let ch = match sequence(self.bytes, self.index) {
+            Ok(len) => {
+                let ch = decode(self.bytes, self.index, len);
+                self.index += len;
+                ch
+            }
+            Err(len) => {
+                self.index += len;
+                REPLACEMENT
+            }
+        };
+        Some(ch)
+    }
+}
+pub fn chars(bytes: &[u8]) -> Chars<'_> {
+    Chars { bytes, index: 0 }
+}
+pub fn byte_len(bytes: &[u8]) -> usize {
+    bytes.len()
+}
+pub fn char_len(bytes: &[u8]) -> usize {
+    chars(bytes).count()
+}
+// the end counts as a boundary
+pub fn is_char_boundary(bytes: &[u8], index: usize) -> bool {
+    index == bytes.len()
+        || bytes
+            .get(index)
+            .is_some_and(|byte| !CONTINUATION.contains(byte))
+}
+// `None` when either end is out of bounds or in the middle of a character
+pub fn slice(bytes: &[u8], start: usize, end: usize) -> Option<&[u8]> {
+    if start <= end && is_char_boundary(bytes, start) && is_char_boundary(bytes, end) {
+        Some(&bytes[start..end])
+    } else {
+        None
+    }
+}
+// the index of the first occurrence, an empty pattern is found at the start
+pub fn find(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    bytes
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+}
+// an empty separator doesn't split at all
+pub fn split<'a>(bytes: &'a [u8], separator: &[u8]) -> Vec<&'a [u8]> {
+    if separator.is_empty() {
+        return vec![bytes];
+    }
+    let mut pieces = Vec::new();
+    let mut rest = bytes;
+    while let Some(index) = find(rest, separator) {
+        pieces.push(&rest[..index]);
+        rest = &rest[index + separator.len()..];
+    }
+    pieces.push(rest);
+    pieces
+}
+// removes whitespace as defined by Unicode from both ends, trimming stops at
+// any ill-formed part
+pub fn trim(bytes: &[u8]) -> &[u8] {
+    let mut start = 0;
+    while let Some((ch, len)) = decode_char(bytes, start) {
+        if !ch.is_whitespace() {
+            break;
+        }
+        start += len;
+    }
+    let mut end = bytes.len();
+    while end > start {
+        let Some(index) = (start.max(end.saturating_sub(4))..end)
+            .rev()
+            .find(|index| !CONTINUATION.contains(&bytes[*index]))
+        else {
+            break;
+        };
+        match decode_char(bytes, index) {
+            Some((ch, len)) if index + len == end && ch.is_whitespace() => end = index,
+            _ => break,
+        }
+    }
+    &bytes[start..end]
+}
+#[cfg(test)]
+mod test {
+    use super::{
+        byte_len, char_len, chars, decode_char, encode_char, find, is_utf8, slice, split, trim,
+        valid_up_to,
+    };
+
+    // bytes that fall on every edge of the ranges in the decoder
+    const EDGES: [u8; 21] = [
+        0x00, 0x41, 0x7F, 0x80, 0x8F, 0x90, 0x9F, 0xA0, 0xBF, 0xC0, 0xC1, 0xC2, 0xDF, 0xE0, 0xED,
+        0xEE, 0xEF, 0xF0, 0xF4, 0xF5, 0xFF,
+    ];
+    fn assert_like_std(bytes: &[u8]) {
+        let valid = std::str::from_utf8(bytes).map_or_else(|err| err.valid_up_to(), str::len);
+        assert_eq!(valid_up_to(bytes), valid, "{:x?}", bytes);
+        assert_eq!(is_utf8(bytes), valid == bytes.len(), "{:x?}", bytes);
+        let lossy: String = chars(bytes).collect();
+        assert_eq!(lossy, String::from_utf8_lossy(bytes), "{:x?}", bytes);
+    }
+    #[test]
+    fn decode_like_std() {
+        for first in 0..=0xFF {
+            for second in 0..=0xFF {
+                assert_like_std(&[first, second]);
+            }
+        }
+        for a in EDGES {
+            for b in EDGES {
+                for c in EDGES {
+                    assert_like_std(&[a, b, c]);
+                    for d in EDGES {
+                        assert_like_std(&[a, b, c, d]);
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn encode_like_std() {
+        for code in 0..=0x11_0000 {
+            let expected = char::from_u32(code).map(|ch| ch.to_string().into_bytes());
+            assert_eq!(encode_char(code), expected, "{:x}", code);
+        }
+    }
+    #[test]
+    fn decode_at_index() {
+        let text = "aß💣".as_bytes();
+        assert_eq!(decode_char(text, 0), Some(('a', 1)));
+        assert_eq!(decode_char(text, 1), Some(('ß', 2)));
+        assert_eq!(decode_char(text, 2), None);
+        assert_eq!(decode_char(text, 3), Some(('💣', 4)));
+        assert_eq!(decode_char(text, 7), None);
+        assert_eq!(char_len(text), 3);
+        assert_eq!(byte_len(text), 7);
+    }
+    #[test]
+    fn slicing() {
+        let text = "ℝ=x".as_bytes();
+        assert_eq!(slice(text, 0, 3), Some("ℝ".as_bytes()));
+        assert_eq!(slice(text, 3, 5), Some("=x".as_bytes()));
+        assert_eq!(slice(text, 5, 5), Some(&[][..]));
+        assert_eq!(slice(text, 1, 3), None);
+        assert_eq!(slice(text, 3, 6), None);
+        assert_eq!(slice(text, 4, 3), None);
+    }
+    #[test]
+    fn search() {
+        let text = "a, b,, ℝ".as_bytes();
+        assert_eq!(find(text, b","), Some(1));
+        assert_eq!(find(text, "ℝ".as_bytes()), Some(7));
+        assert_eq!(find(text, b"c"), None);
+        assert_eq!(find(text, b""), Some(0));
+        let pieces: Vec<_> = split(text, b",")
+            .into_iter()
+            .map(|piece| piece.to_vec())
+            .collect();
+        assert_eq!(pieces, [&b"a"[..], b" b", b"", " ℝ".as_bytes()]);
+        assert_eq!(split(b"", b","), [b""]);
+        assert_eq!(split(text, b""), [text]);
+    }
+    #[test]
+    fn trimming() {
+        assert_eq!(trim("\u{3000} a b\n\u{85}".as_bytes()), b"a b");
+        assert_eq!(trim(b" \t\n"), b"");
+        assert_eq!(trim(b" \xFF "), b"\xFF");
+        assert_eq!(trim(b"\xE3\x80 a"), b"\xE3\x80 a");
+    }
+}
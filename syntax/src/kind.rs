@@ -0,0 +1,274 @@
+macro_rules! syntax_kinds {
+    ($($kind:ident),* $(,)?) => {
+        #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+        #[repr(u16)]
+        pub enum SyntaxKind {
+            $($kind),*
+        }
+        const KINDS: &[SyntaxKind] = &[$(SyntaxKind::$kind),*];
+    };
+}
+syntax_kinds! {
+    // trivia
+    Whitespace,
+    Comment,
+    Shebang,
+
+    // tokens
+    Ident,
+    // `#name`, a private field
+    PrivateIdent,
+    Number,
+    Char,
+    Str,
+    // `'name`, the label of a loop
+    Label,
+    Error,
+
+    UnderscoreKw,
+    AsKw,
+    BreakKw,
+    CloneKw,
+    ContinueKw,
+    DoKw,
+    ElseKw,
+    FalseKw,
+    ForKw,
+    HandlingKw,
+    IfKw,
+    InKw,
+    LoopKw,
+    MatchKw,
+    ModKw,
+    MutKw,
+    NewtypeKw,
+    PubKw,
+    RefKw,
+    ReturnKw,
+    TrueKw,
+    WhileKw,
+    YieldKw,
+
+    LeftArrow,
+    FatArrow,
+    PlusPlus,
+    EqualEqual,
+    EqualColon,
+    BangEqual,
+    LessEqual,
+    GreaterEqual,
+    AmpersandAmpersand,
+    PipePipe,
+    PipeGreater,
+    SlashSlash,
+    DotDot,
+    DotLess,
+    GreaterDot,
+    GreaterLess,
+    HashParen,
+    HashBracket,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Colon,
+    Semicolon,
+    Equal,
+    Dot,
+    Caret,
+    At,
+    Star,
+    Ampersand,
+    Bang,
+    Minus,
+    Plus,
+    Slash,
+    Percent,
+    Less,
+    Greater,
+    Question,
+    Pipe,
+
+    // nodes
+    Root,
+    ErrorNode,
+
+    Declare,
+    FunDeclare,
+    ModuleDeclare,
+    NewTypeDeclare,
+    NewTypeField,
+    Visibility,
+    ExprStatement,
+
+    Literal,
+    NameRef,
+    TagExpr,
+    UnaryExpr,
+    BinaryExpr,
+    AssignExpr,
+    ParenExpr,
+    TupleExpr,
+    RecordExpr,
+    Field,
+    Splat,
+    // `?` of a partial application
+    Placeholder,
+    ArrayExpr,
+    DictExpr,
+    Entry,
+    SetExpr,
+    RangeExpr,
+    FunExpr,
+    ParamList,
+    Param,
+    CallExpr,
+    FieldAccess,
+    IndexExpr,
+    SliceExpr,
+    DerefExpr,
+    Block,
+    IfExpr,
+    MatchCondition,
+    ForExpr,
+    WhileExpr,
+    LoopExpr,
+    MatchExpr,
+    MatchArm,
+    BreakExpr,
+    ContinueExpr,
+    ReturnExpr,
+    YieldExpr,
+    HandleExpr,
+    Handler,
+    ModuleExpr,
+
+    VarPattern,
+    IgnorePattern,
+    LiteralPattern,
+    TagPattern,
+    RefPattern,
+    ParenPattern,
+    RecordPattern,
+    FieldPattern,
+    RestPattern,
+    ArrayPattern,
+    NewTypePattern,
+    OrPattern,
+    RangePattern,
+    AsPattern,
+    EqualPattern,
+
+    InferType,
+    ArrayType,
+    RefType,
+    NamedType,
+}
+// punctuations, longer ones come first so they're matched greedily
+pub(crate) const PUNCTS: [(&str, SyntaxKind); 42] = [
+    ("<-", SyntaxKind::LeftArrow),
+    ("=>", SyntaxKind::FatArrow),
+    ("++", SyntaxKind::PlusPlus),
+    ("==", SyntaxKind::EqualEqual),
+    ("=:", SyntaxKind::EqualColon),
+    ("!=", SyntaxKind::BangEqual),
+    ("<=", SyntaxKind::LessEqual),
+    (">=", SyntaxKind::GreaterEqual),
+    ("&&", SyntaxKind::AmpersandAmpersand),
+    ("||", SyntaxKind::PipePipe),
+    ("|>", SyntaxKind::PipeGreater),
+    ("//", SyntaxKind::SlashSlash),
+    ("..", SyntaxKind::DotDot),
+    (".<", SyntaxKind::DotLess),
+    (">.", SyntaxKind::GreaterDot),
+    ("><", SyntaxKind::GreaterLess),
+    ("#(", SyntaxKind::HashParen),
+    ("#[", SyntaxKind::HashBracket),
+    ("(", SyntaxKind::LeftParen),
+    (")", SyntaxKind::RightParen),
+    ("[", SyntaxKind::LeftBracket),
+    ("]", SyntaxKind::RightBracket),
+    ("{", SyntaxKind::LeftBrace),
+    ("}", SyntaxKind::RightBrace),
+    (",", SyntaxKind::Comma),
+    (":", SyntaxKind::Colon),
+    (";", SyntaxKind::Semicolon),
+    ("=", SyntaxKind::Equal),
+    (".", SyntaxKind::Dot),
+    ("^", SyntaxKind::Caret),
+    ("@", SyntaxKind::At),
+    ("*", SyntaxKind::Star),
+    ("&", SyntaxKind::Ampersand),
+    ("!", SyntaxKind::Bang),
+    ("-", SyntaxKind::Minus),
+    ("+", SyntaxKind::Plus),
+    ("/", SyntaxKind::Slash),
+    ("%", SyntaxKind::Percent),
+    ("<", SyntaxKind::Less),
+    (">", SyntaxKind::Greater),
+    ("?", SyntaxKind::Question),
+    ("|", SyntaxKind::Pipe),
+];
+impl SyntaxKind {
+    pub fn is_trivia(self) -> bool {
+        matches!(self, Self::Whitespace | Self::Comment | Self::Shebang)
+    }
+    pub fn is_keyword(self) -> bool {
+        (Self::UnderscoreKw..=Self::YieldKw).contains(&self)
+    }
+    pub fn is_punct(self) -> bool {
+        (Self::LeftArrow..=Self::Pipe).contains(&self)
+    }
+    pub fn is_range(self) -> bool {
+        matches!(
+            self,
+            Self::DotDot | Self::DotLess | Self::GreaterDot | Self::GreaterLess
+        )
+    }
+    pub fn keyword(ident: &str) -> Option<Self> {
+        let keyword = match ident {
+            "_" => Self::UnderscoreKw,
+            "as" => Self::AsKw,
+            "break" => Self::BreakKw,
+            "clone" => Self::CloneKw,
+            "continue" => Self::ContinueKw,
+            "do" => Self::DoKw,
+            "else" => Self::ElseKw,
+            "false" => Self::FalseKw,
+            "for" => Self::ForKw,
+            "handling" => Self::HandlingKw,
+            "if" => Self::IfKw,
+            "in" => Self::InKw,
+            "loop" => Self::LoopKw,
+            "match" => Self::MatchKw,
+            "mod" => Self::ModKw,
+            "mut" => Self::MutKw,
+            "newtype" => Self::NewtypeKw,
+            "pub" => Self::PubKw,
+            "ref" => Self::RefKw,
+            "return" => Self::ReturnKw,
+            "true" => Self::TrueKw,
+            "while" => Self::WhileKw,
+            "yield" => Self::YieldKw,
+            _ => return None,
+        };
+        Some(keyword)
+    }
+}
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Butter {}
+impl rowan::Language for Butter {
+    type Kind = SyntaxKind;
+    fn kind_from_raw(raw: rowan::SyntaxKind) -> SyntaxKind {
+        KINDS[raw.0 as usize]
+    }
+    fn kind_to_raw(kind: SyntaxKind) -> rowan::SyntaxKind {
+        rowan::SyntaxKind(kind as u16)
+    }
+}
+pub type SyntaxNode = rowan::SyntaxNode<Butter>;
+pub type SyntaxToken = rowan::SyntaxToken<Butter>;
+pub type SyntaxElement = rowan::SyntaxElement<Butter>;
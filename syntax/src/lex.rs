@@ -0,0 +1,243 @@
+use crate::{
+    kind::{SyntaxKind, PUNCTS},
+    SyntaxError,
+};
+use rowan::{TextRange, TextSize};
+use std::ops::Range;
+
+// a range within the token and its message
+type Error = (Range<usize>, &'static str);
+
+fn rest(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+fn take_while(src: &str, predicate: impl Fn(char) -> bool) -> usize {
+    src.find(|ch| !predicate(ch)).unwrap_or(src.len())
+}
+// the length and whether it's closed, it can't span lines
+fn quoted(src: &str, delimiter: char) -> (usize, bool) {
+    let mut chars = src.char_indices().skip(1);
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                chars.next();
+            }
+            '\n' => return (i, false),
+            ch if ch == delimiter => return (i + 1, true),
+            _ => (),
+        }
+    }
+    (src.len(), false)
+}
+// length of the name after `'`, zero if it's a char literal instead
+fn label(src: &str) -> usize {
+    let src = &src[1..];
+    if !src.starts_with(|ch: char| rest(ch) && !ch.is_ascii_digit()) {
+        return 0;
+    }
+    let len = take_while(src, rest);
+    if src[len..].starts_with('\'') {
+        0
+    } else {
+        len
+    }
+}
+fn number(src: &str) -> usize {
+    let radix = src.starts_with("0x") || src.starts_with("0o") || src.starts_with("0b");
+    let mut len = 0;
+    loop {
+        len += take_while(&src[len..], rest);
+        let after = &src[len..];
+        let digit_follows = |i| after[i..].starts_with(|ch: char| ch.is_ascii_digit());
+        let fraction = after.starts_with('.') && digit_follows(1) && !src[..len].contains('.');
+        let exponent_sign = src[..len].trim_end_matches('_').ends_with(['e', 'E'])
+            && after.starts_with(['+', '-'])
+            && after[1..].starts_with(|ch: char| ch.is_ascii_digit() || ch == '_');
+        if radix || !(fraction || exponent_sign) {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+// the `-/` closing a block comment, which doesn't nest
+fn block_comment(src: &str) -> (usize, Option<Error>) {
+    match src[2..].find("-/") {
+        Some(i) => (i + 4, None),
+        None => (src.len(), Some((0..src.len(), "unterminated comment"))),
+    }
+}
+// `{- -}` nests and its content is still split into tokens, so a `-}` within a
+// string doesn't close it
+fn ignore_block(src: &str) -> (usize, Option<Error>) {
+    let mut len = 2;
+    let mut error = None;
+    while len < src.len() {
+        if src[len..].starts_with("-}") {
+            return (len + 2, error);
+        }
+        let (_, token_len, token_error) = token(&src[len..]);
+        error = error.or_else(|| {
+            token_error.map(|(range, message)| (range.start + len..range.end + len, message))
+        });
+        len += token_len;
+    }
+    (src.len(), Some((0..src.len(), "unterminated ignore block")))
+}
+// `#"` up to the first `"#`, there are no escapes
+fn raw_string(src: &str) -> (usize, Option<Error>) {
+    match src[2..].find("\"#") {
+        Some(i) => (i + 4, None),
+        None => (src.len(), Some((0..src.len(), "unterminated raw string"))),
+    }
+}
+// `` `name` ``, an identifier which may be a keyword
+fn raw_ident(src: &str) -> (usize, Option<Error>) {
+    let len = 1 + take_while(&src[1..], rest);
+    if src[len..].starts_with('`') {
+        (len + 1, None)
+    } else {
+        (len, Some((0..len, "unterminated raw identifier")))
+    }
+}
+// the token at the start of the source along with its length
+fn token(src: &str) -> (SyntaxKind, usize, Option<Error>) {
+    let ch = src.chars().next().unwrap();
+    let literal = |kind, message| {
+        let (len, closed) = quoted(src, ch);
+        (kind, len, (!closed).then_some((0..len, message)))
+    };
+    let (kind, len) = if ch.is_whitespace() {
+        (SyntaxKind::Whitespace, take_while(src, char::is_whitespace))
+    } else if src.starts_with("--") {
+        (SyntaxKind::Comment, take_while(src, |ch| ch != '\n'))
+    } else if src.starts_with("/-") {
+        let (len, error) = block_comment(src);
+        return (SyntaxKind::Comment, len, error);
+    } else if src.starts_with("{-") {
+        let (len, error) = ignore_block(src);
+        return (SyntaxKind::Comment, len, error);
+    } else if src.starts_with("#\"") {
+        let (len, error) = raw_string(src);
+        return (SyntaxKind::Str, len, error);
+    } else if ch == '"' {
+        return literal(SyntaxKind::Str, "unterminated string");
+    } else if ch == '\'' && label(src) > 0 {
+        (SyntaxKind::Label, 1 + label(src))
+    } else if ch == '\'' {
+        return literal(SyntaxKind::Char, "unterminated char");
+    } else if ch == '`' && src[1..].starts_with(|ch: char| rest(ch) && !ch.is_ascii_digit()) {
+        let (len, error) = raw_ident(src);
+        return (SyntaxKind::Ident, len, error);
+    } else if ch.is_ascii_digit()
+        || (ch == '.' && src[1..].starts_with(|ch: char| ch.is_ascii_digit()))
+    {
+        (SyntaxKind::Number, number(src))
+    } else if ch == '#' && src[1..].starts_with(|ch: char| rest(ch) && !ch.is_ascii_digit()) {
+        (SyntaxKind::PrivateIdent, 1 + take_while(&src[1..], rest))
+    } else if rest(ch) {
+        let len = take_while(src, rest);
+        let kind = SyntaxKind::keyword(&src[..len]).unwrap_or(SyntaxKind::Ident);
+        (kind, len)
+    } else {
+        PUNCTS
+            .iter()
+            .find(|(punct, _)| src.starts_with(punct))
+            .map_or((SyntaxKind::Error, ch.len_utf8()), |(punct, kind)| {
+                (*kind, punct.len())
+            })
+    };
+    (kind, len, None)
+}
+// splits the source into tokens including whitespaces and comments, so that
+// concatenating them gives back the source
+pub fn lex(src: &str) -> Vec<(SyntaxKind, &str)> {
+    lex_with_errors(src).0
+}
+// along with the tokens that aren't terminated
+pub(crate) fn lex_with_errors(src: &str) -> (Vec<(SyntaxKind, &str)>, Vec<SyntaxError>) {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    let mut i = 0;
+    if src.starts_with("#!") {
+        i = take_while(src, |ch| ch != '\n');
+        tokens.push((SyntaxKind::Shebang, &src[..i]));
+    }
+    while i < src.len() {
+        let (kind, len, error) = token(&src[i..]);
+        if let Some((range, message)) = error {
+            let offset = |offset: usize| TextSize::from((i + offset) as u32);
+            errors.push(SyntaxError {
+                message: message.to_string(),
+                range: TextRange::new(offset(range.start), offset(range.end)),
+            });
+        }
+        tokens.push((kind, &src[i..i + len]));
+        i += len;
+    }
+    (tokens, errors)
+}
+#[cfg(test)]
+mod test {
+    use super::lex;
+    use crate::SyntaxKind;
+
+    #[test]
+    fn tokens() {
+        let src = "#!butter\nfoo <- [1..2.5e-3, .5] -- comment\n'\\'' 'a' 'b 0x_1e-2 $ p.#y";
+        let tokens: Vec<_> = lex(src)
+            .into_iter()
+            .filter(|(kind, _)| *kind != SyntaxKind::Whitespace)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (SyntaxKind::Shebang, "#!butter"),
+                (SyntaxKind::Ident, "foo"),
+                (SyntaxKind::LeftArrow, "<-"),
+                (SyntaxKind::LeftBracket, "["),
+                (SyntaxKind::Number, "1"),
+                (SyntaxKind::DotDot, ".."),
+                (SyntaxKind::Number, "2.5e-3"),
+                (SyntaxKind::Comma, ","),
+                (SyntaxKind::Number, ".5"),
+                (SyntaxKind::RightBracket, "]"),
+                (SyntaxKind::Comment, "-- comment"),
+                (SyntaxKind::Char, "'\\''"),
+                (SyntaxKind::Char, "'a'"),
+                (SyntaxKind::Label, "'b"),
+                (SyntaxKind::Number, "0x_1e"),
+                (SyntaxKind::Minus, "-"),
+                (SyntaxKind::Number, "2"),
+                (SyntaxKind::Error, "$"),
+                (SyntaxKind::Ident, "p"),
+                (SyntaxKind::Dot, "."),
+                (SyntaxKind::PrivateIdent, "#y"),
+            ],
+        );
+    }
+    #[test]
+    fn comments_and_raw() {
+        let src = "#!butter /-\n/- {- -/ {- a {- \"-}\" -} '-' -} `loop` #\"\\\"#";
+        let tokens: Vec<_> = lex(src)
+            .into_iter()
+            .filter(|(kind, _)| *kind != SyntaxKind::Whitespace)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (SyntaxKind::Shebang, "#!butter /-"),
+                (SyntaxKind::Comment, "/- {- -/"),
+                (SyntaxKind::Comment, "{- a {- \"-}\" -} '-' -}"),
+                (SyntaxKind::Ident, "`loop`"),
+                (SyntaxKind::Str, "#\"\\\"#"),
+            ],
+        );
+    }
+    #[test]
+    fn lossless() {
+        let src = "  foo(\"bar\" ++ 'c') -- \u{1F4A3}\n\t";
+        let text: String = lex(src).into_iter().map(|(_, text)| text).collect();
+        assert_eq!(text, src);
+    }
+}
@@ -0,0 +1,246 @@
+#![warn(clippy::all)]
+#![deny(clippy::correctness)]
+#![forbid(unsafe_code)]
+
+use hir::{expr::Literal, statement::Statement};
+use rowan::GreenNode;
+use std::fmt::{self, Display, Formatter};
+
+pub use kind::{Butter, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+pub use lex::lex;
+pub use rowan::{TextRange, TextSize};
+
+mod kind;
+mod lex;
+mod literal;
+mod lower;
+mod parse;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SyntaxError {
+    pub message: String,
+    pub range: TextRange,
+}
+impl Display for SyntaxError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.message)
+    }
+}
+impl std::error::Error for SyntaxError {}
+
+// a lossless syntax tree, every byte of the source including whitespaces and
+// comments is kept in its tokens
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Parse {
+    green: GreenNode,
+    errors: Vec<SyntaxError>,
+}
+impl Parse {
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+    // lowers the syntax tree into HIR, fails with the first syntax error if
+    // there's some
+    pub fn lower<T>(&self) -> Result<Vec<Statement<T>>, SyntaxError>
+    where
+        T: Default + Clone,
+    {
+        match self.errors.first() {
+            Some(error) => Err(error.clone()),
+            None => lower::root(&self.syntax()),
+        }
+    }
+}
+// lowers a single top-level statement node, syntax errors within it aren't
+// checked
+pub fn lower_statement<T>(node: &SyntaxNode) -> Result<Statement<T>, SyntaxError>
+where
+    T: Default + Clone,
+{
+    lower::statement(node)
+}
+// parses the source into a syntax tree, the tree is always produced and
+// errors are collected alongside
+pub fn parse(src: &str) -> Parse {
+    let (green, errors) = parse::parse(src);
+    Parse { green, errors }
+}
+// the value of a number literal written by itself, `None` for anything else
+pub fn number_literal(src: &str) -> Option<Literal> {
+    match lex(src)[..] {
+        [(SyntaxKind::Number, text)] => literal::number(text).ok(),
+        _ => None,
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::{parse, SyntaxKind};
+    use hir::statement::Statement;
+    use parser::{ast, EasyParser};
+
+    const SOURCES: [&str; 26] = [
+        "#!/usr/bin/env butter\nid(x) => x; foo = id(10);",
+        "-- leading comment
+        first(arr) => &arr^[0]; -- trailing comment
+        mut foo = [10, 20, 30,];
+        elem <- first(&foo);",
+        "rec = (foo = 1, bar = true, = baz, *rest);
+        (foo = a, *rest) = rec;
+        tuple = (1,); unit = (); group = ((a));
+        [first, *rest, last] = [1, 2, 3, 4];
+        splat = (*a); tuple = (*a, b); tuple = (a, *b, c,);",
+        "foo = if a { 1 } else if b { 2 } else { 3 };
+        if a { foo() } else { bar(); }
+        while !done { done <- next(&state); }
+        loop { break; }
+        for x in xs { sum <- sum + x; }
+        { inner = 1; }",
+        "result = match value { @some x => x, @none => { 0 } _ => -1 };",
+        "a, b <- b, a; foo <- bar <- baz;
+        text = \"hello -- not a comment\" ++ 'c';
+        slice = arr[1..end] ++ arr[.<2] ++ arr[1>.] ++ [a >< b] ++ [..];
+        x = - -1; y = a - -b; z = >x; w = clone w;",
+        "call(
+            -- explains the first
+            first, -- and the second
+            second
+        );
+        f(); f(*a); f(a = 1, *b); f(a)(b).c.len^;",
+        "x = 1 + 2 * 3 // 4 % 5 - 6 ++ 7 == 8 != 9 <= 10 >= 11 < 12 > 13 & a && b | c || d;",
+        "fun = (ref x, mut y,) => x; nil = () => 0; t = @tag -1; u = @tag + 1;",
+        "f(x) => { return x; }; g(x) => if x { 1 } else { 2 } h() => match x { _ => 0 };",
+        "@some &[a, _, true, 0x10] = x; (= ref mut a, b = @c (d)) = e; () = f;",
+        "n = 1.5e-3 + .5 + 0b_101 + 'x' + \"\\x41\".len;",
+        "mod math; io = mod io; mod inner { pi = 3; mod deeper {} double(x) => x * 2; }",
+        "pub mod a { pub(a) x = 1; pub f(y) => y; pub(a . b) mod b; } pub [c, d] = e;",
+        "newtype Point(x: Num, #y: [_], pub(geo) #z, w: &mut Box(Num, &Str),); pub newtype Unit();
+        p = Point(x = 1, #y = 2, = z); Point(x = a, #y = b, *_) = p; p.#y <- p.#z;",
+        "r = match n { 1 | 2 | -3 => a, 4 .. 9 as m => m, .<0 | 10 >. => 0, _ => 1 };
+        s = match o { @some (1 | 2) | @none as all => all, &-1.. => 2, @some mut x => x };",
+        "t = match x { == a + 1 | == -b => 0, @some (== f(c)) => 1, _ => 2 };",
+        "if o =: @some (= a, *_) { a } else if p =: [b] | [_, b] { b } else { 0 }
+        while next(&q) =: @val item { push(item); } while done {}",
+        "f(o) => { @some x = o else { return 0 }; [a, *_] = x else { panic(); }; a }",
+        "n = 'outer: loop { 'inner: for x in xs { if x { break 'outer x } continue 'inner; } };
+        'a: while b { loop { break; } continue 'a; c = 'd'; }",
+        "r = 40 |> add(?, 2) |> f(x = ?, *rest) | g;
+        s = m(a = ?, b = n(?)); t = x |> ((y) => y);",
+        "d = #(1 = a, \"b\" = [c],); s = #[#[]]; e = #(); d[k] <- s[#[x, y]];",
+        "gen() => { yield item(1); x = yield ask(); yield log(text = x, *rest); };
+        n = do gen() handling { item(x) => print(x), ask() => { 2 } log(ref text, mut n) => () };",
+        "r = do { yield a(*b) } handling { a(x) => if x { 1 } else { return 0 } };",
+        "#!butter {- /-\n/- not {- nested\n-/ `loop` = #\"raw \\n \"quoted\"\"#;
+        {- x = \"-}\"; {- y = '-'; -} -} p = (`match` = 1, b = #\"a\"#, = `c`); t = (#\"d\"#, `e`); p.`match` <- `loop`;",
+        "f(`in`) => `in` + {- ignored -} 1; /- inline -/ g = f(`in` = 2);",
+    ];
+    const INVALID: [&str; 35] = [
+        "foo = ;",
+        "foo",
+        "(a, b) = c;",
+        "a, b <- c;",
+        "1 <- 2;",
+        "(a = 1, b);",
+        "f(*a, *b);",
+        "x = 18446744073709551616;",
+        "x = (1 + 1 = 2);",
+        "[a, *b, *c] = d;",
+        "mod inner { pi }",
+        "pub x;",
+        "pub() x = 1;",
+        "newtype Point(x, x);",
+        "newtype Point(x: );",
+        "Point (x = a) = b;",
+        "x = match y { 1 as 2 => 0 };",
+        "-9223372036854775809 = x;",
+        "if x =: { y }",
+        "x = y else z;",
+        "'a: { b }",
+        "continue 'a b;",
+        "x = (?, y);",
+        "x = f(= ?);",
+        "x = #(a);",
+        "x = #[a = 1];",
+        "x = yield a;",
+        "x = yield a(?);",
+        "x = do a handling b;",
+        "x = do a handling { b => c };",
+        "x = 1; /- unterminated",
+        "x = 1; {- {- -}",
+        "x = 1; {- \"-} -}",
+        "x = #\"unterminated;",
+        "`loop = 1;",
+    ];
+    #[test]
+    fn lossless() {
+        for src in SOURCES.iter().chain(&INVALID) {
+            assert_eq!(parse(src).syntax().to_string(), *src);
+        }
+    }
+    #[test]
+    fn same_as_parser() {
+        for src in SOURCES {
+            let expected: Vec<Statement<()>> = ast().easy_parse(src).unwrap().0;
+            assert_eq!(parse(src).lower(), Ok(expected), "{}", src);
+        }
+    }
+    #[test]
+    fn invalid() {
+        for src in INVALID {
+            assert!(ast::<(), _>().easy_parse(src).is_err(), "{}", src);
+            assert!(parse(src).lower::<()>().is_err(), "{}", src);
+        }
+    }
+    #[test]
+    fn trivia_outside_nodes() {
+        let src = "-- comment\nfoo = 1; -- trailing\n\nbar = 2;\n";
+        let root = parse(src).syntax();
+        let statements: Vec<_> = root
+            .children()
+            .map(|node| (node.kind(), node.to_string()))
+            .collect();
+        assert_eq!(
+            statements,
+            [
+                (SyntaxKind::Declare, "foo = 1;".to_string()),
+                (SyntaxKind::Declare, "bar = 2;".to_string()),
+            ],
+        );
+        let comments = root
+            .children_with_tokens()
+            .filter(|element| element.kind() == SyntaxKind::Comment)
+            .count();
+        assert_eq!(comments, 2);
+    }
+    #[test]
+    fn unterminated() {
+        let parse = parse("x = 1; {- y = \"-}\n-} `loop = /- z");
+        let errors: Vec<_> = parse
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), u32::from(error.range.start())))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("unterminated string", 14),
+                ("unterminated raw identifier", 21),
+                ("unterminated comment", 29),
+                ("expected expression", 33),
+                ("expected `;`", 33),
+            ]
+        );
+    }
+    #[test]
+    fn error_range() {
+        let parse = parse("foo = 1;\nbar = ;");
+        let errors: Vec<_> = parse
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), u32::from(error.range.start())))
+            .collect();
+        assert_eq!(errors, [("expected expression", 15)]);
+    }
+}
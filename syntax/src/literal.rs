@@ -0,0 +1,182 @@
+use hir::expr::Literal;
+
+// Decodes the text of number, char and string tokens. The lexer already found
+// where each ends, what's left to check is what the text inside means.
+
+type Result<T> = std::result::Result<T, &'static str>;
+
+fn integer(src: &str, base: u32) -> Result<u64> {
+    let mut result: u64 = 0;
+    for ch in src.chars().filter(|ch| *ch != '_') {
+        let digit = ch.to_digit(base).ok_or("invalid number")?;
+        result = result
+            .checked_mul(base.into())
+            .and_then(|result| result.checked_add(digit.into()))
+            .ok_or("integer overflow")?;
+    }
+    Ok(result)
+}
+// digits that may be separated by `_` but not start with it
+fn is_digits(src: &str) -> bool {
+    src.starts_with(|ch: char| ch.is_ascii_digit())
+        && src.chars().all(|ch| ch.is_ascii_digit() || ch == '_')
+}
+fn float(src: &str) -> Result<f64> {
+    let (mantissa, exp) = match src.find(['e', 'E']) {
+        Some(i) => (&src[..i], Some(&src[i + 1..])),
+        None => (src, None),
+    };
+    let (whole, decimal) = match mantissa.split_once('.') {
+        Some((whole, decimal)) => (whole, decimal),
+        None => (mantissa, ""),
+    };
+    let (exp_sign, exp) = match exp.map(|exp| exp.trim_start_matches('_')) {
+        Some(exp) => match exp.strip_prefix('-') {
+            Some(exp) => ("-", exp),
+            None => ("+", exp.strip_prefix('+').unwrap_or(exp)),
+        },
+        None => ("+", ""),
+    };
+    let valid = (whole.is_empty() || is_digits(whole))
+        && (decimal.is_empty() || is_digits(decimal))
+        && (!src.contains('.') || !decimal.is_empty())
+        && exp.chars().all(|ch| ch.is_ascii_digit() || ch == '_')
+        && (src.len() == mantissa.len() || !exp.is_empty());
+    if !valid {
+        return Err("invalid number");
+    }
+    // the zeros make every part non-empty
+    let src: String = format!("0{}.{}0e{}0{}", whole, decimal, exp_sign, exp)
+        .chars()
+        .filter(|ch| *ch != '_')
+        .collect();
+    let float: f64 = src.parse().map_err(|_| "invalid number")?;
+    if float.is_finite() {
+        Ok(float)
+    } else {
+        Err("magnitude overflow")
+    }
+}
+pub(crate) fn number(src: &str) -> Result<Literal> {
+    for (prefix, base) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if src
+            .get(..2)
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        {
+            if src.len() == 2 {
+                return Err("invalid number");
+            }
+            return integer(&src[2..], base).map(Literal::UInt);
+        }
+    }
+    if src.contains(['.', 'e', 'E']) {
+        float(src).map(Literal::Float)
+    } else {
+        integer(src, 10).map(Literal::UInt)
+    }
+}
+// the bytes between the delimiters with escapes decoded
+fn quoted(src: &str, delimiter: char, message: &'static str) -> Result<Vec<u8>> {
+    let inner = src
+        .strip_prefix(delimiter)
+        .and_then(|src| src.strip_suffix(delimiter))
+        .ok_or(message)?;
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch == delimiter || ch == '\n' {
+            return Err(message);
+        }
+        if ch != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(ch.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        let byte = match chars.next() {
+            Some('\\') => b'\\',
+            Some('"') => b'"',
+            Some('\'') => b'\'',
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('v') => 11,
+            Some('0') => 0,
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) if hex.len() == 2 && hex.chars().all(|ch| ch.is_ascii_hexdigit()) => {
+                        byte
+                    }
+                    _ => return Err("invalid escape"),
+                }
+            }
+            _ => return Err("invalid escape"),
+        };
+        bytes.push(byte);
+    }
+    Ok(bytes)
+}
+pub(crate) fn char(src: &str) -> Result<u64> {
+    match quoted(src, '\'', "unterminated char")?[..] {
+        [byte] => Ok(byte.into()),
+        [] => Err("empty char literal"),
+        _ => Err("multiple character in char literal"),
+    }
+}
+// `#"..."#` is taken as is
+pub(crate) fn string(src: &str) -> Result<Vec<u8>> {
+    match src.strip_prefix("#\"") {
+        Some(raw) => raw
+            .strip_suffix("\"#")
+            .map(|raw| raw.as_bytes().to_vec())
+            .ok_or("unterminated raw string"),
+        None => quoted(src, '"', "unterminated string"),
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::{char, number, string};
+    use hir::expr::Literal;
+
+    #[test]
+    fn integer() {
+        assert_eq!(number("123"), Ok(Literal::UInt(123)));
+        assert_eq!(number("1_000"), Ok(Literal::UInt(1000)));
+        assert_eq!(number("0x_12e"), Ok(Literal::UInt(0x12e)));
+        assert_eq!(number("0O_127"), Ok(Literal::UInt(0o127)));
+        assert_eq!(number("0b_11110000"), Ok(Literal::UInt(0b11110000)));
+        assert_eq!(number("18446744073709551616"), Err("integer overflow"));
+        assert_eq!(number("12ab"), Err("invalid number"));
+        assert_eq!(number("0b12"), Err("invalid number"));
+    }
+    #[test]
+    fn float() {
+        assert_eq!(number("1.2"), Ok(Literal::Float(1.2)));
+        assert_eq!(number(".5"), Ok(Literal::Float(0.5)));
+        assert_eq!(number("01.2e3"), Ok(Literal::Float(1.2e3)));
+        assert_eq!(number("1.2E+3"), Ok(Literal::Float(1.2e3)));
+        assert_eq!(number("2.5e-3"), Ok(Literal::Float(2.5e-3)));
+        assert_eq!(number("1e_5"), Ok(Literal::Float(1e5)));
+        assert_eq!(number("1e999"), Err("magnitude overflow"));
+        assert_eq!(number("1e"), Err("invalid number"));
+        assert_eq!(number("1.5x"), Err("invalid number"));
+    }
+    #[test]
+    fn chars() {
+        assert_eq!(char("'a'"), Ok(b'a'.into()));
+        assert_eq!(char("'\\n'"), Ok(b'\n'.into()));
+        assert_eq!(char("'\\x41'"), Ok(0x41));
+        assert_eq!(char("'ab'"), Err("multiple character in char literal"));
+        assert_eq!(char("'ß'"), Err("multiple character in char literal"));
+        assert_eq!(char("'\\q'"), Err("invalid escape"));
+    }
+    #[test]
+    fn strings() {
+        let expected: Vec<u8> = "\x41Aßℝ💣\n".into();
+        assert_eq!(string(r#""\x41Aßℝ💣\n""#), Ok(expected));
+        let expected: Vec<u8> = "\\n \"quoted\"\nnext line".into();
+        assert_eq!(string("#\"\\n \"quoted\"\nnext line\"#"), Ok(expected));
+        assert_eq!(string("\"foo"), Err("unterminated string"));
+        assert_eq!(string("\"\\x4\""), Err("invalid escape"));
+    }
+}
@@ -0,0 +1,804 @@
+use crate::{literal, SyntaxError, SyntaxKind, SyntaxNode, SyntaxToken};
+use hir::{
+    expr::{
+        Arg, Assign, Binary, BinaryType, Block, Bound, BoundType, Break, Call, Condition,
+        ControlFlow, Element, ElementKind, Entry, Expr, Field, FieldAccess, For, Fun, Handle,
+        Handler, If, Index, Jump, Literal, Loop, Match, MatchArm, MatchCondition, Module,
+        PlaceExpr, Range, Record, RecordWithSplat, Slice, Tag, Tuple, TupleWithSplat, Unary,
+        UnaryType, While, Yield,
+    },
+    keyword,
+    pattern::{
+        AsPattern, ListPattern, ListWithRest, NewTypePattern, Pattern, PatternBound, RangePattern,
+        RecordPattern, TaggedPattern, Var,
+    },
+    statement::{
+        Declare, FunDeclare, NewType, NewTypeField, Statement, TypeAnnotation, Visibility,
+    },
+    Atom,
+};
+use std::collections::BTreeMap;
+
+type Result<T> = std::result::Result<T, SyntaxError>;
+
+fn error(node: &SyntaxNode, message: &str) -> SyntaxError {
+    SyntaxError {
+        message: message.to_string(),
+        range: node.text_range(),
+    }
+}
+fn tokens(node: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    node.children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| !token.kind().is_trivia())
+}
+fn token(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    tokens(node).find(|token| token.kind() == kind)
+}
+// raw identifiers are without their backticks
+fn name(token: &SyntaxToken) -> Atom {
+    Atom::from(token.text().trim_matches('`'))
+}
+fn ident(node: &SyntaxNode) -> Option<Atom> {
+    token(node, SyntaxKind::Ident).map(|token| name(&token))
+}
+// label of a loop or a jump without its `'`
+fn label(node: &SyntaxNode) -> Option<Atom> {
+    token(node, SyntaxKind::Label).map(|token| Atom::from(&token.text()[1..]))
+}
+// name of a field, private ones keep their `#`
+fn field_name(node: &SyntaxNode) -> Option<Atom> {
+    tokens(node)
+        .find(|token| matches!(token.kind(), SyntaxKind::Ident | SyntaxKind::PrivateIdent))
+        .map(|token| name(&token))
+}
+// child nodes besides the visibility, which is read by `visibility`
+fn children(node: &SyntaxNode) -> impl Iterator<Item = SyntaxNode> {
+    node.children()
+        .filter(|child| child.kind() != SyntaxKind::Visibility)
+}
+fn nth(node: &SyntaxNode, n: usize) -> Result<SyntaxNode> {
+    children(node)
+        .nth(n)
+        .ok_or_else(|| error(node, "incomplete syntax tree"))
+}
+// child nodes before and after the first token of the given kind
+fn split(node: &SyntaxNode, kind: SyntaxKind) -> (Vec<SyntaxNode>, Vec<SyntaxNode>) {
+    match token(node, kind) {
+        Some(token) => node
+            .children()
+            .partition(|child| child.text_range().start() < token.text_range().start()),
+        None => (node.children().collect(), Vec::new()),
+    }
+}
+fn literal<T>(token: &SyntaxToken) -> Result<Expr<T>>
+where
+    T: Default + Clone,
+{
+    let text = token.text();
+    let expr = match token.kind() {
+        SyntaxKind::TrueKw => Ok(Expr::Literal(Literal::True)),
+        SyntaxKind::FalseKw => Ok(Expr::Literal(Literal::False)),
+        SyntaxKind::Number => literal::number(text).map(Expr::Literal),
+        SyntaxKind::Char => literal::char(text).map(|ch| Expr::Literal(Literal::UInt(ch))),
+        // strings are arrays of their bytes
+        SyntaxKind::Str => literal::string(text).map(|bytes| {
+            let elements = bytes
+                .into_iter()
+                .map(|byte| Element {
+                    expr: Expr::Literal(Literal::UInt(byte.into())),
+                    kind: ElementKind::Element,
+                })
+                .collect();
+            Expr::Array(elements)
+        }),
+        _ => Err("expected literal"),
+    };
+    expr.map_err(|message| SyntaxError {
+        message: message.to_string(),
+        range: token.text_range(),
+    })
+}
+fn param<T>(node: &SyntaxNode) -> Result<Var<T>>
+where
+    T: Default,
+{
+    Ok(Var {
+        ident: ident(node).ok_or_else(|| error(node, "expected identifier"))?,
+        mutable: token(node, SyntaxKind::MutKw).is_some(),
+        bind_to_ref: token(node, SyntaxKind::RefKw).is_some(),
+        ty: T::default(),
+    })
+}
+fn param_list<T>(node: &SyntaxNode) -> Result<Box<[Var<T>]>>
+where
+    T: Default,
+{
+    node.children().map(|param| self::param(&param)).collect()
+}
+fn fun<T>(node: &SyntaxNode) -> Result<Fun<T>>
+where
+    T: Default + Clone,
+{
+    Ok(Fun {
+        param: param_list(&nth(node, 0)?)?,
+        body: Box::new(expr(&nth(node, 1)?)?),
+    })
+}
+fn handler<T>(node: &SyntaxNode) -> Result<Handler<T>>
+where
+    T: Default + Clone,
+{
+    Ok(Handler {
+        effect: ident(node).ok_or_else(|| error(node, "expected effect name"))?,
+        param: param_list(&nth(node, 0)?)?,
+        body: expr(&nth(node, 1)?)?,
+    })
+}
+pub(crate) fn root<T>(node: &SyntaxNode) -> Result<Vec<Statement<T>>>
+where
+    T: Default + Clone,
+{
+    node.children().map(|node| statement(&node)).collect()
+}
+fn visibility(node: &SyntaxNode) -> Visibility {
+    let visibility = match node
+        .children()
+        .find(|child| child.kind() == SyntaxKind::Visibility)
+    {
+        Some(visibility) => visibility,
+        None => return Visibility::Private,
+    };
+    if token(&visibility, SyntaxKind::LeftParen).is_some() {
+        let path = tokens(&visibility)
+            .filter(|token| token.kind() == SyntaxKind::Ident)
+            .map(|token| name(&token))
+            .collect();
+        Visibility::Restricted(path)
+    } else {
+        Visibility::Public
+    }
+}
+pub(crate) fn statement<T>(node: &SyntaxNode) -> Result<Statement<T>>
+where
+    T: Default + Clone,
+{
+    let statement = match node.kind() {
+        SyntaxKind::Declare => Statement::Declare(Declare {
+            visibility: visibility(node),
+            pattern: pattern(&nth(node, 0)?)?,
+            expr: expr(&nth(node, 1)?)?,
+            else_part: children(node).nth(2).map(|node| block(&node)).transpose()?,
+        }),
+        SyntaxKind::FunDeclare => Statement::FunDeclare(FunDeclare {
+            visibility: visibility(node),
+            ident: ident(node).ok_or_else(|| error(node, "expected identifier"))?,
+            fun: fun(node)?,
+            ty: T::default(),
+        }),
+        SyntaxKind::ModuleDeclare => {
+            let ident = ident(node).ok_or_else(|| error(node, "expected module name"))?;
+            let module = if token(node, SyntaxKind::LeftBrace).is_some() {
+                let statement = children(node)
+                    .map(|node| statement(&node))
+                    .collect::<Result<Vec<_>>>()?;
+                Module::Inline(statement.into())
+            } else {
+                Module::File(ident.clone())
+            };
+            Statement::Declare(Declare {
+                visibility: visibility(node),
+                pattern: Pattern::Var(Var {
+                    ident,
+                    mutable: false,
+                    bind_to_ref: false,
+                    ty: T::default(),
+                }),
+                expr: Expr::Module(module),
+                else_part: None,
+            })
+        }
+        SyntaxKind::NewTypeDeclare => {
+            let fields = children(node)
+                .map(|field| newtype_field(&field))
+                .collect::<Result<Vec<_>>>()?;
+            let unique = fields
+                .iter()
+                .enumerate()
+                .all(|(i, field)| fields[..i].iter().all(|other| other.name != field.name));
+            if !unique {
+                return Err(error(node, "duplicate field name"));
+            }
+            Statement::NewType(NewType {
+                visibility: visibility(node),
+                ident: ident(node).ok_or_else(|| error(node, "expected type name"))?,
+                fields: fields.into(),
+                ty: T::default(),
+            })
+        }
+        SyntaxKind::ExprStatement => Statement::Expr(expr(&nth(node, 0)?)?),
+        _ => return Err(error(node, "expected statement")),
+    };
+    Ok(statement)
+}
+fn newtype_field(node: &SyntaxNode) -> Result<NewTypeField> {
+    let name = field_name(node).ok_or_else(|| error(node, "expected field name"))?;
+    let public = node
+        .children()
+        .any(|child| child.kind() == SyntaxKind::Visibility);
+    let visibility = if public {
+        visibility(node)
+    } else if name.starts_with('#') {
+        Visibility::Private
+    } else {
+        Visibility::Public
+    };
+    let ty = match children(node).next() {
+        Some(ty) => annotation(&ty)?,
+        None => TypeAnnotation::Infer,
+    };
+    Ok(NewTypeField {
+        visibility,
+        name,
+        ty,
+    })
+}
+fn annotation(node: &SyntaxNode) -> Result<TypeAnnotation> {
+    let annotation = match node.kind() {
+        SyntaxKind::InferType => TypeAnnotation::Infer,
+        SyntaxKind::ArrayType => TypeAnnotation::Array(Box::new(annotation(&nth(node, 0)?)?)),
+        SyntaxKind::RefType => TypeAnnotation::Ref(
+            token(node, SyntaxKind::MutKw).is_some(),
+            Box::new(annotation(&nth(node, 0)?)?),
+        ),
+        SyntaxKind::NamedType => TypeAnnotation::Named(
+            ident(node).ok_or_else(|| error(node, "expected type name"))?,
+            node.children()
+                .map(|arg| annotation(&arg))
+                .collect::<Result<_>>()?,
+        ),
+        _ => return Err(error(node, "expected type")),
+    };
+    Ok(annotation)
+}
+fn block<T>(node: &SyntaxNode) -> Result<Block<T>>
+where
+    T: Default + Clone,
+{
+    let mut statements: Vec<_> = node.children().collect();
+    // the last expression without `;` is the value of the block
+    let expr = match statements.last() {
+        Some(last)
+            if last.kind() == SyntaxKind::ExprStatement
+                && token(last, SyntaxKind::Semicolon).is_none() =>
+        {
+            let expr = expr(&nth(last, 0)?)?;
+            statements.pop();
+            Some(Box::new(expr))
+        }
+        _ => None,
+    };
+    let statement = statements
+        .iter()
+        .map(statement)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Block {
+        statement: statement.into(),
+        expr,
+    })
+}
+fn place<T>(node: &SyntaxNode) -> Result<PlaceExpr<T>>
+where
+    T: Default + Clone,
+{
+    match expr(node)? {
+        Expr::Place(place) => Ok(place),
+        _ => Err(error(node, "expected place expression")),
+    }
+}
+// elements of a tuple or a record around its splat
+type AroundSplat<E, T> = (Vec<E>, Option<Expr<T>>, Vec<E>);
+
+fn around_splat<T, E>(
+    node: &SyntaxNode,
+    element: impl Fn(&SyntaxNode) -> Result<E>,
+) -> Result<AroundSplat<E, T>>
+where
+    T: Default + Clone,
+{
+    let mut left = Vec::new();
+    let mut splat = None;
+    let mut right = Vec::new();
+    for child in node.children() {
+        if child.kind() == SyntaxKind::Splat {
+            splat = Some(expr(&nth(&child, 0)?)?);
+        } else if splat.is_some() {
+            right.push(element(&child)?);
+        } else {
+            left.push(element(&child)?);
+        }
+    }
+    Ok((left, splat, right))
+}
+fn field<T>(node: &SyntaxNode) -> Result<Field<T>>
+where
+    T: Default + Clone,
+{
+    let expr = expr(&nth(node, 0)?)?;
+    let name = field_name(node)
+        .or_else(|| expr.field_name().filter(|name| *name != keyword!("?")))
+        .ok_or_else(|| error(node, "couldn't infer field name"))?;
+    Ok(Field { name, expr })
+}
+fn record<T>(node: &SyntaxNode) -> Result<Record<T>>
+where
+    T: Default + Clone,
+{
+    let record = match around_splat(node, field)? {
+        (left, Some(splat), right) => Record::RecordWithSplat(RecordWithSplat {
+            left: left.into(),
+            splat: Box::new(splat),
+            right: right.into(),
+        }),
+        (left, None, _) => Record::Record(left.into()),
+    };
+    if record.all_name_unique() {
+        Ok(record)
+    } else {
+        Err(error(node, "duplicate field name"))
+    }
+}
+fn arg<T>(node: &SyntaxNode) -> Result<Arg<T>>
+where
+    T: Default + Clone,
+{
+    if node.kind() == SyntaxKind::RecordExpr {
+        return Ok(Arg::Record(record(node)?));
+    }
+    let arg = match around_splat(node, expr)? {
+        (left, None, _) if left.is_empty() => Arg::Unit,
+        (left, Some(splat), right) if left.is_empty() && right.is_empty() => {
+            Arg::Splat(Box::new(splat))
+        }
+        (left, Some(splat), right) => Arg::Tuple(Tuple::TupleWithSplat(TupleWithSplat {
+            left: left.into(),
+            splat: Box::new(splat),
+            right: right.into(),
+        })),
+        (left, None, _) => Arg::Tuple(Tuple::Tuple(left.into())),
+    };
+    Ok(arg)
+}
+fn bound_types(operator: SyntaxKind) -> (BoundType, BoundType) {
+    match operator {
+        SyntaxKind::DotDot => (BoundType::Inclusive, BoundType::Inclusive),
+        SyntaxKind::DotLess => (BoundType::Inclusive, BoundType::Exclusive),
+        SyntaxKind::GreaterDot => (BoundType::Exclusive, BoundType::Inclusive),
+        _ => (BoundType::Exclusive, BoundType::Exclusive),
+    }
+}
+fn range<T>(node: &SyntaxNode) -> Result<Range<T>>
+where
+    T: Default + Clone,
+{
+    let operator = tokens(node)
+        .find(|token| token.kind().is_range())
+        .ok_or_else(|| error(node, "expected range operator"))?;
+    let (left_kind, right_kind) = bound_types(operator.kind());
+    let (left, right) = split(node, operator.kind());
+    let bound = |nodes: Vec<SyntaxNode>, kind| -> Result<_> {
+        match nodes.first() {
+            Some(node) => Ok(Some(Bound {
+                kind,
+                expr: Box::new(expr(node)?),
+            })),
+            None => Ok(None),
+        }
+    };
+    Ok(Range {
+        left: bound(left, left_kind)?,
+        right: bound(right, right_kind)?,
+    })
+}
+fn unary_type(kind: SyntaxKind) -> Option<UnaryType> {
+    let kind = match kind {
+        SyntaxKind::Bang => UnaryType::Not,
+        SyntaxKind::Ampersand => UnaryType::Ref,
+        SyntaxKind::Minus => UnaryType::Minus,
+        SyntaxKind::Greater => UnaryType::Move,
+        SyntaxKind::CloneKw => UnaryType::Clone,
+        _ => return None,
+    };
+    Some(kind)
+}
+fn binary_type(kind: SyntaxKind) -> Option<BinaryType> {
+    let kind = match kind {
+        SyntaxKind::Plus => BinaryType::Add,
+        SyntaxKind::Minus => BinaryType::Sub,
+        SyntaxKind::Star => BinaryType::Multiply,
+        SyntaxKind::Slash => BinaryType::Div,
+        SyntaxKind::SlashSlash => BinaryType::FloorDiv,
+        SyntaxKind::Percent => BinaryType::Mod,
+        SyntaxKind::Ampersand => BinaryType::And,
+        SyntaxKind::Pipe => BinaryType::Or,
+        SyntaxKind::AmpersandAmpersand => BinaryType::LazyAnd,
+        SyntaxKind::PipePipe => BinaryType::LazyOr,
+        SyntaxKind::PipeGreater => BinaryType::Pipe,
+        SyntaxKind::EqualEqual => BinaryType::Equal,
+        SyntaxKind::BangEqual => BinaryType::NotEqual,
+        SyntaxKind::Greater => BinaryType::Greater,
+        SyntaxKind::GreaterEqual => BinaryType::GreaterEqual,
+        SyntaxKind::Less => BinaryType::Less,
+        SyntaxKind::LessEqual => BinaryType::LessEqual,
+        SyntaxKind::PlusPlus => BinaryType::Concatenate,
+        _ => return None,
+    };
+    Some(kind)
+}
+fn boxed<T>(node: &SyntaxNode, n: usize) -> Result<Box<Expr<T>>>
+where
+    T: Default + Clone,
+{
+    Ok(Box::new(expr(&nth(node, n)?)?))
+}
+fn optional<T>(node: &SyntaxNode) -> Result<Option<Box<Expr<T>>>>
+where
+    T: Default + Clone,
+{
+    node.children()
+        .next()
+        .map(|child| expr(&child).map(Box::new))
+        .transpose()
+}
+fn condition<T>(node: &SyntaxNode) -> Result<Condition<T>>
+where
+    T: Default + Clone,
+{
+    let condition = match node.kind() {
+        SyntaxKind::MatchCondition => Condition::Match(MatchCondition {
+            expr: boxed(node, 0)?,
+            pattern: pattern(&nth(node, 1)?)?,
+        }),
+        _ => Condition::Bool(Box::new(expr(node)?)),
+    };
+    Ok(condition)
+}
+fn control_flow<T>(node: &SyntaxNode) -> Result<ControlFlow<T>>
+where
+    T: Default + Clone,
+{
+    let control_flow = match node.kind() {
+        SyntaxKind::Block => ControlFlow::Block(block(node)?),
+        SyntaxKind::IfExpr => {
+            let else_part = match node.children().nth(2) {
+                Some(else_part) => Some(Box::new(control_flow(&else_part)?)),
+                None => None,
+            };
+            ControlFlow::If(If {
+                condition: condition(&nth(node, 0)?)?,
+                body: block(&nth(node, 1)?)?,
+                else_part,
+            })
+        }
+        SyntaxKind::ForExpr => ControlFlow::For(For {
+            label: label(node),
+            pattern: pattern(&nth(node, 0)?)?,
+            expr: boxed(node, 1)?,
+            body: block(&nth(node, 2)?)?,
+        }),
+        SyntaxKind::WhileExpr => ControlFlow::While(While {
+            label: label(node),
+            condition: condition(&nth(node, 0)?)?,
+            body: block(&nth(node, 1)?)?,
+        }),
+        SyntaxKind::LoopExpr => ControlFlow::Loop(Loop {
+            label: label(node),
+            body: block(&nth(node, 0)?)?,
+        }),
+        SyntaxKind::MatchExpr => {
+            let arm = node
+                .children()
+                .skip(1)
+                .map(|arm| {
+                    Ok(MatchArm {
+                        pattern: pattern(&nth(&arm, 0)?)?,
+                        expr: expr(&nth(&arm, 1)?)?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            ControlFlow::Match(Match {
+                expr: boxed(node, 0)?,
+                arm: arm.into(),
+            })
+        }
+        _ => return Err(error(node, "expected control flow")),
+    };
+    Ok(control_flow)
+}
+pub(crate) fn expr<T>(node: &SyntaxNode) -> Result<Expr<T>>
+where
+    T: Default + Clone,
+{
+    let expr = match node.kind() {
+        SyntaxKind::Literal => {
+            let token = tokens(node)
+                .next()
+                .ok_or_else(|| error(node, "expected literal"))?;
+            literal(&token)?
+        }
+        SyntaxKind::NameRef => Expr::Place(PlaceExpr::Var(
+            ident(node).ok_or_else(|| error(node, "expected identifier"))?,
+        )),
+        SyntaxKind::TagExpr => Expr::Tag(Tag {
+            tag: ident(node).ok_or_else(|| error(node, "expected tag name"))?,
+            expr: optional(node)?,
+        }),
+        SyntaxKind::UnaryExpr => Expr::Unary(Unary {
+            kind: tokens(node)
+                .find_map(|token| unary_type(token.kind()))
+                .ok_or_else(|| error(node, "expected unary operator"))?,
+            expr: boxed(node, 0)?,
+        }),
+        SyntaxKind::BinaryExpr => Expr::Binary(Binary {
+            kind: tokens(node)
+                .find_map(|token| binary_type(token.kind()))
+                .ok_or_else(|| error(node, "expected binary operator"))?,
+            left: boxed(node, 0)?,
+            right: boxed(node, 1)?,
+        }),
+        SyntaxKind::AssignExpr => {
+            let (place, expr) = split(node, SyntaxKind::LeftArrow);
+            if place.len() != expr.len() {
+                return Err(error(
+                    node,
+                    "mismatching count of place and value expressions",
+                ));
+            }
+            let assign = place
+                .iter()
+                .zip(&expr)
+                .map(|(place, expr)| {
+                    Ok(Assign {
+                        place: self::place(place)?,
+                        expr: self::expr(expr)?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Expr::Assign(assign.into())
+        }
+        SyntaxKind::ParenExpr => expr(&nth(node, 0)?)?,
+        SyntaxKind::TupleExpr => match arg(node)? {
+            Arg::Unit => Expr::Unit,
+            Arg::Splat(splat) => Expr::Splat(splat),
+            Arg::Tuple(tuple) => Expr::Tuple(tuple),
+            Arg::Record(record) => Expr::Record(record),
+        },
+        SyntaxKind::RecordExpr => Expr::Record(record(node)?),
+        SyntaxKind::ArrayExpr => {
+            let elements = node
+                .children()
+                .map(|child| {
+                    if child.kind() == SyntaxKind::Splat {
+                        Ok(Element {
+                            expr: expr(&nth(&child, 0)?)?,
+                            kind: ElementKind::Splat,
+                        })
+                    } else {
+                        Ok(Element {
+                            expr: expr(&child)?,
+                            kind: ElementKind::Element,
+                        })
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Expr::Array(elements.into())
+        }
+        SyntaxKind::DictExpr => {
+            let entries = node
+                .children()
+                .map(|entry| {
+                    Ok(Entry {
+                        key: expr(&nth(&entry, 0)?)?,
+                        value: expr(&nth(&entry, 1)?)?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Expr::Dict(entries.into())
+        }
+        SyntaxKind::SetExpr => {
+            let elements = node
+                .children()
+                .map(|child| expr(&child))
+                .collect::<Result<Vec<_>>>()?;
+            Expr::Set(elements.into())
+        }
+        SyntaxKind::RangeExpr => Expr::ArrayRange(range(node)?),
+        SyntaxKind::FunExpr => Expr::Fun(fun(node)?),
+        SyntaxKind::CallExpr => Call {
+            expr: boxed(node, 0)?,
+            arg: arg(&nth(node, 1)?)?,
+        }
+        .partially_applied(),
+        SyntaxKind::Placeholder => Expr::Place(PlaceExpr::Var(keyword!("?"))),
+        SyntaxKind::FieldAccess => {
+            let expr = boxed(node, 0)?;
+            match field_name(node) {
+                Some(keyword!("len")) => Expr::Place(PlaceExpr::Len(expr)),
+                Some(name) => Expr::Place(PlaceExpr::FieldAccess(FieldAccess { expr, name })),
+                None => return Err(error(node, "expected field name")),
+            }
+        }
+        SyntaxKind::IndexExpr => Expr::Place(PlaceExpr::Index(Index {
+            expr: boxed(node, 0)?,
+            index: boxed(node, 1)?,
+        })),
+        SyntaxKind::SliceExpr => Expr::Place(PlaceExpr::Slice(Slice {
+            expr: boxed(node, 0)?,
+            range: range(&nth(node, 1)?)?,
+        })),
+        SyntaxKind::DerefExpr => Expr::Place(PlaceExpr::Deref(boxed(node, 0)?)),
+        SyntaxKind::BreakExpr => Expr::Jump(Jump::Break(Break {
+            label: label(node),
+            expr: optional(node)?,
+        })),
+        SyntaxKind::ContinueExpr => Expr::Jump(Jump::Continue(label(node))),
+        SyntaxKind::ReturnExpr => Expr::Jump(Jump::Return(optional(node)?)),
+        SyntaxKind::YieldExpr => {
+            let arg = nth(node, 0)?;
+            // unlike calls, effects can't be partially applied
+            let placeholder = arg.children().any(|child| {
+                child.kind() == SyntaxKind::Placeholder
+                    || child.kind() == SyntaxKind::Field
+                        && child
+                            .children()
+                            .any(|child| child.kind() == SyntaxKind::Placeholder)
+            });
+            if placeholder {
+                return Err(error(&arg, "expected expression"));
+            }
+            Expr::Yield(Yield {
+                effect: ident(node).ok_or_else(|| error(node, "expected effect name"))?,
+                arg: self::arg(&arg)?,
+            })
+        }
+        SyntaxKind::HandleExpr => {
+            let handler = node
+                .children()
+                .skip(1)
+                .map(|handler| self::handler(&handler))
+                .collect::<Result<Vec<_>>>()?;
+            Expr::Handle(Handle {
+                expr: boxed(node, 0)?,
+                handler: handler.into(),
+            })
+        }
+        SyntaxKind::ModuleExpr => Expr::Module(Module::File(
+            ident(node).ok_or_else(|| error(node, "expected module name"))?,
+        )),
+        _ => Expr::ControlFlow(control_flow(node)?),
+    };
+    Ok(expr)
+}
+fn list_pattern<T>(node: &SyntaxNode) -> Result<ListPattern<T>>
+where
+    T: Default + Clone,
+{
+    let mut left = Vec::new();
+    let mut rest = None;
+    let mut right = Vec::new();
+    for child in node.children() {
+        if child.kind() == SyntaxKind::RestPattern {
+            rest = Some(pattern(&nth(&child, 0)?)?);
+        } else if rest.is_some() {
+            right.push(pattern(&child)?);
+        } else {
+            left.push(pattern(&child)?);
+        }
+    }
+    let list = match rest {
+        Some(rest) => ListPattern::ListWithRest(ListWithRest {
+            left: left.into(),
+            rest: Box::new(rest),
+            right: right.into(),
+        }),
+        None => ListPattern::List(left.into()),
+    };
+    Ok(list)
+}
+pub(crate) fn pattern<T>(node: &SyntaxNode) -> Result<Pattern<T>>
+where
+    T: Default + Clone,
+{
+    let pattern = match node.kind() {
+        SyntaxKind::VarPattern => Pattern::Var(param(node)?),
+        SyntaxKind::IgnorePattern => Pattern::Ignore,
+        SyntaxKind::LiteralPattern => {
+            let mut tokens = tokens(node).peekable();
+            let minus = tokens
+                .next_if(|token| token.kind() == SyntaxKind::Minus)
+                .is_some();
+            let token = tokens
+                .next()
+                .ok_or_else(|| error(node, "expected literal"))?;
+            match literal::<T>(&token)? {
+                Expr::Literal(Literal::True) if !minus => Pattern::True,
+                Expr::Literal(Literal::False) if !minus => Pattern::False,
+                Expr::Literal(Literal::UInt(uint)) if minus => {
+                    match 0_i64.checked_sub_unsigned(uint) {
+                        Some(int) => Pattern::Int(int),
+                        None => return Err(error(node, "integer overflow")),
+                    }
+                }
+                Expr::Literal(Literal::UInt(uint)) => Pattern::UInt(uint),
+                _ => return Err(error(node, "expected integer")),
+            }
+        }
+        SyntaxKind::TagPattern => Pattern::Tag(TaggedPattern {
+            tag: ident(node).ok_or_else(|| error(node, "expected tag name"))?,
+            pattern: match node.children().next() {
+                Some(child) => Some(Box::new(pattern(&child)?)),
+                None => None,
+            },
+        }),
+        SyntaxKind::RefPattern => Pattern::Ref(Box::new(pattern(&nth(node, 0)?)?)),
+        SyntaxKind::ParenPattern => pattern(&nth(node, 0)?)?,
+        SyntaxKind::RecordPattern => {
+            let mut fields = BTreeMap::new();
+            let mut rest = None;
+            for child in node.children() {
+                let pattern = pattern(&nth(&child, 0)?)?;
+                if child.kind() == SyntaxKind::RestPattern {
+                    rest = Some(Box::new(pattern));
+                } else {
+                    let name = field_name(&child)
+                        .or_else(|| pattern.field_name())
+                        .ok_or_else(|| error(&child, "couldn't infer field name"))?;
+                    fields.insert(name, pattern);
+                }
+            }
+            Pattern::Record(RecordPattern { fields, rest })
+        }
+        SyntaxKind::ArrayPattern => Pattern::Array(list_pattern(node)?),
+        SyntaxKind::NewTypePattern => match pattern(&nth(node, 0)?)? {
+            Pattern::Record(fields) if nth(node, 0)?.kind() == SyntaxKind::RecordPattern => {
+                Pattern::NewType(NewTypePattern {
+                    name: ident(node).ok_or_else(|| error(node, "expected type name"))?,
+                    fields,
+                })
+            }
+            _ => return Err(error(node, "expected field pattern")),
+        },
+        SyntaxKind::OrPattern => Pattern::Or(
+            node.children()
+                .map(|child| pattern(&child))
+                .collect::<Result<_>>()?,
+        ),
+        SyntaxKind::RangePattern => {
+            let operator = tokens(node)
+                .find(|token| token.kind().is_range())
+                .ok_or_else(|| error(node, "expected range operator"))?;
+            let (left_kind, right_kind) = bound_types(operator.kind());
+            let (left, right) = split(node, operator.kind());
+            let bound = |nodes: Vec<SyntaxNode>, kind| -> Result<_> {
+                match nodes.first() {
+                    Some(node) => Ok(Some(PatternBound {
+                        kind,
+                        pattern: Box::new(pattern(node)?),
+                    })),
+                    None => Ok(None),
+                }
+            };
+            Pattern::Range(RangePattern {
+                left: bound(left, left_kind)?,
+                right: bound(right, right_kind)?,
+            })
+        }
+        SyntaxKind::AsPattern => Pattern::As(AsPattern {
+            pattern: Box::new(pattern(&nth(node, 0)?)?),
+            var: param(&nth(node, 1)?)?,
+        }),
+        SyntaxKind::EqualPattern => Pattern::Equal(Box::new(expr(&nth(node, 0)?)?)),
+        _ => return Err(error(node, "expected pattern")),
+    };
+    Ok(pattern)
+}
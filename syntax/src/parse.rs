@@ -0,0 +1,196 @@
+use crate::{kind::Butter, lex::lex_with_errors, SyntaxError, SyntaxKind};
+use rowan::{Checkpoint, GreenNode, GreenNodeBuilder, Language, TextRange, TextSize};
+
+mod expr;
+mod pattern;
+mod statement;
+
+struct Input<'a> {
+    // every token including trivia
+    tokens: Vec<(SyntaxKind, &'a str)>,
+    offsets: Vec<usize>,
+    // indices of the non-trivia tokens
+    significant: Vec<usize>,
+    len: usize,
+    // tokens that aren't terminated
+    errors: Vec<SyntaxError>,
+}
+impl<'a> Input<'a> {
+    fn new(src: &'a str) -> Self {
+        let (tokens, errors) = lex_with_errors(src);
+        let mut offsets = Vec::with_capacity(tokens.len());
+        let mut offset = 0;
+        for (_, text) in &tokens {
+            offsets.push(offset);
+            offset += text.len();
+        }
+        let significant = tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, (kind, _))| !kind.is_trivia())
+            .map(|(i, _)| i)
+            .collect();
+        Self {
+            tokens,
+            offsets,
+            significant,
+            len: src.len(),
+            errors,
+        }
+    }
+}
+// recursive descent parser building the green tree directly, trivia are
+// placed outside of nodes so that nodes span from their first to their last
+// significant token
+pub(crate) struct Parser<'i, 'a> {
+    input: &'i Input<'a>,
+    // position within the significant tokens
+    pos: usize,
+    // next token to be pushed to the builder
+    emitted: usize,
+    builder: GreenNodeBuilder<'static>,
+    errors: Vec<SyntaxError>,
+}
+impl<'i, 'a> Parser<'i, 'a> {
+    fn new(input: &'i Input<'a>) -> Self {
+        Self {
+            input,
+            pos: 0,
+            emitted: 0,
+            builder: GreenNodeBuilder::new(),
+            errors: input.errors.clone(),
+        }
+    }
+    fn nth(&self, n: usize) -> Option<SyntaxKind> {
+        self.input
+            .significant
+            .get(self.pos + n)
+            .map(|i| self.input.tokens[*i].0)
+    }
+    fn current(&self) -> Option<SyntaxKind> {
+        self.nth(0)
+    }
+    fn at(&self, kind: SyntaxKind) -> bool {
+        self.current() == Some(kind)
+    }
+    fn at_end(&self) -> bool {
+        self.current().is_none()
+    }
+    // whether there's no trivia between the previous and the current token
+    fn joined(&self) -> bool {
+        match (
+            self.pos.checked_sub(1),
+            self.input.significant.get(self.pos),
+        ) {
+            (Some(prev), Some(current)) => self.input.significant[prev] + 1 == *current,
+            _ => false,
+        }
+    }
+    // whether there's no trivia between the current and the next token
+    fn next_joined(&self) -> bool {
+        match (
+            self.input.significant.get(self.pos),
+            self.input.significant.get(self.pos + 1),
+        ) {
+            (Some(current), Some(next)) => current + 1 == *next,
+            _ => false,
+        }
+    }
+    fn emit_until(&mut self, end: usize) {
+        while self.emitted < end {
+            let (kind, text) = self.input.tokens[self.emitted];
+            self.builder.token(Butter::kind_to_raw(kind), text);
+            self.emitted += 1;
+        }
+    }
+    fn flush_trivia(&mut self) {
+        let end = match self.input.significant.get(self.pos) {
+            Some(i) => *i,
+            None => self.input.tokens.len(),
+        };
+        self.emit_until(end);
+    }
+    fn bump(&mut self) {
+        if let Some(i) = self.input.significant.get(self.pos) {
+            self.emit_until(i + 1);
+            self.pos += 1;
+        }
+    }
+    fn eat(&mut self, kind: SyntaxKind) -> bool {
+        if self.at(kind) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+    fn expect(&mut self, kind: SyntaxKind, expected: &str) -> bool {
+        if self.eat(kind) {
+            true
+        } else {
+            self.error(format!("expected {}", expected));
+            false
+        }
+    }
+    fn start(&mut self, kind: SyntaxKind) {
+        self.flush_trivia();
+        self.builder.start_node(Butter::kind_to_raw(kind));
+    }
+    fn finish(&mut self) {
+        self.builder.finish_node();
+    }
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.flush_trivia();
+        self.builder.checkpoint()
+    }
+    fn start_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        self.builder
+            .start_node_at(checkpoint, Butter::kind_to_raw(kind));
+    }
+    fn range(&self) -> TextRange {
+        let (start, end) = match self.input.significant.get(self.pos) {
+            Some(i) => {
+                let start = self.input.offsets[*i];
+                (start, start + self.input.tokens[*i].1.len())
+            }
+            None => (self.input.len, self.input.len),
+        };
+        TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32))
+    }
+    fn error(&mut self, message: String) {
+        let range = self.range();
+        self.errors.push(SyntaxError { message, range });
+    }
+    // reports the current token as unexpected and skips it
+    fn error_and_bump(&mut self, expected: &str) {
+        self.error(format!("expected {}", expected));
+        if !self.at_end() {
+            self.start(SyntaxKind::ErrorNode);
+            self.bump();
+            self.finish();
+        }
+    }
+    // whether the parser succeeds from the current position without errors,
+    // nothing is consumed
+    fn lookahead(&self, parser: impl FnOnce(&mut Parser) -> bool) -> bool {
+        let mut scratch = Parser {
+            input: self.input,
+            pos: self.pos,
+            emitted: self.emitted,
+            builder: GreenNodeBuilder::new(),
+            errors: Vec::new(),
+        };
+        parser(&mut scratch) && scratch.errors.is_empty()
+    }
+}
+pub(crate) fn parse(src: &str) -> (GreenNode, Vec<SyntaxError>) {
+    let input = Input::new(src);
+    let mut parser = Parser::new(&input);
+    parser
+        .builder
+        .start_node(Butter::kind_to_raw(SyntaxKind::Root));
+    statement::statements(&mut parser, None);
+    parser.flush_trivia();
+    parser.finish();
+    (parser.builder.finish(), parser.errors)
+}
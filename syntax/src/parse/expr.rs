@@ -0,0 +1,557 @@
+use crate::{
+    parse::{pattern::pattern, statement::statements, Parser},
+    SyntaxKind,
+};
+
+pub(crate) fn is_control_flow_start(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::LeftBrace
+            | SyntaxKind::IfKw
+            | SyntaxKind::ForKw
+            | SyntaxKind::WhileKw
+            | SyntaxKind::LoopKw
+            | SyntaxKind::MatchKw
+            | SyntaxKind::Label
+    )
+}
+fn is_expr_start(kind: SyntaxKind) -> bool {
+    is_control_flow_start(kind)
+        || matches!(
+            kind,
+            SyntaxKind::LeftParen
+                | SyntaxKind::LeftBracket
+                | SyntaxKind::HashParen
+                | SyntaxKind::HashBracket
+                | SyntaxKind::Str
+                | SyntaxKind::Bang
+                | SyntaxKind::Ampersand
+                | SyntaxKind::Minus
+                | SyntaxKind::Greater
+                | SyntaxKind::CloneKw
+                | SyntaxKind::At
+                | SyntaxKind::Ident
+                | SyntaxKind::Number
+                | SyntaxKind::Char
+                | SyntaxKind::TrueKw
+                | SyntaxKind::FalseKw
+                | SyntaxKind::BreakKw
+                | SyntaxKind::ContinueKw
+                | SyntaxKind::ReturnKw
+                | SyntaxKind::YieldKw
+                | SyntaxKind::DoKw
+                | SyntaxKind::ModKw
+        )
+}
+fn binary_precedence(kind: SyntaxKind) -> Option<u8> {
+    let precedence = match kind {
+        SyntaxKind::SlashSlash | SyntaxKind::Star | SyntaxKind::Slash | SyntaxKind::Percent => 6,
+        SyntaxKind::PlusPlus | SyntaxKind::Plus | SyntaxKind::Minus => 5,
+        SyntaxKind::EqualEqual
+        | SyntaxKind::BangEqual
+        | SyntaxKind::LessEqual
+        | SyntaxKind::GreaterEqual
+        | SyntaxKind::Less
+        | SyntaxKind::Greater => 4,
+        SyntaxKind::AmpersandAmpersand | SyntaxKind::Ampersand => 3,
+        SyntaxKind::PipePipe | SyntaxKind::Pipe => 2,
+        SyntaxKind::PipeGreater => 1,
+        _ => return None,
+    };
+    Some(precedence)
+}
+fn at_range(parser: &Parser) -> bool {
+    parser.current().is_some_and(SyntaxKind::is_range)
+}
+fn expect_expr(parser: &mut Parser, precedence: u8) {
+    if !expr(parser, precedence) {
+        parser.error("expected expression".to_string());
+    }
+}
+// parses an expression made of operators of the given precedence or higher,
+// returns false without consuming anything when there's no expression
+pub(crate) fn expr(parser: &mut Parser, precedence: u8) -> bool {
+    let checkpoint = parser.checkpoint();
+    match precedence {
+        0 => {
+            if !expr(parser, 1) {
+                return false;
+            }
+            if parser.at(SyntaxKind::LeftArrow) {
+                parser.start_at(checkpoint, SyntaxKind::AssignExpr);
+                parser.bump();
+                expect_expr(parser, 0);
+                parser.finish();
+            }
+        }
+        1..=6 => {
+            if !expr(parser, precedence + 1) {
+                return false;
+            }
+            while parser.current().and_then(binary_precedence) == Some(precedence) {
+                parser.start_at(checkpoint, SyntaxKind::BinaryExpr);
+                parser.bump();
+                expect_expr(parser, precedence + 1);
+                parser.finish();
+            }
+        }
+        _ => {
+            if !prefix_expr(parser) {
+                return false;
+            }
+            while postfix(parser, checkpoint) {}
+        }
+    }
+    true
+}
+fn postfix(parser: &mut Parser, checkpoint: rowan::Checkpoint) -> bool {
+    let kind = match parser.current() {
+        Some(SyntaxKind::LeftParen) => {
+            tuple_record(parser, true);
+            SyntaxKind::CallExpr
+        }
+        Some(SyntaxKind::Dot) => {
+            parser.bump();
+            if !parser.eat(SyntaxKind::PrivateIdent) {
+                parser.expect(SyntaxKind::Ident, "field name");
+            }
+            SyntaxKind::FieldAccess
+        }
+        Some(SyntaxKind::LeftBracket) => {
+            if bracket(parser, false) {
+                SyntaxKind::SliceExpr
+            } else {
+                SyntaxKind::IndexExpr
+            }
+        }
+        Some(SyntaxKind::Caret) => {
+            parser.bump();
+            SyntaxKind::DerefExpr
+        }
+        _ => return false,
+    };
+    parser.start_at(checkpoint, kind);
+    parser.finish();
+    true
+}
+fn element(parser: &mut Parser) -> bool {
+    if parser.at(SyntaxKind::Star) {
+        parser.start(SyntaxKind::Splat);
+        parser.bump();
+        expect_expr(parser, 0);
+        parser.finish();
+        true
+    } else {
+        expr(parser, 0)
+    }
+}
+// a range, an array or an index within brackets, returns whether it's a range
+fn bracket(parser: &mut Parser, array: bool) -> bool {
+    let checkpoint = parser.checkpoint();
+    parser.bump();
+    let range = if at_range(parser) {
+        true
+    } else if array && parser.at(SyntaxKind::RightBracket) {
+        false
+    } else if array && parser.at(SyntaxKind::Star) {
+        element(parser);
+        false
+    } else {
+        expect_expr(parser, 0);
+        at_range(parser)
+    };
+    if range {
+        parser.bump();
+        if parser.current().is_some_and(is_expr_start) {
+            expr(parser, 0);
+        }
+    } else if array {
+        while parser.eat(SyntaxKind::Comma) && !parser.at(SyntaxKind::RightBracket) {
+            if !element(parser) {
+                parser.error("expected expression".to_string());
+                break;
+            }
+        }
+    }
+    parser.expect(SyntaxKind::RightBracket, "`]`");
+    if range {
+        parser.start_at(checkpoint, SyntaxKind::RangeExpr);
+        parser.finish();
+    } else if array {
+        parser.start_at(checkpoint, SyntaxKind::ArrayExpr);
+        parser.finish();
+    }
+    range
+}
+// `#(key = value)` or `#[elem]`
+fn dict_set(parser: &mut Parser, dict: bool) {
+    let (kind, close, expected) = if dict {
+        (SyntaxKind::DictExpr, SyntaxKind::RightParen, "`)`")
+    } else {
+        (SyntaxKind::SetExpr, SyntaxKind::RightBracket, "`]`")
+    };
+    parser.start(kind);
+    parser.bump();
+    while !parser.at(close) {
+        if dict {
+            parser.start(SyntaxKind::Entry);
+        }
+        if !expr(parser, 0) {
+            parser.error("expected expression".to_string());
+            if dict {
+                parser.finish();
+            }
+            break;
+        }
+        if dict {
+            parser.expect(SyntaxKind::Equal, "`=`");
+            expect_expr(parser, 0);
+            parser.finish();
+        }
+        if !parser.eat(SyntaxKind::Comma) {
+            break;
+        }
+    }
+    parser.expect(close, expected);
+    parser.finish();
+}
+pub(crate) fn param_list(parser: &mut Parser) -> bool {
+    if !parser.at(SyntaxKind::LeftParen) {
+        return false;
+    }
+    parser.start(SyntaxKind::ParamList);
+    parser.bump();
+    while matches!(
+        parser.current(),
+        Some(SyntaxKind::RefKw | SyntaxKind::MutKw | SyntaxKind::Ident)
+    ) {
+        parser.start(SyntaxKind::Param);
+        parser.eat(SyntaxKind::RefKw);
+        parser.eat(SyntaxKind::MutKw);
+        parser.expect(SyntaxKind::Ident, "identifier");
+        parser.finish();
+        if !parser.eat(SyntaxKind::Comma) {
+            break;
+        }
+    }
+    parser.expect(SyntaxKind::RightParen, "`)`");
+    parser.finish();
+    true
+}
+fn fun(parser: &mut Parser) -> bool {
+    param_list(parser) && parser.at(SyntaxKind::FatArrow)
+}
+// an expression, or a `?` placeholder for call arguments
+fn arg_expr(parser: &mut Parser, call: bool) -> bool {
+    if call && parser.at(SyntaxKind::Question) {
+        parser.start(SyntaxKind::Placeholder);
+        parser.bump();
+        parser.finish();
+        true
+    } else {
+        expr(parser, 0)
+    }
+}
+// parenthesized elements, that is a unit, a group, a tuple, a record or a
+// splat, a group isn't possible for call arguments
+fn tuple_record(parser: &mut Parser, call: bool) {
+    let checkpoint = parser.checkpoint();
+    parser.bump();
+    let mut record = None;
+    let mut plain = 0;
+    let mut splat = false;
+    let mut commas = 0;
+    while !parser.at(SyntaxKind::RightParen) {
+        match parser.current() {
+            Some(SyntaxKind::Star) => {
+                if splat {
+                    parser.error("expected only one splat".to_string());
+                }
+                splat = true;
+                element(parser);
+            }
+            Some(SyntaxKind::Equal | SyntaxKind::Ident | SyntaxKind::PrivateIdent)
+                if parser.at(SyntaxKind::Equal) || parser.nth(1) == Some(SyntaxKind::Equal) =>
+            {
+                if record == Some(false) {
+                    parser.error("expected expression".to_string());
+                }
+                record = Some(true);
+                parser.start(SyntaxKind::Field);
+                if !parser.eat(SyntaxKind::Ident) {
+                    parser.eat(SyntaxKind::PrivateIdent);
+                }
+                parser.bump();
+                if !arg_expr(parser, call) {
+                    parser.error("expected expression".to_string());
+                }
+                parser.finish();
+            }
+            _ => {
+                if record == Some(true) {
+                    parser.error("expected field".to_string());
+                }
+                if !arg_expr(parser, call) {
+                    parser.error("expected expression".to_string());
+                    break;
+                }
+                record = Some(false);
+                plain += 1;
+            }
+        }
+        if !parser.eat(SyntaxKind::Comma) {
+            break;
+        }
+        commas += 1;
+    }
+    parser.expect(SyntaxKind::RightParen, "`)`");
+    let kind = if record == Some(true) {
+        SyntaxKind::RecordExpr
+    } else if !call && plain == 1 && !splat && commas == 0 {
+        SyntaxKind::ParenExpr
+    } else {
+        SyntaxKind::TupleExpr
+    };
+    parser.start_at(checkpoint, kind);
+    parser.finish();
+}
+fn prefix_expr(parser: &mut Parser) -> bool {
+    let Some(kind) = parser.current() else {
+        return false;
+    };
+    match kind {
+        SyntaxKind::LeftParen if parser.lookahead(fun) => {
+            parser.start(SyntaxKind::FunExpr);
+            param_list(parser);
+            parser.bump();
+            expect_expr(parser, 0);
+            parser.finish();
+        }
+        SyntaxKind::LeftParen => tuple_record(parser, false),
+        SyntaxKind::LeftBracket => {
+            bracket(parser, true);
+        }
+        SyntaxKind::HashParen => dict_set(parser, true),
+        SyntaxKind::HashBracket => dict_set(parser, false),
+        SyntaxKind::Str
+        | SyntaxKind::Number
+        | SyntaxKind::Char
+        | SyntaxKind::TrueKw
+        | SyntaxKind::FalseKw => {
+            parser.start(SyntaxKind::Literal);
+            parser.bump();
+            parser.finish();
+        }
+        SyntaxKind::Bang
+        | SyntaxKind::Ampersand
+        | SyntaxKind::Minus
+        | SyntaxKind::Greater
+        | SyntaxKind::CloneKw => {
+            parser.start(SyntaxKind::UnaryExpr);
+            parser.bump();
+            expect_expr(parser, 7);
+            parser.finish();
+        }
+        SyntaxKind::At => {
+            parser.start(SyntaxKind::TagExpr);
+            parser.bump();
+            parser.expect(SyntaxKind::Ident, "tag name");
+            if parser.current().is_some_and(is_expr_start) {
+                expr(parser, 7);
+            }
+            parser.finish();
+        }
+        SyntaxKind::Ident => {
+            parser.start(SyntaxKind::NameRef);
+            parser.bump();
+            parser.finish();
+        }
+        SyntaxKind::BreakKw | SyntaxKind::ReturnKw => {
+            let kind = if kind == SyntaxKind::BreakKw {
+                SyntaxKind::BreakExpr
+            } else {
+                SyntaxKind::ReturnExpr
+            };
+            parser.start(kind);
+            let is_break = kind == SyntaxKind::BreakExpr;
+            parser.bump();
+            if is_break {
+                parser.eat(SyntaxKind::Label);
+            }
+            if parser.current().is_some_and(is_expr_start) {
+                expr(parser, 0);
+            }
+            parser.finish();
+        }
+        SyntaxKind::ContinueKw => {
+            parser.start(SyntaxKind::ContinueExpr);
+            parser.bump();
+            parser.eat(SyntaxKind::Label);
+            parser.finish();
+        }
+        SyntaxKind::YieldKw => {
+            parser.start(SyntaxKind::YieldExpr);
+            parser.bump();
+            parser.expect(SyntaxKind::Ident, "effect name");
+            if parser.at(SyntaxKind::LeftParen) {
+                tuple_record(parser, true);
+            } else {
+                parser.error("expected `(`".to_string());
+            }
+            parser.finish();
+        }
+        SyntaxKind::DoKw => handle(parser),
+        SyntaxKind::ModKw => {
+            parser.start(SyntaxKind::ModuleExpr);
+            parser.bump();
+            parser.expect(SyntaxKind::Ident, "module name");
+            parser.finish();
+        }
+        kind if is_control_flow_start(kind) => control_flow(parser),
+        _ => return false,
+    }
+    true
+}
+pub(crate) fn block(parser: &mut Parser) {
+    if !parser.at(SyntaxKind::LeftBrace) {
+        parser.error("expected block".to_string());
+        return;
+    }
+    parser.start(SyntaxKind::Block);
+    parser.bump();
+    statements(parser, Some(SyntaxKind::RightBrace));
+    parser.expect(SyntaxKind::RightBrace, "`}`");
+    parser.finish();
+}
+// the condition of `if` and `while`, possibly `expr =: pattern`
+fn condition(parser: &mut Parser) {
+    let checkpoint = parser.checkpoint();
+    expect_expr(parser, 0);
+    if parser.at(SyntaxKind::EqualColon) {
+        parser.start_at(checkpoint, SyntaxKind::MatchCondition);
+        parser.bump();
+        if !pattern(parser) {
+            parser.error("expected pattern".to_string());
+        }
+        parser.finish();
+    }
+}
+fn if_expr(parser: &mut Parser) {
+    parser.start(SyntaxKind::IfExpr);
+    parser.bump();
+    condition(parser);
+    block(parser);
+    if parser.eat(SyntaxKind::ElseKw) {
+        if parser.at(SyntaxKind::IfKw) {
+            if_expr(parser);
+        } else {
+            block(parser);
+        }
+    }
+    parser.finish();
+}
+// `do expr handling { effect(param) => body }`
+fn handle(parser: &mut Parser) {
+    parser.start(SyntaxKind::HandleExpr);
+    parser.bump();
+    expect_expr(parser, 0);
+    parser.expect(SyntaxKind::HandlingKw, "`handling`");
+    if parser.expect(SyntaxKind::LeftBrace, "handlers") {
+        while !parser.at_end() && !parser.at(SyntaxKind::RightBrace) {
+            let pos = parser.pos;
+            parser.start(SyntaxKind::Handler);
+            parser.expect(SyntaxKind::Ident, "effect name");
+            if !param_list(parser) {
+                parser.error("expected parameters".to_string());
+            }
+            arm_body(parser);
+            parser.finish();
+            if parser.pos == pos {
+                parser.error_and_bump("handler");
+            }
+        }
+        parser.expect(SyntaxKind::RightBrace, "`}`");
+    }
+    parser.finish();
+}
+fn match_arm(parser: &mut Parser) {
+    parser.start(SyntaxKind::MatchArm);
+    if !pattern(parser) {
+        parser.error("expected pattern".to_string());
+    }
+    arm_body(parser);
+    parser.finish();
+}
+// `=> expr` of match arms and handlers, the comma is optional after control
+// flow and before `}`
+fn arm_body(parser: &mut Parser) {
+    parser.expect(SyntaxKind::FatArrow, "`=>`");
+    if parser.current().is_some_and(is_control_flow_start) {
+        control_flow(parser);
+        parser.eat(SyntaxKind::Comma);
+    } else {
+        expect_expr(parser, 0);
+        if !parser.eat(SyntaxKind::Comma) && !parser.at(SyntaxKind::RightBrace) {
+            parser.error("expected `,`".to_string());
+        }
+    }
+}
+// blocks and expressions ending with a block
+pub(crate) fn control_flow(parser: &mut Parser) {
+    // loops may be labelled with `'label:`, the label is part of the loop node
+    let checkpoint = parser.checkpoint();
+    if parser.eat(SyntaxKind::Label) {
+        parser.expect(SyntaxKind::Colon, "`:`");
+        if !matches!(
+            parser.current(),
+            Some(SyntaxKind::ForKw | SyntaxKind::WhileKw | SyntaxKind::LoopKw)
+        ) {
+            parser.error("expected loop".to_string());
+            return;
+        }
+    }
+    match parser.current() {
+        Some(SyntaxKind::IfKw) => if_expr(parser),
+        Some(SyntaxKind::ForKw) => {
+            parser.start_at(checkpoint, SyntaxKind::ForExpr);
+            parser.bump();
+            if !pattern(parser) {
+                parser.error("expected pattern".to_string());
+            }
+            parser.expect(SyntaxKind::InKw, "`in`");
+            expect_expr(parser, 0);
+            block(parser);
+            parser.finish();
+        }
+        Some(SyntaxKind::WhileKw) => {
+            parser.start_at(checkpoint, SyntaxKind::WhileExpr);
+            parser.bump();
+            condition(parser);
+            block(parser);
+            parser.finish();
+        }
+        Some(SyntaxKind::LoopKw) => {
+            parser.start_at(checkpoint, SyntaxKind::LoopExpr);
+            parser.bump();
+            block(parser);
+            parser.finish();
+        }
+        Some(SyntaxKind::MatchKw) => {
+            parser.start(SyntaxKind::MatchExpr);
+            parser.bump();
+            expect_expr(parser, 0);
+            if parser.expect(SyntaxKind::LeftBrace, "match body") {
+                while !parser.at_end() && !parser.at(SyntaxKind::RightBrace) {
+                    let pos = parser.pos;
+                    match_arm(parser);
+                    if parser.pos == pos {
+                        parser.error_and_bump("match arm");
+                    }
+                }
+                parser.expect(SyntaxKind::RightBrace, "`}`");
+            }
+            parser.finish();
+        }
+        _ => block(parser),
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{
+    parse::{expr::expr, Parser},
+    SyntaxKind,
+};
+
+fn is_pattern_start(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::At
+            | SyntaxKind::Ampersand
+            | SyntaxKind::Number
+            | SyntaxKind::Minus
+            | SyntaxKind::DotDot
+            | SyntaxKind::DotLess
+            | SyntaxKind::GreaterDot
+            | SyntaxKind::GreaterLess
+            | SyntaxKind::LeftParen
+            | SyntaxKind::LeftBracket
+            | SyntaxKind::UnderscoreKw
+            | SyntaxKind::TrueKw
+            | SyntaxKind::FalseKw
+            | SyntaxKind::RefKw
+            | SyntaxKind::MutKw
+            | SyntaxKind::Ident
+            | SyntaxKind::EqualEqual
+    )
+}
+fn expect_pattern(parser: &mut Parser) {
+    if !pattern(parser) {
+        parser.error("expected pattern".to_string());
+    }
+}
+fn rest(parser: &mut Parser, has_rest: &mut bool) {
+    if *has_rest {
+        parser.error("expected only one rest pattern".to_string());
+    }
+    *has_rest = true;
+    parser.start(SyntaxKind::RestPattern);
+    parser.bump();
+    expect_pattern(parser);
+    parser.finish();
+}
+// a group or a record pattern, tuple patterns are not supported
+fn paren(parser: &mut Parser) {
+    let checkpoint = parser.checkpoint();
+    parser.bump();
+    let mut fields = 0;
+    let mut plain = 0;
+    let mut has_rest = false;
+    let mut commas = 0;
+    while !parser.at(SyntaxKind::RightParen) {
+        if parser.at(SyntaxKind::Star) {
+            rest(parser, &mut has_rest);
+        } else if parser.at(SyntaxKind::Equal)
+            || (matches!(
+                parser.current(),
+                Some(SyntaxKind::Ident | SyntaxKind::PrivateIdent)
+            ) && parser.nth(1) == Some(SyntaxKind::Equal))
+        {
+            fields += 1;
+            parser.start(SyntaxKind::FieldPattern);
+            if !parser.eat(SyntaxKind::Ident) {
+                parser.eat(SyntaxKind::PrivateIdent);
+            }
+            parser.bump();
+            expect_pattern(parser);
+            parser.finish();
+        } else if pattern(parser) {
+            plain += 1;
+        } else {
+            parser.error("expected pattern".to_string());
+            break;
+        }
+        if !parser.eat(SyntaxKind::Comma) {
+            break;
+        }
+        commas += 1;
+    }
+    parser.expect(SyntaxKind::RightParen, "`)`");
+    let kind = if fields == 0 && plain == 1 && !has_rest && commas == 0 {
+        SyntaxKind::ParenPattern
+    } else {
+        if plain > 0 {
+            parser.error("expected field pattern".to_string());
+        }
+        SyntaxKind::RecordPattern
+    };
+    parser.start_at(checkpoint, kind);
+    parser.finish();
+}
+fn array(parser: &mut Parser) {
+    parser.start(SyntaxKind::ArrayPattern);
+    parser.bump();
+    let mut has_rest = false;
+    while !parser.at(SyntaxKind::RightBracket) {
+        if parser.at(SyntaxKind::Star) {
+            rest(parser, &mut has_rest);
+        } else if !pattern(parser) {
+            parser.error("expected pattern".to_string());
+            break;
+        }
+        if !parser.eat(SyntaxKind::Comma) {
+            break;
+        }
+    }
+    parser.expect(SyntaxKind::RightBracket, "`]`");
+    parser.finish();
+}
+// an integer, possibly negative
+fn integer(parser: &mut Parser) {
+    parser.start(SyntaxKind::LiteralPattern);
+    parser.eat(SyntaxKind::Minus);
+    parser.expect(SyntaxKind::Number, "integer");
+    parser.finish();
+}
+fn var(parser: &mut Parser) {
+    parser.start(SyntaxKind::VarPattern);
+    parser.eat(SyntaxKind::RefKw);
+    parser.eat(SyntaxKind::MutKw);
+    parser.expect(SyntaxKind::Ident, "identifier");
+    parser.finish();
+}
+// a pattern without `|` or `as`, these need parentheses within tag and
+// reference patterns
+fn primary(parser: &mut Parser) -> bool {
+    let Some(kind) = parser.current() else {
+        return false;
+    };
+    match kind {
+        SyntaxKind::At => {
+            parser.start(SyntaxKind::TagPattern);
+            parser.bump();
+            parser.expect(SyntaxKind::Ident, "tag name");
+            if parser.current().is_some_and(is_pattern_start) {
+                primary(parser);
+            }
+            parser.finish();
+        }
+        SyntaxKind::Ampersand => {
+            parser.start(SyntaxKind::RefPattern);
+            parser.bump();
+            if !primary(parser) {
+                parser.error("expected pattern".to_string());
+            }
+            parser.finish();
+        }
+        SyntaxKind::Number | SyntaxKind::Minus => {
+            let checkpoint = parser.checkpoint();
+            integer(parser);
+            if parser.current().is_some_and(SyntaxKind::is_range) {
+                parser.start_at(checkpoint, SyntaxKind::RangePattern);
+                parser.bump();
+                if matches!(
+                    parser.current(),
+                    Some(SyntaxKind::Number | SyntaxKind::Minus)
+                ) {
+                    integer(parser);
+                }
+                parser.finish();
+            }
+        }
+        kind if kind.is_range() => {
+            parser.start(SyntaxKind::RangePattern);
+            parser.bump();
+            integer(parser);
+            parser.finish();
+        }
+        SyntaxKind::TrueKw | SyntaxKind::FalseKw => {
+            parser.start(SyntaxKind::LiteralPattern);
+            parser.bump();
+            parser.finish();
+        }
+        // the expression binds tighter than `|` and comparisons
+        SyntaxKind::EqualEqual => {
+            parser.start(SyntaxKind::EqualPattern);
+            parser.bump();
+            if !expr(parser, 5) {
+                parser.error("expected expression".to_string());
+            }
+            parser.finish();
+        }
+        SyntaxKind::LeftParen => paren(parser),
+        SyntaxKind::LeftBracket => array(parser),
+        SyntaxKind::UnderscoreKw => {
+            parser.start(SyntaxKind::IgnorePattern);
+            parser.bump();
+            parser.finish();
+        }
+        // `Name(field = pattern)` without space before `(`
+        SyntaxKind::Ident
+            if parser.nth(1) == Some(SyntaxKind::LeftParen) && parser.next_joined() =>
+        {
+            parser.start(SyntaxKind::NewTypePattern);
+            parser.bump();
+            paren(parser);
+            parser.finish();
+        }
+        SyntaxKind::RefKw | SyntaxKind::MutKw | SyntaxKind::Ident => var(parser),
+        _ => return false,
+    }
+    true
+}
+fn as_pattern(parser: &mut Parser) -> bool {
+    let checkpoint = parser.checkpoint();
+    if !primary(parser) {
+        return false;
+    }
+    if parser.at(SyntaxKind::AsKw) {
+        parser.start_at(checkpoint, SyntaxKind::AsPattern);
+        parser.bump();
+        var(parser);
+        parser.finish();
+    }
+    true
+}
+// returns false without consuming anything when there's no pattern
+pub(crate) fn pattern(parser: &mut Parser) -> bool {
+    let checkpoint = parser.checkpoint();
+    if !as_pattern(parser) {
+        return false;
+    }
+    if parser.at(SyntaxKind::Pipe) {
+        parser.start_at(checkpoint, SyntaxKind::OrPattern);
+        while parser.eat(SyntaxKind::Pipe) {
+            if !as_pattern(parser) {
+                parser.error("expected pattern".to_string());
+                break;
+            }
+        }
+        parser.finish();
+    }
+    true
+}
@@ -0,0 +1,214 @@
+use crate::{
+    parse::{
+        expr::{block, control_flow, expr, is_control_flow_start, param_list},
+        pattern::pattern,
+        Parser,
+    },
+    SyntaxKind,
+};
+use rowan::Checkpoint;
+
+// statements until the end token or the end of file, the last expression
+// statement may omit `;` when there's an end token
+pub(crate) fn statements(parser: &mut Parser, end: Option<SyntaxKind>) {
+    while !parser.at_end() && parser.current() != end {
+        let pos = parser.pos;
+        statement(parser, end);
+        if parser.pos == pos {
+            parser.error_and_bump("statement");
+        }
+    }
+}
+fn declare(parser: &mut Parser) -> bool {
+    pattern(parser) && parser.at(SyntaxKind::Equal)
+}
+fn fun_declare(parser: &mut Parser) -> bool {
+    parser.eat(SyntaxKind::Ident)
+        && parser.joined()
+        && param_list(parser)
+        && parser.at(SyntaxKind::FatArrow)
+}
+// `pub` or `pub(path.to.module)`
+fn visibility(parser: &mut Parser) {
+    parser.start(SyntaxKind::Visibility);
+    parser.bump();
+    if parser.eat(SyntaxKind::LeftParen) {
+        loop {
+            parser.expect(SyntaxKind::Ident, "module name");
+            if !parser.eat(SyntaxKind::Dot) {
+                break;
+            }
+        }
+        parser.expect(SyntaxKind::RightParen, "`)`");
+    }
+    parser.finish();
+}
+// `mod name;` or `mod name { ... }`, unlike blocks every expression
+// statement within needs `;`
+fn module_declare(parser: &mut Parser, checkpoint: Checkpoint) {
+    parser.start_at(checkpoint, SyntaxKind::ModuleDeclare);
+    parser.bump();
+    parser.bump();
+    if parser.eat(SyntaxKind::LeftBrace) {
+        while !parser.at_end() && !parser.at(SyntaxKind::RightBrace) {
+            let pos = parser.pos;
+            statement(parser, None);
+            if parser.pos == pos {
+                parser.error_and_bump("statement");
+            }
+        }
+        parser.expect(SyntaxKind::RightBrace, "`}`");
+    } else {
+        parser.bump();
+    }
+    parser.finish();
+}
+// `_`, `[type]`, `&type`, `&mut type`, `Name` or `Name(type, ...)`
+fn type_annotation(parser: &mut Parser) {
+    match parser.current() {
+        Some(SyntaxKind::UnderscoreKw) => {
+            parser.start(SyntaxKind::InferType);
+            parser.bump();
+            parser.finish();
+        }
+        Some(SyntaxKind::LeftBracket) => {
+            parser.start(SyntaxKind::ArrayType);
+            parser.bump();
+            type_annotation(parser);
+            parser.expect(SyntaxKind::RightBracket, "`]`");
+            parser.finish();
+        }
+        Some(SyntaxKind::Ampersand) => {
+            parser.start(SyntaxKind::RefType);
+            parser.bump();
+            parser.eat(SyntaxKind::MutKw);
+            type_annotation(parser);
+            parser.finish();
+        }
+        Some(SyntaxKind::Ident) => {
+            parser.start(SyntaxKind::NamedType);
+            parser.bump();
+            if parser.eat(SyntaxKind::LeftParen) {
+                while !parser.at_end() && !parser.at(SyntaxKind::RightParen) {
+                    type_annotation(parser);
+                    if !parser.eat(SyntaxKind::Comma) {
+                        break;
+                    }
+                }
+                parser.expect(SyntaxKind::RightParen, "`)`");
+            }
+            parser.finish();
+        }
+        _ => parser.error("expected type".to_string()),
+    }
+}
+// `newtype Name(field: Type, #private);`
+fn newtype_declare(parser: &mut Parser, checkpoint: Checkpoint) {
+    parser.start_at(checkpoint, SyntaxKind::NewTypeDeclare);
+    parser.bump();
+    parser.expect(SyntaxKind::Ident, "type name");
+    if parser.expect(SyntaxKind::LeftParen, "`(`") {
+        while !parser.at_end() && !parser.at(SyntaxKind::RightParen) {
+            parser.start(SyntaxKind::NewTypeField);
+            if parser.at(SyntaxKind::PubKw) {
+                visibility(parser);
+            }
+            if !parser.eat(SyntaxKind::Ident) {
+                parser.expect(SyntaxKind::PrivateIdent, "field name");
+            }
+            if parser.eat(SyntaxKind::Colon) {
+                type_annotation(parser);
+            }
+            parser.finish();
+            if !parser.eat(SyntaxKind::Comma) {
+                break;
+            }
+        }
+        parser.expect(SyntaxKind::RightParen, "`)`");
+    }
+    parser.expect(SyntaxKind::Semicolon, "`;`");
+    parser.finish();
+}
+fn statement(parser: &mut Parser, end: Option<SyntaxKind>) {
+    // the visibility is part of the declaration after it
+    let checkpoint = parser.checkpoint();
+    let public = parser.at(SyntaxKind::PubKw);
+    if public {
+        visibility(parser);
+    }
+    if parser.at(SyntaxKind::NewtypeKw) {
+        newtype_declare(parser, checkpoint);
+    } else if parser.at(SyntaxKind::ModKw)
+        && parser.nth(1) == Some(SyntaxKind::Ident)
+        && matches!(
+            parser.nth(2),
+            Some(SyntaxKind::Semicolon | SyntaxKind::LeftBrace)
+        )
+    {
+        module_declare(parser, checkpoint);
+    } else if !public && parser.current().is_some_and(is_control_flow_start) {
+        parser.start(SyntaxKind::ExprStatement);
+        control_flow(parser);
+        parser.eat(SyntaxKind::Semicolon);
+        parser.finish();
+    } else if parser.lookahead(declare) {
+        parser.start_at(checkpoint, SyntaxKind::Declare);
+        pattern(parser);
+        parser.bump();
+        if !expr(parser, 0) {
+            parser.error("expected expression".to_string());
+        }
+        if parser.eat(SyntaxKind::ElseKw) {
+            block(parser);
+        }
+        parser.expect(SyntaxKind::Semicolon, "`;`");
+        parser.finish();
+    } else if parser.lookahead(fun_declare) {
+        parser.start_at(checkpoint, SyntaxKind::FunDeclare);
+        parser.bump();
+        param_list(parser);
+        parser.bump();
+        if parser.current().is_some_and(is_control_flow_start) {
+            control_flow(parser);
+            parser.eat(SyntaxKind::Semicolon);
+        } else {
+            if !expr(parser, 0) {
+                parser.error("expected expression".to_string());
+            }
+            parser.expect(SyntaxKind::Semicolon, "`;`");
+        }
+        parser.finish();
+    } else if public {
+        parser.error("expected declaration".to_string());
+    } else {
+        if !expr(parser, 1) {
+            return;
+        }
+        parser.start_at(checkpoint, SyntaxKind::ExprStatement);
+        if parser.at(SyntaxKind::Comma) || parser.at(SyntaxKind::LeftArrow) {
+            // parallel assignment
+            parser.start_at(checkpoint, SyntaxKind::AssignExpr);
+            while parser.eat(SyntaxKind::Comma) {
+                if !expr(parser, 1) {
+                    parser.error("expected place expression".to_string());
+                }
+            }
+            if parser.expect(SyntaxKind::LeftArrow, "`<-`") {
+                loop {
+                    if !expr(parser, 0) {
+                        parser.error("expected expression".to_string());
+                    }
+                    if !parser.eat(SyntaxKind::Comma) {
+                        break;
+                    }
+                }
+            }
+            parser.finish();
+        }
+        let end = end.is_some() && parser.current() == end;
+        if !end {
+            parser.expect(SyntaxKind::Semicolon, "`;`");
+        }
+        parser.finish();
+    }
+}
@@ -0,0 +1,743 @@
+use crate::ty::{
+    cons::{Cons, Keyed, OrderedAnd},
+    MutType, Subs, Substitutable, Type, TypeError,
+};
+use hir::{
+    expr::{
+        Arg, Assign, Block, Bound, Break, Condition, ControlFlow, Element, Entry, Expr, Field,
+        Jump, Module, PlaceExpr, Range, Record, Tuple, UnaryType,
+    },
+    pattern::{ListPattern, Pattern, Var},
+    statement::Statement,
+    Atom,
+};
+use std::{
+    collections::HashMap,
+    mem::{replace, take},
+};
+
+// Ordered from the least to the most restrictive so the kind of a compound
+// type is simply the maximum of its parts
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub(super) enum CloneKind {
+    Copy,
+    Clone,
+    Unclonable,
+}
+fn combine(kinds: impl IntoIterator<Item = Option<CloneKind>>) -> Option<CloneKind> {
+    let mut result = Some(CloneKind::Copy);
+    for kind in kinds {
+        match (kind, result) {
+            (Some(CloneKind::Unclonable), _) => return Some(CloneKind::Unclonable),
+            (None, _) => result = None,
+            (Some(kind), Some(prev)) => result = Some(kind.max(prev)),
+            (Some(_), None) => (),
+        }
+    }
+    result
+}
+fn keyed_kind(keyed: &Keyed) -> Option<CloneKind> {
+    let rest = keyed.rest.as_ref().map(|_| None);
+    combine(keyed.fields.values().map(Type::clone_kind).chain(rest))
+}
+fn ordered_kind<T>(
+    ordered: &OrderedAnd<T>,
+    kind: impl Fn(&T) -> Option<CloneKind>,
+) -> Option<CloneKind> {
+    match ordered {
+        OrderedAnd::NonRow(tuple) => combine(tuple.iter().map(kind)),
+        OrderedAnd::Row(left, _, right) => {
+            combine(left.iter().chain(right.iter()).map(kind).chain([None]))
+        }
+    }
+}
+impl Type {
+    // `None` means the type isn't known enough yet to be classified
+    pub(super) fn clone_kind(&self) -> Option<CloneKind> {
+        match self {
+            Self::Var(_) => None,
+            Self::Cons(cons) => cons.clone_kind(),
+        }
+    }
+    fn has_mut_ref(&self) -> bool {
+        match self {
+            Self::Var(_) => false,
+            Self::Cons(cons) => cons.has_mut_ref(),
+        }
+    }
+}
+impl Cons {
+    pub(super) fn clone_kind(&self) -> Option<CloneKind> {
+        match self {
+            Self::Num | Self::Bool => Some(CloneKind::Copy),
+            Self::Nominal(nominal, args) => {
+                combine(nominal.fields(args).map(|(_, _, ty)| ty.clone_kind()))
+            }
+            Self::Ref(MutType::Imm, _) => Some(CloneKind::Copy),
+            Self::Ref(MutType::Mut, _) => Some(CloneKind::Unclonable),
+            Self::Ref(MutType::Var(_), _) => None,
+            // iterators may hold on to the state of a generator
+            Self::Fun(..) | Self::Iter(..) => Some(CloneKind::Unclonable),
+            Self::Array(ty) | Self::Set(ty) => combine([ty.clone_kind(), Some(CloneKind::Clone)]),
+            Self::Dict(key, value) => {
+                combine([key.clone_kind(), value.clone_kind(), Some(CloneKind::Clone)])
+            }
+            Self::Record(record) => keyed_kind(record),
+            Self::Union(union) => keyed_kind(union),
+            Self::Tuple(tuple) => ordered_kind(tuple, Type::clone_kind),
+            Self::RecordTuple(record_tuple) => {
+                ordered_kind(record_tuple, |(_, ty)| ty.clone_kind())
+            }
+        }
+    }
+    fn has_mut_ref(&self) -> bool {
+        match self {
+            Self::Num | Self::Bool | Self::Fun(..) => false,
+            Self::Nominal(nominal, args) => nominal.fields(args).any(|(_, _, ty)| ty.has_mut_ref()),
+            Self::Ref(MutType::Mut, _) => true,
+            Self::Ref(_, ty) | Self::Array(ty) | Self::Set(ty) | Self::Iter(ty, _) => {
+                ty.has_mut_ref()
+            }
+            Self::Dict(key, value) => key.has_mut_ref() || value.has_mut_ref(),
+            Self::Record(keyed) | Self::Union(keyed) => {
+                keyed.fields.values().any(Type::has_mut_ref)
+            }
+            Self::Tuple(OrderedAnd::NonRow(tuple)) => tuple.iter().any(Type::has_mut_ref),
+            Self::Tuple(OrderedAnd::Row(left, _, right)) => {
+                left.iter().chain(right.iter()).any(Type::has_mut_ref)
+            }
+            Self::RecordTuple(OrderedAnd::NonRow(tuple)) => {
+                tuple.iter().any(|(_, ty)| ty.has_mut_ref())
+            }
+            Self::RecordTuple(OrderedAnd::Row(left, _, right)) => left
+                .iter()
+                .chain(right.iter())
+                .any(|(_, ty)| ty.has_mut_ref()),
+        }
+    }
+}
+pub(super) fn check_clone(ty: &Type) -> Result<(), TypeError> {
+    match ty.clone_kind() {
+        Some(CloneKind::Unclonable) => Err(TypeError::Unclonable),
+        _ => Ok(()),
+    }
+}
+struct Binding {
+    ty: Type,
+    mutable: bool,
+}
+// Checks that places whose values can't be implicitly copied aren't used more
+// than once without `>` or `clone`. Immutable values without mutable
+// references are exempted as these are shared instead, see `doc/language/share.md`
+pub(super) struct UseChecker<'a> {
+    subs: &'a Subs,
+    bindings: Vec<Binding>,
+    scopes: Vec<HashMap<Atom, usize>>,
+    uses: HashMap<usize, u32>,
+}
+impl<'a> UseChecker<'a> {
+    pub fn new(subs: &'a Subs) -> Self {
+        Self {
+            subs,
+            bindings: Vec::new(),
+            scopes: vec![HashMap::new()],
+            uses: HashMap::new(),
+        }
+    }
+    fn lookup(&self, name: &Atom) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+    fn bind(&mut self, var: &Var<Type>) {
+        let id = self.bindings.len();
+        self.bindings.push(Binding {
+            ty: var.ty.clone(),
+            mutable: var.mutable,
+        });
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(var.ident.clone(), id);
+    }
+    fn bind_pattern(&mut self, pattern: &Pattern<Type>) {
+        match pattern {
+            Pattern::True
+            | Pattern::False
+            | Pattern::UInt(_)
+            | Pattern::Int(_)
+            | Pattern::Ignore => (),
+            Pattern::Var(var) => self.bind(var),
+            Pattern::Record(record) => {
+                for pattern in record.fields.values() {
+                    self.bind_pattern(pattern);
+                }
+                if let Some(rest) = &record.rest {
+                    self.bind_pattern(rest);
+                }
+            }
+            Pattern::Tuple(list) | Pattern::Array(list) => match list {
+                ListPattern::List(list) => {
+                    for pattern in list.iter() {
+                        self.bind_pattern(pattern);
+                    }
+                }
+                ListPattern::ListWithRest(list) => {
+                    for pattern in list.left.iter().chain(list.right.iter()) {
+                        self.bind_pattern(pattern);
+                    }
+                    self.bind_pattern(&list.rest);
+                }
+            },
+            Pattern::Tag(tag) => {
+                if let Some(pattern) = &tag.pattern {
+                    self.bind_pattern(pattern);
+                }
+            }
+            Pattern::Ref(pattern) => self.bind_pattern(pattern),
+            Pattern::NewType(newtype) => {
+                for pattern in newtype.fields.fields.values() {
+                    self.bind_pattern(pattern);
+                }
+            }
+            // every alternative binds the same names with the same types
+            Pattern::Or(patterns) => self.bind_pattern(&patterns[0]),
+            Pattern::Range(_) => (),
+            Pattern::As(pattern) => {
+                self.bind_pattern(&pattern.pattern);
+                self.bind(&pattern.var);
+            }
+            // its expression is walked by `compared` before binding
+            Pattern::Equal(_) => (),
+        }
+    }
+    // the expressions of `== expr` patterns, evaluated while matching before
+    // anything is bound
+    fn compared(&mut self, pattern: &Pattern<Type>) -> Result<(), TypeError> {
+        match pattern {
+            Pattern::True
+            | Pattern::False
+            | Pattern::UInt(_)
+            | Pattern::Int(_)
+            | Pattern::Ignore
+            | Pattern::Var(_) => Ok(()),
+            Pattern::Record(record) => {
+                for pattern in record.fields.values().chain(record.rest.as_deref()) {
+                    self.compared(pattern)?;
+                }
+                Ok(())
+            }
+            Pattern::Tuple(list) | Pattern::Array(list) => match list {
+                ListPattern::List(list) => {
+                    for pattern in list.iter() {
+                        self.compared(pattern)?;
+                    }
+                    Ok(())
+                }
+                ListPattern::ListWithRest(list) => {
+                    for pattern in list.left.iter().chain(list.right.iter()) {
+                        self.compared(pattern)?;
+                    }
+                    self.compared(&list.rest)
+                }
+            },
+            Pattern::Tag(tag) => match &tag.pattern {
+                Some(pattern) => self.compared(pattern),
+                None => Ok(()),
+            },
+            Pattern::Ref(pattern) => self.compared(pattern),
+            Pattern::NewType(newtype) => {
+                for pattern in newtype.fields.fields.values() {
+                    self.compared(pattern)?;
+                }
+                Ok(())
+            }
+            // alternatives are tried in order so any of them may be
+            Pattern::Or(patterns) => {
+                for pattern in patterns.iter() {
+                    self.compared(pattern)?;
+                }
+                Ok(())
+            }
+            Pattern::Range(range) => {
+                for bound in range.left.iter().chain(range.right.iter()) {
+                    self.compared(&bound.pattern)?;
+                }
+                Ok(())
+            }
+            Pattern::As(pattern) => self.compared(&pattern.pattern),
+            Pattern::Equal(expr) => self.expr(expr),
+        }
+    }
+    fn scoped<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push(HashMap::new());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+    fn merge(&mut self, uses: HashMap<usize, u32>) {
+        for (id, count) in uses {
+            let merged = self.uses.entry(id).or_insert(0);
+            *merged = (*merged).max(count);
+        }
+    }
+    fn place_ty(&self, place: &PlaceExpr<Type>) -> Result<Option<(usize, Type)>, TypeError> {
+        let inner = |expr: &Expr<Type>| match expr {
+            Expr::Place(place) => self.place_ty(place),
+            _ => Ok(None),
+        };
+        let (id, ty) = match place {
+            PlaceExpr::Var(var) => match self.lookup(var) {
+                Some(id) => {
+                    let mut ty = self.bindings[id].ty.clone();
+                    ty.substitute(self.subs)?;
+                    return Ok(Some((id, ty)));
+                }
+                None => return Ok(None),
+            },
+            PlaceExpr::FieldAccess(field) => match inner(&field.expr)? {
+                Some((id, Type::Cons(Cons::Record(record)))) => {
+                    match record.fields.get(&field.name) {
+                        Some(ty) => (id, ty.clone()),
+                        None => return Ok(None),
+                    }
+                }
+                Some((id, Type::Cons(Cons::RecordTuple(record_tuple)))) => {
+                    let found = match &record_tuple {
+                        OrderedAnd::NonRow(fields) => {
+                            fields.iter().find(|(name, _)| *name == field.name)
+                        }
+                        OrderedAnd::Row(left, _, right) => left
+                            .iter()
+                            .chain(right.iter())
+                            .find(|(name, _)| *name == field.name),
+                    };
+                    match found {
+                        Some((_, ty)) => (id, ty.clone()),
+                        None => return Ok(None),
+                    }
+                }
+                _ => return Ok(None),
+            },
+            PlaceExpr::Index(index) => match inner(&index.expr)? {
+                Some((id, Type::Cons(Cons::Array(ty) | Cons::Dict(_, ty)))) => (id, *ty),
+                Some((id, Type::Cons(Cons::Set(_)))) => (id, Type::Cons(Cons::Bool)),
+                _ => return Ok(None),
+            },
+            PlaceExpr::Slice(slice) => match inner(&slice.expr)? {
+                Some((id, ty @ Type::Cons(Cons::Array(_)))) => (id, ty),
+                _ => return Ok(None),
+            },
+            PlaceExpr::Deref(expr) => match inner(expr)? {
+                Some((id, Type::Cons(Cons::Ref(_, ty)))) => (id, *ty),
+                _ => return Ok(None),
+            },
+            PlaceExpr::Len(expr) => match inner(expr)? {
+                Some((id, _)) => (id, Type::Cons(Cons::Num)),
+                None => return Ok(None),
+            },
+        };
+        Ok(Some((id, ty)))
+    }
+    fn place_inner(&mut self, place: &PlaceExpr<Type>) -> Result<(), TypeError> {
+        let inner = match place {
+            PlaceExpr::Var(_) => return Ok(()),
+            PlaceExpr::FieldAccess(field) => &field.expr,
+            PlaceExpr::Index(index) => {
+                self.expr(&index.index)?;
+                &index.expr
+            }
+            PlaceExpr::Slice(slice) => {
+                self.range(&slice.range)?;
+                &slice.expr
+            }
+            PlaceExpr::Deref(expr) | PlaceExpr::Len(expr) => expr,
+        };
+        match inner.as_ref() {
+            Expr::Place(place) => self.place_inner(place),
+            expr => self.expr(expr),
+        }
+    }
+    fn use_place(&mut self, place: &PlaceExpr<Type>) -> Result<(), TypeError> {
+        self.place_inner(place)?;
+        if let Some((id, ty)) = self.place_ty(place)? {
+            // a type not known by now is generic, it may not be copy where
+            // it's instantiated
+            let copy = ty.clone_kind() == Some(CloneKind::Copy);
+            let shared = !self.bindings[id].mutable && !ty.has_mut_ref();
+            if !copy && !shared {
+                let count = self.uses.entry(id).or_insert(0);
+                *count += 1;
+                if *count > 1 {
+                    return Err(TypeError::UsedTwice);
+                }
+            }
+        }
+        Ok(())
+    }
+    fn range(&mut self, range: &Range<Type>) -> Result<(), TypeError> {
+        for Bound { expr, kind: _ } in range.left.iter().chain(range.right.iter()) {
+            self.expr(expr)?;
+        }
+        Ok(())
+    }
+    fn fields(&mut self, fields: &[Field<Type>]) -> Result<(), TypeError> {
+        for field in fields {
+            self.expr(&field.expr)?;
+        }
+        Ok(())
+    }
+    fn record(&mut self, record: &Record<Type>) -> Result<(), TypeError> {
+        match record {
+            Record::Record(fields) => self.fields(fields),
+            Record::RecordWithSplat(record) => {
+                self.fields(&record.left)?;
+                self.expr(&record.splat)?;
+                self.fields(&record.right)
+            }
+        }
+    }
+    fn tuple(&mut self, tuple: &Tuple<Type>) -> Result<(), TypeError> {
+        match tuple {
+            Tuple::Tuple(tuple) => self.exprs(tuple.iter()),
+            Tuple::TupleWithSplat(tuple) => self.exprs(
+                tuple
+                    .left
+                    .iter()
+                    .chain(Some(tuple.splat.as_ref()))
+                    .chain(tuple.right.iter()),
+            ),
+        }
+    }
+    fn exprs<'b>(
+        &mut self,
+        exprs: impl IntoIterator<Item = &'b Expr<Type>>,
+    ) -> Result<(), TypeError> {
+        for expr in exprs {
+            self.expr(expr)?;
+        }
+        Ok(())
+    }
+    pub fn statements(&mut self, statements: &[Statement<Type>]) -> Result<(), TypeError> {
+        for statement in statements {
+            match statement {
+                Statement::Declare(declare) => {
+                    self.expr(&declare.expr)?;
+                    self.compared(&declare.pattern)?;
+                    // the else block never finishes, what it uses stays
+                    // usable afterwards
+                    if let Some(else_part) = &declare.else_part {
+                        let before = self.uses.clone();
+                        self.block(else_part)?;
+                        self.uses = before;
+                    }
+                    self.bind_pattern(&declare.pattern);
+                }
+                Statement::FunDeclare(fun) => {
+                    self.bind(&Var {
+                        ident: fun.ident.clone(),
+                        mutable: false,
+                        bind_to_ref: false,
+                        ty: fun.ty.clone(),
+                    });
+                    self.scoped(|this| {
+                        for param in fun.fun.param.iter() {
+                            this.bind(param);
+                        }
+                        this.expr(&fun.fun.body)
+                    })?;
+                }
+                Statement::NewType(newtype) => self.bind(&Var {
+                    ident: newtype.ident.clone(),
+                    mutable: false,
+                    bind_to_ref: false,
+                    ty: newtype.ty.clone(),
+                }),
+                Statement::Expr(expr) => self.expr(expr)?,
+            }
+        }
+        Ok(())
+    }
+    fn block(&mut self, block: &Block<Type>) -> Result<(), TypeError> {
+        self.scoped(|this| {
+            this.statements(&block.statement)?;
+            this.exprs(block.expr.iter().map(AsRef::as_ref))
+        })
+    }
+    // the bindings of `expr =: pattern` are only in scope of the body
+    fn conditional_block(
+        &mut self,
+        condition: &Condition<Type>,
+        block: &Block<Type>,
+    ) -> Result<(), TypeError> {
+        if let Condition::Match(condition) = condition {
+            self.compared(&condition.pattern)?;
+        }
+        self.scoped(|this| {
+            if let Condition::Match(condition) = condition {
+                this.bind_pattern(&condition.pattern);
+            }
+            this.block(block)
+        })
+    }
+    // A loop may run its body more than once, so it's checked twice for a use
+    // in one iteration to be seen as a second use by the next
+    fn looped(
+        &mut self,
+        mut f: impl FnMut(&mut Self) -> Result<(), TypeError>,
+    ) -> Result<(), TypeError> {
+        f(self)?;
+        f(self)
+    }
+    fn control_flow(&mut self, control_flow: &ControlFlow<Type>) -> Result<(), TypeError> {
+        match control_flow {
+            ControlFlow::Block(block) => self.block(block)?,
+            ControlFlow::Loop(loop_expr) => self.looped(|this| this.block(&loop_expr.body))?,
+            ControlFlow::If(if_expr) => {
+                self.expr(if_expr.condition.expr())?;
+                let before = self.uses.clone();
+                self.conditional_block(&if_expr.condition, &if_expr.body)?;
+                let body_uses = replace(&mut self.uses, before);
+                if let Some(else_part) = &if_expr.else_part {
+                    self.control_flow(else_part)?;
+                }
+                self.merge(body_uses);
+            }
+            ControlFlow::For(for_expr) => {
+                self.expr(&for_expr.expr)?;
+                self.looped(|this| {
+                    this.compared(&for_expr.pattern)?;
+                    this.scoped(|this| {
+                        this.bind_pattern(&for_expr.pattern);
+                        this.block(&for_expr.body)
+                    })
+                })?;
+            }
+            ControlFlow::While(while_expr) => self.looped(|this| {
+                this.expr(while_expr.condition.expr())?;
+                this.conditional_block(&while_expr.condition, &while_expr.body)
+            })?,
+            ControlFlow::Match(match_expr) => {
+                self.expr(&match_expr.expr)?;
+                // an arm is only tried after the patterns of the previous
+                // ones didn't match
+                let mut before = self.uses.clone();
+                let mut arm_uses = Vec::with_capacity(match_expr.arm.len());
+                for arm in match_expr.arm.iter() {
+                    self.uses = before;
+                    self.compared(&arm.pattern)?;
+                    before = self.uses.clone();
+                    self.scoped(|this| {
+                        this.bind_pattern(&arm.pattern);
+                        this.expr(&arm.expr)
+                    })?;
+                    arm_uses.push(take(&mut self.uses));
+                }
+                self.uses = before;
+                for uses in arm_uses {
+                    self.merge(uses);
+                }
+            }
+        }
+        Ok(())
+    }
+    pub fn expr(&mut self, expr: &Expr<Type>) -> Result<(), TypeError> {
+        match expr {
+            Expr::Literal(_) | Expr::Unit => (),
+            Expr::Tag(tag) => self.exprs(tag.expr.iter().map(AsRef::as_ref))?,
+            Expr::Assign(assigns) => {
+                for Assign { place, expr } in assigns.iter() {
+                    self.expr(expr)?;
+                    self.place_inner(place)?;
+                    if let PlaceExpr::Var(var) = place {
+                        if let Some(id) = self.lookup(var) {
+                            self.uses.remove(&id);
+                        }
+                    }
+                }
+            }
+            Expr::Array(elements) => {
+                self.exprs(elements.iter().map(|Element { expr, kind: _ }| expr))?
+            }
+            Expr::Dict(entries) => {
+                self.exprs(entries.iter().flat_map(|Entry { key, value }| [key, value]))?
+            }
+            Expr::Set(elements) => self.exprs(elements.iter())?,
+            Expr::ArrayRange(range) => self.range(range)?,
+            Expr::Splat(expr) => self.expr(expr)?,
+            Expr::Record(record) => self.record(record)?,
+            Expr::Tuple(tuple) => self.tuple(tuple)?,
+            Expr::Unary(unary) => match (unary.kind, unary.expr.as_ref()) {
+                (UnaryType::Ref | UnaryType::Move, Expr::Place(place)) => {
+                    self.place_inner(place)?
+                }
+                // the type may only be known after the whole expression has
+                // been inferred so this is checked again here
+                (UnaryType::Clone, Expr::Place(place)) => {
+                    self.place_inner(place)?;
+                    if let Some((_, ty)) = self.place_ty(place)? {
+                        check_clone(&ty)?;
+                    }
+                }
+                (_, expr) => self.expr(expr)?,
+            },
+            Expr::Binary(binary) => {
+                self.expr(&binary.left)?;
+                self.expr(&binary.right)?;
+            }
+            Expr::Place(place) => self.use_place(place)?,
+            Expr::Call(call) => {
+                self.expr(&call.expr)?;
+                self.arg(&call.arg)?;
+            }
+            Expr::ControlFlow(control_flow) => self.control_flow(control_flow)?,
+            Expr::Fun(fun) => self.scoped(|this| {
+                for param in fun.param.iter() {
+                    this.bind(param);
+                }
+                this.expr(&fun.body)
+            })?,
+            Expr::Jump(jump) => match jump {
+                Jump::Break(Break { expr, .. }) | Jump::Return(expr) => {
+                    self.exprs(expr.iter().map(AsRef::as_ref))?
+                }
+                Jump::Continue(_) => (),
+            },
+            Expr::Yield(yield_expr) => self.arg(&yield_expr.arg)?,
+            // handlers are checked like functions, they may run any number of
+            // times
+            Expr::Handle(handle) => {
+                self.expr(&handle.expr)?;
+                for handler in handle.handler.iter() {
+                    self.scoped(|this| {
+                        for param in handler.param.iter() {
+                            this.bind(param);
+                        }
+                        this.expr(&handler.body)
+                    })?;
+                }
+            }
+            Expr::Module(Module::File(_)) => (),
+            Expr::Module(Module::Inline(statements)) => {
+                self.scoped(|this| this.statements(statements))?
+            }
+        }
+        Ok(())
+    }
+    fn arg(&mut self, arg: &Arg<Type>) -> Result<(), TypeError> {
+        match arg {
+            Arg::Unit => Ok(()),
+            Arg::Splat(expr) => self.expr(expr),
+            Arg::Record(record) => self.record(record),
+            Arg::Tuple(tuple) => self.tuple(tuple),
+        }
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::{test::infer, TypeError};
+
+    #[test]
+    fn copy_reused() {
+        assert_eq!(infer("{ mut foo = 10; bar = foo; baz = foo; }"), Ok(()));
+    }
+    #[test]
+    fn shared_reused() {
+        assert_eq!(infer("{ foo = [10]; bar = foo; baz = foo; }"), Ok(()));
+    }
+    #[test]
+    fn mutable_reused() {
+        assert_eq!(
+            infer("{ mut foo = [10]; bar = foo; baz = foo; }"),
+            Err(TypeError::UsedTwice),
+        );
+    }
+    #[test]
+    fn generic_reused() {
+        assert_eq!(
+            infer("{ g(a) => 1; f(mut x) => { k = g(x); l = g(x); }; y = f([1]); }"),
+            Err(TypeError::UsedTwice),
+        );
+        assert_eq!(
+            infer("{ g(a) => 1; f(x) => { k = g(x); l = g(x); }; y = f([1]); }"),
+            Ok(()),
+        );
+    }
+    #[test]
+    fn mutable_moved() {
+        assert_eq!(infer("{ mut foo = [10]; bar = foo; baz = >foo; }"), Ok(()));
+    }
+    #[test]
+    fn mutable_cloned() {
+        assert_eq!(
+            infer("{ mut foo = [10]; bar = clone foo; baz = foo; }"),
+            Ok(()),
+        );
+    }
+    #[test]
+    fn reinitialized() {
+        assert_eq!(
+            infer("{ mut foo = [10]; bar = foo; foo <- [20]; baz = foo; }"),
+            Ok(()),
+        );
+    }
+    #[test]
+    fn reused_in_loop() {
+        assert_eq!(
+            infer("{ mut foo = [10]; while true { bar = foo; } }"),
+            Err(TypeError::UsedTwice),
+        );
+        assert_eq!(
+            infer("{ mut foo = [10]; loop { bar = foo; } }"),
+            Err(TypeError::UsedTwice),
+        );
+        assert_eq!(
+            infer("{ mut foo = [10]; for _ in [1, 2] { bar = foo; } }"),
+            Err(TypeError::UsedTwice),
+        );
+    }
+    #[test]
+    fn loop_local_reused() {
+        assert_eq!(
+            infer("{ mut foo = [10]; loop { mut bar = [20]; baz = bar; foo <- baz; } }"),
+            Ok(()),
+        );
+        assert_eq!(
+            infer("{ mut foo = [10]; while true { bar = foo; foo <- [20]; } }"),
+            Ok(()),
+        );
+    }
+    #[test]
+    fn reused_in_pattern() {
+        let src = "{
+            g(a) => 1;
+            f() => {
+                mut arr = [1];
+                k = match 1 { == g(arr) => 0, _ => 1 };
+                l = g(arr);
+            };
+        }";
+        assert_eq!(infer(src), Err(TypeError::UsedTwice));
+        let src = "{
+            g(a) => 1;
+            f() => {
+                mut arr = [1];
+                k = match 1 { == g(clone arr) => 0, _ => 1 };
+                l = g(arr);
+            };
+        }";
+        assert_eq!(infer(src), Ok(()));
+    }
+    #[test]
+    fn clone_fun() {
+        assert_eq!(infer("clone () => 10"), Err(TypeError::Unclonable));
+    }
+    #[test]
+    fn clone_mut_ref() {
+        assert_eq!(
+            infer("{ mut foo = 10; mut bar = &foo; bar^ <- 20; clone bar }"),
+            Err(TypeError::Unclonable),
+        );
+    }
+}
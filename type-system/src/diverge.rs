@@ -0,0 +1,328 @@
+use crate::expr::bound_names;
+use hir::{
+    expr::{
+        Arg, Binary, BinaryType, Block, Break, Call, Condition, ControlFlow, Expr, FieldAccess, If,
+        Jump, Loop, Match, PlaceExpr, Record, Tuple,
+    },
+    statement::{Declare, FunDeclare, Statement},
+    Atom,
+};
+
+// Whether evaluating something never finishes, found from the code itself
+// rather than its type. It diverges when it always reaches `return`, `break`,
+// `continue`, a call to `std.panic` or a `loop` nothing breaks out of.
+
+fn exprs_of_statement<T>(statement: &Statement<T>) -> Vec<&Expr<T>> {
+    match statement {
+        Statement::Declare(Declare {
+            expr, else_part, ..
+        }) => {
+            let mut exprs = vec![expr];
+            if let Some(else_part) = else_part {
+                exprs.extend(exprs_of_block(else_part));
+            }
+            exprs
+        }
+        Statement::Expr(expr) => vec![expr],
+        // jumps can't leave a function
+        Statement::FunDeclare(_) | Statement::NewType(_) => Vec::new(),
+    }
+}
+fn exprs_of_block<T>(block: &Block<T>) -> Vec<&Expr<T>> {
+    let mut exprs: Vec<_> = block
+        .statement
+        .iter()
+        .flat_map(exprs_of_statement)
+        .collect();
+    exprs.extend(block.expr.as_deref());
+    exprs
+}
+fn exprs_of_arg<T>(arg: &Arg<T>) -> Vec<&Expr<T>> {
+    match arg {
+        Arg::Unit => Vec::new(),
+        Arg::Splat(expr) => vec![expr],
+        Arg::Record(record) => exprs_of_record(record),
+        Arg::Tuple(tuple) => exprs_of_tuple(tuple),
+    }
+}
+fn exprs_of_record<T>(record: &Record<T>) -> Vec<&Expr<T>> {
+    match record {
+        Record::Record(fields) => fields.iter().map(|field| &field.expr).collect(),
+        Record::RecordWithSplat(record) => record
+            .left
+            .iter()
+            .map(|field| &field.expr)
+            .chain([&*record.splat])
+            .chain(record.right.iter().map(|field| &field.expr))
+            .collect(),
+    }
+}
+fn exprs_of_tuple<T>(tuple: &Tuple<T>) -> Vec<&Expr<T>> {
+    match tuple {
+        Tuple::Tuple(exprs) => exprs.iter().collect(),
+        Tuple::TupleWithSplat(tuple) => tuple
+            .left
+            .iter()
+            .chain([&*tuple.splat])
+            .chain(tuple.right.iter())
+            .collect(),
+    }
+}
+fn exprs_of_place<T>(place: &PlaceExpr<T>) -> Vec<&Expr<T>> {
+    match place {
+        PlaceExpr::Var(_) => Vec::new(),
+        PlaceExpr::FieldAccess(access) => vec![&access.expr],
+        PlaceExpr::Index(index) => vec![&index.expr, &index.index],
+        PlaceExpr::Slice(slice) => [&*slice.expr]
+            .into_iter()
+            .chain(
+                [&slice.range.left, &slice.range.right]
+                    .into_iter()
+                    .flatten()
+                    .map(|bound| &*bound.expr),
+            )
+            .collect(),
+        PlaceExpr::Deref(expr) | PlaceExpr::Len(expr) => vec![expr],
+    }
+}
+fn exprs_of_control_flow<T>(control_flow: &ControlFlow<T>) -> Vec<&Expr<T>> {
+    match control_flow {
+        ControlFlow::Block(block) | ControlFlow::Loop(Loop { body: block, .. }) => {
+            exprs_of_block(block)
+        }
+        ControlFlow::If(If {
+            condition,
+            body,
+            else_part,
+        }) => {
+            let mut exprs = vec![condition.expr()];
+            exprs.extend(exprs_of_block(body));
+            if let Some(else_part) = else_part {
+                exprs.extend(exprs_of_control_flow(else_part));
+            }
+            exprs
+        }
+        ControlFlow::For(for_loop) => {
+            let mut exprs = vec![&*for_loop.expr];
+            exprs.extend(exprs_of_block(&for_loop.body));
+            exprs
+        }
+        ControlFlow::While(while_loop) => {
+            let mut exprs = vec![while_loop.condition.expr()];
+            exprs.extend(exprs_of_block(&while_loop.body));
+            exprs
+        }
+        ControlFlow::Match(Match { expr, arm }) => [&**expr]
+            .into_iter()
+            .chain(arm.iter().map(|arm| &arm.expr))
+            .collect(),
+    }
+}
+// the expressions directly within one, function bodies aren't as jumps can't
+// leave them
+fn children<T>(expr: &Expr<T>) -> Vec<&Expr<T>> {
+    match expr {
+        Expr::Literal(_)
+        | Expr::Unit
+        | Expr::Fun(_)
+        | Expr::Module(_)
+        | Expr::Jump(Jump::Continue(_)) => Vec::new(),
+        Expr::Tag(tag) => tag.expr.as_deref().into_iter().collect(),
+        Expr::Assign(assigns) => assigns
+            .iter()
+            .flat_map(|assign| {
+                exprs_of_place(&assign.place)
+                    .into_iter()
+                    .chain([&assign.expr])
+            })
+            .collect(),
+        Expr::Array(elements) => elements.iter().map(|element| &element.expr).collect(),
+        Expr::ArrayRange(range) => [&range.left, &range.right]
+            .into_iter()
+            .flatten()
+            .map(|bound| &*bound.expr)
+            .collect(),
+        Expr::Dict(entries) => entries
+            .iter()
+            .flat_map(|entry| [&entry.key, &entry.value])
+            .collect(),
+        Expr::Set(exprs) => exprs.iter().collect(),
+        Expr::Splat(expr) => vec![expr],
+        Expr::Record(record) => exprs_of_record(record),
+        Expr::Tuple(tuple) => exprs_of_tuple(tuple),
+        Expr::Unary(unary) => vec![&unary.expr],
+        Expr::Binary(binary) => vec![&binary.left, &binary.right],
+        Expr::Place(place) => exprs_of_place(place),
+        Expr::Call(call) => [&*call.expr]
+            .into_iter()
+            .chain(exprs_of_arg(&call.arg))
+            .collect(),
+        Expr::ControlFlow(control_flow) => exprs_of_control_flow(control_flow),
+        Expr::Jump(Jump::Break(Break { label: _, expr }) | Jump::Return(expr)) => {
+            expr.as_deref().into_iter().collect()
+        }
+        Expr::Yield(yield_expr) => exprs_of_arg(&yield_expr.arg),
+        Expr::Handle(handle) => [&*handle.expr]
+            .into_iter()
+            .chain(handle.handler.iter().map(|handler| &handler.body))
+            .collect(),
+    }
+}
+// whether a `break` within the expression leaves the loop with the label,
+// `depth` counts the loops in between
+fn breaks<T>(expr: &Expr<T>, label: Option<&Atom>, depth: usize) -> bool {
+    let depth = match expr {
+        Expr::Jump(Jump::Break(Break { label: target, .. })) => {
+            let left = match target {
+                Some(target) => Some(target) == label,
+                None => depth == 0,
+            };
+            if left {
+                return true;
+            }
+            depth
+        }
+        Expr::ControlFlow(ControlFlow::Loop(_) | ControlFlow::For(_) | ControlFlow::While(_)) => {
+            depth + 1
+        }
+        _ => depth,
+    };
+    children(expr)
+        .into_iter()
+        .any(|child| breaks(child, label, depth))
+}
+pub(super) struct Divergence {
+    // whether `std` is still the builtin one
+    std: bool,
+}
+impl Divergence {
+    pub fn new(std: bool) -> Self {
+        Self { std }
+    }
+    fn is_panic<T>(&self, expr: &Expr<T>) -> bool {
+        match expr {
+            Expr::Place(PlaceExpr::FieldAccess(FieldAccess { expr, name })) => {
+                let expr: &Expr<T> = expr;
+                self.std
+                    && &**name == "panic"
+                    && matches!(expr, Expr::Place(PlaceExpr::Var(var)) if &**var == "std")
+            }
+            _ => false,
+        }
+    }
+    // the statements are run in order, so one diverging is enough
+    pub fn block<T>(&mut self, block: &Block<T>) -> bool {
+        let std = self.std;
+        let mut diverges = false;
+        for statement in block.statement.iter() {
+            diverges = match statement {
+                Statement::Declare(declare) => {
+                    let diverges = self.expr(&declare.expr);
+                    let mut names = Vec::new();
+                    bound_names(&declare.pattern, &mut names);
+                    self.shadow(&names);
+                    diverges
+                }
+                Statement::FunDeclare(FunDeclare { ident, .. }) => {
+                    self.shadow([ident]);
+                    false
+                }
+                Statement::NewType(newtype) => {
+                    self.shadow([&newtype.ident]);
+                    false
+                }
+                Statement::Expr(expr) => self.expr(expr),
+            };
+            if diverges {
+                break;
+            }
+        }
+        let diverges = diverges || block.expr.as_ref().is_some_and(|expr| self.expr(expr));
+        self.std = std;
+        diverges
+    }
+    fn shadow<'a>(&mut self, names: impl IntoIterator<Item = &'a Atom>) {
+        if names.into_iter().any(|name| &**name == "std") {
+            self.std = false;
+        }
+    }
+    fn condition<T>(&mut self, condition: &Condition<T>) -> bool {
+        self.expr(condition.expr())
+    }
+    fn control_flow<T>(&mut self, control_flow: &ControlFlow<T>) -> bool {
+        match control_flow {
+            ControlFlow::Block(block) => self.block(block),
+            // every branch has to diverge
+            ControlFlow::If(If {
+                condition,
+                body,
+                else_part,
+            }) => {
+                self.condition(condition)
+                    || else_part
+                        .as_ref()
+                        .is_some_and(|else_part| self.block(body) && self.control_flow(else_part))
+            }
+            ControlFlow::Match(Match { expr, arm }) => {
+                self.expr(expr) || (!arm.is_empty() && arm.iter().all(|arm| self.expr(&arm.expr)))
+            }
+            ControlFlow::For(for_loop) => self.expr(&for_loop.expr),
+            ControlFlow::While(while_loop) => self.condition(&while_loop.condition),
+            ControlFlow::Loop(Loop { label, body }) => !exprs_of_block(body)
+                .into_iter()
+                .any(|expr| breaks(expr, label.as_ref(), 0)),
+        }
+    }
+    fn exprs<'a, T: 'a>(&mut self, exprs: impl IntoIterator<Item = &'a Expr<T>>) -> bool {
+        exprs.into_iter().any(|expr| self.expr(expr))
+    }
+    pub fn expr<T>(&mut self, expr: &Expr<T>) -> bool {
+        match expr {
+            Expr::Jump(_) => true,
+            Expr::Call(Call { expr, arg }) => {
+                self.is_panic(expr) || self.expr(expr) || self.exprs(exprs_of_arg(arg))
+            }
+            // the right side of these may not be evaluated
+            Expr::Binary(Binary {
+                kind: BinaryType::LazyAnd | BinaryType::LazyOr,
+                left,
+                right: _,
+            }) => self.expr(left),
+            Expr::ControlFlow(control_flow) => self.control_flow(control_flow),
+            // functions aren't called where they're written, and handlers
+            // aren't always run
+            Expr::Fun(_) | Expr::Handle(_) => false,
+            expr => self.exprs(children(expr)),
+        }
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::Divergence;
+    use hir::expr::Expr;
+    use parser::{expr_parser, EasyParser};
+
+    fn diverges(src: &str) -> bool {
+        let (expr, _) = expr_parser().easy_parse(src).unwrap();
+        let expr: Expr<()> = expr;
+        Divergence::new(true).expr(&expr)
+    }
+    #[test]
+    fn divergence() {
+        assert!(diverges("{ return 0; }"));
+        assert!(diverges("{ std.panic(\"a\"); }"));
+        assert!(diverges("{ f(1); loop { g(); } }"));
+        assert!(diverges("{ if a { return 1 } else { break } }"));
+        assert!(diverges(
+            "match x { @a => return 1, @b => std.panic(\"b\") }"
+        ));
+        assert!(diverges("f(std.panic(\"a\"))"));
+        assert!(!diverges("{ 0 }"));
+        assert!(!diverges("{ if a { return 1 } }"));
+        assert!(!diverges("loop { loop { break }; break }"));
+        assert!(!diverges("'a: loop { loop { break 'a } }"));
+        assert!(!diverges("a || std.panic(\"a\")"));
+        assert!(!diverges("() => return 1"));
+        assert!(!diverges("{ std = (panic = (x) => x); std.panic(1) }"));
+    }
+}
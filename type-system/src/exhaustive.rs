@@ -0,0 +1,538 @@
+use crate::{
+    ty::{cons::OrderedAnd, Subs, Substitutable, Unifiable, VarState},
+    Cons, Keyed, Type, TypeError,
+};
+use hir::{
+    expr::BoundType,
+    pattern::{ListPattern, Pattern, PatternBound},
+    Atom,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Checks that the arms of a `match` cover every value, a simplified take on
+// the usefulness algorithm of "Warnings for pattern matching" by Luc Maranget.
+//
+// Unions matched by tags without any catch-all arm are closed to the matched
+// tags, this is what lets a `match` cover an open union in the first place.
+
+// Numbers are doubled so the midpoint of any two integers is an integer too
+#[derive(Debug, Clone, Copy)]
+struct Interval {
+    start: Option<(i128, bool)>,
+    end: Option<(i128, bool)>,
+}
+impl Interval {
+    fn point(num: i128) -> Self {
+        Self {
+            start: Some((num * 2, true)),
+            end: Some((num * 2, true)),
+        }
+    }
+    fn contains(self, num: i128) -> bool {
+        let after_start = match self.start {
+            Some((start, inclusive)) => num > start || inclusive && num == start,
+            None => true,
+        };
+        let before_end = match self.end {
+            Some((end, inclusive)) => num < end || inclusive && num == end,
+            None => true,
+        };
+        after_start && before_end
+    }
+}
+#[derive(Debug, Clone)]
+enum Pat {
+    Wild,
+    Bool(bool),
+    Num(Interval),
+    Tag(Atom, Box<Pat>),
+    // records and newtypes, fields left out are wildcards
+    Fields(BTreeMap<Atom, Pat>),
+    Ref(Box<Pat>),
+    // elements before and after the rest if there's one, the rest itself is
+    // irrefutable
+    Tuple(Vec<Pat>, Option<Vec<Pat>>),
+    Array(Vec<Pat>, Option<Vec<Pat>>),
+    Or(Vec<Pat>),
+    // a refutable pattern this check can't see through, it's taken as
+    // matching nothing
+    Opaque,
+}
+impl Pat {
+    fn new(pattern: &Pattern<Type>) -> Self {
+        let list = |list: &ListPattern<Type>| match list {
+            ListPattern::List(list) => Some((list.iter().map(Self::new).collect(), None)),
+            ListPattern::ListWithRest(list) => Self::new(&list.rest).is_irrefutable().then(|| {
+                (
+                    list.left.iter().map(Self::new).collect(),
+                    Some(list.right.iter().map(Self::new).collect()),
+                )
+            }),
+        };
+        match pattern {
+            Pattern::True => Self::Bool(true),
+            Pattern::False => Self::Bool(false),
+            Pattern::UInt(uint) => Self::Num(Interval::point(*uint as i128)),
+            Pattern::Int(int) => Self::Num(Interval::point(*int as i128)),
+            Pattern::Ignore | Pattern::Var(_) => Self::Wild,
+            Pattern::Record(record) => match record.rest.as_deref().map(Self::new) {
+                Some(rest) if !rest.is_irrefutable() => Self::Opaque,
+                _ => Self::Fields(
+                    record
+                        .fields
+                        .iter()
+                        .map(|(name, pattern)| (name.clone(), Self::new(pattern)))
+                        .collect(),
+                ),
+            },
+            Pattern::NewType(newtype) => Self::Fields(
+                newtype
+                    .fields
+                    .fields
+                    .iter()
+                    .map(|(name, pattern)| (name.clone(), Self::new(pattern)))
+                    .collect(),
+            ),
+            Pattern::Tuple(tuple) => match list(tuple) {
+                Some((left, right)) => Self::Tuple(left, right),
+                None => Self::Opaque,
+            },
+            Pattern::Array(array) => match list(array) {
+                Some((left, right)) => Self::Array(left, right),
+                None => Self::Opaque,
+            },
+            Pattern::Tag(tag) => Self::Tag(
+                tag.tag.clone(),
+                Box::new(tag.pattern.as_deref().map_or(Self::Wild, Self::new)),
+            ),
+            Pattern::Ref(pattern) => Self::Ref(Box::new(Self::new(pattern))),
+            Pattern::Or(patterns) => Self::Or(patterns.iter().map(Self::new).collect()),
+            Pattern::Range(range) => {
+                let bound = |bound: &Option<PatternBound<Type>>| {
+                    bound.as_ref().map(|bound| {
+                        let num = match *bound.pattern {
+                            Pattern::UInt(uint) => uint as i128,
+                            Pattern::Int(int) => int as i128,
+                            _ => unreachable!(),
+                        };
+                        (num * 2, bound.kind == BoundType::Inclusive)
+                    })
+                };
+                Self::Num(Interval {
+                    start: bound(&range.left),
+                    end: bound(&range.right),
+                })
+            }
+            Pattern::As(pattern) => Self::new(&pattern.pattern),
+            Pattern::Equal(_) => Self::Opaque,
+        }
+    }
+    fn is_irrefutable(&self) -> bool {
+        match self {
+            Self::Wild => true,
+            Self::Fields(fields) => fields.values().all(Self::is_irrefutable),
+            Self::Ref(pattern) => pattern.is_irrefutable(),
+            Self::Tuple(left, right) => left
+                .iter()
+                .chain(right.iter().flatten())
+                .all(Self::is_irrefutable),
+            Self::Array(left, Some(right)) => left.is_empty() && right.is_empty(),
+            Self::Or(patterns) => patterns.iter().any(Self::is_irrefutable),
+            _ => false,
+        }
+    }
+}
+type Row = Vec<Pat>;
+
+// the rows whose first pattern is a wildcard, without it
+fn default(rows: &[Row]) -> Vec<Row> {
+    rows.iter()
+        .filter(|row| matches!(row[0], Pat::Wild))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+// the rows whose first pattern matches the constructor, its fields take its
+// place
+fn specialize(rows: &[Row], arity: usize, fields: impl Fn(&Pat) -> Option<Row>) -> Vec<Row> {
+    rows.iter()
+        .filter_map(|row| {
+            let mut new_row = match &row[0] {
+                Pat::Wild => vec![Pat::Wild; arity],
+                pattern => fields(pattern)?,
+            };
+            new_row.extend_from_slice(&row[1..]);
+            Some(new_row)
+        })
+        .collect()
+}
+fn with_types(types: impl IntoIterator<Item = Type>, rest: &[Type]) -> Vec<Type> {
+    types.into_iter().chain(rest.iter().cloned()).collect()
+}
+// the elements of a list pattern for the given length
+fn list_fields(left: &[Pat], right: &Option<Vec<Pat>>, len: usize) -> Option<Row> {
+    match right {
+        None => (left.len() == len).then(|| left.to_vec()),
+        Some(right) => {
+            let middle = len.checked_sub(left.len() + right.len())?;
+            Some(
+                left.iter()
+                    .cloned()
+                    .chain(vec![Pat::Wild; middle])
+                    .chain(right.iter().cloned())
+                    .collect(),
+            )
+        }
+    }
+}
+// whether some value isn't matched by any of the rows
+fn is_missing(
+    rows: Vec<Row>,
+    types: &[Type],
+    subs: &mut Subs,
+    var_state: &mut VarState,
+) -> Result<bool, TypeError> {
+    let Some((ty, rest_types)) = types.split_first() else {
+        return Ok(rows.is_empty());
+    };
+    let mut ty = ty.clone();
+    ty.substitute(subs)?;
+    let mut expanded = Vec::with_capacity(rows.len());
+    let mut stack: Vec<_> = rows.into_iter().rev().collect();
+    while let Some(row) = stack.pop() {
+        match &row[0] {
+            Pat::Or(patterns) => {
+                for pattern in patterns.iter().rev() {
+                    let mut row = row.clone();
+                    row[0] = pattern.clone();
+                    stack.push(row);
+                }
+            }
+            Pat::Opaque => (),
+            _ => expanded.push(row),
+        }
+    }
+    let rows = expanded;
+    let Some(head) = rows
+        .iter()
+        .map(|row| &row[0])
+        .find(|pattern| !matches!(pattern, Pat::Wild))
+    else {
+        return is_missing(default(&rows), rest_types, subs, var_state);
+    };
+    let has_wild = rows.iter().any(|row| matches!(row[0], Pat::Wild));
+    match head {
+        Pat::Bool(_) => {
+            let heads: BTreeSet<_> = rows
+                .iter()
+                .filter_map(|row| match row[0] {
+                    Pat::Bool(bool) => Some(bool),
+                    _ => None,
+                })
+                .collect();
+            if heads.len() < 2 {
+                return is_missing(default(&rows), rest_types, subs, var_state);
+            }
+            for bool in [true, false] {
+                let rows = specialize(&rows, 0, |pattern| {
+                    matches!(pattern, Pat::Bool(other) if *other == bool).then(Vec::new)
+                });
+                if is_missing(rows, rest_types, subs, var_state)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Pat::Num(_) => {
+            let intervals: Vec<_> = rows
+                .iter()
+                .filter_map(|row| match row[0] {
+                    Pat::Num(interval) => Some(interval),
+                    _ => None,
+                })
+                .collect();
+            // a number from each of the pieces the bounds split the numbers
+            // into, every number of a piece is matched by the same intervals
+            let bounds: BTreeSet<_> = intervals
+                .iter()
+                .flat_map(|interval| [interval.start, interval.end])
+                .flatten()
+                .map(|(num, _)| num)
+                .collect();
+            let bounds: Vec<_> = bounds.into_iter().collect();
+            let mut samples = Vec::with_capacity(bounds.len() * 2 + 1);
+            samples.push(bounds[0] - 1);
+            for pair in bounds.windows(2) {
+                samples.push(pair[0]);
+                samples.push((pair[0] + pair[1]) / 2);
+            }
+            samples.push(bounds[bounds.len() - 1]);
+            samples.push(bounds[bounds.len() - 1] + 1);
+            let complete = samples
+                .iter()
+                .all(|num| intervals.iter().any(|interval| interval.contains(*num)));
+            if !complete {
+                return is_missing(default(&rows), rest_types, subs, var_state);
+            }
+            for num in samples {
+                let rows = specialize(&rows, 0, |pattern| {
+                    matches!(pattern, Pat::Num(interval) if interval.contains(num)).then(Vec::new)
+                });
+                if is_missing(rows, rest_types, subs, var_state)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Pat::Tag(_, _) => {
+            if let Type::Cons(Cons::Union(Keyed {
+                rest: Some(rest), ..
+            })) = &ty
+            {
+                if has_wild {
+                    return is_missing(default(&rows), rest_types, subs, var_state);
+                }
+                let closed = Type::Cons(Cons::Union(Keyed {
+                    fields: BTreeMap::new(),
+                    rest: None,
+                }));
+                Type::Var(rest.clone()).unify_with(closed, subs, var_state)?;
+                ty.substitute(subs)?;
+            }
+            let Type::Cons(Cons::Union(Keyed { fields, rest: None })) = ty else {
+                return is_missing(default(&rows), rest_types, subs, var_state);
+            };
+            for (tag, payload) in fields {
+                let rows = specialize(&rows, 1, |pattern| match pattern {
+                    Pat::Tag(other, pattern) if *other == tag => Some(vec![(**pattern).clone()]),
+                    _ => None,
+                });
+                let types = with_types([payload], rest_types);
+                if is_missing(rows, &types, subs, var_state)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Pat::Fields(_) => {
+            let names: BTreeSet<_> = rows
+                .iter()
+                .filter_map(|row| match &row[0] {
+                    Pat::Fields(fields) => Some(fields.keys().cloned()),
+                    _ => None,
+                })
+                .flatten()
+                .collect();
+            let types = names.iter().map(|name| {
+                let field = match &ty {
+                    Type::Cons(Cons::Record(record)) => record.fields.get(name).cloned(),
+                    Type::Cons(Cons::Nominal(nominal, args)) => {
+                        nominal.field(args, name).map(|(_, ty)| ty)
+                    }
+                    _ => None,
+                };
+                field.unwrap_or_else(|| Type::Var(var_state.new_var()))
+            });
+            let types = with_types(types.collect::<Vec<_>>(), rest_types);
+            let rows = specialize(&rows, names.len(), |pattern| match pattern {
+                Pat::Fields(fields) => Some(
+                    names
+                        .iter()
+                        .map(|name| fields.get(name).cloned().unwrap_or(Pat::Wild))
+                        .collect(),
+                ),
+                _ => None,
+            });
+            is_missing(rows, &types, subs, var_state)
+        }
+        Pat::Ref(_) => {
+            let inner = match &ty {
+                Type::Cons(Cons::Ref(_, inner)) => (**inner).clone(),
+                _ => Type::Var(var_state.new_var()),
+            };
+            let rows = specialize(&rows, 1, |pattern| match pattern {
+                Pat::Ref(pattern) => Some(vec![(**pattern).clone()]),
+                _ => None,
+            });
+            is_missing(rows, &with_types([inner], rest_types), subs, var_state)
+        }
+        Pat::Tuple(_, _) => {
+            let (len, known) = match &ty {
+                Type::Cons(Cons::Tuple(OrderedAnd::NonRow(types))) => (types.len(), types.to_vec()),
+                // the length is unknown when every pattern has a rest, only
+                // the elements before it can be looked into
+                _ => {
+                    let len = rows
+                        .iter()
+                        .filter_map(|row| match &row[0] {
+                            Pat::Tuple(left, _) => Some(left.len()),
+                            _ => None,
+                        })
+                        .max()
+                        .unwrap_or(0);
+                    let known = match &ty {
+                        Type::Cons(Cons::Tuple(OrderedAnd::Row(left, _, _))) => left.clone(),
+                        _ => Vec::new(),
+                    };
+                    (len, known)
+                }
+            };
+            let types: Vec<_> = (0..len)
+                .map(|i| {
+                    known
+                        .get(i)
+                        .cloned()
+                        .unwrap_or_else(|| Type::Var(var_state.new_var()))
+                })
+                .collect();
+            let fixed = matches!(&ty, Type::Cons(Cons::Tuple(OrderedAnd::NonRow(_))));
+            let rows = specialize(&rows, len, |pattern| match pattern {
+                Pat::Tuple(left, right) if fixed => list_fields(left, right, len),
+                Pat::Tuple(left, Some(right)) if right.is_empty() => {
+                    list_fields(left, &Some(Vec::new()), len)
+                }
+                _ => None,
+            });
+            is_missing(rows, &with_types(types, rest_types), subs, var_state)
+        }
+        Pat::Array(_, _) => {
+            let elem = match &ty {
+                Type::Cons(Cons::Array(elem)) => (**elem).clone(),
+                _ => Type::Var(var_state.new_var()),
+            };
+            let max = rows
+                .iter()
+                .filter_map(|row| match &row[0] {
+                    Pat::Array(left, right) => {
+                        Some(left.len() + right.as_ref().map_or(0, Vec::len))
+                    }
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0);
+            // arrays longer than every pattern are matched by the same rows,
+            // those with a rest
+            for len in 0..=max + 1 {
+                let rows = specialize(&rows, len, |pattern| match pattern {
+                    Pat::Array(left, right) => list_fields(left, right, len),
+                    _ => None,
+                });
+                let types = with_types(vec![elem.clone(); len], rest_types);
+                if is_missing(rows, &types, subs, var_state)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Pat::Wild | Pat::Or(_) | Pat::Opaque => unreachable!(),
+    }
+}
+pub(super) fn check(
+    patterns: &[&Pattern<Type>],
+    ty: Type,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+) -> Result<(), TypeError> {
+    let rows = patterns
+        .iter()
+        .map(|pattern| vec![Pat::new(pattern)])
+        .collect();
+    if is_missing(rows, &[ty], subs, var_state)? {
+        Err(TypeError::NonExhaustive)
+    } else {
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::{
+        test::{infer, infer_ty},
+        TypeError,
+    };
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            infer("(x) => match x { 1 => 0, 2 => 0 }"),
+            Err(TypeError::NonExhaustive)
+        );
+        assert_eq!(infer("(x) => match x { 1 | 2 => 0, _ => 1 }"), Ok(()));
+        assert_eq!(infer("(x) => match x { .<0 => 0, 0.. => 1 }"), Ok(()));
+        assert_eq!(
+            infer("(x) => match x { .<0 => 0, 1.. => 1 }"),
+            Err(TypeError::NonExhaustive),
+        );
+        assert_eq!(
+            infer("(x) => match x { ..0 => 0, 0 >< 5 => 1, 5.. => 2 }"),
+            Ok(())
+        );
+        assert_eq!(
+            infer("(x) => match x { -1..1 => 0, .<-1 => 1, 1 >. => 2 }"),
+            Ok(()),
+        );
+    }
+    #[test]
+    fn tags() {
+        assert_eq!(
+            infer_ty("(x) => match x { @some (true) => 1, @some (false) | @none => 0 }"),
+            Ok("(x: @none | @some Bool) -> Num".to_string()),
+        );
+        assert_eq!(
+            infer("(x) => match x { @some (@a) => 1, @none => 0 }"),
+            Ok(()),
+        );
+        assert_eq!(
+            infer_ty("(x) => match x { @some (@a) => 1, _ => 0 }"),
+            Ok("(x: @some (@a | *_) | *_) -> Num".to_string()),
+        );
+        assert_eq!(
+            infer("{ x = @a 1; match x { @b _ => 1 } }"),
+            Err(TypeError::NonExhaustive),
+        );
+        assert_eq!(
+            infer("(x) => match x { @some (true) => 1, @none => 0 }"),
+            Err(TypeError::NonExhaustive),
+        );
+        assert_eq!(
+            infer("{ f(x) => match x { @a => 1, @b => 2 }; f(@c) }"),
+            Err(TypeError::MismatchName),
+        );
+    }
+    #[test]
+    fn structures() {
+        let src = "(x) => match x {
+            (a = true, b = _) => 0,
+            (a = _, b = true) => 1,
+            (a = false, b = false) => 2,
+        }";
+        assert_eq!(infer(src), Ok(()));
+        assert_eq!(
+            infer("(x) => match x { (a = true, b = _) => 0, (a = _, b = true) => 1 }"),
+            Err(TypeError::NonExhaustive),
+        );
+        assert_eq!(
+            infer("(x) => match x { (a = true, *_) => 0, (a = false, = b) => b }"),
+            Ok(()),
+        );
+        assert_eq!(infer("(x) => match x { &true => 0, &false => 1 }"), Ok(()));
+        assert_eq!(
+            infer("(x) => match x { [] => 0, [first, *_] => first }"),
+            Ok(()),
+        );
+        assert_eq!(
+            infer("(x) => match x { [] => 0, [a, b, *_] => a + b }"),
+            Err(TypeError::NonExhaustive),
+        );
+        assert_eq!(
+            infer("(x) => match x { [*_, true] => 0, [*_, false] => 1, [] => 2 }"),
+            Ok(()),
+        );
+        assert_eq!(
+            infer("(x) => match x { (a = 1, *_) => 0, (a = -1, *rest) => 1 }"),
+            Err(TypeError::NonExhaustive),
+        );
+        assert_eq!(
+            infer("(x) => match x { (a = 1, *_) => 0, (a = _, *_) => 1 }"),
+            Ok(()),
+        );
+    }
+}
@@ -1,31 +1,72 @@
 use crate::{
+    clone::check_clone,
+    diverge::Divergence,
+    exhaustive,
     pattern::InferablePattern,
     ty::{
         cons::OrderedAnd,
-        cons::{Cons, Keyed},
-        Env, MutType, Scheme, SchemeMut, Subs, Substitutable, Type, TypeError, Unifiable, Var,
-        VarState,
+        cons::{Cons, Keyed, Nominal},
+        Constraint, Env, Member, ModuleMembers, MutType, Scheme, SchemeMut, Subs, Substitutable,
+        Type, TypeError, Unifiable, Var, VarState,
     },
     Typed,
 };
 use hir::{
     expr::{
-        Arg, Assign, Binary, BinaryType, Block, Bound, Call, ControlFlow, Element, ElementKind,
-        Expr, Field, FieldAccess, Fun, If, Index, Jump, Literal, PlaceExpr, Range, Record,
-        RecordWithSplat, Slice, Tag, Tuple, TupleWithSplat, Unary, UnaryType,
+        Arg, Assign, Binary, BinaryType, Block, Bound, Break, Call, Condition, ControlFlow,
+        Element, ElementKind, Entry, Expr, Field, FieldAccess, For, Fun, Handle, Handler, If,
+        Index, Jump, Literal, Loop, Match, MatchArm, MatchCondition, Module, PlaceExpr, Range,
+        Record, RecordWithSplat, Slice, Tag, Tuple, TupleWithSplat, Unary, UnaryType, While, Yield,
     },
     keyword, pattern,
-    statement::{Declare, FunDeclare, Statement},
+    statement::{Declare, FunDeclare, NewType, Statement, TypeAnnotation, Visibility},
     Atom,
 };
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet},
     iter::once,
+    rc::Rc,
 };
 
-fn unit() -> Type {
+pub(super) fn unit() -> Type {
     Type::Cons(Cons::RecordTuple(OrderedAnd::NonRow(vec![].into())))
 }
+pub(super) fn no_effects() -> Type {
+    Type::Cons(Cons::Union(Keyed::default()))
+}
+// Adds the effects of a call into those the enclosing code may perform
+pub(super) fn perform(
+    mut effects: Type,
+    mut available: Type,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+) -> Result<(), TypeError> {
+    effects.substitute(subs)?;
+    available.substitute(subs)?;
+    if let (Type::Cons(Cons::Union(performed)), Type::Cons(Cons::Union(available))) =
+        (&effects, &available)
+    {
+        if available.rest.is_none() {
+            if let Some(effect) = performed
+                .fields
+                .keys()
+                .find(|effect| !available.fields.contains_key(*effect))
+            {
+                return Err(TypeError::UnhandledEffect(effect.clone()));
+            }
+        }
+    }
+    // a closed row only lists what the callee performs, the caller may
+    // perform more
+    let effects = match effects {
+        Type::Cons(Cons::Union(Keyed { fields, rest: None })) => Type::Cons(Cons::Union(Keyed {
+            fields,
+            rest: Some(var_state.new_var()),
+        })),
+        effects => effects,
+    };
+    effects.unify_with(available, subs, var_state)
+}
 pub(super) trait Inferable {
     type TypedSelf;
     fn infer(
@@ -80,9 +121,12 @@ impl Inferable for Atom {
         var_state: &mut VarState,
         env: &Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        if let Some(nominal) = env.get_nominal(Var::new_bare(self.clone())) {
+            check_constructor(&nominal, env)?;
+        }
         match env.get_ty(Var::new_bare(self.clone())) {
             Some(scheme) => Ok(Typed {
-                ty: scheme.instantiate(var_state)?,
+                ty: scheme.instantiate(var_state),
                 value: self,
             }),
             None => Err(TypeError::UnboundVar),
@@ -98,21 +142,68 @@ impl Inferable for FieldAccess<()> {
         var_state: &mut VarState,
         env: &Env,
     ) -> Result<(Option<Var>, Typed<Self::TypedSelf>), TypeError> {
+        let mut restricted = None;
+        if let Some(module) = module_of(&self.expr, env) {
+            if let Some(member) = module.members.get(&self.name) {
+                if !member.visibility.is_visible_from(env.module()) {
+                    let path: Vec<&str> = module.path.iter().map(|name| &**name).collect();
+                    return Err(TypeError::InvisibleMember {
+                        module: path.join("."),
+                        member: self.name,
+                    });
+                }
+                if let Some(nominal) = &member.nominal {
+                    check_constructor(nominal, env)?;
+                }
+                restricted = member.restricted.clone();
+            }
+        }
         let name = self.name;
         let (mut_var, typed_expr) = self.expr.infer_with_mut(subs, var_state, env)?;
+        if let Some(scheme) = restricted {
+            return Ok((
+                mut_var,
+                Typed {
+                    ty: scheme.instantiate(var_state),
+                    value: FieldAccess {
+                        expr: Box::new(typed_expr.value),
+                        name,
+                    },
+                },
+            ));
+        }
+        // fields of newtypes are only found when the type is already known
+        let mut ty = typed_expr.ty.clone();
+        ty.substitute(subs)?;
+        if let Type::Cons(Cons::Nominal(nominal, args)) = ty {
+            let (visibility, ty) = nominal.field(&args, &name).ok_or(TypeError::MismatchName)?;
+            if !nominal.is_visible_from(visibility, env.module()) {
+                return Err(TypeError::InvisibleField {
+                    ty: nominal.name.clone(),
+                    field: name,
+                });
+            }
+            return Ok((
+                mut_var,
+                Typed {
+                    ty,
+                    value: FieldAccess {
+                        expr: Box::new(typed_expr.value),
+                        name,
+                    },
+                },
+            ));
+        }
         let var = var_state.new_var();
-        let mut more_subs = Subs::new();
         typed_expr.ty.unify_with(
             Type::Cons(Cons::Record(Keyed {
                 fields: once((name.clone(), Type::Var(var.clone()))).collect(),
                 rest: Some(var_state.new_var()),
             })),
-            &mut more_subs,
+            subs,
             var_state,
         )?;
-        let mut ty = Type::Var(var);
-        ty.substitute(&more_subs)?;
-        subs.compose_with(more_subs)?;
+        let ty = Type::Var(var);
         Ok((
             mut_var,
             Typed {
@@ -135,20 +226,16 @@ impl Inferable for Index<()> {
         env: &Env,
     ) -> Result<(Option<Var>, Typed<Self::TypedSelf>), TypeError> {
         let (mut_var, typed_expr) = self.expr.infer_with_mut(subs, var_state, env)?;
-        let var = var_state.new_var();
-        let mut more_subs = Subs::new();
-        typed_expr.ty.unify_with(
-            Type::Cons(Cons::Array(Box::new(Type::Var(var.clone())))),
-            &mut more_subs,
-            var_state,
-        )?;
-        let mut ty = Type::Var(var);
-        ty.substitute(&more_subs)?;
-        subs.compose_with(more_subs)?;
         let typed_index = self.index.infer(subs, var_state, env)?;
-        typed_index
-            .ty
-            .unify_with(Type::Cons(Cons::Num), subs, var_state)?;
+        let ty = Type::Var(var_state.new_var());
+        var_state.constrain(
+            Constraint::Indexable {
+                container: typed_expr.ty.clone(),
+                index: typed_index.ty.clone(),
+                elem: ty.clone(),
+            },
+            subs,
+        )?;
         Ok((
             mut_var,
             Typed {
@@ -172,15 +259,12 @@ impl Inferable for Slice<()> {
     ) -> Result<(Option<Var>, Typed<Self::TypedSelf>), TypeError> {
         let (mut_var, typed_expr) = self.expr.infer_with_mut(subs, var_state, env)?;
         let var = var_state.new_var();
-        let mut elem_ty = Type::Var(var.clone());
-        let mut more_subs = Subs::new();
+        let elem_ty = Type::Var(var.clone());
         typed_expr.ty.unify_with(
             Type::Cons(Cons::Array(Box::new(Type::Var(var)))),
-            &mut more_subs,
+            subs,
             var_state,
         )?;
-        elem_ty.substitute(&more_subs)?;
-        subs.compose_with(more_subs)?;
         let typed_range = self.range.infer(subs, var_state, env)?;
         Ok((
             mut_var,
@@ -237,18 +321,15 @@ impl Inferable for PlaceExpr<()> {
                 let (mut_var, typed_expr) = expr.infer_with_mut(subs, var_state, env)?;
                 let var = var_state.new_var();
                 let mut_var = mut_var.unwrap_or_else(|| var_state.new_var());
-                let mut ty = Type::Var(var);
-                let mut more_subs = Subs::new();
+                let ty = Type::Var(var);
                 typed_expr.ty.unify_with(
                     Type::Cons(Cons::Ref(
                         MutType::Var(mut_var.clone()),
                         Box::new(ty.clone()),
                     )),
-                    &mut more_subs,
+                    subs,
                     var_state,
                 )?;
-                ty.substitute(&more_subs)?;
-                subs.compose_with(more_subs)?;
                 (
                     Some(mut_var),
                     Typed {
@@ -271,8 +352,8 @@ impl Inferable for Box<[Element<()>]> {
         env: &Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
         let mut typed_elements = Vec::new();
-        let mut ty_var = Type::Var(var_state.new_var());
-        let mut arr_ty = Type::Cons(Cons::Array(Box::new(ty_var.clone())));
+        let ty_var = Type::Var(var_state.new_var());
+        let arr_ty = Type::Cons(Cons::Array(Box::new(ty_var.clone())));
         for element in Vec::from(self) {
             let typed_expr = element.expr.infer(subs, var_state, env)?;
             typed_elements.push(Element {
@@ -283,13 +364,7 @@ impl Inferable for Box<[Element<()>]> {
                 ElementKind::Splat => arr_ty.clone(),
                 ElementKind::Element => ty_var.clone(),
             };
-            let mut arr_subs = Subs::new();
-            typed_expr
-                .ty
-                .unify_with(unify_to, &mut arr_subs, var_state)?;
-            ty_var.substitute(&arr_subs)?;
-            arr_ty.substitute(&arr_subs)?;
-            subs.compose_with(arr_subs)?;
+            typed_expr.ty.unify_with(unify_to, subs, var_state)?;
         }
         Ok(Typed {
             ty: arr_ty,
@@ -309,11 +384,9 @@ impl Inferable for Option<Bound<()>> {
         let expr = match self {
             Some(bound) => {
                 let typed = bound.expr.infer(subs, var_state, env)?;
-                let mut more_subs = Subs::new();
                 typed
                     .ty
-                    .unify_with(Type::Cons(Cons::Num), &mut more_subs, var_state)?;
-                subs.compose_with(more_subs)?;
+                    .unify_with(Type::Cons(Cons::Num), subs, var_state)?;
                 Some(Bound {
                     kind: bound.kind,
                     expr: Box::new(typed.value),
@@ -374,7 +447,7 @@ impl Inferable for Tag<()> {
 }
 fn partial_infer_field_list(
     expr: Box<[Field<()>]>,
-    ty: &mut HashMap<Atom, Type>,
+    ty: &mut BTreeMap<Atom, Type>,
     subs: &mut Subs,
     var_state: &mut VarState,
     env: &Env,
@@ -400,7 +473,7 @@ impl Inferable for Box<[Field<()>]> {
         var_state: &mut VarState,
         env: &Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
-        let mut fields = HashMap::new();
+        let mut fields = BTreeMap::new();
         let typed = partial_infer_field_list(self, &mut fields, subs, var_state, env)?;
         Ok(Typed {
             ty: Type::Cons(Cons::Record(Keyed { fields, rest: None })),
@@ -417,21 +490,18 @@ impl Inferable for RecordWithSplat<()> {
         var_state: &mut VarState,
         env: &Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
-        let mut fields = HashMap::new();
+        let mut fields = BTreeMap::new();
         let typed_left = partial_infer_field_list(self.left, &mut fields, subs, var_state, env)?;
         let typed_splat = self.splat.infer(subs, var_state, env)?;
         let typed_right = partial_infer_field_list(self.right, &mut fields, subs, var_state, env)?;
         let var = var_state.new_var();
-        let mut more_subs = Subs::new();
         typed_splat
             .ty
-            .unify_with(Type::Var(var.clone()), &mut more_subs, var_state)?;
-        let mut ty = Type::Cons(Cons::Record(Keyed {
+            .unify_with(Type::Var(var.clone()), subs, var_state)?;
+        let ty = Type::Cons(Cons::Record(Keyed {
             fields,
             rest: Some(var),
         }));
-        ty.substitute(&more_subs)?;
-        subs.compose_with(more_subs)?;
         Ok(Typed {
             ty,
             value: RecordWithSplat {
@@ -460,6 +530,61 @@ impl Inferable for Record<()> {
         Ok(typed)
     }
 }
+fn infer_dict(
+    entries: Box<[Entry<()>]>,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+    env: &Env,
+) -> Result<Typed<Expr<Type>>, TypeError> {
+    let key_ty = Type::Var(var_state.new_var());
+    let value_ty = Type::Var(var_state.new_var());
+    let entries: Vec<_> = entries.into();
+    let entries = entries
+        .into_iter()
+        .map(|Entry { key, value }| {
+            let key = key.infer(subs, var_state, env)?;
+            key.ty.unify_with(key_ty.clone(), subs, var_state)?;
+            let value = value.infer(subs, var_state, env)?;
+            value.ty.unify_with(value_ty.clone(), subs, var_state)?;
+            Ok(Entry {
+                key: key.value,
+                value: value.value,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    var_state.constrain(Constraint::Hashable { ty: key_ty.clone() }, subs)?;
+    Ok(Typed {
+        ty: Type::Cons(Cons::Dict(Box::new(key_ty), Box::new(value_ty))),
+        value: Expr::Dict(entries.into()),
+    })
+}
+fn infer_set(
+    elements: Box<[Expr<()>]>,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+    env: &Env,
+) -> Result<Typed<Expr<Type>>, TypeError> {
+    let elem_ty = Type::Var(var_state.new_var());
+    let elements: Vec<_> = elements.into();
+    let elements = elements
+        .into_iter()
+        .map(|elem| {
+            let elem = elem.infer(subs, var_state, env)?;
+            elem.ty.unify_with(elem_ty.clone(), subs, var_state)?;
+            Ok(elem.value)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    var_state.constrain(
+        Constraint::Hashable {
+            ty: elem_ty.clone(),
+        },
+        subs,
+    )?;
+    Ok(Typed {
+        ty: Type::Cons(Cons::Set(Box::new(elem_ty))),
+        value: Expr::Set(elements.into()),
+    })
+}
 fn infer_tuple(
     tuple: Box<[Expr<()>]>,
     subs: &mut Subs,
@@ -508,17 +633,12 @@ impl Inferable for TupleWithSplat<()> {
         let splat = self.splat.infer(subs, var_state, env)?;
         let (right_type, right_expr) = infer_tuple(self.right, subs, var_state, env)?;
         let var = var_state.new_var();
-        let mut ty = Type::Cons(Cons::Tuple(OrderedAnd::Row(
+        let ty = Type::Cons(Cons::Tuple(OrderedAnd::Row(
             left_type,
             var.clone(),
             right_type,
         )));
-        let mut more_subs = Subs::new();
-        splat
-            .ty
-            .unify_with(Type::Var(var), &mut more_subs, var_state)?;
-        ty.substitute(&more_subs)?;
-        subs.compose_with(more_subs)?;
+        splat.ty.unify_with(Type::Var(var), subs, var_state)?;
         Ok(Typed {
             ty,
             value: TupleWithSplat {
@@ -556,15 +676,28 @@ impl Inferable for Unary<()> {
         var_state: &mut VarState,
         env: &Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let root = match self.expr.as_ref() {
+            Expr::Place(place) => place.var(),
+            _ => None,
+        };
         let (mut_var, typed) = self.expr.infer_with_mut(subs, var_state, env)?;
         let typed = match self.kind {
-            // TODO: implement error when cloning function and mutable reference
-            // Or maybe not, constraining Clonables may better be implemented
-            // by typeclasses or trait, which we don't have yet
-            kind @ (UnaryType::Move | UnaryType::Clone) => typed.map(|expr| Unary {
-                kind,
+            UnaryType::Move => typed.map(|expr| Unary {
+                kind: UnaryType::Move,
                 expr: Box::new(expr),
             }),
+            // TODO: types that are still unknown here can't be checked, this
+            // may better be implemented by typeclasses or trait, which we
+            // don't have yet
+            UnaryType::Clone => {
+                let mut ty = typed.ty.clone();
+                ty.substitute(subs)?;
+                check_clone(&ty)?;
+                typed.map(|expr| Unary {
+                    kind: UnaryType::Clone,
+                    expr: Box::new(expr),
+                })
+            }
             kind @ (UnaryType::Minus | UnaryType::Not) => {
                 let ty = match kind {
                     UnaryType::Minus => Type::Cons(Cons::Num),
@@ -581,9 +714,19 @@ impl Inferable for Unary<()> {
                 }
             }
             UnaryType::Ref => {
-                let var = mut_var.unwrap_or_else(|| var_state.new_var());
+                // places behind a reference have the same mutability as the
+                // reference, otherwise it depends on the variable declaration
+                let mutability = match (mut_var, root) {
+                    (Some(var), _) => MutType::Var(var),
+                    (None, Some(root))
+                        if env.get_mut(Var::new_bare(root.clone())) == Some(false) =>
+                    {
+                        MutType::Imm
+                    }
+                    (None, _) => MutType::Var(var_state.new_var()),
+                };
                 Typed {
-                    ty: Type::Cons(Cons::Ref(MutType::Var(var), Box::new(typed.ty))),
+                    ty: Type::Cons(Cons::Ref(mutability, Box::new(typed.ty))),
                     value: Unary {
                         kind: UnaryType::Ref,
                         expr: Box::new(typed.value),
@@ -607,17 +750,9 @@ impl Inferable for Binary<()> {
         let right = self.right.infer(subs, var_state, env)?;
         if self.kind == BinaryType::Concatenate {
             let var = var_state.new_var();
-            let mut ty = Type::Cons(Cons::Array(Box::new(Type::Var(var))));
-            let mut left_subs = Subs::new();
-            left.ty.unify_with(ty.clone(), &mut left_subs, var_state)?;
-            ty.substitute(&left_subs)?;
-            subs.compose_with(left_subs)?;
-            let mut right_subs = Subs::new();
-            right
-                .ty
-                .unify_with(ty.clone(), &mut right_subs, var_state)?;
-            ty.substitute(&right_subs)?;
-            subs.compose_with(right_subs)?;
+            let ty = Type::Cons(Cons::Array(Box::new(Type::Var(var))));
+            left.ty.unify_with(ty.clone(), subs, var_state)?;
+            right.ty.unify_with(ty.clone(), subs, var_state)?;
             return Ok(Typed {
                 ty,
                 value: Binary {
@@ -627,8 +762,32 @@ impl Inferable for Binary<()> {
                 },
             });
         }
+        if self.kind == BinaryType::Pipe {
+            // `value |> fun` is typed as `fun(value)`
+            let var = var_state.new_var();
+            let effects = var_state.new_var();
+            let arg = Type::Cons(Cons::Tuple(OrderedAnd::NonRow(vec![left.ty].into())));
+            right.ty.unify_with(
+                Type::Cons(Cons::Fun(
+                    Box::new(arg),
+                    Box::new(Type::Var(var.clone())),
+                    Box::new(Type::Var(effects.clone())),
+                )),
+                subs,
+                var_state,
+            )?;
+            perform(Type::Var(effects), env.effects().clone(), subs, var_state)?;
+            return Ok(Typed {
+                ty: Type::Var(var),
+                value: Binary {
+                    kind: BinaryType::Pipe,
+                    left: Box::new(left.value),
+                    right: Box::new(right.value),
+                },
+            });
+        }
         let (op_type, return_type) = match self.kind {
-            BinaryType::Concatenate => unreachable!(),
+            BinaryType::Concatenate | BinaryType::Pipe => unreachable!(),
             BinaryType::Add
             | BinaryType::Sub
             | BinaryType::Multiply
@@ -667,7 +826,7 @@ impl Inferable for Fun<()> {
         env: &Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
         // TODO: handle `ref` parameters
-        let param_map: HashMap<_, _> = self
+        let param_map: BTreeMap<_, _> = self
             .param
             .iter()
             .map(|var| {
@@ -677,16 +836,20 @@ impl Inferable for Fun<()> {
                 )
             })
             .collect();
-        let mut env = env.clone();
+        let effects = Type::Var(var_state.new_var());
+        let mut env = env.in_fun(effects.clone());
         env.extend(param_map.iter().map(|(var, (new_var, var_hir))| {
             (
                 Var::new_bare(var.clone()),
                 SchemeMut {
                     is_mut: var_hir.mutable,
                     scheme: Scheme {
-                        for_all: HashSet::new(),
+                        for_all: BTreeSet::new(),
                         ty: Type::Var(new_var.clone()),
+                        constraints: Vec::new(),
                     },
+                    module: None,
+                    nominal: None,
                 },
             )
         }));
@@ -696,12 +859,15 @@ impl Inferable for Fun<()> {
             SchemeMut {
                 is_mut: false,
                 scheme: Scheme {
-                    for_all: HashSet::new(),
+                    for_all: BTreeSet::new(),
                     ty: Type::Var(return_var.clone()),
+                    constraints: Vec::new(),
                 },
+                module: None,
+                nominal: None,
             },
         );
-        let mut param_ty = Type::Cons(Cons::RecordTuple(OrderedAnd::NonRow(
+        let param_ty = Type::Cons(Cons::RecordTuple(OrderedAnd::NonRow(
             self.param
                 .iter()
                 .map(|var| {
@@ -713,33 +879,25 @@ impl Inferable for Fun<()> {
                 .collect::<Vec<_>>()
                 .into(),
         )));
-        let mut body_subs = Subs::new();
-        let body = self.body.infer(&mut body_subs, var_state, &env)?;
-        let mut return_ty = Type::Var(return_var);
-        return_ty.substitute(&body_subs)?;
-        param_ty.substitute(&body_subs)?;
+        let body = self.body.infer(subs, var_state, &env)?;
         let param: Vec<_> = self.param.into();
-        let typed_param = param
+        let typed_param: Vec<_> = param
             .into_iter()
-            .map(|var| {
-                let mut ty = Type::Var(param_map.get(&var.ident).unwrap().0.clone());
-                ty.substitute(&body_subs)?;
-                Ok(pattern::Var {
-                    ident: var.ident,
-                    mutable: var.mutable,
-                    bind_to_ref: var.bind_to_ref,
-                    ty,
-                })
+            .map(|var| pattern::Var {
+                ty: Type::Var(param_map.get(&var.ident).unwrap().0.clone()),
+                ident: var.ident,
+                mutable: var.mutable,
+                bind_to_ref: var.bind_to_ref,
             })
-            .collect::<Result<Vec<_>, TypeError>>()?;
-        subs.compose_with(body_subs)?;
-        let mut body_ty = body.ty;
-        let mut body_return_subs = Subs::new();
-        return_ty.unify_with(body_ty.clone(), &mut body_return_subs, var_state)?;
-        body_ty.substitute(&body_return_subs)?;
-        subs.compose_with(body_return_subs)?;
+            .collect();
+        let body_ty = body.ty;
+        Type::Var(return_var).unify_with(body_ty.clone(), subs, var_state)?;
         Ok(Typed {
-            ty: Type::Cons(Cons::Fun(Box::new(param_ty), Box::new(body_ty))),
+            ty: Type::Cons(Cons::Fun(
+                Box::new(param_ty),
+                Box::new(body_ty),
+                Box::new(effects),
+            )),
             value: Fun {
                 param: typed_param.into(),
                 body: Box::new(body.value),
@@ -764,17 +922,12 @@ impl Inferable for Arg<()> {
             Arg::Splat(expr) => {
                 let typed = expr.infer(subs, var_state, env)?;
                 let var = var_state.new_var();
-                let mut ty = Type::Cons(Cons::RecordTuple(OrderedAnd::Row(
+                let ty = Type::Cons(Cons::RecordTuple(OrderedAnd::Row(
                     Vec::new(),
                     var.clone(),
                     Vec::new(),
                 )));
-                let mut more_subs = Subs::new();
-                typed
-                    .ty
-                    .unify_with(Type::Var(var), &mut more_subs, var_state)?;
-                ty.substitute(&more_subs)?;
-                subs.compose_with(more_subs)?;
+                typed.ty.unify_with(Type::Var(var), subs, var_state)?;
                 Typed {
                     ty,
                     value: Arg::Splat(Box::new(typed.value)),
@@ -796,30 +949,21 @@ impl Inferable for Call<()> {
         env: &Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
         let var = var_state.new_var();
-        let mut subs1 = Subs::new();
-        let typed1 = self.expr.infer(&mut subs1, var_state, env)?;
-        let mut env2 = env.clone();
-        env2.substitute(&subs1)?;
-        let mut subs2 = Subs::new();
-        let typed2 = self.arg.infer(&mut subs2, var_state, &env2)?;
-        let mut ty1 = typed1.ty;
-        ty1.substitute(&subs2)?;
-        let mut subs3 = Subs::new();
-        ty1.unify_with(
+        let effects = var_state.new_var();
+        let typed1 = self.expr.infer(subs, var_state, env)?;
+        let typed2 = self.arg.infer(subs, var_state, env)?;
+        typed1.ty.unify_with(
             Type::Cons(Cons::Fun(
                 Box::new(typed2.ty),
                 Box::new(Type::Var(var.clone())),
+                Box::new(Type::Var(effects.clone())),
             )),
-            &mut subs3,
+            subs,
             var_state,
         )?;
-        let mut ty = Type::Var(var);
-        ty.substitute(&subs3)?;
-        subs.compose_with(subs3)?;
-        subs.compose_with(subs2)?;
-        subs.compose_with(subs1)?;
+        perform(Type::Var(effects), env.effects().clone(), subs, var_state)?;
         Ok(Typed {
-            ty,
+            ty: Type::Var(var),
             value: Call {
                 expr: Box::new(typed1.value),
                 arg: typed2.value,
@@ -827,6 +971,125 @@ impl Inferable for Call<()> {
         })
     }
 }
+impl Inferable for Yield<()> {
+    type TypedSelf = Yield<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let arg = self.arg.infer(subs, var_state, env)?;
+        // the `yield` evaluates into what the handler resumes it with
+        let resume = var_state.new_var();
+        let operation = Type::Cons(Cons::Fun(
+            Box::new(arg.ty),
+            Box::new(Type::Var(resume.clone())),
+            Box::new(no_effects()),
+        ));
+        let effects = Type::Cons(Cons::Union(Keyed {
+            fields: once((self.effect.clone(), operation)).collect(),
+            rest: None,
+        }));
+        perform(effects, env.effects().clone(), subs, var_state)?;
+        Ok(Typed {
+            ty: Type::Var(resume),
+            value: Yield {
+                effect: self.effect,
+                arg: arg.value,
+            },
+        })
+    }
+}
+impl Inferable for Handle<()> {
+    type TypedSelf = Handle<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let mut handled = BTreeMap::new();
+        let handler: Vec<_> = self.handler.into();
+        let mut typed_handler = Vec::with_capacity(handler.len());
+        for handler in handler {
+            // handlers are typed outside of the `do` so jumps in them leave
+            // the whole expression, their parameters are bound like those of
+            // a function
+            let param: Vec<_> = handler
+                .param
+                .iter()
+                .map(|var| pattern::Var {
+                    ty: Type::Var(var_state.new_named(var.ident.clone())),
+                    ident: var.ident.clone(),
+                    mutable: var.mutable,
+                    bind_to_ref: var.bind_to_ref,
+                })
+                .collect();
+            let mut handler_env = env.scoped();
+            handler_env.extend(param.iter().map(|var| {
+                (
+                    Var::new_bare(var.ident.clone()),
+                    SchemeMut {
+                        is_mut: var.mutable,
+                        scheme: Scheme {
+                            for_all: BTreeSet::new(),
+                            ty: var.ty.clone(),
+                            constraints: Vec::new(),
+                        },
+                        module: None,
+                        nominal: None,
+                    },
+                )
+            }));
+            let body = handler.body.infer(subs, var_state, &handler_env)?;
+            let param_ty = Type::Cons(Cons::RecordTuple(OrderedAnd::NonRow(
+                param
+                    .iter()
+                    .map(|var| (var.ident.clone(), var.ty.clone()))
+                    .collect::<Vec<_>>()
+                    .into(),
+            )));
+            let operation = Type::Cons(Cons::Fun(
+                Box::new(param_ty),
+                Box::new(body.ty),
+                Box::new(no_effects()),
+            ));
+            if handled.insert(handler.effect.clone(), operation).is_some() {
+                return Err(TypeError::Overlap);
+            }
+            typed_handler.push(Handler {
+                effect: handler.effect,
+                param: param.into(),
+                body: body.value,
+            });
+        }
+        // effects not handled here are left to the surrounding code, those
+        // handled here can't reach it even when it handles them too
+        let names: Vec<_> = handled.keys().cloned().collect();
+        let rest = var_state.new_var();
+        let inner = env.handling(Type::Cons(Cons::Union(Keyed {
+            fields: handled,
+            rest: Some(rest.clone()),
+        })));
+        let expr = self.expr.infer(subs, var_state, &inner)?;
+        let mut outer = env.effects().clone();
+        outer.substitute(subs)?;
+        if let Type::Cons(Cons::Union(keyed)) = &mut outer {
+            keyed.fields.retain(|effect, _| !names.contains(effect));
+        }
+        perform(Type::Var(rest), outer, subs, var_state)?;
+        Ok(Typed {
+            ty: expr.ty,
+            value: Handle {
+                expr: Box::new(expr.value),
+                handler: typed_handler.into(),
+            },
+        })
+    }
+}
 impl Inferable for Assign<()> {
     type TypedSelf = Assign<Type>;
 
@@ -847,7 +1110,9 @@ impl Inferable for Assign<()> {
         let typed_expr = self.expr.infer(subs, var_state, env)?;
         let (mut_var, typed_place) = self.place.infer_with_mut(subs, var_state, env)?;
         if let Some(mut_var) = mut_var {
-            MutType::Var(mut_var).unify_with(MutType::Mut, subs, var_state)?;
+            let mut mutability = MutType::Var(mut_var);
+            mutability.substitute(subs)?;
+            mutability.unify_with(MutType::Mut, subs, var_state)?;
         }
         typed_place.ty.unify_with(typed_expr.ty, subs, var_state)?;
         Ok(Typed {
@@ -890,8 +1155,26 @@ impl Inferable for Jump<()> {
         env: &Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
         let typed = match self {
-            Jump::Break(_) => todo!(),
-            Jump::Continue => todo!(),
+            Jump::Break(Break { label, expr }) => {
+                let typed_expr = match expr {
+                    Some(expr) => expr.infer(subs, var_state, env)?.map(Some),
+                    None => Typed {
+                        ty: unit(),
+                        value: None,
+                    },
+                };
+                typed_expr
+                    .ty
+                    .unify_with(env.loop_ty(label.as_ref())?, subs, var_state)?;
+                Jump::Break(Break {
+                    label,
+                    expr: typed_expr.value.map(Box::new),
+                })
+            }
+            Jump::Continue(label) => {
+                env.loop_ty(label.as_ref())?;
+                Jump::Continue(label)
+            }
             Jump::Return(expr) => {
                 let typed_expr = match expr {
                     Some(expr) => expr.infer(subs, var_state, env)?.map(Some),
@@ -900,13 +1183,11 @@ impl Inferable for Jump<()> {
                         value: None,
                     },
                 };
-                let mut more_subs = Subs::new();
                 typed_expr.ty.unify_with(
                     keyword!("return").infer(subs, var_state, env)?.ty,
-                    &mut more_subs,
+                    subs,
                     var_state,
                 )?;
-                subs.compose_with(more_subs)?;
                 Jump::Return(typed_expr.value.map(Box::new))
             }
         };
@@ -917,68 +1198,274 @@ impl Inferable for Jump<()> {
         })
     }
 }
-fn infer_statement(
+pub(super) fn infer_statement(
     subs: &mut Subs,
     env: &mut Env,
     var_state: &mut VarState,
     statement: Statement<()>,
 ) -> Result<Statement<Type>, TypeError> {
     let typed = match statement {
+        // modules are generalized like functions, so their functions stay
+        // polymorphic when accessed from the module
+        Statement::Declare(Declare {
+            visibility,
+            pattern: pattern::Pattern::Var(var),
+            expr: Expr::Module(module),
+            else_part: None,
+        }) if !var.mutable && !var.bind_to_ref => {
+            let path: Rc<[_]> = env
+                .module()
+                .iter()
+                .cloned()
+                .chain([var.ident.clone()])
+                .collect();
+            var_state.enter_level();
+            let (typed_module, mut members) = infer_module(subs, var_state, module, path)?;
+            var_state.leave_level();
+            var_state.solve(subs)?;
+            let mut ty = typed_module.ty;
+            ty.substitute(subs)?;
+            let scheme = var_state.generalize(ty.clone());
+            for scheme in members
+                .members
+                .values_mut()
+                .filter_map(|member| member.restricted.as_mut())
+            {
+                scheme.ty.substitute(subs)?;
+                *scheme = var_state.generalize(scheme.ty.clone());
+            }
+            env.insert(
+                Var::new_bare(var.ident.clone()),
+                SchemeMut {
+                    is_mut: false,
+                    scheme,
+                    module: Some(Rc::new(members)),
+                    nominal: None,
+                },
+            );
+            Statement::Declare(Declare {
+                visibility,
+                pattern: pattern::Pattern::Var(pattern::Var {
+                    ident: var.ident,
+                    mutable: false,
+                    bind_to_ref: false,
+                    ty,
+                }),
+                expr: Expr::Module(typed_module.value),
+                else_part: None,
+            })
+        }
         Statement::Declare(declare) => {
+            let module = module_of(&declare.expr, env);
+            let nominal = nominal_of(&declare.expr, env);
             let typed_expr = declare.expr.infer(subs, var_state, env)?;
-            let typed_pattern = declare.pattern.infer(var_state, env)?;
-            let mut more_subs = Subs::new();
+            // the else block doesn't see the bindings of the pattern
+            let else_part = match declare.else_part {
+                Some(else_part) => Some(infer_diverging(else_part, subs, var_state, env)?),
+                None => None,
+            };
+            let typed_pattern = declare.pattern.infer(subs, var_state, env)?;
             typed_expr
                 .ty
-                .unify_with(typed_pattern.ty, &mut more_subs, var_state)?;
-            env.substitute(&more_subs)?;
-            subs.compose_with(more_subs)?;
+                .unify_with(typed_pattern.ty, subs, var_state)?;
+            // an alias of a module keeps its members hidden, as does an alias
+            // of a constructor its fields
+            if let pattern::Pattern::Var(var) = &typed_pattern.value {
+                let var = Var::new_bare(var.ident.clone());
+                if let Some(module) = module {
+                    env.set_module(var.clone(), module);
+                }
+                if let Some(nominal) = nominal {
+                    env.set_nominal(var, nominal);
+                }
+            }
             Statement::Declare(Declare {
+                visibility: declare.visibility,
                 pattern: typed_pattern.value,
                 expr: typed_expr.value,
+                else_part,
             })
         }
         Statement::FunDeclare(fun) => {
             let var = Var::new_bare(fun.ident.clone());
             env.remove(var.clone());
+            var_state.enter_level();
             let mut ty = Type::Cons(Cons::Fun(
                 Box::new(Type::Var(var_state.new_var())),
                 Box::new(Type::Var(var_state.new_var())),
+                Box::new(Type::Var(var_state.new_var())),
             ));
             env.insert(
                 var.clone(),
                 SchemeMut {
                     is_mut: false,
                     scheme: Scheme {
-                        for_all: HashSet::new(),
+                        for_all: BTreeSet::new(),
                         ty: ty.clone(),
+                        constraints: Vec::new(),
                     },
+                    module: None,
+                    nominal: None,
                 },
             );
             let typed_fun = fun.fun.infer(subs, var_state, env)?;
-            let mut more_subs = Subs::new();
-            typed_fun
-                .ty
-                .unify_with(ty.clone(), &mut more_subs, var_state)?;
-            ty.substitute(&more_subs)?;
-            subs.compose_with(more_subs)?;
+            typed_fun.ty.unify_with(ty.clone(), subs, var_state)?;
+            var_state.leave_level();
+            var_state.solve(subs)?;
+            ty.substitute(subs)?;
             env.insert(
                 var,
                 SchemeMut {
                     is_mut: false,
-                    scheme: env.generalize(ty.clone()),
+                    scheme: var_state.generalize(ty.clone()),
+                    module: None,
+                    nominal: None,
                 },
             );
             Statement::FunDeclare(FunDeclare {
+                visibility: fun.visibility,
                 ident: fun.ident,
                 fun: typed_fun.value,
                 ty,
             })
         }
+        Statement::NewType(newtype) => {
+            let (nominal, ty) = infer_newtype(var_state, env, &newtype)?;
+            env.insert(
+                Var::new_bare(newtype.ident.clone()),
+                SchemeMut {
+                    is_mut: false,
+                    scheme: var_state.generalize(ty.clone()),
+                    module: None,
+                    nominal: Some(nominal),
+                },
+            );
+            Statement::NewType(NewType {
+                visibility: newtype.visibility,
+                ident: newtype.ident,
+                fields: newtype.fields,
+                ty,
+            })
+        }
         Statement::Expr(expr) => Statement::Expr(expr.infer(subs, var_state, env)?.value),
     };
     Ok(typed)
 }
+// The else block of a declaration is run when the pattern doesn't match, so
+// it must not finish, see `Divergence` for what's accepted
+fn infer_diverging(
+    block: Block<()>,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+    env: &Env,
+) -> Result<Block<Type>, TypeError> {
+    let std = env.is_builtin(&Var::new_bare(Atom::from("std")));
+    let typed = block.infer(subs, var_state, env)?;
+    if Divergence::new(std).block(&typed.value) {
+        Ok(typed.value)
+    } else {
+        Err(TypeError::FallThrough)
+    }
+}
+// The newtype and the type of its constructor, a function taking the fields
+// in the order they're declared. Every field without type annotation is a
+// parameter of the newtype.
+fn infer_newtype(
+    var_state: &mut VarState,
+    env: &Env,
+    newtype: &NewType<()>,
+) -> Result<(Rc<Nominal>, Type), TypeError> {
+    var_state.enter_level();
+    let mut params = Vec::new();
+    let mut param_fields = Vec::with_capacity(newtype.fields.len());
+    let mut fields = BTreeMap::new();
+    for field in newtype.fields.iter() {
+        let ty = annotation_ty(&field.ty, var_state, env, &mut params)?;
+        param_fields.push((field.name.clone(), ty.clone()));
+        if fields
+            .insert(field.name.clone(), (field.visibility.clone(), ty))
+            .is_some()
+        {
+            return Err(TypeError::Overlap);
+        }
+    }
+    var_state.leave_level();
+    let args = params.iter().cloned().map(Type::Var).collect();
+    let nominal = Rc::new(Nominal::new(
+        newtype.ident.clone(),
+        env.module().clone(),
+        params.into(),
+        fields,
+    ));
+    let ty = Type::Cons(Cons::Fun(
+        Box::new(Type::Cons(Cons::RecordTuple(OrderedAnd::NonRow(
+            param_fields.into(),
+        )))),
+        Box::new(Type::Cons(Cons::Nominal(nominal.clone(), args))),
+        Box::new(no_effects()),
+    ));
+    Ok((nominal, ty))
+}
+fn annotation_ty(
+    annotation: &TypeAnnotation,
+    var_state: &mut VarState,
+    env: &Env,
+    params: &mut Vec<Var>,
+) -> Result<Type, TypeError> {
+    let ty = match annotation {
+        TypeAnnotation::Infer => {
+            let var = var_state.new_var();
+            params.push(var.clone());
+            Type::Var(var)
+        }
+        TypeAnnotation::Array(ty) => Type::Cons(Cons::Array(Box::new(annotation_ty(
+            ty, var_state, env, params,
+        )?))),
+        TypeAnnotation::Ref(mutable, ty) => {
+            let mutability = if *mutable { MutType::Mut } else { MutType::Imm };
+            let ty = annotation_ty(ty, var_state, env, params)?;
+            Type::Cons(Cons::Ref(mutability, Box::new(ty)))
+        }
+        TypeAnnotation::Named(name, args) => {
+            let builtin = match &**name {
+                "Num" | "Char" => Some(Type::Cons(Cons::Num)),
+                "Bool" => Some(Type::Cons(Cons::Bool)),
+                "Str" => Some(Type::Cons(Cons::Array(Box::new(Type::Cons(Cons::Num))))),
+                _ => None,
+            };
+            match builtin {
+                Some(_) if !args.is_empty() => return Err(TypeError::MismatchArity),
+                Some(ty) => ty,
+                None => {
+                    let nominal = env
+                        .get_nominal(Var::new_bare(name.clone()))
+                        .ok_or_else(|| TypeError::UnknownType(name.clone()))?;
+                    if nominal.param_count() != args.len() {
+                        return Err(TypeError::MismatchArity);
+                    }
+                    let args = args
+                        .iter()
+                        .map(|arg| annotation_ty(arg, var_state, env, params))
+                        .collect::<Result<_, _>>()?;
+                    Type::Cons(Cons::Nominal(nominal, args))
+                }
+            }
+        }
+    };
+    Ok(ty)
+}
+// a constructor can only be used where every field of the newtype is visible
+fn check_constructor(nominal: &Nominal, env: &Env) -> Result<(), TypeError> {
+    for (name, visibility) in nominal.visibilities() {
+        if !nominal.is_visible_from(visibility, env.module()) {
+            return Err(TypeError::InvisibleField {
+                ty: nominal.name.clone(),
+                field: name.clone(),
+            });
+        }
+    }
+    Ok(())
+}
 impl Inferable for Block<()> {
     type TypedSelf = Block<Type>;
 
@@ -989,11 +1476,10 @@ impl Inferable for Block<()> {
         env: &Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
         let mut typed_statement = Vec::with_capacity(self.statement.len());
-        let mut env = env.clone();
+        let mut env = env.scoped();
         let statement: Vec<_> = self.statement.into();
-        let mut more_subs = Subs::new();
         for statement in statement {
-            let typed = infer_statement(&mut more_subs, &mut env, var_state, statement)?;
+            let typed = infer_statement(subs, &mut env, var_state, statement)?;
             typed_statement.push(typed);
         }
         let typed_expr = match self.expr {
@@ -1003,11 +1489,8 @@ impl Inferable for Block<()> {
                 value: None,
             },
         };
-        let mut ty = typed_expr.ty;
-        ty.substitute(&more_subs)?;
-        subs.compose_with(more_subs)?;
         Ok(Typed {
-            ty,
+            ty: typed_expr.ty,
             value: Block {
                 statement: typed_statement.into(),
                 expr: typed_expr.value.map(Box::new),
@@ -1027,11 +1510,8 @@ impl Inferable for If<()> {
     where
         Self: Sized,
     {
-        let typed_condition = self.condition.infer(subs, var_state, env)?;
-        typed_condition
-            .ty
-            .unify_with(Type::Cons(Cons::Bool), subs, var_state)?;
-        let typed_body = self.body.infer(subs, var_state, env)?;
+        let (condition, body_env) = infer_condition(self.condition, subs, var_state, env)?;
+        let typed_body = self.body.infer(subs, var_state, &body_env)?;
         let typed_else = match self.else_part {
             Some(else_part) => else_part.infer(subs, var_state, env)?.map(Some),
             None => Typed {
@@ -1039,22 +1519,129 @@ impl Inferable for If<()> {
                 value: None,
             },
         };
-        let mut ty = typed_body.ty;
-        let mut more_subs = Subs::new();
-        ty.clone()
-            .unify_with(typed_else.ty, &mut more_subs, var_state)?;
-        ty.substitute(&more_subs)?;
-        subs.compose_with(more_subs)?;
+        let ty = typed_body.ty;
+        ty.clone().unify_with(typed_else.ty, subs, var_state)?;
         Ok(Typed {
             ty,
             value: If {
-                condition: Box::new(typed_condition.value),
+                condition,
                 body: typed_body.value,
                 else_part: typed_else.value.map(Box::new),
             },
         })
     }
 }
+impl Inferable for While<()> {
+    type TypedSelf = While<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let (condition, body_env) = infer_condition(self.condition, subs, var_state, env)?;
+        let body_env = body_env.in_loop(self.label.clone(), unit())?;
+        let typed_body = self.body.infer(subs, var_state, &body_env)?;
+        Ok(Typed {
+            ty: unit(),
+            value: While {
+                label: self.label,
+                condition,
+                body: typed_body.value,
+            },
+        })
+    }
+}
+impl Inferable for For<()> {
+    type TypedSelf = For<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let typed_expr = self.expr.infer(subs, var_state, env)?;
+        let elem = Type::Var(var_state.new_var());
+        var_state.constrain(
+            Constraint::Iterable {
+                container: typed_expr.ty.clone(),
+                elem: elem.clone(),
+                effects: env.effects().clone(),
+            },
+            subs,
+        )?;
+        let mut body_env = env.scoped();
+        let typed_pattern = self.pattern.infer(subs, var_state, &mut body_env)?;
+        typed_pattern.ty.unify_with(elem, subs, var_state)?;
+        let body_env = body_env.in_loop(self.label.clone(), unit())?;
+        let typed_body = self.body.infer(subs, var_state, &body_env)?;
+        Ok(Typed {
+            ty: unit(),
+            value: For {
+                label: self.label,
+                pattern: typed_pattern.value,
+                expr: Box::new(typed_expr.value),
+                body: typed_body.value,
+            },
+        })
+    }
+}
+impl Inferable for Loop<()> {
+    type TypedSelf = Loop<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        // the loop only finishes through `break` so it has the type of its
+        // values, or it diverges when there's none
+        let ty = Type::Var(var_state.new_var());
+        let body_env = env.in_loop(self.label.clone(), ty.clone())?;
+        let typed_body = self.body.infer(subs, var_state, &body_env)?;
+        Ok(Typed {
+            ty,
+            value: Loop {
+                label: self.label,
+                body: typed_body.value,
+            },
+        })
+    }
+}
+// returns the environment of the body, with the bindings of the pattern in
+// case of `expr =: pattern`
+fn infer_condition(
+    condition: Condition<()>,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+    env: &Env,
+) -> Result<(Condition<Type>, Env), TypeError> {
+    match condition {
+        Condition::Bool(expr) => {
+            let typed = expr.infer(subs, var_state, env)?;
+            typed
+                .ty
+                .unify_with(Type::Cons(Cons::Bool), subs, var_state)?;
+            Ok((Condition::Bool(Box::new(typed.value)), env.scoped()))
+        }
+        Condition::Match(MatchCondition { expr, pattern }) => {
+            let typed_expr = expr.infer(subs, var_state, env)?;
+            let mut body_env = env.scoped();
+            let typed_pattern = pattern.infer(subs, var_state, &mut body_env)?;
+            typed_pattern
+                .ty
+                .unify_with(typed_expr.ty, subs, var_state)?;
+            let condition = Condition::Match(MatchCondition {
+                expr: Box::new(typed_expr.value),
+                pattern: typed_pattern.value,
+            });
+            Ok((condition, body_env))
+        }
+    }
+}
 impl Inferable for ControlFlow<()> {
     type TypedSelf = ControlFlow<Type>;
 
@@ -1067,14 +1654,218 @@ impl Inferable for ControlFlow<()> {
         let typed = match self {
             Self::Block(block) => block.infer(subs, var_state, env)?.map(ControlFlow::Block),
             Self::If(if_expr) => if_expr.infer(subs, var_state, env)?.map(ControlFlow::If),
-            Self::For(_) => todo!(),
-            Self::While(_) => todo!(),
-            Self::Loop(_) => todo!(),
-            Self::Match(_) => todo!(),
+            Self::For(for_expr) => for_expr.infer(subs, var_state, env)?.map(ControlFlow::For),
+            Self::While(while_expr) => while_expr
+                .infer(subs, var_state, env)?
+                .map(ControlFlow::While),
+            Self::Loop(loop_expr) => loop_expr
+                .infer(subs, var_state, env)?
+                .map(ControlFlow::Loop),
+            Self::Match(match_expr) => match_expr
+                .infer(subs, var_state, env)?
+                .map(ControlFlow::Match),
         };
         Ok(typed)
     }
 }
+impl Inferable for Match<()> {
+    type TypedSelf = Match<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let typed_expr = self.expr.infer(subs, var_state, env)?;
+        let ty = Type::Var(var_state.new_var());
+        let mut typed_arm = Vec::with_capacity(self.arm.len());
+        for arm in Vec::from(self.arm) {
+            let mut env = env.scoped();
+            let pattern = arm.pattern.infer(subs, var_state, &mut env)?;
+            pattern
+                .ty
+                .unify_with(typed_expr.ty.clone(), subs, var_state)?;
+            let expr = arm.expr.infer(subs, var_state, &env)?;
+            expr.ty.unify_with(ty.clone(), subs, var_state)?;
+            typed_arm.push(MatchArm {
+                pattern: pattern.value,
+                expr: expr.value,
+            });
+        }
+        let patterns: Vec<_> = typed_arm.iter().map(|arm| &arm.pattern).collect();
+        exhaustive::check(&patterns, typed_expr.ty, subs, var_state)?;
+        Ok(Typed {
+            ty,
+            value: Match {
+                expr: Box::new(typed_expr.value),
+                arm: typed_arm.into(),
+            },
+        })
+    }
+}
+impl Inferable for Module<()> {
+    type TypedSelf = Module<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let (typed, _) = infer_module(subs, var_state, self, env.module().clone())?;
+        Ok(typed)
+    }
+}
+// The type of a module is a record of its public top-level bindings, those
+// public only to some path are kept in the members instead
+fn infer_module(
+    subs: &mut Subs,
+    var_state: &mut VarState,
+    module: Module<()>,
+    path: Rc<[Atom]>,
+) -> Result<(Typed<Module<Type>>, ModuleMembers), TypeError> {
+    let statement = match module {
+        Module::File(_) => return Err(TypeError::UnloadedModule),
+        Module::Inline(statement) => statement,
+    };
+    // modules don't see the scope they're in
+    let mut env = Env::in_module(path.clone());
+    let mut visibility = BTreeMap::new();
+    let typed: Box<[_]> = statement
+        .into_vec()
+        .into_iter()
+        .map(|statement| {
+            match &statement {
+                Statement::Declare(declare) => {
+                    let mut names = Vec::new();
+                    bound_names(&declare.pattern, &mut names);
+                    for name in names {
+                        visibility.insert(name, declare.visibility.clone());
+                    }
+                }
+                Statement::FunDeclare(fun) => {
+                    visibility.insert(fun.ident.clone(), fun.visibility.clone());
+                }
+                Statement::NewType(newtype) => {
+                    visibility.insert(newtype.ident.clone(), newtype.visibility.clone());
+                }
+                Statement::Expr(_) => (),
+            }
+            infer_statement(subs, &mut env, var_state, statement)
+        })
+        .collect::<Result<_, _>>()?;
+    let mut fields = BTreeMap::new();
+    let mut members = BTreeMap::new();
+    for (var, scheme) in env.scope() {
+        let visibility = visibility.remove(&var.name).unwrap_or_default();
+        let mut restricted = None;
+        match visibility {
+            Visibility::Private => (),
+            Visibility::Public => {
+                let ty = scheme.scheme.clone().instantiate(var_state);
+                fields.insert(var.name.clone(), ty);
+            }
+            // generalized along with the record by the declaration
+            Visibility::Restricted(_) => {
+                let ty = scheme.scheme.clone().instantiate(var_state);
+                restricted = Some(Scheme {
+                    for_all: BTreeSet::new(),
+                    ty,
+                    constraints: Vec::new(),
+                });
+            }
+        }
+        let member = Member {
+            visibility,
+            module: scheme.module.clone(),
+            nominal: scheme.nominal.clone(),
+            restricted,
+        };
+        members.insert(var.name.clone(), member);
+    }
+    let typed = Typed {
+        ty: Type::Cons(Cons::Record(Keyed { fields, rest: None })),
+        value: Module::Inline(typed),
+    };
+    Ok((typed, ModuleMembers { path, members }))
+}
+pub(super) fn bound_names<T>(pattern: &pattern::Pattern<T>, names: &mut Vec<Atom>) {
+    match pattern {
+        pattern::Pattern::Var(var) => names.push(var.ident.clone()),
+        pattern::Pattern::Record(record) => {
+            for pattern in record.fields.values().chain(record.rest.as_deref()) {
+                bound_names(pattern, names);
+            }
+        }
+        pattern::Pattern::Tuple(list) | pattern::Pattern::Array(list) => match list {
+            pattern::ListPattern::List(patterns) => {
+                for pattern in patterns.iter() {
+                    bound_names(pattern, names);
+                }
+            }
+            pattern::ListPattern::ListWithRest(list) => {
+                for pattern in list
+                    .left
+                    .iter()
+                    .chain([&*list.rest])
+                    .chain(list.right.iter())
+                {
+                    bound_names(pattern, names);
+                }
+            }
+        },
+        pattern::Pattern::Tag(tag) => {
+            if let Some(pattern) = &tag.pattern {
+                bound_names(pattern, names);
+            }
+        }
+        pattern::Pattern::Ref(pattern) => bound_names(pattern, names),
+        pattern::Pattern::NewType(newtype) => {
+            for pattern in newtype.fields.fields.values() {
+                bound_names(pattern, names);
+            }
+        }
+        pattern::Pattern::Or(patterns) => bound_names(&patterns[0], names),
+        pattern::Pattern::As(pattern) => {
+            bound_names(&pattern.pattern, names);
+            names.push(pattern.var.ident.clone());
+        }
+        pattern::Pattern::True
+        | pattern::Pattern::False
+        | pattern::Pattern::UInt(_)
+        | pattern::Pattern::Int(_)
+        | pattern::Pattern::Ignore
+        | pattern::Pattern::Range(_)
+        | pattern::Pattern::Equal(_) => (),
+    }
+}
+// the members of the module an expression names, either a variable bound to
+// a module or a member module of one
+fn module_of(expr: &Expr<()>, env: &Env) -> Option<Rc<ModuleMembers>> {
+    match expr {
+        Expr::Place(PlaceExpr::Var(var)) => env.get_module(Var::new_bare(var.clone())),
+        Expr::Place(PlaceExpr::FieldAccess(access)) => module_of(&access.expr, env)?
+            .members
+            .get(&access.name)?
+            .module
+            .clone(),
+        _ => None,
+    }
+}
+// the newtype an expression names the constructor of, in the same way as
+// `module_of`
+fn nominal_of(expr: &Expr<()>, env: &Env) -> Option<Rc<Nominal>> {
+    match expr {
+        Expr::Place(PlaceExpr::Var(var)) => env.get_nominal(Var::new_bare(var.clone())),
+        Expr::Place(PlaceExpr::FieldAccess(access)) => module_of(&access.expr, env)?
+            .members
+            .get(&access.name)?
+            .nominal
+            .clone(),
+        _ => None,
+    }
+}
 impl Inferable for Expr<()> {
     type TypedSelf = Expr<Type>;
 
@@ -1089,6 +1880,8 @@ impl Inferable for Expr<()> {
             Self::Place(place) => place.infer(subs, var_state, env)?.map(Expr::Place),
             Self::Array(elements) => elements.infer(subs, var_state, env)?.map(Expr::Array),
             Self::ArrayRange(range) => range.infer(subs, var_state, env)?.map(Expr::ArrayRange),
+            Self::Dict(entries) => infer_dict(entries, subs, var_state, env)?,
+            Self::Set(elements) => infer_set(elements, subs, var_state, env)?,
             Self::Tag(tag) => tag.infer(subs, var_state, env)?.map(Expr::Tag),
             Self::Record(record) => record.infer(subs, var_state, env)?.map(Expr::Record),
             Self::Tuple(tuple) => tuple.infer(subs, var_state, env)?.map(Expr::Tuple),
@@ -1099,17 +1892,12 @@ impl Inferable for Expr<()> {
             Self::Splat(splat) => {
                 let splat = splat.infer(subs, var_state, env)?;
                 let var = var_state.new_var();
-                let mut ty = Type::Cons(Cons::RecordTuple(OrderedAnd::Row(
+                let ty = Type::Cons(Cons::RecordTuple(OrderedAnd::Row(
                     Vec::new(),
                     var.clone(),
                     Vec::new(),
                 )));
-                let mut more_subs = Subs::new();
-                splat
-                    .ty
-                    .unify_with(Type::Var(var), &mut more_subs, var_state)?;
-                ty.substitute(&more_subs)?;
-                subs.compose_with(more_subs)?;
+                splat.ty.unify_with(Type::Var(var), subs, var_state)?;
                 Typed {
                     ty,
                     value: Expr::Splat(Box::new(splat.value)),
@@ -1119,11 +1907,14 @@ impl Inferable for Expr<()> {
             Self::Binary(binary) => binary.infer(subs, var_state, env)?.map(Expr::Binary),
             Self::Fun(fun) => fun.infer(subs, var_state, env)?.map(Expr::Fun),
             Self::Call(call) => call.infer(subs, var_state, env)?.map(Expr::Call),
+            Self::Yield(yield_expr) => yield_expr.infer(subs, var_state, env)?.map(Expr::Yield),
+            Self::Handle(handle) => handle.infer(subs, var_state, env)?.map(Expr::Handle),
             Self::Assign(assigns) => assigns.infer(subs, var_state, env)?.map(Expr::Assign),
             Self::Jump(jump) => jump.infer(subs, var_state, env)?.map(Expr::Jump),
             Self::ControlFlow(control_flow) => control_flow
                 .infer(subs, var_state, env)?
                 .map(Expr::ControlFlow),
+            Self::Module(module) => module.infer(subs, var_state, env)?.map(Expr::Module),
         };
         Ok(ty_expr)
     }
@@ -1142,3 +1933,499 @@ impl Inferable for Expr<()> {
         Ok(mut_typed)
     }
 }
+#[cfg(test)]
+mod test {
+    use crate::{
+        expr::infer_statement,
+        test::{infer, infer_ty},
+        ty::{Env, Subs, Var, VarState},
+        TypeError,
+    };
+    use hir::Atom;
+    use parser::{ast, EasyParser};
+
+    #[test]
+    fn ref_immutable() {
+        assert_eq!(
+            infer("{ foo = 10; mut bar = &foo; bar^ <- 20; }"),
+            Err(TypeError::MismatchCons),
+        );
+    }
+    #[test]
+    fn mutability_polymorphism() {
+        let src = "{
+            first(arr) => &arr^[0];
+            mut foo = [10];
+            bar = [20];
+            mut elem = first(&foo);
+            elem^ <- 30;
+            (elem, first(&bar))
+        }";
+        assert_eq!(infer_ty(src), Ok("(&:mut Num, &Num)".to_string()));
+        let src = "{
+            first(arr) => &arr^[0];
+            foo = [10];
+            mut elem = first(&foo);
+            elem^ <- 30;
+        }";
+        assert_eq!(infer(src), Err(TypeError::MismatchCons));
+    }
+    #[test]
+    fn field_access() {
+        assert_eq!(infer_ty("(foo = 1, bar = true).foo"), Ok("Num".to_string()));
+        assert_eq!(
+            infer_ty("(foo = 1, bar = true).baz"),
+            Err(TypeError::MismatchName),
+        );
+    }
+    #[test]
+    fn generalization() {
+        let src = "{
+            id(x) => x;
+            (id(10), id(true))
+        }";
+        assert_eq!(infer_ty(src), Ok("(Num, Bool)".to_string()));
+        // `x` is unified with the outer `y` and so can't be generalized
+        let src = "(y) => {
+            pair(x) => [x, y];
+            (pair(10), y)
+        }";
+        assert_eq!(infer_ty(src), Ok("(y: Num) -> ([Num], Num)".to_string()));
+        let src = "(y) => {
+            pair(x) => [x, y];
+            (pair(10), pair(true))
+        }";
+        assert_eq!(infer(src), Err(TypeError::MismatchCons));
+    }
+    #[test]
+    fn bound_through_vars() {
+        // `x` is bound to `y` by the time `y` is unified with `[x]`
+        assert_eq!(
+            infer("(x, y) => { [x, y]; [y, [x]] }"),
+            Err(TypeError::InfiniteOccurrence),
+        );
+        // `z` is bound to `[y]` so `y` is lowered to the level of `x`
+        let src = "(x) => {
+            id = (y) => { z = [y]; [x, z]; y };
+            (id(true), id(1))
+        }";
+        assert_eq!(infer(src), Err(TypeError::MismatchCons));
+    }
+    #[test]
+    fn mutability_scheme() {
+        let (statements, _) = ast::<(), _>()
+            .easy_parse("first(arr) => &arr^[0];")
+            .unwrap();
+        let mut env = Env::new();
+        let mut var_state = VarState::new();
+        for statement in statements {
+            infer_statement(&mut Subs::new(), &mut env, &mut var_state, statement).unwrap();
+        }
+        let scheme = env.get_ty(Var::new_bare(Atom::from("first"))).unwrap();
+        assert_eq!(
+            scheme.to_string(),
+            ":(:a, b, c) (arr: &:a b) -> &:a c where b[Num]: c",
+        );
+    }
+    #[test]
+    fn visibility() {
+        let src = "{
+            mod util { pub id(x) => x; pub pi = 3; tau = 6; }
+            (util.id(util.pi), util.id(true))
+        }";
+        assert_eq!(infer_ty(src), Ok("(Num, Bool)".to_string()));
+        let private = |module: &str, member: &str| {
+            Err(TypeError::InvisibleMember {
+                module: module.to_string(),
+                member: Atom::from(member),
+            })
+        };
+        let src = "{ mod math { pub pi = 3; tau = 6; } math.tau }";
+        assert_eq!(infer(src), private("math", "tau"));
+        let src = "{ mod math { tau = 6; } alias = math; alias.tau }";
+        assert_eq!(infer(src), private("math", "tau"));
+        let src = "{
+            mod outer {
+                pub mod inner { pub(outer) x = 1; }
+                pub y = inner.x;
+            }
+            outer.y
+        }";
+        assert_eq!(infer_ty(src), Ok("Num".to_string()));
+        let src = "{
+            mod outer {
+                pub mod inner { pub(outer) x = 1; }
+            }
+            outer.inner.x
+        }";
+        assert_eq!(infer(src), private("outer.inner", "x"));
+        // the member isn't part of the module's type, so it can't be reached
+        // other than through its path
+        let src = "{
+            mod outer {
+                pub mod inner { pub(outer) x = 1; pub y = 2; }
+            }
+            (= x, *_) = outer.inner;
+            x
+        }";
+        assert_eq!(infer(src), Err(TypeError::MismatchName));
+        let src = "{
+            mod outer {
+                pub mod inner { pub(outer) x = 1; pub y = 2; }
+            }
+            get(m) => m.x;
+            get(outer.inner)
+        }";
+        assert_eq!(infer(src), Err(TypeError::MismatchName));
+        let src = "{
+            mod outer {
+                pub mod inner { pub(outer) id(x) => x; pub y = 2; }
+                pub pair = (inner.id(1), inner.id(true));
+            }
+            (= y) = outer.inner;
+            (outer.pair, y)
+        }";
+        assert_eq!(infer_ty(src), Ok("((Num, Bool), Num)".to_string()));
+    }
+    #[test]
+    fn newtype() {
+        let src = "{
+            newtype Point(x: Num, #y: Num);
+            p = Point(x = 1, #y = 2);
+            Point(#y = y, *_) = p;
+            Point(x = x, #y = _y) = p;
+            (p, p.x, p.#y, x)
+        }";
+        assert_eq!(infer_ty(src), Ok("(Point, Num, Num, Num)".to_string()));
+        let src = "{ newtype Box(value); (Box(value = 1), Box(value = true).value) }";
+        assert_eq!(infer_ty(src), Ok("(Box(Num), Bool)".to_string()));
+        let src = "{ newtype Meters(value: Num); Meters(value = 1) == (value = 1) }";
+        assert_eq!(infer(src), Err(TypeError::MismatchCons));
+        // declaring the same newtype again makes another one
+        let src = "{
+            a = { newtype P(x: Num); P(x = 1) };
+            b = { newtype P(x: Num); P(x = 2) };
+            c = [a, b];
+        }";
+        assert_eq!(infer(src), Err(TypeError::MismatchCons));
+        let src = "{ newtype P(x: Num); a = P(x = 1); newtype P(x: Num); [a, P(x = 2)] }";
+        assert_eq!(infer(src), Err(TypeError::MismatchCons));
+        let src = "{ newtype Wrap(inner: Missing); }";
+        assert_eq!(
+            infer(src),
+            Err(TypeError::UnknownType(Atom::from("Missing")))
+        );
+        let private = |ty: &str, field: &str| {
+            Err(TypeError::InvisibleField {
+                ty: Atom::from(ty),
+                field: Atom::from(field),
+            })
+        };
+        let src = "{
+            mod geo {
+                pub newtype Point(x: Num, #y: Num);
+                pub origin = Point(x = 0, #y = 0);
+                pub y = origin.#y;
+            }
+            (geo.origin.x, geo.y)
+        }";
+        assert_eq!(infer_ty(src), Ok("(Num, Num)".to_string()));
+        let src =
+            "{ mod geo { pub newtype Point(#y); pub origin = Point(#y = 0); } geo.origin.#y }";
+        assert_eq!(infer(src), private("Point", "#y"));
+        let src = "{ mod geo { pub newtype Point(x, #y); } geo.Point(x = 0, #y = 0) }";
+        assert_eq!(infer(src), private("Point", "#y"));
+        let src = "{ mod geo { pub newtype Point(x, pub #y); } geo.Point(x = 0, #y = 0).#y }";
+        assert_eq!(infer_ty(src), Ok("Num".to_string()));
+    }
+    #[test]
+    fn match_condition() {
+        assert_eq!(
+            infer_ty("(o) => if o =: @some x { x } else { 0 }"),
+            Ok("(o: @some Num | *_) -> Num".to_string()),
+        );
+        let src = "(next) => { mut sum = 0; while next() =: @val item { sum <- sum + item; } sum }";
+        assert_eq!(
+            infer_ty(src),
+            Ok("(next: () -> (@val Num | *_)) -> Num".to_string())
+        );
+        assert_eq!(
+            infer("(o) => if o =: @some x { x } else { x }"),
+            Err(TypeError::UnboundVar),
+        );
+        assert_eq!(
+            infer("(o) => { while o =: @some x {} x }"),
+            Err(TypeError::UnboundVar),
+        );
+        assert_eq!(infer("while 1 {}"), Err(TypeError::MismatchCons));
+    }
+    #[test]
+    fn else_part() {
+        assert_eq!(
+            infer_ty("(o) => { @some x = o else { return 0 }; x + 1 }"),
+            Ok("(o: @some Num | *_) -> Num".to_string()),
+        );
+        let src = "(o) => { [x] = o else { std.panic(\"empty\") }; x }";
+        assert_eq!(infer_ty(src), Ok("(o: [a]) -> a".to_string()));
+        assert_eq!(
+            infer_ty("(o) => { @some x = o else { return 0; }; x + 1 }"),
+            Ok("(o: @some Num | *_) -> Num".to_string()),
+        );
+        let src = "(o) => { [x] = o else { std.panic(\"empty\"); }; x }";
+        assert_eq!(infer_ty(src), Ok("(o: [a]) -> a".to_string()));
+        assert_eq!(
+            infer_ty("(o) => { loop { [x] = o else { break; }; x; } }"),
+            Ok("(o: [a]) -> ()".to_string()),
+        );
+        let fall_through = Err(TypeError::FallThrough);
+        assert_eq!(infer("(o) => { @some x = o else { 0 }; x }"), fall_through);
+        assert_eq!(
+            infer("(o, d) => { @some x = o else { d }; x }"),
+            fall_through
+        );
+        assert_eq!(
+            infer("(o, c) => { @some x = o else { if c { return 0; } }; x }"),
+            fall_through,
+        );
+        assert_eq!(
+            infer("(o) => { @some x = o else { loop { break; } }; x }"),
+            fall_through,
+        );
+        assert_eq!(
+            infer("(o) => { @some x = o else { x }; x }"),
+            Err(TypeError::UnboundVar),
+        );
+    }
+    #[test]
+    fn labels() {
+        assert_eq!(
+            infer_ty("(n) => 'outer: loop { loop { break 'outer n } }"),
+            Ok("(n: a) -> a".to_string()),
+        );
+        assert_eq!(
+            infer_ty("(b) => 'outer: while b { loop { continue 'outer } }"),
+            Ok("(b: Bool) -> ()".to_string()),
+        );
+        assert_eq!(
+            infer("'outer: loop { loop { break 'outer 1 }; break 'outer true }"),
+            Err(TypeError::MismatchCons),
+        );
+        assert_eq!(
+            infer("loop { break 'outer }"),
+            Err(TypeError::UnknownLabel(Atom::from("outer"))),
+        );
+        assert_eq!(
+            infer("'outer: loop { 'outer: loop { break } }"),
+            Err(TypeError::ShadowedLabel(Atom::from("outer"))),
+        );
+        assert_eq!(infer("() => break"), Err(TypeError::OutsideLoop));
+        assert_eq!(
+            infer("loop { () => continue }"),
+            Err(TypeError::OutsideLoop)
+        );
+    }
+    #[test]
+    fn pipe() {
+        assert_eq!(
+            infer_ty("(x) => x |> ((y) => y + 1)"),
+            Ok("(x: Num) -> Num".to_string()),
+        );
+        let src = "{ add(a, b) => a + b; 40 |> add(?, 2) |> add(b = 1, a = ?) }";
+        assert_eq!(infer_ty(src), Ok("Num".to_string()));
+        assert_eq!(
+            infer_ty("(f) => f(?, 1)"),
+            Ok("(f: (a, Num) -> b) -> (a,) -> b".to_string()),
+        );
+        assert_eq!(
+            infer_ty("(f) => f(?, 1, ?)"),
+            Ok("(f: (a, Num, b) -> c) -> (a, b) -> c".to_string()),
+        );
+        assert_eq!(
+            infer_ty("(f) => f(x = ?, y = 1)"),
+            Ok("(f: {x: a, y: Num} -> b) -> (a,) -> b".to_string()),
+        );
+        assert_eq!(
+            infer("{ add(a, b) => a + b; true |> add(?, 2) }"),
+            Err(TypeError::MismatchCons),
+        );
+    }
+    #[test]
+    fn dict_set() {
+        assert_eq!(
+            infer_ty("#(1 = true, 2 = false)"),
+            Ok("#(Num = Bool)".to_string())
+        );
+        assert_eq!(infer_ty("#[]"), Ok("#[a]".to_string()));
+        let src = "{ mut d = #(); d[(1, 2)] <- [3]; d[(4, 5)] }";
+        assert_eq!(infer_ty(src), Ok("[Num]".to_string()));
+        assert_eq!(
+            infer_ty("(x) => { s = #[1]; s[x] }"),
+            Ok("(x: Num) -> Bool".to_string()),
+        );
+        assert_eq!(
+            infer("{ mut d = #(1 = 2); d[true] <- 4; }"),
+            Err(TypeError::MismatchCons),
+        );
+        assert_eq!(
+            infer("{ d = #(1 = 2); d[3] <- 4; }"),
+            Err(TypeError::AssignedImm),
+        );
+        // the container is only known after it's indexed
+        let src = "(d) => { x = d[1]; [d, #(1 = true)]; x }";
+        assert_eq!(infer_ty(src), Ok("(d: #(Num = Bool)) -> Bool".to_string()));
+        let src = "(s) => { x = s[1]; [s, #[1]]; x }";
+        assert_eq!(infer_ty(src), Ok("(s: #[Num]) -> Bool".to_string()));
+        let src = "{
+            get(container, key) => container[key];
+            (get([true], 0), get(#(\"a\" = 1), \"a\"), get(#[1], 1))
+        }";
+        assert_eq!(infer_ty(src), Ok("(Bool, Num, Bool)".to_string()));
+        assert_eq!(infer_ty("(a) => a[0]"), Ok("(a: [a]) -> a".to_string()));
+        assert_eq!(
+            infer("(i) => { x = i[0]; [i, std.iter.from([1])]; }"),
+            Err(TypeError::MismatchCons),
+        );
+        assert_eq!(infer("#(1 = 2, true = 3)"), Err(TypeError::MismatchCons));
+        assert_eq!(infer("#[() => 1]"), Err(TypeError::Unhashable));
+        assert_eq!(infer("#(#[1] = 2)"), Err(TypeError::Unhashable));
+        assert_eq!(
+            infer("{ f(g) => #[g]; h(x) => x; d = f(h); }"),
+            Err(TypeError::Unhashable),
+        );
+        let (statements, _) = ast::<(), _>().easy_parse("key(x) => #(x = 1);").unwrap();
+        let mut env = Env::new();
+        let mut var_state = VarState::new();
+        for statement in statements {
+            infer_statement(&mut Subs::new(), &mut env, &mut var_state, statement).unwrap();
+        }
+        let scheme = env.get_ty(Var::new_bare(Atom::from("key"))).unwrap();
+        assert_eq!(scheme.to_string(), ":(a) (x: a) -> #(a = Num) where #[a]");
+        assert_eq!(
+            infer("(x) => { mut s = #[x]; s[&1] <- true; }"),
+            Err(TypeError::Unhashable),
+        );
+        assert_eq!(
+            infer_ty("{ f(g) => #[g]; f([1]) }"),
+            Ok("#[[Num]]".to_string())
+        );
+    }
+    #[test]
+    fn for_loops() {
+        assert_eq!(
+            infer_ty("(xs) => for x in xs { x + 1; }"),
+            Ok("(xs: [Num]) -> ()".to_string()),
+        );
+        assert_eq!(
+            infer("(n) => for i in [0.<n] { std.assert(i < n); }"),
+            Ok(())
+        );
+        let src = "(f) => for (=key, =value) in #(1 = true) { f(key, value); }";
+        assert_eq!(infer_ty(src), Ok("(f: (Num, Bool) -> a) -> ()".to_string()));
+        let src = "'outer: for x in [1] { for y in #[2] { if x == y { break 'outer; } } }";
+        assert_eq!(infer(src), Ok(()));
+        assert_eq!(
+            infer("for x in [1] { break 2; }"),
+            Err(TypeError::MismatchCons)
+        );
+        // iterating a parameter leaves which container it is to the caller
+        let src = "{
+            f(it) => for x in it { x; };
+            (f([1]), f(#[true]), f(std.iter.from([1])))
+        }";
+        assert_eq!(infer_ty(src), Ok("((), (), ())".to_string()));
+        let src = "{
+            f(it) => { mut last = (); for x in it { last <- (); x; }; it };
+            (f([1]), f(#[true]), f(std.iter.from([()])))
+        }";
+        assert_eq!(infer_ty(src), Ok("([Num], #[Bool], Iter(()))".to_string()));
+        let src = "{
+            sum(it) => { mut total = 0; for x in it { total <- total + x; }; total };
+            (sum([1]), sum(#[2]), sum(std.iter.from([3])))
+        }";
+        assert_eq!(infer_ty(src), Ok("(Num, Num, Num)".to_string()));
+        assert_eq!(
+            infer("{ sum(it) => for x in it { x + 1; }; sum(#[true]) }"),
+            Err(TypeError::MismatchCons)
+        );
+        assert_eq!(infer("(xs) => { for x in xs {}; [xs, #[1]] }"), Ok(()));
+        assert_eq!(infer("for x in 1 {}"), Err(TypeError::MismatchCons));
+        assert_eq!(infer("{ for x in [1] {}; x }"), Err(TypeError::UnboundVar));
+    }
+    #[test]
+    fn effects() {
+        let src = "{
+            numbers() => { yield item(1); yield item(2); };
+            mut sum = 0;
+            do numbers() handling { item(number) => { sum <- sum + number; } };
+            sum
+        }";
+        assert_eq!(infer_ty(src), Ok("Num".to_string()));
+        assert_eq!(
+            infer_ty("() => yield ask(1)"),
+            Ok("() -> a ! {ask: (Num,) -> a, *_}".to_string()),
+        );
+        let src = "{ apply(f) => f(); do apply(() => yield ask()) handling { ask() => 1 } }";
+        assert_eq!(infer_ty(src), Ok("Num".to_string()));
+        let src = "(n) => do { if n == 0 { yield fail(); }; n } handling { fail() => return 0 }";
+        assert_eq!(infer_ty(src), Ok("(n: Num) -> Num".to_string()));
+        assert_eq!(
+            infer("{ f() => yield item(1); f() }"),
+            Err(TypeError::UnhandledEffect(Atom::from("item"))),
+        );
+        assert_eq!(
+            infer("do yield ask() + 1 handling { ask() => true }"),
+            Err(TypeError::MismatchCons),
+        );
+        assert_eq!(
+            infer("do 1 handling { ask() => 1, ask() => 2 }"),
+            Err(TypeError::Overlap),
+        );
+        // an effect handled inside another handler of it never reaches the
+        // outer one
+        let src = "(f) => do { do f() handling { e(x) => () }; f() } handling { e(x) => () }";
+        assert_eq!(
+            infer_ty(src),
+            Ok("(f: () -> a ! {e: (x: b) -> (), *_}) -> a".to_string()),
+        );
+        let src = "{
+            g(f) => do { do f() handling { e(x) => x + 1 }; yield e(true) } handling { e(x) => !x };
+            () => g(() => yield e(1))
+        }";
+        assert_eq!(infer_ty(src), Ok("() -> Bool".to_string()));
+        // effects of callbacks given to iterators are performed where the
+        // iterator is iterated
+        assert_eq!(
+            infer_ty("std.iter.map(std.iter.from([1]), (x) => yield item(x))"),
+            Ok("Iter(a ! {item: (Num,) -> a, *_})".to_string()),
+        );
+        let src = "() => {
+            it = std.iter.map(std.iter.from([1]), (x) => yield item(x));
+            do std.iter.collect(it) handling { item(x) => x + 1 }
+        }";
+        assert_eq!(infer_ty(src), Ok("() -> [Num]".to_string()));
+        let src = "() => {
+            it = do std.iter.map(std.iter.from([1]), (x) => yield item(x)) handling {
+                item(x) => 1,
+            };
+            std.iter.collect(it)
+        }";
+        assert_eq!(
+            infer_ty(src),
+            Ok("() -> [a] ! {item: (Num,) -> a, *_}".to_string()),
+        );
+        let src = "(it) => for x in std.iter.filter(it, (x) => yield keep(x)) {}";
+        assert_eq!(
+            infer_ty(src),
+            Ok(
+                "(it: Iter(a ! {keep: (&:b a,) -> Bool, *c})) -> () ! {keep: (&:b a,) -> Bool, *c}"
+                    .to_string()
+            ),
+        );
+        assert_eq!(
+            infer(
+                "{ it = std.iter.map(std.iter.from([1]), (x) => yield item(x)); for x in it {} }"
+            ),
+            Err(TypeError::UnhandledEffect(Atom::from("item"))),
+        );
+    }
+}
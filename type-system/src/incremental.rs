@@ -0,0 +1,596 @@
+use crate::{
+    clone::UseChecker,
+    expr::infer_statement,
+    ty::{Env, SchemeMut, Subs, Substitutable, VarState},
+    Type, TypeError, Var,
+};
+use hir::{
+    expr::{
+        Arg, Assign, Block, Bound, Break, Condition, ControlFlow, Element, Entry, Expr, Field,
+        Jump, PlaceExpr, Range, Record, Tuple,
+    },
+    pattern::{ListPattern, Pattern},
+    statement::{Statement, TypeAnnotation},
+    Atom,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    mem::take,
+    rc::Rc,
+    slice,
+};
+
+// Every identifier a statement mentions, including the ones it binds itself.
+// This over-approximates its dependencies which only costs extra re-checks.
+struct Names(BTreeSet<Atom>);
+impl Names {
+    fn statements<T>(&mut self, statements: &[Statement<T>]) {
+        for statement in statements {
+            match statement {
+                Statement::Declare(declare) => {
+                    self.pattern(&declare.pattern);
+                    self.expr(&declare.expr);
+                    if let Some(else_part) = &declare.else_part {
+                        self.block(else_part);
+                    }
+                }
+                Statement::FunDeclare(fun) => self.expr(&fun.fun.body),
+                Statement::NewType(newtype) => {
+                    for field in newtype.fields.iter() {
+                        self.annotation(&field.ty);
+                    }
+                }
+                Statement::Expr(expr) => self.expr(expr),
+            }
+        }
+    }
+    // only newtype patterns use a name
+    fn pattern<T>(&mut self, pattern: &Pattern<T>) {
+        match pattern {
+            Pattern::NewType(newtype) => {
+                self.0.insert(newtype.name.clone());
+                for pattern in newtype.fields.fields.values() {
+                    self.pattern(pattern);
+                }
+            }
+            Pattern::Record(record) => {
+                for pattern in record.fields.values().chain(record.rest.as_deref()) {
+                    self.pattern(pattern);
+                }
+            }
+            Pattern::Tuple(list) | Pattern::Array(list) => match list {
+                ListPattern::List(patterns) => {
+                    for pattern in patterns.iter() {
+                        self.pattern(pattern);
+                    }
+                }
+                ListPattern::ListWithRest(list) => {
+                    for pattern in list.left.iter().chain(list.right.iter()) {
+                        self.pattern(pattern);
+                    }
+                    self.pattern(&list.rest);
+                }
+            },
+            Pattern::Tag(tag) => {
+                if let Some(pattern) = &tag.pattern {
+                    self.pattern(pattern);
+                }
+            }
+            Pattern::Ref(pattern) => self.pattern(pattern),
+            Pattern::Or(patterns) => {
+                for pattern in patterns.iter() {
+                    self.pattern(pattern);
+                }
+            }
+            Pattern::As(pattern) => self.pattern(&pattern.pattern),
+            Pattern::Equal(expr) => self.expr(expr),
+            Pattern::Range(_)
+            | Pattern::True
+            | Pattern::False
+            | Pattern::UInt(_)
+            | Pattern::Int(_)
+            | Pattern::Ignore
+            | Pattern::Var(_) => (),
+        }
+    }
+    fn annotation(&mut self, annotation: &TypeAnnotation) {
+        match annotation {
+            TypeAnnotation::Infer => (),
+            TypeAnnotation::Array(ty) | TypeAnnotation::Ref(_, ty) => self.annotation(ty),
+            TypeAnnotation::Named(name, args) => {
+                self.0.insert(name.clone());
+                for arg in args.iter() {
+                    self.annotation(arg);
+                }
+            }
+        }
+    }
+    fn exprs<'a, T: 'a>(&mut self, exprs: impl IntoIterator<Item = &'a Expr<T>>) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+    fn fields<'a, T: 'a>(&mut self, fields: impl IntoIterator<Item = &'a Field<T>>) {
+        self.exprs(fields.into_iter().map(|field| &field.expr));
+    }
+    fn tuple<T>(&mut self, tuple: &Tuple<T>) {
+        match tuple {
+            Tuple::Tuple(tuple) => self.exprs(tuple.iter()),
+            Tuple::TupleWithSplat(tuple) => {
+                self.exprs(tuple.left.iter());
+                self.expr(&tuple.splat);
+                self.exprs(tuple.right.iter());
+            }
+        }
+    }
+    fn record<T>(&mut self, record: &Record<T>) {
+        match record {
+            Record::Record(record) => self.fields(record.iter()),
+            Record::RecordWithSplat(record) => {
+                self.fields(record.left.iter());
+                self.expr(&record.splat);
+                self.fields(record.right.iter());
+            }
+        }
+    }
+    fn range<T>(&mut self, range: &Range<T>) {
+        let bounds = [&range.left, &range.right];
+        self.exprs(
+            bounds
+                .into_iter()
+                .flatten()
+                .map(|bound: &Bound<T>| &*bound.expr),
+        );
+    }
+    fn block<T>(&mut self, block: &Block<T>) {
+        self.statements(&block.statement);
+        self.exprs(block.expr.iter().map(AsRef::as_ref));
+    }
+    fn condition<T>(&mut self, condition: &Condition<T>) {
+        self.expr(condition.expr());
+        if let Condition::Match(condition) = condition {
+            self.pattern(&condition.pattern);
+        }
+    }
+    fn control_flow<T>(&mut self, control_flow: &ControlFlow<T>) {
+        match control_flow {
+            ControlFlow::Block(block) => self.block(block),
+            ControlFlow::Loop(loop_expr) => self.block(&loop_expr.body),
+            ControlFlow::If(if_expr) => {
+                self.condition(&if_expr.condition);
+                self.block(&if_expr.body);
+                if let Some(else_part) = &if_expr.else_part {
+                    self.control_flow(else_part);
+                }
+            }
+            ControlFlow::For(for_expr) => {
+                self.expr(&for_expr.expr);
+                self.block(&for_expr.body);
+            }
+            ControlFlow::While(while_expr) => {
+                self.condition(&while_expr.condition);
+                self.block(&while_expr.body);
+            }
+            ControlFlow::Match(match_expr) => {
+                self.expr(&match_expr.expr);
+                self.exprs(match_expr.arm.iter().map(|arm| &arm.expr));
+            }
+        }
+    }
+    fn place<T>(&mut self, place: &PlaceExpr<T>) {
+        match place {
+            PlaceExpr::Var(var) => {
+                self.0.insert(var.clone());
+            }
+            PlaceExpr::FieldAccess(field) => self.expr(&field.expr),
+            PlaceExpr::Index(index) => {
+                self.expr(&index.expr);
+                self.expr(&index.index);
+            }
+            PlaceExpr::Slice(slice) => {
+                self.expr(&slice.expr);
+                self.range(&slice.range);
+            }
+            PlaceExpr::Deref(expr) | PlaceExpr::Len(expr) => self.expr(expr),
+        }
+    }
+    fn expr<T>(&mut self, expr: &Expr<T>) {
+        match expr {
+            Expr::Literal(_) | Expr::Unit | Expr::Jump(Jump::Continue(_)) => (),
+            Expr::Tag(tag) => self.exprs(tag.expr.iter().map(AsRef::as_ref)),
+            Expr::Assign(assign) => {
+                for Assign { place, expr } in assign.iter() {
+                    self.place(place);
+                    self.expr(expr);
+                }
+            }
+            Expr::Array(elements) => {
+                self.exprs(elements.iter().map(|element: &Element<T>| &element.expr))
+            }
+            Expr::Dict(entries) => self.exprs(
+                entries
+                    .iter()
+                    .flat_map(|entry: &Entry<T>| [&entry.key, &entry.value]),
+            ),
+            Expr::Set(elements) => self.exprs(elements.iter()),
+            Expr::ArrayRange(range) => self.range(range),
+            Expr::Splat(expr) => self.expr(expr),
+            Expr::Record(record) => self.record(record),
+            Expr::Tuple(tuple) => self.tuple(tuple),
+            Expr::Unary(unary) => self.expr(&unary.expr),
+            Expr::Binary(binary) => {
+                self.expr(&binary.left);
+                self.expr(&binary.right);
+            }
+            Expr::Place(place) => self.place(place),
+            Expr::Call(call) => {
+                self.expr(&call.expr);
+                self.arg(&call.arg);
+            }
+            Expr::ControlFlow(control_flow) => self.control_flow(control_flow),
+            Expr::Fun(fun) => self.expr(&fun.body),
+            Expr::Jump(Jump::Break(Break { expr, .. }) | Jump::Return(expr)) => {
+                self.exprs(expr.iter().map(AsRef::as_ref))
+            }
+            Expr::Yield(yield_expr) => self.arg(&yield_expr.arg),
+            Expr::Handle(handle) => {
+                self.expr(&handle.expr);
+                for handler in handle.handler.iter() {
+                    self.expr(&handler.body);
+                }
+            }
+            // modules don't see the scope they're in
+            Expr::Module(_) => (),
+        }
+    }
+    fn arg<T>(&mut self, arg: &Arg<T>) {
+        match arg {
+            Arg::Unit => (),
+            Arg::Splat(expr) => self.expr(expr),
+            Arg::Record(record) => self.record(record),
+            Arg::Tuple(tuple) => self.tuple(tuple),
+        }
+    }
+}
+// A top-level statement along with what identifies it across edits, usually
+// its source text
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub key: Rc<str>,
+    pub statement: Rc<Statement<()>>,
+}
+// where a name used by a member of a group comes from
+#[derive(Debug, PartialEq, Clone)]
+enum Import {
+    // a closed scheme exported by a statement outside of the group
+    Closed(SchemeMut),
+    // an export of a previous member, given by its position in the group
+    Member(usize),
+}
+#[derive(Debug, Clone)]
+struct Export {
+    scheme: SchemeMut,
+    closed: bool,
+}
+type Output = Result<Vec<(Statement<Type>, Vec<(Var, Export)>)>, (usize, TypeError)>;
+struct Memo {
+    imports: Vec<Vec<(Var, Import)>>,
+    output: Rc<Output>,
+}
+type Memos = HashMap<Box<[Rc<str>]>, Vec<Memo>>;
+// Infers a group of statements sharing a single substitution as though they
+// were the only statements, everything else they use is imported
+fn infer_group(items: &[&Item], imports: &[Vec<(Var, Import)>]) -> Output {
+    let mut subs = Subs::new();
+    let mut var_state = VarState::new();
+    let mut typed = Vec::with_capacity(items.len());
+    let mut exports: Vec<Vec<(Var, SchemeMut)>> = Vec::with_capacity(items.len());
+    for (position, (item, imports)) in items.iter().zip(imports).enumerate() {
+        let mut env = Env::new();
+        for (var, import) in imports {
+            let scheme = match import {
+                Import::Closed(scheme) => scheme.clone(),
+                Import::Member(member) => exports[*member]
+                    .iter()
+                    .find(|(export, _)| export == var)
+                    .map(|(_, scheme)| scheme.clone())
+                    .unwrap(),
+            };
+            env.insert(var.clone(), scheme);
+        }
+        let mut env = env.scoped();
+        let statement = Statement::clone(&item.statement);
+        let statement = infer_statement(&mut subs, &mut env, &mut var_state, statement)
+            .map_err(|err| (position, err))?;
+        typed.push(statement);
+        exports.push(
+            env.scope()
+                .map(|(var, scheme)| (var.clone(), scheme.clone()))
+                .collect(),
+        );
+    }
+    // blamed on the last statement, any of them may have left it
+    var_state
+        .default_constraints(&mut subs)
+        .map_err(|err| (items.len().saturating_sub(1), err))?;
+    typed
+        .into_iter()
+        .zip(exports)
+        .enumerate()
+        .map(|(position, (mut statement, exports))| {
+            statement.substitute(&subs).map_err(|err| (position, err))?;
+            let exports = exports
+                .into_iter()
+                .map(|(var, mut scheme)| {
+                    scheme.scheme.substitute(&subs)?;
+                    let closed = scheme.scheme.is_closed();
+                    if closed {
+                        scheme.scheme = scheme.scheme.canonical();
+                    }
+                    Ok((var, Export { scheme, closed }))
+                })
+                .collect::<Result<_, _>>()
+                .map_err(|err| (position, err))?;
+            Ok((statement, exports))
+        })
+        .collect()
+}
+// Type checks top-level statements while reusing what's unaffected from the
+// previous check.
+//
+// Statements are inferred in groups. A statement joins the group of every
+// statement it uses a not yet fully inferred binding from, as they need to
+// share the substitution. Otherwise it only needs the closed schemes of what
+// it uses. The inference of a group is memoized on the keys of its members and
+// everything they import, so editing a function body only re-checks the
+// function, and the statements using it if its type changed.
+#[derive(Default)]
+pub struct Incremental {
+    memos: Memos,
+    inferred: usize,
+}
+impl Incremental {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // number of statements inferred in the last check, the rest were reused
+    pub fn inferred(&self) -> usize {
+        self.inferred
+    }
+    // same as `infer` but the error comes with the index of the statement
+    // that caused it
+    pub fn infer(&mut self, items: &[Item]) -> Result<Vec<Statement<Type>>, (usize, TypeError)> {
+        let mut old = take(&mut self.memos);
+        self.inferred = 0;
+        let result = self.infer_items(&mut old, items);
+        // memos from before are kept while the source is broken, it's
+        // likely to go back to how it was
+        if result.is_err() {
+            for (keys, memos) in old {
+                self.memos.entry(keys).or_default().extend(memos);
+            }
+        }
+        result
+    }
+    fn infer_items(
+        &mut self,
+        old: &mut Memos,
+        items: &[Item],
+    ) -> Result<Vec<Statement<Type>>, (usize, TypeError)> {
+        // the last statement binding each name so far
+        let mut binders: HashMap<Var, usize> = HashMap::new();
+        let mut dependencies: Vec<Vec<(Var, usize)>> = Vec::with_capacity(items.len());
+        let mut exports: Vec<Vec<(Var, Export)>> = Vec::with_capacity(items.len());
+        let mut typed: Vec<Option<Statement<Type>>> = Vec::with_capacity(items.len());
+        let mut group_of: Vec<usize> = Vec::with_capacity(items.len());
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            let mut names = Names(BTreeSet::new());
+            names.statements(slice::from_ref(&*item.statement));
+            let dependency: Vec<_> = names
+                .0
+                .into_iter()
+                .filter_map(|name| {
+                    let var = Var::new_bare(name);
+                    let binder = *binders.get(&var)?;
+                    Some((var, binder))
+                })
+                .collect();
+            let mut members: Vec<_> = dependency
+                .iter()
+                .filter(|(var, binder)| {
+                    exports[*binder]
+                        .iter()
+                        .any(|(export, export_ty)| export == var && !export_ty.closed)
+                })
+                .flat_map(|(_, binder)| groups[group_of[*binder]].iter().copied())
+                .chain([i])
+                .collect();
+            members.sort_unstable();
+            members.dedup();
+            dependencies.push(dependency);
+            exports.push(Vec::new());
+            typed.push(None);
+            group_of.push(groups.len());
+            for member in &members {
+                group_of[*member] = groups.len();
+            }
+            let imports: Vec<Vec<_>> = members
+                .iter()
+                .map(|member| {
+                    dependencies[*member]
+                        .iter()
+                        .map(|(var, binder)| {
+                            let import = match members.binary_search(binder) {
+                                Ok(position) => Import::Member(position),
+                                Err(_) => {
+                                    let (_, export) = exports[*binder]
+                                        .iter()
+                                        .find(|(export, _)| export == var)
+                                        .unwrap();
+                                    Import::Closed(export.scheme.clone())
+                                }
+                            };
+                            (var.clone(), import)
+                        })
+                        .collect()
+                })
+                .collect();
+            let keys: Box<[_]> = members
+                .iter()
+                .map(|member| items[*member].key.clone())
+                .collect();
+            let output = self.memoized(old, keys, imports, |imports| {
+                let items: Vec<_> = members.iter().map(|member| &items[*member]).collect();
+                infer_group(&items, imports)
+            });
+            let output = match &*output {
+                Ok(output) => output,
+                Err((position, err)) => return Err((members[*position], err.clone())),
+            };
+            for (member, (statement, export)) in members.iter().zip(output) {
+                typed[*member] = Some(statement.clone());
+                exports[*member] = export.clone();
+            }
+            for (var, _) in &exports[i] {
+                binders.insert(var.clone(), i);
+            }
+            groups.push(members);
+        }
+        let typed: Vec<_> = typed.into_iter().map(Option::unwrap).collect();
+        // the statements are already substituted
+        let subs = Subs::new();
+        let mut use_checker = UseChecker::new(&subs);
+        for (i, statement) in typed.iter().enumerate() {
+            use_checker
+                .statements(slice::from_ref(statement))
+                .map_err(|err| (i, err))?;
+        }
+        Ok(typed)
+    }
+    fn memoized(
+        &mut self,
+        old: &mut Memos,
+        keys: Box<[Rc<str>]>,
+        imports: Vec<Vec<(Var, Import)>>,
+        infer: impl FnOnce(&[Vec<(Var, Import)>]) -> Output,
+    ) -> Rc<Output> {
+        let find = |memos: &mut Memos| {
+            let memos = memos.get_mut(&keys)?;
+            let index = memos.iter().position(|memo| memo.imports == imports)?;
+            Some(memos.swap_remove(index))
+        };
+        let memo = match find(&mut self.memos).or_else(|| find(old)) {
+            Some(memo) => memo,
+            None => {
+                self.inferred += keys.len();
+                let output = Rc::new(infer(&imports));
+                Memo { imports, output }
+            }
+        };
+        let output = memo.output.clone();
+        self.memos.entry(keys).or_default().push(memo);
+        output
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::{Incremental, Item};
+    use crate::TypeError;
+    use parser::{ast, EasyParser};
+    use std::rc::Rc;
+
+    fn items(src: &[&str]) -> Vec<Item> {
+        src.iter()
+            .map(|src| {
+                let (mut statements, _) = ast().easy_parse(*src).unwrap();
+                Item {
+                    key: Rc::from(*src),
+                    statement: Rc::new(statements.pop().unwrap()),
+                }
+            })
+            .collect()
+    }
+    fn check(
+        incremental: &mut Incremental,
+        src: &[&str],
+    ) -> Result<Vec<String>, (usize, TypeError)> {
+        let src = items(src);
+        let statements: Vec<_> = src.iter().map(|item| (*item.statement).clone()).collect();
+        let typed = incremental.infer(&src);
+        assert_eq!(
+            typed.as_ref().map(|_| ()).map_err(|(_, err)| err.clone()),
+            crate::infer(statements).map(|_| ()),
+        );
+        typed.map(|typed| {
+            typed
+                .iter()
+                .map(|statement| format!("{:?}", statement))
+                .collect()
+        })
+    }
+    #[test]
+    fn function_body() {
+        let mut incremental = Incremental::new();
+        let program = |body| {
+            [
+                "foo = 10;",
+                body,
+                "bar = id(foo);",
+                "baz(x) => x + 1;",
+                "qux = baz(2);",
+            ]
+        };
+        check(&mut incremental, &program("id(x) => x;")).unwrap();
+        assert_eq!(incremental.inferred(), 5);
+        check(&mut incremental, &program("id(x) => x;")).unwrap();
+        assert_eq!(incremental.inferred(), 0);
+        // the type stays the same so nothing else is re-checked
+        check(&mut incremental, &program("id(x) => { y = x; y };")).unwrap();
+        assert_eq!(incremental.inferred(), 1);
+        // only the use of `id` is affected
+        check(&mut incremental, &program("id(x) => x + 0;")).unwrap();
+        assert_eq!(incremental.inferred(), 2);
+    }
+    #[test]
+    fn shared_substitution() {
+        let mut incremental = Incremental::new();
+        let src = [
+            "mut arr = [];",
+            "len(x) => 1;",
+            "arr <- [true];",
+            "foo = arr;",
+        ];
+        let typed = check(&mut incremental, &src).unwrap();
+        assert_eq!(incremental.inferred(), 5);
+        assert!(typed[0].contains("Bool"));
+        let src = [
+            "mut arr = [];",
+            "len(x) => 2;",
+            "arr <- [true];",
+            "foo = arr;",
+        ];
+        check(&mut incremental, &src).unwrap();
+        assert_eq!(incremental.inferred(), 1);
+        let src = ["mut arr = [];", "len(x) => 2;", "arr <- [1];", "foo = arr;"];
+        let typed = check(&mut incremental, &src).unwrap();
+        assert!(typed[0].contains("Num"));
+    }
+    #[test]
+    fn error() {
+        let mut incremental = Incremental::new();
+        let src = ["foo = 1;", "bar = foo + true;", "baz = 2;"];
+        assert_eq!(
+            check(&mut incremental, &src),
+            Err((1, TypeError::MismatchCons)),
+        );
+        let src = ["foo = 1;", "bar = foo + 1;", "baz = 2;"];
+        check(&mut incremental, &src).unwrap();
+        assert_eq!(incremental.inferred(), 2);
+        let src = ["mut foo = [1];", "bar = foo;", "baz = foo;"];
+        assert_eq!(
+            check(&mut incremental, &src),
+            Err((2, TypeError::UsedTwice))
+        );
+    }
+}
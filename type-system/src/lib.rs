@@ -2,14 +2,22 @@
 #![deny(clippy::correctness)]
 #![forbid(unsafe_code)]
 
-use expr::Inferable;
+use clone::UseChecker;
+use expr::{infer_statement, Inferable};
 use hir::{expr::Expr, statement::Statement};
 use ty::{Env, Subs, Substitutable, VarState};
 
+mod clone;
+mod diverge;
+mod exhaustive;
 mod expr;
+mod incremental;
 mod pattern;
+mod prelude;
 mod ty;
+mod typed;
 
+pub use crate::incremental::{Incremental, Item};
 pub use crate::ty::{
     cons::{Cons, Keyed},
     MutType, Type, TypeError, Var,
@@ -27,12 +35,113 @@ impl<T> Typed<T> {
     }
 }
 pub fn infer(statements: Vec<Statement<()>>) -> Result<Vec<Statement<Type>>, TypeError> {
-    todo!()
+    let mut subs = Subs::new();
+    let mut env = Env::new();
+    let mut var_state = VarState::new();
+    let mut typed = statements
+        .into_iter()
+        .map(|statement| infer_statement(&mut subs, &mut env, &mut var_state, statement))
+        .collect::<Result<Vec<_>, _>>()?;
+    var_state.default_constraints(&mut subs)?;
+    UseChecker::new(&subs).statements(&typed)?;
+    typed.substitute(&subs)?;
+    Ok(typed)
 }
 pub fn test_infer(expr: Expr<()>) -> Result<Type, TypeError> {
     let mut subs = Subs::new();
-    let typed_expr = expr.infer(&mut subs, &mut VarState::new(), &Env::new())?;
+    let mut var_state = VarState::new();
+    let typed_expr = expr.infer(&mut subs, &mut var_state, &Env::new())?;
+    var_state.default_constraints(&mut subs)?;
+    UseChecker::new(&subs).expr(&typed_expr.value)?;
     let mut ty = typed_expr.ty;
     ty.substitute(&subs)?;
     Ok(ty)
 }
+#[cfg(test)]
+mod test {
+    use crate::{test_infer, TypeError};
+    use hir::{pattern::Pattern, statement::Statement};
+    use parser::{ast, expr_parser, EasyParser};
+
+    pub(super) fn infer(src: &str) -> Result<(), TypeError> {
+        infer_ty(src).map(|_| ())
+    }
+    pub(super) fn infer_ty(src: &str) -> Result<String, TypeError> {
+        let (expr, _) = expr_parser().easy_parse(src).unwrap();
+        test_infer(expr).map(|ty| ty.to_string())
+    }
+    // every map and set is freshly seeded when hashed, so repeating the
+    // inference catches any output that depends on iteration order
+    fn debug_snapshot(src: &str) -> String {
+        let (expr, _) = expr_parser().easy_parse(src).unwrap();
+        format!("{:?}", test_infer(expr))
+    }
+    fn statements_snapshot(src: &str) -> String {
+        let (statements, _) = ast().easy_parse(src).unwrap();
+        format!("{:?}", crate::infer(statements))
+    }
+    const SOURCES: [&str; 5] = [
+        "(foo = 1, bar = true, baz = [2], qux = ())",
+        "(rec) => (foo = rec.foo, bar = rec.bar, *rec.baz)",
+        "(rec) => [@foo rec.foo, @bar rec.bar, @baz rec.baz]",
+        "[@a 1, @b true, @c (), @d [1]]",
+        "(foo = 1, bar = true, baz = 2).qux",
+    ];
+    const STATEMENTS: [&str; 2] = [
+        "(foo = a, bar = b, *rest) = (foo = 1, bar = true, baz = ());",
+        "get(rec) => { (foo = a, bar = b) = rec; [@foo a, @bar b] };",
+    ];
+    // The snapshot is compared with the checked-in output so it's also the
+    // same across runs, set `BLESS` to rewrite it after an intended change
+    fn snapshot() -> String {
+        let mut snapshot = String::new();
+        for src in SOURCES {
+            let pretty = infer_ty(src);
+            snapshot += &format!("{}\n{:?}\n{}\n\n", src, pretty, debug_snapshot(src));
+        }
+        for src in STATEMENTS {
+            snapshot += &format!("{}\n{}\n\n", src, statements_snapshot(src));
+        }
+        snapshot
+    }
+    #[test]
+    fn deterministic() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/deterministic.txt");
+        let first = snapshot();
+        if std::env::var_os("BLESS").is_some() {
+            std::fs::write(path, &first).unwrap();
+        }
+        for _ in 0..16 {
+            assert_eq!(snapshot(), first);
+        }
+        assert_eq!(first, std::fs::read_to_string(path).unwrap());
+    }
+    #[test]
+    fn statements() {
+        let (statements, _) = ast().easy_parse("id(x) => x; foo = id(10);").unwrap();
+        let typed: Vec<_> = crate::infer(statements)
+            .unwrap()
+            .into_iter()
+            .map(|statement| match statement {
+                Statement::Declare(declare) => match declare.pattern {
+                    Pattern::Var(var) => var.ty.to_string(),
+                    _ => unreachable!(),
+                },
+                Statement::FunDeclare(fun) => fun.ty.to_string(),
+                Statement::NewType(_) | Statement::Expr(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(typed, ["(x: a) -> a", "Num"]);
+    }
+    #[test]
+    fn field_order() {
+        assert_eq!(
+            infer_ty("(zeta = 1, alpha = true, mu = ())"),
+            Ok("{alpha: Bool, mu: (), zeta: Num}".to_string()),
+        );
+        assert_eq!(
+            infer_ty("[@zeta 1, @alpha true]"),
+            Ok("[@alpha Bool | @zeta Num | *_]".to_string()),
+        );
+    }
+}
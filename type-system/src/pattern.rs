@@ -1,10 +1,14 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet};
 
-use hir::pattern::{self, Pattern};
+use hir::pattern::{
+    self, AsPattern, ListPattern, ListWithRest, NewTypePattern, Pattern, PatternBound,
+    RangePattern, RecordPattern, TaggedPattern,
+};
 
 use crate::{
-    ty::{Env, Scheme, SchemeMut, VarState},
-    Cons, MutType, Type, TypeError, Typed, Var,
+    expr::{unit, Inferable},
+    ty::{cons::OrderedAnd, Env, Scheme, SchemeMut, Subs, Unifiable, VarState},
+    Cons, Keyed, MutType, Type, TypeError, Typed, Var,
 };
 
 pub(super) trait InferablePattern {
@@ -12,6 +16,7 @@ pub(super) trait InferablePattern {
 
     fn infer(
         self,
+        subs: &mut Subs,
         var_state: &mut VarState,
         env: &mut Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError>;
@@ -21,6 +26,7 @@ impl InferablePattern for pattern::Var<()> {
 
     fn infer(
         self,
+        _: &mut Subs,
         var_state: &mut VarState,
         env: &mut Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
@@ -34,9 +40,12 @@ impl InferablePattern for pattern::Var<()> {
             SchemeMut {
                 is_mut: self.mutable,
                 scheme: Scheme {
-                    for_all: HashSet::new(),
+                    for_all: BTreeSet::new(),
                     ty: ty.clone(),
+                    constraints: Vec::new(),
                 },
+                module: None,
+                nominal: None,
             },
         );
         Ok(Typed {
@@ -55,22 +64,401 @@ impl InferablePattern for Pattern<()> {
 
     fn infer(
         self,
+        subs: &mut Subs,
         var_state: &mut VarState,
         env: &mut Env,
     ) -> Result<Typed<Self::TypedSelf>, TypeError> {
         let typed = match self {
-            Pattern::Var(var) => var.infer(var_state, env)?.map(Pattern::Var),
-            Pattern::True => todo!(),
-            Pattern::False => todo!(),
-            Pattern::UInt(_) => todo!(),
-            Pattern::Int(_) => todo!(),
-            Pattern::Ignore => todo!(),
-            Pattern::Record(_) => todo!(),
-            Pattern::Tuple(_) => todo!(),
-            Pattern::Array(_) => todo!(),
-            Pattern::Tag(_) => todo!(),
-            Pattern::Ref(_) => todo!(),
+            Pattern::Var(var) => var.infer(subs, var_state, env)?.map(Pattern::Var),
+            Pattern::True => Typed {
+                ty: Type::Cons(Cons::Bool),
+                value: Pattern::True,
+            },
+            Pattern::False => Typed {
+                ty: Type::Cons(Cons::Bool),
+                value: Pattern::False,
+            },
+            Pattern::UInt(uint) => Typed {
+                ty: Type::Cons(Cons::Num),
+                value: Pattern::UInt(uint),
+            },
+            Pattern::Int(int) => Typed {
+                ty: Type::Cons(Cons::Num),
+                value: Pattern::Int(int),
+            },
+            Pattern::Ignore => Typed {
+                ty: Type::Var(var_state.new_var()),
+                value: Pattern::Ignore,
+            },
+            Pattern::Record(record) => record.infer(subs, var_state, env)?.map(Pattern::Record),
+            Pattern::Tuple(list) => infer_tuple(list, subs, var_state, env)?.map(Pattern::Tuple),
+            Pattern::Array(list) => infer_array(list, subs, var_state, env)?.map(Pattern::Array),
+            Pattern::Tag(tag) => tag.infer(subs, var_state, env)?.map(Pattern::Tag),
+            Pattern::Ref(pattern) => {
+                let typed = pattern.infer(subs, var_state, env)?;
+                Typed {
+                    ty: Type::Cons(Cons::Ref(
+                        MutType::Var(var_state.new_var()),
+                        Box::new(typed.ty),
+                    )),
+                    value: Pattern::Ref(Box::new(typed.value)),
+                }
+            }
+            Pattern::NewType(newtype) => newtype.infer(subs, var_state, env)?.map(Pattern::NewType),
+            Pattern::Or(patterns) => infer_or(patterns, subs, var_state, env)?.map(Pattern::Or),
+            Pattern::Range(range) => range.infer(subs, var_state, env)?.map(Pattern::Range),
+            Pattern::As(pattern) => pattern.infer(subs, var_state, env)?.map(Pattern::As),
+            // compared the same way as `==`
+            Pattern::Equal(expr) => {
+                let typed = expr.infer(subs, var_state, env)?;
+                let ty = Type::Cons(Cons::Num);
+                typed.ty.unify_with(ty.clone(), subs, var_state)?;
+                Typed {
+                    ty,
+                    value: Pattern::Equal(Box::new(typed.value)),
+                }
+            }
         };
         Ok(typed)
     }
 }
+fn infer_list(
+    patterns: Box<[Pattern<()>]>,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+    env: &mut Env,
+) -> Result<(Vec<Type>, Vec<Pattern<Type>>), TypeError> {
+    let patterns: Vec<_> = patterns.into();
+    let mut types = Vec::with_capacity(patterns.len());
+    let mut typed = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+        let pattern = pattern.infer(subs, var_state, env)?;
+        types.push(pattern.ty);
+        typed.push(pattern.value);
+    }
+    Ok((types, typed))
+}
+fn infer_tuple(
+    list: ListPattern<()>,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+    env: &mut Env,
+) -> Result<Typed<ListPattern<Type>>, TypeError> {
+    let typed = match list {
+        ListPattern::List(list) => {
+            let (types, list) = infer_list(list, subs, var_state, env)?;
+            Typed {
+                ty: Type::Cons(Cons::Tuple(OrderedAnd::NonRow(types.into()))),
+                value: ListPattern::List(list.into()),
+            }
+        }
+        ListPattern::ListWithRest(list) => {
+            let (left_types, left) = infer_list(list.left, subs, var_state, env)?;
+            let rest = list.rest.infer(subs, var_state, env)?;
+            let (right_types, right) = infer_list(list.right, subs, var_state, env)?;
+            let var = var_state.new_var();
+            rest.ty
+                .unify_with(Type::Var(var.clone()), subs, var_state)?;
+            Typed {
+                ty: Type::Cons(Cons::Tuple(OrderedAnd::Row(left_types, var, right_types))),
+                value: ListPattern::ListWithRest(ListWithRest {
+                    left: left.into(),
+                    rest: Box::new(rest.value),
+                    right: right.into(),
+                }),
+            }
+        }
+    };
+    Ok(typed)
+}
+fn infer_array(
+    list: ListPattern<()>,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+    env: &mut Env,
+) -> Result<Typed<ListPattern<Type>>, TypeError> {
+    let elem = Type::Var(var_state.new_var());
+    let array = Type::Cons(Cons::Array(Box::new(elem.clone())));
+    let (types, value) = match list {
+        ListPattern::List(list) => {
+            let (types, list) = infer_list(list, subs, var_state, env)?;
+            (types, ListPattern::List(list.into()))
+        }
+        ListPattern::ListWithRest(list) => {
+            let (mut types, left) = infer_list(list.left, subs, var_state, env)?;
+            let rest = list.rest.infer(subs, var_state, env)?;
+            let (right_types, right) = infer_list(list.right, subs, var_state, env)?;
+            // the rest is an array of the same elements
+            rest.ty.unify_with(array.clone(), subs, var_state)?;
+            types.extend(right_types);
+            let value = ListPattern::ListWithRest(ListWithRest {
+                left: left.into(),
+                rest: Box::new(rest.value),
+                right: right.into(),
+            });
+            (types, value)
+        }
+    };
+    for ty in types {
+        ty.unify_with(elem.clone(), subs, var_state)?;
+    }
+    Ok(Typed { ty: array, value })
+}
+// every alternative must bind the same variables with the same types and
+// mutability, the bindings of the first one are the ones kept
+fn infer_or(
+    patterns: Box<[Pattern<()>]>,
+    subs: &mut Subs,
+    var_state: &mut VarState,
+    env: &mut Env,
+) -> Result<Typed<Box<[Pattern<Type>]>>, TypeError> {
+    let ty = Type::Var(var_state.new_var());
+    let mut bindings: Option<BTreeMap<Var, SchemeMut>> = None;
+    let mut typed = Vec::with_capacity(patterns.len());
+    for pattern in Vec::from(patterns) {
+        let mut scoped = env.scoped();
+        let pattern = pattern.infer(subs, var_state, &mut scoped)?;
+        pattern.ty.unify_with(ty.clone(), subs, var_state)?;
+        typed.push(pattern.value);
+        let scope: BTreeMap<_, _> = scoped
+            .scope()
+            .map(|(var, scheme)| (var.clone(), scheme.clone()))
+            .collect();
+        let Some(first) = &bindings else {
+            bindings = Some(scope);
+            continue;
+        };
+        for var in first.keys().chain(scope.keys()) {
+            let inconsistent = || TypeError::InconsistentBinding(var.name.clone());
+            let (first, other) = match (first.get(var), scope.get(var)) {
+                (Some(first), Some(other)) => (first, other),
+                _ => return Err(inconsistent()),
+            };
+            if first.is_mut != other.is_mut {
+                return Err(inconsistent());
+            }
+            let (first, other) = (first.scheme.ty.clone(), other.scheme.ty.clone());
+            first.unify_with(other, subs, var_state)?;
+        }
+    }
+    env.extend(bindings.unwrap_or_default());
+    Ok(Typed {
+        ty,
+        value: typed.into(),
+    })
+}
+impl InferablePattern for RecordPattern<()> {
+    type TypedSelf = RecordPattern<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &mut Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let mut types = BTreeMap::new();
+        let mut fields = BTreeMap::new();
+        for (name, pattern) in self.fields {
+            let typed = pattern.infer(subs, var_state, env)?;
+            types.insert(name.clone(), typed.ty);
+            fields.insert(name, typed.value);
+        }
+        let (rest_var, rest) = match self.rest {
+            Some(rest) => {
+                let typed = rest.infer(subs, var_state, env)?;
+                let var = var_state.new_var();
+                typed
+                    .ty
+                    .unify_with(Type::Var(var.clone()), subs, var_state)?;
+                (Some(var), Some(Box::new(typed.value)))
+            }
+            None => (None, None),
+        };
+        Ok(Typed {
+            ty: Type::Cons(Cons::Record(Keyed {
+                fields: types,
+                rest: rest_var,
+            })),
+            value: RecordPattern { fields, rest },
+        })
+    }
+}
+impl InferablePattern for TaggedPattern<()> {
+    type TypedSelf = TaggedPattern<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &mut Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let (pattern, ty) = match self.pattern {
+            Some(pattern) => {
+                let typed = pattern.infer(subs, var_state, env)?;
+                (Some(Box::new(typed.value)), typed.ty)
+            }
+            None => (None, unit()),
+        };
+        Ok(Typed {
+            ty: Type::Cons(Cons::Union(Keyed {
+                fields: [(self.tag.clone(), ty)].into_iter().collect(),
+                rest: Some(var_state.new_var()),
+            })),
+            value: TaggedPattern {
+                tag: self.tag,
+                pattern,
+            },
+        })
+    }
+}
+impl InferablePattern for RangePattern<()> {
+    type TypedSelf = RangePattern<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &mut Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let mut bound = |bound: Option<PatternBound<()>>| {
+            bound
+                .map(|bound| {
+                    let typed = bound.pattern.infer(subs, var_state, env)?;
+                    typed
+                        .ty
+                        .unify_with(Type::Cons(Cons::Num), subs, var_state)?;
+                    Ok(PatternBound {
+                        kind: bound.kind,
+                        pattern: Box::new(typed.value),
+                    })
+                })
+                .transpose()
+        };
+        let left = bound(self.left)?;
+        let right = bound(self.right)?;
+        Ok(Typed {
+            ty: Type::Cons(Cons::Num),
+            value: RangePattern { left, right },
+        })
+    }
+}
+impl InferablePattern for AsPattern<()> {
+    type TypedSelf = AsPattern<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &mut Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let pattern = self.pattern.infer(subs, var_state, env)?;
+        let var = self.var.infer(subs, var_state, env)?;
+        var.ty.unify_with(pattern.ty.clone(), subs, var_state)?;
+        Ok(Typed {
+            ty: pattern.ty,
+            value: AsPattern {
+                pattern: Box::new(pattern.value),
+                var: var.value,
+            },
+        })
+    }
+}
+impl InferablePattern for NewTypePattern<()> {
+    type TypedSelf = NewTypePattern<Type>;
+
+    fn infer(
+        self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+        env: &mut Env,
+    ) -> Result<Typed<Self::TypedSelf>, TypeError> {
+        let nominal = env
+            .get_nominal(Var::new_bare(self.name.clone()))
+            .ok_or_else(|| TypeError::UnknownType(self.name.clone()))?;
+        let args: Box<[_]> = (0..nominal.param_count())
+            .map(|_| Type::Var(var_state.new_var()))
+            .collect();
+        // `*_` allows fields to be left out
+        let rest = match self.fields.rest.as_deref() {
+            None => None,
+            Some(Pattern::Ignore) => Some(Box::new(Pattern::Ignore)),
+            Some(_) => return Err(TypeError::MismatchCons),
+        };
+        if rest.is_none() && nominal.visibilities().count() != self.fields.fields.len() {
+            return Err(TypeError::MismatchName);
+        }
+        let mut fields = BTreeMap::new();
+        for (name, pattern) in self.fields.fields {
+            let (visibility, ty) = nominal.field(&args, &name).ok_or(TypeError::MismatchName)?;
+            if !nominal.is_visible_from(visibility, env.module()) {
+                return Err(TypeError::InvisibleField {
+                    ty: nominal.name.clone(),
+                    field: name,
+                });
+            }
+            let typed = pattern.infer(subs, var_state, env)?;
+            typed.ty.unify_with(ty, subs, var_state)?;
+            fields.insert(name, typed.value);
+        }
+        Ok(Typed {
+            ty: Type::Cons(Cons::Nominal(nominal, args)),
+            value: NewTypePattern {
+                name: self.name,
+                fields: RecordPattern { fields, rest },
+            },
+        })
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::{
+        test::{infer, infer_ty},
+        TypeError,
+    };
+
+    #[test]
+    fn destructure() {
+        let src = "{ (a = x, *rest) = (a = 1, b = true); [first, *others] = [x]; (rest, others) }";
+        assert_eq!(infer_ty(src), Ok("({b: Bool}, [Num])".to_string()));
+        assert_eq!(
+            infer_ty("(pair) => { (= first, *_) = pair; first }"),
+            Ok("(pair: {first: a, *_}) -> a".to_string()),
+        );
+        assert_eq!(
+            infer("{ [a, b] = [1, true]; a }"),
+            Err(TypeError::MismatchCons)
+        );
+    }
+    #[test]
+    fn or_as() {
+        let src =
+            "(x) => match x { @a num | @b (num = num, other = _) => num, 0..10 as num => num }";
+        assert_eq!(infer(src), Err(TypeError::MismatchCons));
+        let src = "(x) => match x { @a num | @b (= num, other = _) => num + 1, @c as all => 0 }";
+        assert_eq!(
+            infer_ty(src),
+            Ok("(x: @a Num | @b {num: Num, other: a} | @c) -> Num".to_string()),
+        );
+        let src = "(x) => match x { @a num | @b _ => num }";
+        assert_eq!(
+            infer(src),
+            Err(TypeError::InconsistentBinding(hir::Atom::from("num"))),
+        );
+        let src = "(x) => match x { @a num | @b (mut num) => num }";
+        assert_eq!(
+            infer(src),
+            Err(TypeError::InconsistentBinding(hir::Atom::from("num"))),
+        );
+        let src = "(x) => match x { @a num | @b num => num }";
+        assert_eq!(infer_ty(src), Ok("(x: @a a | @b a) -> a".to_string()));
+    }
+    #[test]
+    fn equal() {
+        let src = "(x, y) => match x { == y + 1 | 0 => true, _ => false }";
+        assert_eq!(infer_ty(src), Ok("(x: Num, y: Num) -> Bool".to_string()));
+        let src = "(x) => match x { == [1] => 0, _ => 1 }";
+        assert_eq!(infer(src), Err(TypeError::MismatchCons));
+        let src = "(x, y) => match x { == y => 0 }";
+        assert_eq!(infer(src), Err(TypeError::NonExhaustive));
+    }
+}
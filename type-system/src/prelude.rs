@@ -0,0 +1,328 @@
+use crate::{
+    expr::{no_effects, unit},
+    ty::{
+        cons::{Cons, Keyed, OrderedAnd},
+        MutType, SchemeMut, Type, Var, VarState,
+    },
+};
+use hir::Atom;
+use std::{collections::BTreeMap, rc::Rc};
+
+thread_local! {
+    static PRELUDE: Rc<BTreeMap<Var, SchemeMut>> = Rc::new(prelude());
+}
+// the scope every environment starts from, shared between all of them
+pub(super) fn scope() -> Rc<BTreeMap<Var, SchemeMut>> {
+    PRELUDE.with(Rc::clone)
+}
+fn fun(params: &[(&str, Type)], ret: Type) -> Type {
+    performing(params, ret, no_effects())
+}
+fn performing(params: &[(&str, Type)], ret: Type, effects: Type) -> Type {
+    let params = params
+        .iter()
+        .map(|(name, ty)| (Atom::from(*name), ty.clone()))
+        .collect();
+    Type::Cons(Cons::Fun(
+        Box::new(Type::Cons(Cons::RecordTuple(OrderedAnd::NonRow(params)))),
+        Box::new(ret),
+        Box::new(effects),
+    ))
+}
+fn num() -> Type {
+    Type::Cons(Cons::Num)
+}
+fn bool() -> Type {
+    Type::Cons(Cons::Bool)
+}
+fn array(ty: Type) -> Type {
+    Type::Cons(Cons::Array(Box::new(ty)))
+}
+fn str() -> Type {
+    array(num())
+}
+fn iter(ty: Type, effects: Type) -> Type {
+    Type::Cons(Cons::Iter(Box::new(ty), Box::new(effects)))
+}
+// functions passed to builtins, their parameters are positional so any
+// parameter names are accepted
+fn callback(params: Vec<Type>, ret: Type, effects: Type) -> Type {
+    let params = Type::Cons(Cons::Tuple(OrderedAnd::NonRow(params.into())));
+    Type::Cons(Cons::Fun(
+        Box::new(params),
+        Box::new(ret),
+        Box::new(effects),
+    ))
+}
+// references of either mutability are accepted wherever only reading is
+// needed
+fn reference(var_state: &mut VarState, ty: Type) -> Type {
+    let mutability = MutType::Var(var_state.new_named(Atom::from("m")));
+    Type::Cons(Cons::Ref(mutability, Box::new(ty)))
+}
+fn option(var_state: &mut VarState, ty: Type) -> Type {
+    Type::Cons(Cons::Union(Keyed {
+        fields: [(Atom::from("some"), ty), (Atom::from("none"), unit())]
+            .into_iter()
+            .collect(),
+        rest: Some(var_state.new_var()),
+    }))
+}
+fn record(fields: Vec<(&str, Type)>) -> Type {
+    let fields = fields
+        .into_iter()
+        .map(|(name, ty)| (Atom::from(name), ty))
+        .collect();
+    Type::Cons(Cons::Record(Keyed { fields, rest: None }))
+}
+// UTF-8 aware counterparts of the byte based string helpers, indices are
+// still counted in bytes
+fn str_module(var_state: &mut VarState) -> Type {
+    let mut text = || ("text", reference(var_state, str()));
+    let is_utf8 = fun(&[text()], bool());
+    let valid_up_to = fun(&[text()], num());
+    let chars = fun(&[text()], array(num()));
+    let byte_len = fun(&[text()], num());
+    let char_len = fun(&[text()], num());
+    let trim = fun(&[text()], str());
+    let index = ("index", num());
+    let is_char_boundary = fun(&[text(), index.clone()], bool());
+    let decoded = record(vec![("char", num()), ("width", num())]);
+    let decoded = option(var_state, decoded);
+    let decode_char = fun(&[("text", reference(var_state, str())), index], decoded);
+    let encoded = option(var_state, str());
+    let encode_char = fun(&[("char", num())], encoded);
+    let sliced = option(var_state, str());
+    let slice = fun(
+        &[
+            ("text", reference(var_state, str())),
+            ("start", num()),
+            ("end", num()),
+        ],
+        sliced,
+    );
+    let separator = ("separator", reference(var_state, str()));
+    let split = fun(
+        &[("text", reference(var_state, str())), separator],
+        array(str()),
+    );
+    let found = option(var_state, num());
+    let pattern = ("pattern", reference(var_state, str()));
+    let find = fun(&[("text", reference(var_state, str())), pattern], found);
+    record(vec![
+        ("is_utf8", is_utf8),
+        ("valid_up_to", valid_up_to),
+        ("chars", chars),
+        ("decode_char", decode_char),
+        ("encode_char", encode_char),
+        ("byte_len", byte_len),
+        ("char_len", char_len),
+        ("is_char_boundary", is_char_boundary),
+        ("slice", slice),
+        ("split", split),
+        ("trim", trim),
+        ("find", find),
+    ])
+}
+// adaptors consume the iterator they're given and return a new one, nothing
+// runs until it's iterated. The effects of the callbacks are those of the
+// iterator, they're performed wherever it's iterated. Paired values are
+// records as tuple patterns aren't supported
+fn iter_module(var_state: &mut VarState) -> Type {
+    let var =
+        |var_state: &mut VarState, name: &str| Type::Var(var_state.new_named(Atom::from(name)));
+    let (elem, effects) = (var(var_state, "a"), var(var_state, "e"));
+    let from = fun(&[("items", array(elem.clone()))], iter(elem, effects));
+    let (elem, state, effects) = (
+        var(var_state, "a"),
+        var(var_state, "s"),
+        var(var_state, "e"),
+    );
+    let step = record(vec![("value", elem.clone()), ("state", state.clone())]);
+    let stepped = option(var_state, step);
+    let next = callback(vec![state.clone()], stepped, effects.clone());
+    let unfold = fun(&[("state", state), ("next", next)], iter(elem, effects));
+    let (elem, mapped, effects) = (
+        var(var_state, "a"),
+        var(var_state, "b"),
+        var(var_state, "e"),
+    );
+    let f = callback(vec![elem.clone()], mapped.clone(), effects.clone());
+    let map = fun(
+        &[("iter", iter(elem, effects.clone())), ("f", f)],
+        iter(mapped, effects),
+    );
+    let (elem, effects) = (var(var_state, "a"), var(var_state, "e"));
+    let f = callback(
+        vec![reference(var_state, elem.clone())],
+        bool(),
+        effects.clone(),
+    );
+    let filter = fun(
+        &[("iter", iter(elem.clone(), effects.clone())), ("f", f)],
+        iter(elem, effects),
+    );
+    let (left, right, effects) = (
+        var(var_state, "a"),
+        var(var_state, "b"),
+        var(var_state, "e"),
+    );
+    let zip = fun(
+        &[
+            ("left", iter(left.clone(), effects.clone())),
+            ("right", iter(right.clone(), effects.clone())),
+        ],
+        iter(record(vec![("left", left), ("right", right)]), effects),
+    );
+    let (elem, effects) = (var(var_state, "a"), var(var_state, "e"));
+    let enumerate = fun(
+        &[("iter", iter(elem.clone(), effects.clone()))],
+        iter(record(vec![("index", num()), ("item", elem)]), effects),
+    );
+    let (elem, effects) = (var(var_state, "a"), var(var_state, "e"));
+    let collect = performing(
+        &[("iter", iter(elem.clone(), effects.clone()))],
+        array(elem),
+        effects,
+    );
+    record(vec![
+        ("from", from),
+        ("unfold", unfold),
+        ("map", map),
+        ("filter", filter),
+        ("zip", zip),
+        ("enumerate", enumerate),
+        ("collect", collect),
+    ])
+}
+fn prelude() -> BTreeMap<Var, SchemeMut> {
+    let mut var_state = VarState::new();
+    var_state.enter_level();
+    let var_state = &mut var_state;
+    let var = |var_state: &mut VarState| Type::Var(var_state.new_named(Atom::from("a")));
+    let mut builtins = vec![
+        ("print", fun(&[("text", str())], unit())),
+        ("print_line", fun(&[("text", str())], unit())),
+        ("get_line", fun(&[], str())),
+        ("assert", fun(&[("condition", bool())], unit())),
+        ("panic", fun(&[("message", str())], var(var_state))),
+        ("to_str", fun(&[("number", num())], str())),
+    ];
+    // `.len` is already taken by the length place expression
+    for (name, ret) in [("length", num()), ("is_empty", bool())] {
+        let elem = var(var_state);
+        let array = reference(var_state, array(elem));
+        builtins.push((name, fun(&[("array", array)], ret)));
+    }
+    for name in ["abs", "sqrt", "floor", "ceil", "round"] {
+        builtins.push((name, fun(&[("number", num())], num())));
+    }
+    for name in ["min", "max"] {
+        builtins.push((name, fun(&[("left", num()), ("right", num())], num())));
+    }
+    let text = reference(var_state, str());
+    let parsed = option(var_state, num());
+    builtins.push(("parse_num", fun(&[("text", text)], parsed)));
+    for name in ["to_upper", "to_lower", "trim"] {
+        let text = reference(var_state, str());
+        builtins.push((name, fun(&[("text", text)], str())));
+    }
+    for name in ["contains", "starts_with", "ends_with"] {
+        let text = reference(var_state, str());
+        let pattern = reference(var_state, str());
+        builtins.push((name, fun(&[("text", text), ("pattern", pattern)], bool())));
+    }
+    builtins.push(("str", str_module(var_state)));
+    builtins.push(("iter", iter_module(var_state)));
+    var_state.leave_level();
+    let ty = record(builtins);
+    [(
+        Var::new_bare(Atom::from("std")),
+        SchemeMut {
+            is_mut: false,
+            scheme: var_state.generalize(ty),
+            module: None,
+            nominal: None,
+        },
+    )]
+    .into_iter()
+    .collect()
+}
+#[cfg(test)]
+mod test {
+    use crate::{
+        test::{infer, infer_ty},
+        TypeError,
+    };
+
+    #[test]
+    fn builtins() {
+        let src = "{
+            std.print_line(\"hello\");
+            std.assert(std.length(&[1, 2]) == [1].len);
+            mut flags = [true];
+            root = std.sqrt(4);
+            (root, std.is_empty(&flags), std.contains(&\"abc\", &\"b\"))
+        }";
+        assert_eq!(infer_ty(src), Ok("(Num, Bool, Bool)".to_string()));
+        assert_eq!(
+            infer_ty("std.panic"),
+            Ok("(message: [Num]) -> a".to_string())
+        );
+        assert_eq!(infer("std.print_line(1)"), Err(TypeError::MismatchCons));
+        assert_eq!(infer("{ std = 10; std + 1 }"), Ok(()));
+    }
+    #[test]
+    fn str_module() {
+        let src = "{
+            text = \"a\\xFF\";
+            std.assert(std.str.is_utf8(&text) || std.str.valid_up_to(&text) == 1);
+            pieces = std.str.split(&\"a,b\", &\",\");
+            chars = std.str.chars(&pieces[0]);
+            (chars, std.str.decode_char(&text, 0), std.str.find(&text, &\"a\"))
+        }";
+        assert_eq!(
+            infer_ty(src),
+            Ok(
+                "([Num], @none | @some {char: Num, width: Num} | *_, @none | @some Num | *_)"
+                    .to_string()
+            ),
+        );
+        assert_eq!(
+            infer_ty("std.str.slice"),
+            Ok("(text: &:a [Num], start: Num, end: Num) -> (@none | @some [Num] | *_)".to_string()),
+        );
+    }
+    #[test]
+    fn iter_module() {
+        let src = "{
+            evens = std.iter.filter(std.iter.from([0.<10]), (x) => x^ % 2 == 0);
+            squares = std.iter.enumerate(std.iter.map(evens, (x) => x * x));
+            mut total = 0;
+            for (index = i, item = square) in squares { total <- total + i * square; }
+            next(n) => if n < 3 { @some (value = n, state = n + 1) } else { @none };
+            counter = std.iter.unfold(0, next);
+            std.iter.collect(std.iter.zip(counter, std.iter.from([\"a\"])))
+        }";
+        assert_eq!(infer_ty(src), Ok("[{left: Num, right: [Num]}]".to_string()));
+        assert_eq!(
+            infer_ty("std.iter.map"),
+            Ok("(iter: Iter(a), f: (a,) -> b) -> Iter(b)".to_string()),
+        );
+        assert_eq!(
+            infer("std.iter.map([1], (x) => x)"),
+            Err(TypeError::MismatchCons),
+        );
+    }
+    #[test]
+    fn in_module() {
+        assert_eq!(
+            infer_ty("{ mod m { pub abs = std.abs; } m.abs(-1) }"),
+            Ok("Num".to_string())
+        );
+        assert_eq!(
+            infer_ty("{ mod m { pub x = 1; } m }"),
+            Ok("{x: Num}".to_string()),
+        );
+    }
+}
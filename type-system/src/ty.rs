@@ -1,15 +1,23 @@
-use crate::ty::cons::Cons;
-use hir::{keyword, Atom};
+use crate::{
+    prelude,
+    ty::cons::{Cons, Keyed, Nominal},
+};
+pub(super) use constraint::Constraint;
+use hir::{keyword, statement::Visibility, Atom};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt::{self, Display, Formatter},
     hash::Hash,
     iter::once,
+    mem::take,
+    rc::Rc,
 };
 
 pub mod cons;
+mod constraint;
+mod pretty;
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
 pub struct Var {
     pub name: Atom,
     pub id: u32,
@@ -19,8 +27,17 @@ impl Var {
         Self { name, id: 0 }
     }
 }
+// Every variable remembers the level of the innermost declaration it's
+// created in, variables with deeper level than the current one are free to
+// be generalized
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
-pub(super) struct VarState(HashMap<Atom, u32>);
+pub(super) struct VarState {
+    names: HashMap<Atom, u32>,
+    levels: HashMap<Var, u32>,
+    level: u32,
+    // constraints whose container isn't known yet
+    constraints: Vec<Constraint>,
+}
 impl VarState {
     pub fn new() -> Self {
         Self::default()
@@ -29,25 +46,129 @@ impl VarState {
         self.new_named(keyword!(""))
     }
     pub fn new_named(&mut self, name: Atom) -> Var {
-        let Self(map) = self;
-        let state = map.entry(name.clone()).or_insert(1);
+        let state = self.names.entry(name.clone()).or_insert(1);
         let id = *state;
         *state += 1;
-        Var { name, id }
+        let var = Var { name, id };
+        self.levels.insert(var.clone(), self.level);
+        var
+    }
+    pub fn enter_level(&mut self) {
+        self.level += 1;
+    }
+    pub fn leave_level(&mut self) {
+        self.level -= 1;
+    }
+    fn level_of(&self, var: &Var) -> u32 {
+        self.levels.get(var).copied().unwrap_or(0)
+    }
+    fn lower_level(&mut self, var: &Var, level: u32) {
+        if self.level_of(var) > level {
+            self.levels.insert(var.clone(), level);
+        }
+    }
+    pub fn constrain(&mut self, constraint: Constraint, subs: &mut Subs) -> Result<(), TypeError> {
+        self.constraints.push(constraint);
+        self.solve(subs)
+    }
+    // resolves the constraints whose container became known, until none
+    // does, the rest are left substituted
+    pub fn solve(&mut self, subs: &mut Subs) -> Result<(), TypeError> {
+        loop {
+            let mut progress = false;
+            for mut constraint in take(&mut self.constraints) {
+                constraint.substitute(subs)?;
+                if let Type::Var(_) = constraint.container() {
+                    self.constraints.push(constraint);
+                } else {
+                    progress = true;
+                    let left = constraint.resolve(subs, self)?;
+                    self.constraints.extend(left);
+                }
+            }
+            if !progress {
+                return Ok(());
+            }
+        }
+    }
+    // containers that are still unknown once everything is inferred are
+    // arrays, types left that only need to be hashable can be anything
+    pub fn default_constraints(&mut self, subs: &mut Subs) -> Result<(), TypeError> {
+        self.solve(subs)?;
+        loop {
+            let Some(constraint) = self
+                .constraints
+                .iter()
+                .find(|constraint| !matches!(constraint, Constraint::Hashable { .. }))
+            else {
+                self.constraints.clear();
+                return Ok(());
+            };
+            let container = constraint.container().clone();
+            let elem = Type::Var(self.new_var());
+            container.unify_with(Type::Cons(Cons::Array(Box::new(elem))), subs, self)?;
+            self.solve(subs)?;
+        }
+    }
+    // the constraints on generalized containers the type refers to, directly
+    // or through other such constraints, go along with the scheme
+    pub fn generalize(&mut self, ty: Type) -> Scheme {
+        let mut free_vars = ty.free_vars();
+        let mut constraints = Vec::new();
+        loop {
+            let (reached, pending): (Vec<_>, Vec<_>) = take(&mut self.constraints)
+                .into_iter()
+                .partition(|constraint| {
+                    matches!(
+                        constraint.container(),
+                        Type::Var(var) if self.level_of(var) > self.level
+                            && free_vars.iter().any(|free| free.var == *var)
+                    )
+                });
+            self.constraints = pending;
+            if reached.is_empty() {
+                break;
+            }
+            for constraint in &reached {
+                free_vars.extend(constraint.free_vars());
+            }
+            constraints.extend(reached);
+        }
+        let for_all = free_vars
+            .into_iter()
+            .filter(|var| self.level_of(&var.var) > self.level)
+            .collect();
+        Scheme {
+            for_all,
+            ty,
+            constraints,
+        }
     }
 }
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
 enum Kind {
     Type,
     MutType,
 }
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
 pub(super) struct KindedVar {
     kind: Kind,
     var: Var,
 }
 trait FreeVars {
-    fn free_vars(&self) -> HashSet<KindedVar>;
+    fn free_vars(&self) -> BTreeSet<KindedVar>;
+}
+// replaces the variables all at once, what they're replaced with isn't
+// looked at again unlike with substitution
+trait Rename {
+    fn rename(&mut self, renames: &BTreeMap<Var, Var>);
+}
+impl Rename for Var {
+    fn rename(&mut self, renames: &BTreeMap<Var, Var>) {
+        if let Some(var) = renames.get(self) {
+            *self = var.clone();
+        }
+    }
 }
 pub(super) trait Substitutable {
     fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError>;
@@ -66,7 +187,7 @@ pub enum Type {
     Cons(Cons),
 }
 impl FreeVars for Type {
-    fn free_vars(&self) -> HashSet<KindedVar> {
+    fn free_vars(&self) -> BTreeSet<KindedVar> {
         match self {
             Self::Var(var) => once(KindedVar {
                 kind: Kind::Type,
@@ -77,13 +198,27 @@ impl FreeVars for Type {
         }
     }
 }
+impl Rename for Type {
+    fn rename(&mut self, renames: &BTreeMap<Var, Var>) {
+        match self {
+            Self::Var(var) => var.rename(renames),
+            Self::Cons(cons) => cons.rename(renames),
+        }
+    }
+}
 impl Substitutable for Type {
     fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
         match self {
             Self::Var(var) => {
                 if let Some(ty) = subs.get(var.clone()) {
                     match ty {
-                        Type1::Type(ty) => *self = ty,
+                        Type1::Type(ty) if ty == Self::Var(var.clone()) => (),
+                        // the substituted type may still contain substitutable
+                        // variables when the substitutions weren't composed
+                        Type1::Type(ty) => {
+                            *self = ty;
+                            self.substitute(subs)?;
+                        }
                         Type1::MutType(_) => return Err(TypeError::MismatchKind),
                     }
                 }
@@ -94,11 +229,17 @@ impl Substitutable for Type {
     }
 }
 impl FreeVars for (Atom, Type) {
-    fn free_vars(&self) -> HashSet<KindedVar> {
+    fn free_vars(&self) -> BTreeSet<KindedVar> {
         let (_, ty) = self;
         ty.free_vars()
     }
 }
+impl Rename for (Atom, Type) {
+    fn rename(&mut self, renames: &BTreeMap<Var, Var>) {
+        let (_, ty) = self;
+        ty.rename(renames);
+    }
+}
 impl Substitutable for (Atom, Type) {
     fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
         let (_, ty) = self;
@@ -112,18 +253,11 @@ impl Unifiable for Type {
         subs: &mut Subs,
         var_state: &mut VarState,
     ) -> Result<(), TypeError> {
-        match (self, other) {
+        match (subs.resolve(self)?, subs.resolve(other)?) {
             (Self::Cons(cons1), Self::Cons(cons2)) => cons1.unify_with(cons2, subs, var_state)?,
             (Self::Var(var), ty) | (ty, Self::Var(var)) => {
-                if ty == Self::Var(var.clone()) {
-                    // do nothing
-                } else if ty.free_vars().contains(&KindedVar {
-                    kind: Kind::Type,
-                    var: var.clone(),
-                }) {
-                    return Err(TypeError::InfiniteOccurrence);
-                } else {
-                    subs.insert(var, Type1::Type(ty))
+                if ty != Self::Var(var.clone()) {
+                    subs.bind(var, Type1::Type(ty), var_state)?;
                 }
             }
         }
@@ -153,14 +287,21 @@ pub enum MutType {
     Mut,
 }
 impl FreeVars for MutType {
-    fn free_vars(&self) -> HashSet<KindedVar> {
+    fn free_vars(&self) -> BTreeSet<KindedVar> {
         match self {
             Self::Var(var) => once(KindedVar {
                 kind: Kind::MutType,
                 var: var.clone(),
             })
             .collect(),
-            Self::Imm | Self::Mut => HashSet::new(),
+            Self::Imm | Self::Mut => BTreeSet::new(),
+        }
+    }
+}
+impl Rename for MutType {
+    fn rename(&mut self, renames: &BTreeMap<Var, Var>) {
+        if let Self::Var(var) = self {
+            var.rename(renames);
         }
     }
 }
@@ -169,7 +310,11 @@ impl Substitutable for MutType {
         if let Self::Var(var) = self {
             if let Some(ty) = subs.get(var.clone()) {
                 match ty {
-                    Type1::MutType(mutability) => *self = mutability,
+                    Type1::MutType(mutability) if mutability == Self::Var(var.clone()) => (),
+                    Type1::MutType(mutability) => {
+                        *self = mutability;
+                        self.substitute(subs)?;
+                    }
                     Type1::Type(_) => return Err(TypeError::MismatchKind),
                 }
             }
@@ -178,19 +323,17 @@ impl Substitutable for MutType {
     }
 }
 impl Unifiable for MutType {
-    fn unify_with(self, other: Self, subs: &mut Subs, _: &mut VarState) -> Result<(), TypeError> {
-        match (self, other) {
+    fn unify_with(
+        self,
+        other: Self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+    ) -> Result<(), TypeError> {
+        match (subs.resolve_mut(self)?, subs.resolve_mut(other)?) {
             (Self::Mut, Self::Mut) | (Self::Imm, Self::Imm) => (),
             (Self::Var(var), ty) | (ty, Self::Var(var)) => {
-                if ty == Self::Var(var.clone()) {
-                    // do nothing
-                } else if ty.free_vars().contains(&KindedVar {
-                    kind: Kind::MutType,
-                    var: var.clone(),
-                }) {
-                    return Err(TypeError::InfiniteOccurrence);
-                } else {
-                    subs.insert(var, Type1::MutType(ty))
+                if ty != Self::Var(var.clone()) {
+                    subs.bind(var, Type1::MutType(ty), var_state)?;
                 }
             }
             _ => return Err(TypeError::MismatchCons),
@@ -212,13 +355,28 @@ impl From<KindedVar> for Type1 {
     }
 }
 impl FreeVars for Type1 {
-    fn free_vars(&self) -> HashSet<KindedVar> {
+    fn free_vars(&self) -> BTreeSet<KindedVar> {
         match self {
             Self::Type(ty) => ty.free_vars(),
             Self::MutType(ty) => ty.free_vars(),
         }
     }
 }
+impl Type1 {
+    fn kinded(&self, var: Var) -> KindedVar {
+        let kind = match self {
+            Self::Type(_) => Kind::Type,
+            Self::MutType(_) => Kind::MutType,
+        };
+        KindedVar { kind, var }
+    }
+    fn var(&self) -> Option<&Var> {
+        match self {
+            Self::Type(Type::Var(var)) | Self::MutType(MutType::Var(var)) => Some(var),
+            _ => None,
+        }
+    }
+}
 impl Substitutable for Type1 {
     fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
         match self {
@@ -229,89 +387,147 @@ impl Substitutable for Type1 {
 }
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub(super) struct Scheme {
-    pub(super) for_all: HashSet<KindedVar>,
+    pub(super) for_all: BTreeSet<KindedVar>,
     pub(super) ty: Type,
-}
-impl FreeVars for Scheme {
-    fn free_vars(&self) -> HashSet<KindedVar> {
-        self.ty
-            .free_vars()
-            .into_iter()
-            .filter(|var| !self.for_all.contains(var))
-            .collect()
-    }
+    pub(super) constraints: Vec<Constraint>,
 }
 impl Substitutable for Scheme {
     fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
-        let mut subs = subs.clone();
-        subs.filter_off(&self.for_all);
-        self.ty.substitute(&subs)?;
+        self.ty.substitute(subs)?;
+        for constraint in self.constraints.iter_mut() {
+            constraint.substitute(subs)?;
+        }
         Ok(())
     }
 }
 impl Scheme {
-    pub fn instantiate(self, var_state: &mut VarState) -> Result<Type, TypeError> {
-        let subs = self
+    pub fn instantiate(self, var_state: &mut VarState) -> Type {
+        let renames: BTreeMap<_, _> = self
             .for_all
             .into_iter()
             .map(|var| {
                 let new_var = var_state.new_named(var.var.name.clone());
-                (
-                    var.var,
-                    match var.kind {
-                        Kind::Type => Type1::Type(Type::Var(new_var)),
-                        Kind::MutType => Type1::MutType(MutType::Var(new_var)),
-                    },
-                )
+                (var.var, new_var)
             })
             .collect();
+        for mut constraint in self.constraints {
+            constraint.rename(&renames);
+            var_state.constraints.push(constraint);
+        }
         let mut ty = self.ty;
-        ty.substitute(&subs)?;
-        Ok(ty)
-    }
-}
+        ty.rename(&renames);
+        ty
+    }
+    // a scheme without free variables can't be affected by any further
+    // unification
+    pub fn is_closed(&self) -> bool {
+        self.ty.free_vars().is_subset(&self.for_all)
+            && self
+                .constraints
+                .iter()
+                .all(|constraint| constraint.free_vars().is_subset(&self.for_all))
+    }
+    // renames the quantified variables afresh so schemes that only differ in
+    // variable numbering compare equal
+    pub fn canonical(self) -> Self {
+        let mut var_state = VarState::new();
+        var_state.enter_level();
+        let ty = self.instantiate(&mut var_state);
+        var_state.leave_level();
+        var_state.generalize(ty)
+    }
+}
+// Substitutions are kept triangular, a variable may be bound to a type that
+// still has bound variables. Chains of variables are shortened as they're
+// looked up during unification.
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
-pub(super) struct Subs(HashMap<Var, Type1>);
+pub(super) struct Subs(BTreeMap<Var, Type1>);
 impl Subs {
     pub fn new() -> Self {
         Self::default()
     }
-    fn hashmap(&self) -> &HashMap<Var, Type1> {
-        let Self(map) = self;
-        map
-    }
-    fn hashmap_mut(&mut self) -> &mut HashMap<Var, Type1> {
+    fn map(&self) -> &BTreeMap<Var, Type1> {
         let Self(map) = self;
         map
     }
-    fn into_hashmap(self) -> HashMap<Var, Type1> {
+    fn map_mut(&mut self) -> &mut BTreeMap<Var, Type1> {
         let Self(map) = self;
         map
     }
-    fn is_empty(&self) -> bool {
-        self.hashmap().is_empty()
-    }
     fn get(&self, var: Var) -> Option<Type1> {
-        self.hashmap().get(&var).map(Type1::clone)
-    }
-    fn insert(&mut self, var: Var, ty: Type1) {
-        self.hashmap_mut().insert(var, ty);
+        self.map().get(&var).cloned()
+    }
+    // like `get` but follows the chain of variables
+    fn lookup(&self, var: &Var) -> Option<Type1> {
+        let mut ty = self.map().get(var)?;
+        while let Some(next) = ty.var() {
+            match self.map().get(next) {
+                Some(next_ty) if next_ty.var() != Some(next) => ty = next_ty,
+                _ => break,
+            }
+        }
+        Some(ty.clone())
+    }
+    fn find(&mut self, var: &Var) -> Option<Type1> {
+        let mut path = vec![var.clone()];
+        let mut ty = self.map().get(var)?;
+        while let Some(next) = ty.var() {
+            match self.map().get(next) {
+                Some(next_ty) if next_ty.var() != Some(next) => {
+                    path.push(next.clone());
+                    ty = next_ty;
+                }
+                _ => break,
+            }
+        }
+        let ty = ty.clone();
+        let last = path.pop().unwrap();
+        let last = Type1::from(ty.kinded(last));
+        let map = self.map_mut();
+        for var in path {
+            map.insert(var, last.clone());
+        }
+        Some(ty)
+    }
+    fn resolve(&mut self, ty: Type) -> Result<Type, TypeError> {
+        match &ty {
+            Type::Var(var) => match self.find(var) {
+                Some(Type1::Type(ty)) => Ok(ty),
+                Some(Type1::MutType(_)) => Err(TypeError::MismatchKind),
+                None => Ok(ty),
+            },
+            Type::Cons(_) => Ok(ty),
+        }
     }
-    fn filter_off(&mut self, vars: &HashSet<KindedVar>) {
-        for var in vars {
-            self.hashmap_mut().remove(&var.var);
+    fn resolve_mut(&mut self, ty: MutType) -> Result<MutType, TypeError> {
+        match &ty {
+            MutType::Var(var) => match self.find(var) {
+                Some(Type1::MutType(ty)) => Ok(ty),
+                Some(Type1::Type(_)) => Err(TypeError::MismatchKind),
+                None => Ok(ty),
+            },
+            MutType::Imm | MutType::Mut => Ok(ty),
         }
     }
-    pub fn compose_with(&mut self, other: Self) -> Result<(), TypeError> {
-        if self.is_empty() {
-            *self = other;
-        } else {
-            let map = self.hashmap_mut();
-            for (_, ty) in map.iter_mut() {
-                ty.substitute(&other)?;
+    // The type is stored as is, the occurs check and the lowering of levels
+    // look through the bindings instead. Each variable is only visited once
+    // so types sharing parts aren't walked over and over.
+    fn bind(&mut self, var: Var, ty: Type1, var_state: &mut VarState) -> Result<(), TypeError> {
+        let kinded = ty.kinded(var.clone());
+        let level = var_state.level_of(&var);
+        let mut visited = BTreeSet::new();
+        let mut pending: Vec<_> = ty.free_vars().into_iter().collect();
+        while let Some(free) = pending.pop() {
+            if !visited.insert(free.var.clone()) {
+                continue;
+            }
+            match self.map().get(&free.var) {
+                Some(bound) if bound.var() != Some(&free.var) => pending.extend(bound.free_vars()),
+                _ if free == kinded => return Err(TypeError::InfiniteOccurrence),
+                _ => var_state.lower_level(&free.var, level),
             }
-            map.extend(other.into_hashmap());
         }
+        self.map_mut().insert(var, ty);
         Ok(())
     }
 }
@@ -327,75 +543,174 @@ impl FromIterator<(Var, Type1)> for Subs {
 pub(super) struct SchemeMut {
     pub(super) is_mut: bool,
     pub(super) scheme: Scheme,
+    // the members when it's bound to a module
+    pub(super) module: Option<Rc<ModuleMembers>>,
+    // the newtype when it's bound to its constructor
+    pub(super) nominal: Option<Rc<Nominal>>,
 }
-impl FreeVars for SchemeMut {
-    fn free_vars(&self) -> HashSet<KindedVar> {
-        self.scheme.free_vars()
-    }
+// What field access needs to check whether a member of a module can be
+// accessed from where it's written
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(super) struct ModuleMembers {
+    pub(super) path: Rc<[Atom]>,
+    pub(super) members: BTreeMap<Atom, Member>,
 }
-impl Substitutable for SchemeMut {
-    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
-        self.scheme.substitute(subs)
-    }
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(super) struct Member {
+    pub(super) visibility: Visibility,
+    pub(super) module: Option<Rc<ModuleMembers>>,
+    pub(super) nominal: Option<Rc<Nominal>>,
+    // the type of a `pub(path)` member, it's left out of the record type of
+    // the module so it's only reached by naming it after the module
+    pub(super) restricted: Option<Scheme>,
+}
+// Scopes are shared between clones so entering a function or a block
+// doesn't copy the whole environment, only the innermost scope is written to
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(super) struct Env {
+    scopes: Vec<Rc<BTreeMap<Var, SchemeMut>>>,
+    // path of the module the code is in, empty at the top of the file
+    module: Rc<[Atom]>,
+    // labels of the loops the code is in from the outermost, along with the
+    // type `break` gives to them
+    loops: Vec<(Option<Atom>, Type)>,
+    // the effects the code may perform, those of the function it's in or of
+    // the innermost `do`
+    effects: Type,
 }
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
-pub(super) struct Env(HashMap<Var, SchemeMut>);
 impl Env {
     pub fn new() -> Self {
-        Self::default()
+        Self::in_module(Rc::from([]))
+    }
+    pub fn in_module(module: Rc<[Atom]>) -> Self {
+        Self {
+            scopes: vec![prelude::scope(), Rc::default()],
+            module,
+            loops: Vec::new(),
+            // nothing handles effects outside of functions
+            effects: Type::Cons(Cons::Union(Keyed::default())),
+        }
     }
-    fn hashmap(&self) -> &HashMap<Var, SchemeMut> {
-        let Self(map) = self;
-        map
+    pub fn module(&self) -> &Rc<[Atom]> {
+        &self.module
     }
-    fn hashmap_mut(&mut self) -> &mut HashMap<Var, SchemeMut> {
-        let Self(map) = self;
-        map
+    fn scope_mut(&mut self) -> &mut BTreeMap<Var, SchemeMut> {
+        Rc::make_mut(self.scopes.last_mut().unwrap())
+    }
+    fn get(&self, var: &Var) -> Option<&SchemeMut> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(var))
+    }
+    pub fn scoped(&self) -> Self {
+        let mut scopes = self.scopes.clone();
+        scopes.push(Rc::default());
+        Self {
+            scopes,
+            module: self.module.clone(),
+            loops: self.loops.clone(),
+            effects: self.effects.clone(),
+        }
+    }
+    // a scope within a loop, its label can't shadow one of an outer loop
+    pub fn in_loop(&self, label: Option<Atom>, ty: Type) -> Result<Self, TypeError> {
+        if let Some(label) = &label {
+            if self
+                .loops
+                .iter()
+                .any(|(outer, _)| outer.as_ref() == Some(label))
+            {
+                return Err(TypeError::ShadowedLabel(label.clone()));
+            }
+        }
+        let mut env = self.scoped();
+        env.loops.push((label, ty));
+        Ok(env)
+    }
+    // a scope of a function body, jumps can't leave it
+    pub fn in_fun(&self, effects: Type) -> Self {
+        let mut env = self.scoped();
+        env.loops.clear();
+        env.effects = effects;
+        env
+    }
+    // a scope of the expression of `do`, performing the effects handled there
+    // along with the surrounding ones
+    pub fn handling(&self, effects: Type) -> Self {
+        let mut env = self.scoped();
+        env.effects = effects;
+        env
+    }
+    pub fn effects(&self) -> &Type {
+        &self.effects
+    }
+    // the type `break` gives to the loop with the label, or to the innermost
+    // loop without it
+    pub fn loop_ty(&self, label: Option<&Atom>) -> Result<Type, TypeError> {
+        let target = match label {
+            Some(label) => self
+                .loops
+                .iter()
+                .rev()
+                .find(|(name, _)| name.as_ref() == Some(label))
+                .ok_or_else(|| TypeError::UnknownLabel(label.clone()))?,
+            None => self.loops.last().ok_or(TypeError::OutsideLoop)?,
+        };
+        Ok(target.1.clone())
+    }
+    // whether the name refers to the builtin of the prelude rather than a
+    // binding shadowing it
+    pub fn is_builtin(&self, var: &Var) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|scope| scope.contains_key(var))
+            .is_some_and(|scope| Rc::ptr_eq(scope, &self.scopes[0]))
     }
     pub fn get_ty(&self, var: Var) -> Option<Scheme> {
-        self.hashmap().get(&var).map(|x| Scheme::clone(&x.scheme))
+        self.get(&var).map(|x| Scheme::clone(&x.scheme))
     }
     pub fn get_mut(&self, var: Var) -> Option<bool> {
-        self.hashmap().get(&var).map(|x| x.is_mut)
+        self.get(&var).map(|x| x.is_mut)
+    }
+    pub fn get_module(&self, var: Var) -> Option<Rc<ModuleMembers>> {
+        self.get(&var).and_then(|x| x.module.clone())
+    }
+    pub fn get_nominal(&self, var: Var) -> Option<Rc<Nominal>> {
+        self.get(&var).and_then(|x| x.nominal.clone())
     }
     pub fn insert(&mut self, var: Var, scheme_mut: SchemeMut) -> Option<SchemeMut> {
-        self.hashmap_mut().insert(var, scheme_mut)
+        self.scope_mut().insert(var, scheme_mut)
     }
-    pub fn remove(&mut self, var: Var) {
-        self.hashmap_mut().remove(&var);
+    // marks a variable of the innermost scope as bound to a module
+    pub fn set_module(&mut self, var: Var, module: Rc<ModuleMembers>) {
+        if let Some(scheme_mut) = self.scope_mut().get_mut(&var) {
+            scheme_mut.module = Some(module);
+        }
     }
-    pub fn generalize(&self, ty: Type) -> Scheme {
-        let env_free_vars = self.free_vars();
-        let for_all = ty
-            .free_vars()
-            .into_iter()
-            .filter(|var| !env_free_vars.contains(var))
-            .collect();
-        Scheme { for_all, ty }
+    // marks a variable of the innermost scope as bound to a constructor
+    pub fn set_nominal(&mut self, var: Var, nominal: Rc<Nominal>) {
+        if let Some(scheme_mut) = self.scope_mut().get_mut(&var) {
+            scheme_mut.nominal = Some(nominal);
+        }
     }
-}
-impl FreeVars for Env {
-    fn free_vars(&self) -> HashSet<KindedVar> {
-        self.hashmap()
-            .values()
-            .flat_map(SchemeMut::free_vars)
-            .collect()
+    pub fn remove(&mut self, var: Var) {
+        self.scope_mut().remove(&var);
+    }
+    // bindings of the innermost scope alone
+    pub fn scope(&self) -> impl Iterator<Item = (&Var, &SchemeMut)> {
+        self.scopes.last().unwrap().iter()
     }
 }
-impl Substitutable for Env {
-    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
-        for ty in self.hashmap_mut().values_mut() {
-            ty.substitute(subs)?;
-        }
-        Ok(())
+impl Default for Env {
+    fn default() -> Self {
+        Self::new()
     }
 }
 impl Extend<(Var, SchemeMut)> for Env {
     fn extend<T: IntoIterator<Item = (Var, SchemeMut)>>(&mut self, iter: T) {
-        self.hashmap_mut().extend(iter);
+        self.scope_mut().extend(iter);
     }
 }
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum TypeError {
     MismatchCons,
     MismatchKind,
@@ -405,10 +720,73 @@ pub enum TypeError {
     Overlap,
     UnboundVar,
     AssignedImm,
+    Unclonable,
+    UsedTwice,
+    UnloadedModule,
+    // the module path joined with `.`
+    InvisibleMember { module: String, member: Atom },
+    InvisibleField { ty: Atom, field: Atom },
+    UnknownType(Atom),
+    // a variable missing or bound differently in some alternative of an or
+    // pattern
+    InconsistentBinding(Atom),
+    NonExhaustive,
+    // the else block of a declaration may finish
+    FallThrough,
+    UnknownLabel(Atom),
+    ShadowedLabel(Atom),
+    // `break` or `continue` outside of a loop
+    OutsideLoop,
+    // a key of a dict or an element of a set that can't be compared
+    Unhashable,
+    // an effect performed where nothing handles it
+    UnhandledEffect(Atom),
 }
 impl Display for TypeError {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
-        write!(fmt, "TypeError")
+        let message = match self {
+            Self::InvisibleMember { module, member } => {
+                return write!(
+                    fmt,
+                    "member `{}` of module `{}` isn't visible here",
+                    member, module
+                );
+            }
+            Self::InvisibleField { ty, field } => {
+                return write!(fmt, "field `{}` of `{}` isn't visible here", field, ty);
+            }
+            Self::UnknownType(name) => return write!(fmt, "unknown type `{}`", name),
+            Self::UnknownLabel(label) => return write!(fmt, "unknown label `'{}`", label),
+            Self::ShadowedLabel(label) => {
+                return write!(fmt, "label `'{}` shadows the label of an outer loop", label);
+            }
+            Self::UnhandledEffect(effect) => {
+                return write!(fmt, "effect `{}` isn't handled", effect);
+            }
+            Self::InconsistentBinding(name) => {
+                return write!(
+                    fmt,
+                    "variable `{}` isn't bound the same way in every alternative",
+                    name
+                );
+            }
+            Self::MismatchCons => "mismatching types",
+            Self::MismatchKind => "mismatching kinds",
+            Self::MismatchArity => "mismatching number of elements",
+            Self::MismatchName => "mismatching field or tag names",
+            Self::InfiniteOccurrence => "infinitely recursive type",
+            Self::Overlap => "overlapping field or tag names",
+            Self::UnboundVar => "unbound variable",
+            Self::AssignedImm => "assignment to immutable place",
+            Self::Unclonable => "clone of unclonable value",
+            Self::UnloadedModule => "module file isn't loaded",
+            Self::UsedTwice => "value used twice",
+            Self::NonExhaustive => "match doesn't cover every value",
+            Self::FallThrough => "else block of a declaration must not finish",
+            Self::OutsideLoop => "jump outside of a loop",
+            Self::Unhashable => "type can't be a key of a dict nor an element of a set",
+        };
+        write!(fmt, "{}", message)
     }
 }
 impl Display for Var {
@@ -416,19 +794,6 @@ impl Display for Var {
         write!(fmt, "{}#{}", self.name, self.id)
     }
 }
-impl Display for KindedVar {
-    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        write!(fmt, "{}", self.var)
-    }
-}
-impl Display for Type {
-    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        match &self {
-            Type::Var(var) => write!(fmt, "{}", var),
-            Type::Cons(cons) => write!(fmt, "{}", cons),
-        }
-    }
-}
 impl Display for MutType {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         match &self {
@@ -438,16 +803,41 @@ impl Display for MutType {
         }
     }
 }
-impl Display for Scheme {
-    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        if !self.for_all.is_empty() {
-            write!(fmt, ":(")?;
-            for var in &self.for_all {
-                write!(fmt, "{}, ", var)?;
-            }
-            write!(fmt, ") ")?;
-        }
-        write!(fmt, "{}", &self.ty)?;
-        Ok(())
+#[cfg(test)]
+mod test {
+    use super::{cons::Cons, Kind, KindedVar, Scheme, Type, Var, VarState};
+    use hir::Atom;
+
+    // fresh variables may have the same name as quantified ones, each is
+    // still only renamed once
+    #[test]
+    fn instantiate() {
+        let var = |id| Var {
+            name: Atom::from("a"),
+            id,
+        };
+        let scheme = Scheme {
+            for_all: [1, 2]
+                .into_iter()
+                .map(|id| KindedVar {
+                    kind: Kind::Type,
+                    var: var(id),
+                })
+                .collect(),
+            ty: Type::Cons(Cons::Dict(
+                Box::new(Type::Var(var(1))),
+                Box::new(Type::Var(var(2))),
+            )),
+            constraints: Vec::new(),
+        };
+        let mut var_state = VarState::new();
+        var_state.new_named(Atom::from("a"));
+        assert_eq!(
+            scheme.instantiate(&mut var_state),
+            Type::Cons(Cons::Dict(
+                Box::new(Type::Var(var(2))),
+                Box::new(Type::Var(var(3))),
+            )),
+        );
     }
 }
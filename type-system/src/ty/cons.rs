@@ -1,14 +1,13 @@
-use super::FreeVars;
+use super::{FreeVars, Rename};
 use crate::ty::{
     Kind, KindedVar, MutType, Subs, Substitutable, Type, Type1, TypeError, Unifiable, Var, VarState,
 };
-use hir::Atom;
+use hir::{statement::Visibility, Atom};
 use std::{
-    collections::{HashMap, HashSet},
-    fmt::{self, Display, Formatter},
-    hash::Hash,
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
     iter::once,
     mem::{replace, swap},
+    rc::Rc,
 };
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -17,22 +16,105 @@ pub enum Cons {
     Bool,
     Ref(MutType, Box<Type>),
     Array(Box<Type>),
-    Fun(Box<Type>, Box<Type>),
+    // key and value
+    Dict(Box<Type>, Box<Type>),
+    Set(Box<Type>),
+    // a first-class iterator, whatever produces its values, along with the
+    // effects performed while it's iterated
+    Iter(Box<Type>, Box<Type>),
+    // parameter, return and the effects performed when called, these are a
+    // union of the operations keyed by the effect names
+    Fun(Box<Type>, Box<Type>, Box<Type>),
     RecordTuple(OrderedAnd<(Atom, Type)>),
     Record(Keyed),
     Tuple(OrderedAnd<Type>),
     Union(Keyed),
+    // a newtype along with the arguments for its parameters, it only unifies
+    // with the same newtype
+    Nominal(Rc<Nominal>, Box<[Type]>),
+}
+// Definition of a newtype, the types of the fields refer to the parameters
+#[derive(Debug)]
+pub struct Nominal {
+    pub name: Atom,
+    // path of the module it's declared in, private fields are visible within
+    pub module: Rc<[Atom]>,
+    params: Box<[Var]>,
+    fields: BTreeMap<Atom, (Visibility, Type)>,
+}
+// a newtype is only the same as itself, not another declared the same way
+impl PartialEq for Nominal {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+impl Eq for Nominal {}
+impl Nominal {
+    pub(crate) fn new(
+        name: Atom,
+        module: Rc<[Atom]>,
+        params: Box<[Var]>,
+        fields: BTreeMap<Atom, (Visibility, Type)>,
+    ) -> Self {
+        Self {
+            name,
+            module,
+            params,
+            fields,
+        }
+    }
+    pub fn param_count(&self) -> usize {
+        self.params.len()
+    }
+    // the visibility of the field and its type with the parameters replaced
+    // by the arguments
+    pub(crate) fn field(&self, args: &[Type], name: &Atom) -> Option<(&Visibility, Type)> {
+        let (visibility, ty) = self.fields.get(name)?;
+        let mut ty = ty.clone();
+        let subs: Subs = self
+            .params
+            .iter()
+            .cloned()
+            .zip(args.iter().cloned().map(Type1::Type))
+            .collect();
+        // parameters are only ever bound to types
+        ty.substitute(&subs).unwrap();
+        Some((visibility, ty))
+    }
+    pub(crate) fn fields<'a>(
+        &'a self,
+        args: &'a [Type],
+    ) -> impl Iterator<Item = (&'a Atom, &'a Visibility, Type)> + 'a {
+        self.fields.keys().map(move |name| {
+            let (visibility, ty) = self.field(args, name).unwrap();
+            (name, visibility, ty)
+        })
+    }
+    pub(crate) fn visibilities(&self) -> impl Iterator<Item = (&Atom, &Visibility)> {
+        self.fields
+            .iter()
+            .map(|(name, (visibility, _))| (name, visibility))
+    }
+    pub fn is_visible_from(&self, visibility: &Visibility, module: &[Atom]) -> bool {
+        module.starts_with(&self.module) || visibility.is_visible_from(module)
+    }
 }
 impl FreeVars for Cons {
-    fn free_vars(&self) -> HashSet<KindedVar> {
+    fn free_vars(&self) -> BTreeSet<KindedVar> {
         match self {
-            Self::Num | Self::Bool => HashSet::new(),
+            Self::Num | Self::Bool => BTreeSet::new(),
+            Self::Nominal(_, args) => args.iter().flat_map(Type::free_vars).collect(),
             Self::Ref(mutability, ty) => [mutability.free_vars(), ty.free_vars()]
                 .into_iter()
                 .flatten()
                 .collect(),
-            Self::Array(ty) => ty.free_vars(),
-            Self::Fun(param, ret) => [param, ret]
+            Self::Array(ty) | Self::Set(ty) => ty.free_vars(),
+            Self::Dict(key, value) | Self::Iter(key, value) => [key, value]
+                .into_iter()
+                .map(AsRef::as_ref)
+                .flat_map(Type::free_vars)
+                .collect(),
+            Self::Fun(param, ret, effects) => [param, ret, effects]
                 .into_iter()
                 .map(AsRef::as_ref)
                 .flat_map(Type::free_vars)
@@ -44,18 +126,57 @@ impl FreeVars for Cons {
         }
     }
 }
+impl Rename for Cons {
+    fn rename(&mut self, renames: &BTreeMap<Var, Var>) {
+        match self {
+            Self::Num | Self::Bool => (),
+            Self::Nominal(_, args) => {
+                for ty in args.iter_mut() {
+                    ty.rename(renames);
+                }
+            }
+            Self::Ref(mutability, ty) => {
+                mutability.rename(renames);
+                ty.rename(renames);
+            }
+            Self::Array(ty) | Self::Set(ty) => ty.rename(renames),
+            Self::Dict(key, value) | Self::Iter(key, value) => {
+                key.rename(renames);
+                value.rename(renames);
+            }
+            Self::Fun(param, ret, effects) => {
+                param.rename(renames);
+                ret.rename(renames);
+                effects.rename(renames);
+            }
+            Self::Record(keyed) | Self::Union(keyed) => keyed.rename(renames),
+            Self::Tuple(tuple) => tuple.rename(renames),
+            Self::RecordTuple(record_tuple) => record_tuple.rename(renames),
+        }
+    }
+}
 impl Substitutable for Cons {
     fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
         match self {
             Self::Num | Self::Bool => (),
+            Self::Nominal(_, args) => {
+                for ty in args.iter_mut() {
+                    ty.substitute(subs)?;
+                }
+            }
             Self::Ref(mutability, ty) => {
                 mutability.substitute(subs)?;
                 ty.substitute(subs)?;
             }
-            Self::Array(ty) => ty.substitute(subs)?,
-            Self::Fun(param, ret) => {
+            Self::Array(ty) | Self::Set(ty) => ty.substitute(subs)?,
+            Self::Dict(key, value) | Self::Iter(key, value) => {
+                key.substitute(subs)?;
+                value.substitute(subs)?;
+            }
+            Self::Fun(param, ret, effects) => {
                 param.substitute(subs)?;
                 ret.substitute(subs)?;
+                effects.substitute(subs)?;
             }
             Self::Record(record) => record.substitute(subs, |cons| match cons {
                 Cons::Record(ty) => Some(ty),
@@ -73,11 +194,11 @@ impl Substitutable for Cons {
                         ty.substitute(subs)?;
                     }
                 }
-                OrderedAnd::Row(_, rest, _) => match subs.get(rest.clone()) {
-                    Some(Type1::Type(Type::Var(_) | Type::Cons(Cons::RecordTuple(_)))) => {
+                OrderedAnd::Row(_, rest, _) => match subs.lookup(rest) {
+                    None | Some(Type1::Type(Type::Var(_) | Type::Cons(Cons::RecordTuple(_)))) => {
                         record_tuple.substitute(subs, |cons| match cons {
                             Cons::RecordTuple(record_tuple) => Some(record_tuple),
-                            _ => unreachable!(),
+                            _ => None,
                         })?;
                     }
                     Some(Type1::Type(Type::Cons(other @ (Cons::Record(_) | Cons::Tuple(_))))) => {
@@ -103,23 +224,54 @@ impl Substitutable for Cons {
         Ok(())
     }
 }
+impl Cons {
+    fn is_open(&self) -> bool {
+        match self {
+            Self::Record(keyed) | Self::Union(keyed) => keyed.rest.is_some(),
+            Self::Tuple(OrderedAnd::Row(..)) | Self::RecordTuple(OrderedAnd::Row(..)) => true,
+            _ => false,
+        }
+    }
+}
 impl Unifiable for Cons {
     fn unify_with(
-        self,
-        other: Self,
+        mut self,
+        mut other: Self,
         subs: &mut Subs,
         var_state: &mut VarState,
     ) -> Result<(), TypeError> {
+        // the rest of rows may already be bound, these are expanded first
+        for cons in [&mut self, &mut other] {
+            if cons.is_open() {
+                cons.substitute(subs)?;
+            }
+        }
         match (self, other) {
             (Self::Bool, Self::Bool) | (Self::Num, Self::Num) => (),
+            (Self::Nominal(def1, args1), Self::Nominal(def2, args2)) => {
+                if !Rc::ptr_eq(&def1, &def2) {
+                    return Err(TypeError::MismatchCons);
+                }
+                for (ty1, ty2) in Vec::from(args1).into_iter().zip(Vec::from(args2)) {
+                    ty1.unify_with(ty2, subs, var_state)?;
+                }
+            }
             (Self::Ref(mut1, ty1), Self::Ref(mut2, ty2)) => {
                 mut1.unify_with(mut2, subs, var_state)?;
                 ty1.unify_with(*ty2, subs, var_state)?;
             }
-            (Self::Array(ty1), Self::Array(ty2)) => ty1.unify_with(*ty2, subs, var_state)?,
-            (Self::Fun(param1, ret1), Self::Fun(param2, ret2)) => {
+            (Self::Array(ty1), Self::Array(ty2)) | (Self::Set(ty1), Self::Set(ty2)) => {
+                ty1.unify_with(*ty2, subs, var_state)?
+            }
+            (Self::Dict(key1, value1), Self::Dict(key2, value2))
+            | (Self::Iter(key1, value1), Self::Iter(key2, value2)) => {
+                key1.unify_with(*key2, subs, var_state)?;
+                value1.unify_with(*value2, subs, var_state)?;
+            }
+            (Self::Fun(param1, ret1, effects1), Self::Fun(param2, ret2, effects2)) => {
                 param1.unify_with(*param2, subs, var_state)?;
                 ret1.unify_with(*ret2, subs, var_state)?;
+                effects1.unify_with(*effects2, subs, var_state)?;
             }
             (Self::Record(rec1), Self::Record(rec2)) => {
                 rec1.unify_with(rec2, subs, var_state, Cons::Record)?
@@ -148,11 +300,11 @@ impl Unifiable for Cons {
 }
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct Keyed {
-    pub fields: HashMap<Atom, Type>,
+    pub fields: BTreeMap<Atom, Type>,
     pub rest: Option<Var>,
 }
 impl FreeVars for Keyed {
-    fn free_vars(&self) -> HashSet<KindedVar> {
+    fn free_vars(&self) -> BTreeSet<KindedVar> {
         self.fields
             .values()
             .flat_map(Type::free_vars)
@@ -163,42 +315,48 @@ impl FreeVars for Keyed {
             .collect()
     }
 }
+impl Rename for Keyed {
+    fn rename(&mut self, renames: &BTreeMap<Var, Var>) {
+        for ty in self.fields.values_mut() {
+            ty.rename(renames);
+        }
+        if let Some(rest) = &mut self.rest {
+            rest.rename(renames);
+        }
+    }
+}
 impl Keyed {
     fn substitute(
         &mut self,
         subs: &Subs,
-        matcher: impl FnOnce(Cons) -> Option<Keyed>,
+        matcher: impl Fn(Cons) -> Option<Keyed>,
     ) -> Result<(), TypeError> {
-        for ty in self.fields.values_mut() {
-            ty.substitute(subs)?;
-        }
-        if let Some(var) = &self.rest {
-            match subs.get(var.clone()) {
+        while let Some(var) = &self.rest {
+            match subs.lookup(var) {
                 Some(Type1::Type(Type::Var(new_var))) => {
                     self.rest = Some(new_var);
+                    break;
                 }
                 Some(Type1::Type(Type::Cons(cons))) => {
                     let new_rest = matcher(cons).ok_or(TypeError::MismatchCons)?;
-                    let new_fields = &new_rest.fields;
                     let fields = &mut self.fields;
-                    fields.reserve(new_fields.len());
-                    for (key, ty) in new_fields {
-                        if fields.contains_key(key) {
-                            return Err(TypeError::Overlap);
-                        } else {
-                            fields.insert(key.clone(), ty.clone());
+                    for (key, ty) in new_rest.fields {
+                        match fields.entry(key) {
+                            Entry::Occupied(_) => return Err(TypeError::Overlap),
+                            Entry::Vacant(entry) => {
+                                entry.insert(ty);
+                            }
                         }
                     }
-                    // NOTE: why there's no HashMap::reserve_exact??
-                    if new_rest.rest.is_none() {
-                        fields.shrink_to_fit();
-                    }
                     self.rest = new_rest.rest;
                 }
                 Some(_) => return Err(TypeError::MismatchCons),
-                None => (),
+                None => break,
             }
         }
+        for ty in self.fields.values_mut() {
+            ty.substitute(subs)?;
+        }
         Ok(())
     }
     pub(super) fn unify_with(
@@ -216,36 +374,40 @@ impl Keyed {
         match (self.rest, map1, other.rest, map2) {
             (Some(rest1), map1, Some(rest2), map2) => {
                 let new_var = var_state.new_var();
-                subs.insert(
+                subs.bind(
                     rest1,
                     Type1::Type(Type::Cons(cons(Keyed {
                         fields: map2,
                         rest: Some(new_var.clone()),
                     }))),
-                );
-                subs.insert(
+                    var_state,
+                )?;
+                subs.bind(
                     rest2,
                     Type1::Type(Type::Cons(cons(Keyed {
                         fields: map1,
                         rest: Some(new_var),
                     }))),
-                );
+                    var_state,
+                )?;
             }
             (Some(rest1), map1, None, map2) | (None, map2, Some(rest1), map1) => {
-                if map1.is_empty() {
-                    return Err(TypeError::MismatchArity);
+                // the open side can't have fields missing from the closed one
+                if !map1.is_empty() {
+                    return Err(TypeError::MismatchName);
                 }
-                subs.insert(
+                subs.bind(
                     rest1,
                     Type1::Type(Type::Cons(cons(Keyed {
                         fields: map2,
                         rest: None,
                     }))),
-                );
+                    var_state,
+                )?;
             }
             (None, map1, None, map2) => {
-                if map1.is_empty() || map2.is_empty() {
-                    return Err(TypeError::MismatchArity);
+                if !map1.is_empty() || !map2.is_empty() {
+                    return Err(TypeError::MismatchName);
                 }
             }
         }
@@ -268,7 +430,7 @@ impl OrderedAnd<(Atom, Type)> {
                 }
             }
             Self::Row(left, rest, right) => Keyed {
-                fields: left.into_iter().chain(right.into_iter()).collect(),
+                fields: left.into_iter().chain(right).collect(),
                 rest: Some(rest),
             },
         }
@@ -292,7 +454,7 @@ impl<T> FreeVars for OrderedAnd<T>
 where
     T: FreeVars,
 {
-    fn free_vars(&self) -> HashSet<KindedVar> {
+    fn free_vars(&self) -> BTreeSet<KindedVar> {
         match self {
             Self::NonRow(tuple) => tuple.iter().flat_map(T::free_vars).collect(),
             Self::Row(left, rest, right) => left
@@ -307,62 +469,82 @@ where
         }
     }
 }
+impl<T> Rename for OrderedAnd<T>
+where
+    T: Rename,
+{
+    fn rename(&mut self, renames: &BTreeMap<Var, Var>) {
+        match self {
+            Self::NonRow(tuple) => {
+                for item in tuple.iter_mut() {
+                    item.rename(renames);
+                }
+            }
+            Self::Row(left, rest, right) => {
+                for item in left.iter_mut().chain(right.iter_mut()) {
+                    item.rename(renames);
+                }
+                rest.rename(renames);
+            }
+        }
+    }
+}
 impl<T> OrderedAnd<T> {
     fn substitute(
         &mut self,
         subs: &Subs,
-        matcher: impl FnOnce(Cons) -> Option<Self>,
+        matcher: impl Fn(Cons) -> Option<Self>,
     ) -> Result<(), TypeError>
     where
         T: Substitutable,
     {
+        while let Self::Row(left, rest, right) = self {
+            match subs.lookup(rest) {
+                Some(Type1::Type(Type::Var(var))) => {
+                    *rest = var;
+                    break;
+                }
+                Some(Type1::Type(Type::Cons(cons))) => {
+                    match matcher(cons).ok_or(TypeError::MismatchCons)? {
+                        Self::Row(more_left, new_rest, mut more_right) => {
+                            left.extend(more_left);
+                            *rest = new_rest;
+                            let mut temp = vec![];
+                            swap(right, &mut temp);
+                            more_right.extend(temp);
+                            *right = more_right;
+                        }
+                        Self::NonRow(new_tuple) => {
+                            let new_tuple: Vec<_> = new_tuple.into();
+                            let (left, right) = {
+                                match replace(self, Self::NonRow(vec![].into())) {
+                                    Self::Row(left, _, right) => (left, right),
+                                    _ => unreachable!(),
+                                }
+                            };
+                            *self = Self::NonRow(
+                                left.into_iter().chain(new_tuple).chain(right).collect(),
+                            );
+                        }
+                    }
+                }
+                Some(_) => return Err(TypeError::MismatchCons),
+                None => break,
+            }
+        }
         match self {
             Self::NonRow(tuple) => {
                 for ty in tuple.iter_mut() {
                     ty.substitute(subs)?
                 }
             }
-            Self::Row(left, rest, right) => {
+            Self::Row(left, _, right) => {
                 for ty in left.iter_mut() {
                     ty.substitute(subs)?
                 }
                 for ty in right.iter_mut() {
                     ty.substitute(subs)?
                 }
-                match subs.get(rest.clone()) {
-                    Some(Type1::Type(Type::Var(var))) => {
-                        *rest = var;
-                    }
-                    Some(Type1::Type(Type::Cons(cons))) => {
-                        match matcher(cons).ok_or(TypeError::MismatchCons)? {
-                            Self::Row(more_left, new_rest, mut more_right) => {
-                                left.extend(more_left);
-                                *rest = new_rest;
-                                let mut temp = vec![];
-                                swap(right, &mut temp);
-                                more_right.extend(temp);
-                                *right = more_right;
-                            }
-                            Self::NonRow(new_tuple) => {
-                                let new_tuple: Vec<_> = new_tuple.into();
-                                let (left, right) = {
-                                    match replace(self, Self::NonRow(vec![].into())) {
-                                        Self::Row(left, _, right) => (left, right),
-                                        _ => unreachable!(),
-                                    }
-                                };
-                                *self = Self::NonRow(
-                                    left.into_iter()
-                                        .chain(new_tuple.into_iter())
-                                        .chain(right.into_iter())
-                                        .collect(),
-                                );
-                            }
-                        }
-                    }
-                    Some(_) => return Err(TypeError::MismatchCons),
-                    None => (),
-                }
             }
         }
         Ok(())
@@ -384,7 +566,7 @@ impl<T> OrderedAnd<T> {
                 }
                 let tup1: Vec<_> = tup1.into();
                 let tup2: Vec<_> = tup2.into();
-                for (ty1, ty2) in tup1.into_iter().zip(tup2.into_iter()) {
+                for (ty1, ty2) in tup1.into_iter().zip(tup2) {
                     ty1.unify_with(ty2, subs, var_state)?;
                 }
             }
@@ -397,27 +579,70 @@ impl<T> OrderedAnd<T> {
                 let mut left2 = tup;
                 let mut rest2 = left2.split_off(left.len());
                 let right2 = rest2.split_off(rest2.len() - right.len());
-                for (ty1, ty2) in left.into_iter().zip(left2.into_iter()) {
+                for (ty1, ty2) in left.into_iter().zip(left2) {
                     ty1.unify_with(ty2, subs, var_state)?;
                 }
-                for (ty1, ty2) in right.into_iter().zip(right2.into_iter()) {
+                for (ty1, ty2) in right.into_iter().zip(right2) {
                     ty1.unify_with(ty2, subs, var_state)?;
                 }
-                subs.insert(
+                subs.bind(
                     rest,
                     Type1::Type(Type::Cons(cons(Self::NonRow(rest2.into())))),
-                )
+                    var_state,
+                )?;
+            }
+            (Self::Row(mut left1, rest1, mut right1), Self::Row(mut left2, rest2, mut right2)) => {
+                // the common ends are unified, what's left of the longer end
+                // goes into the rest of the other side
+                let left_len = left1.len().min(left2.len());
+                let more_left1 = left1.split_off(left_len);
+                let more_left2 = left2.split_off(left_len);
+                let right_len = right1.len().min(right2.len());
+                let common_right1 = right1.split_off(right1.len() - right_len);
+                let common_right2 = right2.split_off(right2.len() - right_len);
+                for (ty1, ty2) in left1
+                    .into_iter()
+                    .zip(left2)
+                    .chain(common_right1.into_iter().zip(common_right2))
+                {
+                    ty1.unify_with(ty2, subs, var_state)?;
+                }
+                let rest1_empty = more_left1.is_empty() && right1.is_empty();
+                let rest2_empty = more_left2.is_empty() && right2.is_empty();
+                if rest1 == rest2 {
+                    if !rest1_empty || !rest2_empty {
+                        return Err(TypeError::MismatchArity);
+                    }
+                } else if rest1_empty {
+                    let ty = Self::row(more_left2, rest2, right2, &mut cons);
+                    subs.bind(rest1, Type1::Type(ty), var_state)?;
+                } else if rest2_empty {
+                    let ty = Self::row(more_left1, rest1, right1, &mut cons);
+                    subs.bind(rest2, Type1::Type(ty), var_state)?;
+                } else {
+                    let new_var = var_state.new_var();
+                    let ty1 = Self::row(more_left2, new_var.clone(), right2, &mut cons);
+                    let ty2 = Self::row(more_left1, new_var, right1, &mut cons);
+                    subs.bind(rest1, Type1::Type(ty1), var_state)?;
+                    subs.bind(rest2, Type1::Type(ty2), var_state)?;
+                }
             }
-            (Self::Row(left1, rest1, right1), Self::Row(left2, rest2, right2)) => todo!(),
         }
         Ok(())
     }
+    fn row(left: Vec<T>, rest: Var, right: Vec<T>, cons: &mut impl FnMut(Self) -> Cons) -> Type {
+        if left.is_empty() && right.is_empty() {
+            Type::Var(rest)
+        } else {
+            Type::Cons(cons(Self::Row(left, rest, right)))
+        }
+    }
 }
-fn intersection<K, A, B>(a: &mut HashMap<K, A>, b: &mut HashMap<K, B>) -> HashMap<K, (A, B)>
+fn intersection<K, A, B>(a: &mut BTreeMap<K, A>, b: &mut BTreeMap<K, B>) -> BTreeMap<K, (A, B)>
 where
-    K: Hash + Eq + Clone,
+    K: Ord + Clone,
 {
-    // TODO: avoid allocation, return an iterator instead of `HashMap`, and
+    // TODO: avoid allocation, return an iterator instead of `BTreeMap`, and
     // remove the `Clone` requirement for `K`
     a.keys()
         .filter(|key| b.contains_key(key))
@@ -431,76 +656,49 @@ where
         })
         .collect()
 }
-impl<'a> Display for Cons {
-    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        match self {
-            Self::Num => write!(fmt, "Num"),
-            Self::Bool => write!(fmt, "Bool"),
-            Self::Ref(mutability, ty) => write!(fmt, "&:{} {}", mutability, ty),
-            Self::Array(ty) => write!(fmt, "[{}]", ty),
-            Self::Record(record) => {
-                write!(fmt, "(")?;
-                for (name, ty) in &record.fields {
-                    write!(fmt, "{} = {}, ", name, ty)?
-                }
-                if let Some(rest) = &record.rest {
-                    write!(fmt, "*{}", rest)?;
-                }
-                write!(fmt, ")")?;
-                Ok(())
-            }
-            Self::Tuple(tuple) => {
-                write!(fmt, "(")?;
-                match tuple {
-                    OrderedAnd::NonRow(tuple) => {
-                        for ty in &tuple[..] {
-                            write!(fmt, "{}, ", ty)?;
-                        }
-                    }
-                    OrderedAnd::Row(left, rest, right) => {
-                        for ty in left {
-                            write!(fmt, "{}, ", ty)?;
-                        }
-                        write!(fmt, "*{}, ", rest)?;
-                        for ty in right {
-                            write!(fmt, "{}, ", ty)?;
-                        }
-                    }
-                }
-                write!(fmt, ")")?;
-                Ok(())
-            }
-            Self::RecordTuple(record_tuple) => {
-                write!(fmt, "(")?;
-                match record_tuple {
-                    OrderedAnd::NonRow(record_tuple) => {
-                        for (name, ty) in &record_tuple[..] {
-                            write!(fmt, "{} = {}, ", name, ty)?;
-                        }
-                    }
-                    OrderedAnd::Row(left, rest, right) => {
-                        for (name, ty) in left {
-                            write!(fmt, "{} = {}, ", name, ty)?;
-                        }
-                        write!(fmt, "*{}, ", rest)?;
-                        for (name, ty) in right {
-                            write!(fmt, "{} = {}, ", name, ty)?;
-                        }
-                    }
-                }
-                write!(fmt, ")")?;
-                Ok(())
-            }
-            Self::Fun(param, ret) => write!(fmt, "{} -> {}", param, ret),
-            Self::Union(union) => {
-                for (tag, assoc) in union.fields.iter() {
-                    write!(fmt, "@{} {} | ", tag, assoc)?;
-                }
-                if let Some(rest) = &union.rest {
-                    write!(fmt, "*{} | ", rest)?;
-                }
-                Ok(())
-            }
-        }
+#[cfg(test)]
+mod test {
+    use crate::{test::infer_ty, TypeError};
+
+    // the fields only one side has are left over, they're fine as long as
+    // the other side is open
+    #[test]
+    fn leftover_fields() {
+        assert_eq!(infer_ty("(foo = 1, bar = true).foo"), Ok("Num".to_string()));
+        assert_eq!(
+            infer_ty("(rec) => [rec.foo, (foo = 1, bar = 2).bar]"),
+            Ok("(rec: {foo: Num, *_}) -> [Num]".to_string()),
+        );
+        assert_eq!(
+            infer_ty("(foo = 1, bar = true).baz"),
+            Err(TypeError::MismatchName),
+        );
+        assert_eq!(
+            infer_ty("[(foo = 1, bar = 2), (bar = 3, foo = 4)]"),
+            Ok("[{bar: Num, foo: Num}]".to_string()),
+        );
+        assert_eq!(
+            infer_ty("[(foo = 1), (foo = 1, bar = 2)]"),
+            Err(TypeError::MismatchName),
+        );
+        assert_eq!(
+            infer_ty("[@foo 1, @bar 2]"),
+            Ok("[@bar Num | @foo Num | *_]".to_string()),
+        );
+    }
+    #[test]
+    fn row_tuples() {
+        assert_eq!(
+            infer_ty("(a, b) => [(1, *a), (1, *b)]"),
+            Ok("(a: a, b: a) -> [(Num, *a)]".to_string()),
+        );
+        assert_eq!(
+            infer_ty("(a, b) => [(1, 2, *a), (*b, 3)]"),
+            Ok("(a: (*a, Num), b: (Num, Num, *a)) -> [(Num, Num, *a, Num)]".to_string()),
+        );
+        assert_eq!(
+            infer_ty("(a) => [(1, *a), (1, *a, 2)]"),
+            Err(TypeError::MismatchArity),
+        );
     }
 }
@@ -0,0 +1,233 @@
+use crate::{
+    expr::perform,
+    ty::{
+        cons::{Cons, Keyed, OrderedAnd},
+        FreeVars, KindedVar, Rename, Subs, Substitutable, Type, TypeError, Unifiable, Var,
+        VarState,
+    },
+};
+use hir::Atom;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Iterating and indexing work on several kinds of containers, which one is
+// only decided once the type of the container is known. Until then the
+// constraint is kept in the var state, or in the scheme when the container is
+// generalized, so functions stay polymorphic over the kind of container.
+// Hashability is checked the same way, with the type itself as the container.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) enum Constraint {
+    // `for elem in container`, dicts give their entries and iterators
+    // perform their effects where the loop is
+    Iterable {
+        container: Type,
+        elem: Type,
+        effects: Type,
+    },
+    // `container[index]`, sets give whether they contain the index
+    Indexable {
+        container: Type,
+        index: Type,
+        elem: Type,
+    },
+    // keys of dicts and elements of sets are compared by value, which
+    // functions and references don't have, nor do dicts and sets as their
+    // order isn't fixed
+    Hashable {
+        ty: Type,
+    },
+}
+impl Constraint {
+    pub(in crate::ty) fn container(&self) -> &Type {
+        match self {
+            Self::Iterable { container, .. } | Self::Indexable { container, .. } => container,
+            Self::Hashable { ty } => ty,
+        }
+    }
+    // those shown in schemes
+    pub(in crate::ty) fn types(&self) -> Vec<&Type> {
+        match self {
+            Self::Iterable {
+                container, elem, ..
+            } => vec![container, elem],
+            Self::Indexable {
+                container,
+                index,
+                elem,
+            } => vec![container, index, elem],
+            Self::Hashable { ty } => vec![ty],
+        }
+    }
+    // unifies the types when the container is known, otherwise the
+    // constraint is given back. Hashability is given back as the constraints
+    // on the parts of the type.
+    pub(in crate::ty) fn resolve(
+        mut self,
+        subs: &mut Subs,
+        var_state: &mut VarState,
+    ) -> Result<Vec<Self>, TypeError> {
+        self.substitute(subs)?;
+        let cons = match self.container() {
+            Type::Var(_) => return Ok(vec![self]),
+            Type::Cons(cons) => cons.clone(),
+        };
+        match (self, cons) {
+            (Self::Hashable { .. }, cons) => {
+                return Ok(hashable_parts(cons)?
+                    .into_iter()
+                    .map(|ty| Self::Hashable { ty })
+                    .collect());
+            }
+            (Self::Iterable { elem, .. }, Cons::Array(item) | Cons::Set(item)) => {
+                elem.unify_with(*item, subs, var_state)?
+            }
+            (Self::Iterable { elem, effects, .. }, Cons::Iter(item, performed)) => {
+                elem.unify_with(*item, subs, var_state)?;
+                perform(*performed, effects, subs, var_state)?;
+            }
+            (Self::Iterable { elem, .. }, Cons::Dict(key, value)) => {
+                let entry = Type::Cons(Cons::Record(Keyed {
+                    fields: [(Atom::from("key"), *key), (Atom::from("value"), *value)]
+                        .into_iter()
+                        .collect(),
+                    rest: None,
+                }));
+                elem.unify_with(entry, subs, var_state)?;
+            }
+            (
+                Self::Indexable {
+                    container: _,
+                    index,
+                    elem,
+                },
+                Cons::Array(item),
+            ) => {
+                index.unify_with(Type::Cons(Cons::Num), subs, var_state)?;
+                elem.unify_with(*item, subs, var_state)?;
+            }
+            (
+                Self::Indexable {
+                    container: _,
+                    index,
+                    elem,
+                },
+                Cons::Dict(key, value),
+            ) => {
+                index.unify_with(*key, subs, var_state)?;
+                elem.unify_with(*value, subs, var_state)?;
+            }
+            (
+                Self::Indexable {
+                    container: _,
+                    index,
+                    elem,
+                },
+                Cons::Set(item),
+            ) => {
+                index.unify_with(*item, subs, var_state)?;
+                elem.unify_with(Type::Cons(Cons::Bool), subs, var_state)?;
+            }
+            _ => return Err(TypeError::MismatchCons),
+        }
+        Ok(Vec::new())
+    }
+}
+// the types that have to be hashable for the whole to be. The fields a row
+// may add later aren't checked.
+fn hashable_parts(cons: Cons) -> Result<Vec<Type>, TypeError> {
+    let parts = match cons {
+        Cons::Num | Cons::Bool => Vec::new(),
+        Cons::Ref(_, _) | Cons::Fun(..) | Cons::Dict(_, _) | Cons::Set(_) | Cons::Iter(..) => {
+            return Err(TypeError::Unhashable)
+        }
+        Cons::Array(ty) => vec![*ty],
+        Cons::Nominal(nominal, args) => nominal.fields(&args).map(|(_, _, ty)| ty).collect(),
+        Cons::Record(keyed) | Cons::Union(keyed) => keyed.fields.into_values().collect(),
+        Cons::Tuple(OrderedAnd::NonRow(tuple)) => tuple.into(),
+        Cons::Tuple(OrderedAnd::Row(left, _, right)) => left.into_iter().chain(right).collect(),
+        Cons::RecordTuple(OrderedAnd::NonRow(fields)) => {
+            fields.into_vec().into_iter().map(|(_, ty)| ty).collect()
+        }
+        Cons::RecordTuple(OrderedAnd::Row(left, _, right)) => {
+            left.into_iter().chain(right).map(|(_, ty)| ty).collect()
+        }
+    };
+    Ok(parts)
+}
+impl FreeVars for Constraint {
+    fn free_vars(&self) -> BTreeSet<KindedVar> {
+        match self {
+            Self::Iterable {
+                container,
+                elem,
+                effects,
+            } => {
+                let mut vars = container.free_vars();
+                vars.extend(elem.free_vars());
+                vars.extend(effects.free_vars());
+                vars
+            }
+            Self::Indexable {
+                container,
+                index,
+                elem,
+            } => {
+                let mut vars = container.free_vars();
+                vars.extend(index.free_vars());
+                vars.extend(elem.free_vars());
+                vars
+            }
+            Self::Hashable { ty } => ty.free_vars(),
+        }
+    }
+}
+impl Rename for Constraint {
+    fn rename(&mut self, renames: &BTreeMap<Var, Var>) {
+        match self {
+            Self::Iterable {
+                container,
+                elem,
+                effects,
+            } => {
+                container.rename(renames);
+                elem.rename(renames);
+                effects.rename(renames);
+            }
+            Self::Indexable {
+                container,
+                index,
+                elem,
+            } => {
+                container.rename(renames);
+                index.rename(renames);
+                elem.rename(renames);
+            }
+            Self::Hashable { ty } => ty.rename(renames),
+        }
+    }
+}
+impl Substitutable for Constraint {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::Iterable {
+                container,
+                elem,
+                effects,
+            } => {
+                container.substitute(subs)?;
+                elem.substitute(subs)?;
+                effects.substitute(subs)?;
+            }
+            Self::Indexable {
+                container,
+                index,
+                elem,
+            } => {
+                container.substitute(subs)?;
+                index.substitute(subs)?;
+                elem.substitute(subs)?;
+            }
+            Self::Hashable { ty } => ty.substitute(subs)?,
+        }
+        Ok(())
+    }
+}
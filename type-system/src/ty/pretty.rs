@@ -0,0 +1,427 @@
+use crate::ty::{
+    cons::{Cons, Keyed, OrderedAnd},
+    Constraint, Kind, MutType, Scheme, Type, Var,
+};
+use hir::Atom;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
+
+// Ordered from the loosest to the tightest binding, a type is wrapped in
+// parentheses when it's placed where a tighter one is needed
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+enum Precedence {
+    Union,
+    Fun,
+    Prefix,
+}
+fn letter_name(index: usize) -> String {
+    let letter = (b'a' + (index % 26) as u8) as char;
+    match index / 26 {
+        0 => letter.to_string(),
+        suffix => format!("{}{}", letter, suffix),
+    }
+}
+fn is_unit(ty: &Type) -> bool {
+    match ty {
+        Type::Cons(Cons::RecordTuple(OrderedAnd::NonRow(fields))) => fields.is_empty(),
+        Type::Cons(Cons::Tuple(OrderedAnd::NonRow(fields))) => fields.is_empty(),
+        _ => false,
+    }
+}
+// the effects of a function are only printed when it performs some, a lone
+// row variable is left out
+fn shown_effects(effects: &Type) -> Option<&Keyed> {
+    match effects {
+        Type::Cons(Cons::Union(keyed)) if !keyed.fields.is_empty() => Some(keyed),
+        _ => None,
+    }
+}
+fn list(open: &str, close: &str, items: Vec<String>) -> String {
+    format!("{}{}{}", open, items.join(", "), close)
+}
+// Prints types in the syntax of type annotation, variables are renamed to
+// `a`, `b`, `c`, and so on in order of appearance. Row variables that only
+// appear once are printed as `_`.
+#[derive(Debug, Default)]
+struct PrettyPrinter {
+    names: HashMap<Var, usize>,
+    counts: HashMap<Var, usize>,
+}
+impl PrettyPrinter {
+    fn count_var(&mut self, var: &Var) {
+        *self.counts.entry(var.clone()).or_insert(0) += 1;
+    }
+    fn count_keyed(&mut self, keyed: &Keyed) {
+        for ty in keyed.fields.values() {
+            self.count(ty);
+        }
+        if let Some(rest) = &keyed.rest {
+            self.count_var(rest);
+        }
+    }
+    fn count_ordered<T>(&mut self, ordered: &OrderedAnd<T>, ty: impl Fn(&T) -> &Type) {
+        match ordered {
+            OrderedAnd::NonRow(fields) => {
+                for field in fields.iter() {
+                    self.count(ty(field));
+                }
+            }
+            OrderedAnd::Row(left, rest, right) => {
+                for field in left {
+                    self.count(ty(field));
+                }
+                self.count_var(rest);
+                for field in right {
+                    self.count(ty(field));
+                }
+            }
+        }
+    }
+    fn count(&mut self, ty: &Type) {
+        match ty {
+            Type::Var(var) => self.count_var(var),
+            Type::Cons(cons) => match cons {
+                Cons::Num | Cons::Bool => (),
+                Cons::Nominal(_, args) => {
+                    for ty in args.iter() {
+                        self.count(ty);
+                    }
+                }
+                Cons::Ref(mutability, ty) => {
+                    if let MutType::Var(var) = mutability {
+                        self.count_var(var);
+                    }
+                    self.count(ty);
+                }
+                Cons::Array(ty) | Cons::Set(ty) => self.count(ty),
+                Cons::Iter(ty, effects) => {
+                    self.count(ty);
+                    if let Some(effects) = shown_effects(effects) {
+                        self.count_keyed(effects);
+                    }
+                }
+                Cons::Dict(key, value) => {
+                    self.count(key);
+                    self.count(value);
+                }
+                Cons::Fun(param, ret, effects) => {
+                    self.count(param);
+                    self.count(ret);
+                    if let Some(effects) = shown_effects(effects) {
+                        self.count_keyed(effects);
+                    }
+                }
+                Cons::Record(keyed) | Cons::Union(keyed) => self.count_keyed(keyed),
+                Cons::Tuple(tuple) => self.count_ordered(tuple, |ty| ty),
+                Cons::RecordTuple(record_tuple) => self.count_ordered(record_tuple, |(_, ty)| ty),
+            },
+        }
+    }
+    fn name(&mut self, var: &Var) -> String {
+        let len = self.names.len();
+        letter_name(*self.names.entry(var.clone()).or_insert(len))
+    }
+    fn rest(&mut self, var: &Var) -> String {
+        if self.counts.get(var).copied().unwrap_or(0) <= 1 {
+            "*_".to_string()
+        } else {
+            format!("*{}", self.name(var))
+        }
+    }
+    fn mutability(&mut self, mutability: &MutType) -> String {
+        match mutability {
+            MutType::Imm => String::new(),
+            MutType::Mut => ":mut ".to_string(),
+            MutType::Var(var) => format!(":{} ", self.name(var)),
+        }
+    }
+    fn tuple(&mut self, tuple: &OrderedAnd<Type>) -> String {
+        let items = match tuple {
+            OrderedAnd::NonRow(tuple) => {
+                let items: Vec<_> = tuple
+                    .iter()
+                    .map(|ty| self.ty(ty, Precedence::Union))
+                    .collect();
+                // a single element tuple needs a trailing comma to not be
+                // mistaken for a group
+                if items.len() == 1 {
+                    return format!("({},)", items[0]);
+                }
+                items
+            }
+            OrderedAnd::Row(left, rest, right) => {
+                let mut items: Vec<_> = left
+                    .iter()
+                    .map(|ty| self.ty(ty, Precedence::Union))
+                    .collect();
+                items.push(self.rest(rest));
+                items.extend(right.iter().map(|ty| self.ty(ty, Precedence::Union)));
+                items
+            }
+        };
+        list("(", ")", items)
+    }
+    fn record_tuple(&mut self, record_tuple: &OrderedAnd<(Atom, Type)>) -> String {
+        // parameters of a partial application are named `?0`, `?1`, and so on,
+        // they can't be passed by name so they're shown by their position
+        let field = |this: &mut Self, (name, ty): &(Atom, Type)| {
+            if name.starts_with('?') {
+                this.ty(ty, Precedence::Union)
+            } else {
+                format!("{}: {}", name, this.ty(ty, Precedence::Union))
+            }
+        };
+        let items = match record_tuple {
+            OrderedAnd::NonRow(fields) => {
+                let items: Vec<_> = fields.iter().map(|item| field(self, item)).collect();
+                if let [(name, _)] = &fields[..] {
+                    if name.starts_with('?') {
+                        return format!("({},)", items[0]);
+                    }
+                }
+                items
+            }
+            OrderedAnd::Row(left, rest, right) => {
+                let mut items: Vec<_> = left.iter().map(|item| field(self, item)).collect();
+                items.push(self.rest(rest));
+                items.extend(right.iter().map(|item| field(self, item)));
+                items
+            }
+        };
+        list("(", ")", items)
+    }
+    fn record(&mut self, record: &Keyed) -> String {
+        let mut items: Vec<_> = record
+            .fields
+            .iter()
+            .map(|(name, ty)| format!("{}: {}", name, self.ty(ty, Precedence::Union)))
+            .collect();
+        if let Some(rest) = &record.rest {
+            items.push(self.rest(rest));
+        }
+        list("{", "}", items)
+    }
+    fn union(&mut self, union: &Keyed) -> String {
+        let mut items: Vec<_> = union
+            .fields
+            .iter()
+            .map(|(tag, ty)| {
+                if is_unit(ty) {
+                    format!("@{}", tag)
+                } else {
+                    format!("@{} {}", tag, self.ty(ty, Precedence::Prefix))
+                }
+            })
+            .collect();
+        if let Some(rest) = &union.rest {
+            items.push(self.rest(rest));
+        }
+        items.join(" | ")
+    }
+    fn ty(&mut self, ty: &Type, precedence: Precedence) -> String {
+        let (src, own) = match ty {
+            Type::Var(var) => (self.name(var), Precedence::Prefix),
+            Type::Cons(cons) => match cons {
+                Cons::Num => ("Num".to_string(), Precedence::Prefix),
+                Cons::Bool => ("Bool".to_string(), Precedence::Prefix),
+                Cons::Nominal(nominal, args) if args.is_empty() => {
+                    (nominal.name.to_string(), Precedence::Prefix)
+                }
+                Cons::Nominal(nominal, args) => {
+                    let args = args
+                        .iter()
+                        .map(|ty| self.ty(ty, Precedence::Union))
+                        .collect();
+                    (
+                        list(&format!("{}(", nominal.name), ")", args),
+                        Precedence::Prefix,
+                    )
+                }
+                Cons::Ref(mutability, ty) => {
+                    let mutability = self.mutability(mutability);
+                    let ty = self.ty(ty, Precedence::Prefix);
+                    (format!("&{}{}", mutability, ty), Precedence::Prefix)
+                }
+                Cons::Array(ty) => (
+                    format!("[{}]", self.ty(ty, Precedence::Union)),
+                    Precedence::Prefix,
+                ),
+                Cons::Dict(key, value) => {
+                    let key = self.ty(key, Precedence::Union);
+                    let value = self.ty(value, Precedence::Union);
+                    (format!("#({} = {})", key, value), Precedence::Prefix)
+                }
+                Cons::Set(ty) => (
+                    format!("#[{}]", self.ty(ty, Precedence::Union)),
+                    Precedence::Prefix,
+                ),
+                Cons::Iter(ty, effects) => {
+                    let src = match shown_effects(effects) {
+                        Some(effects) => format!(
+                            "Iter({} ! {})",
+                            self.ty(ty, Precedence::Prefix),
+                            self.record(effects),
+                        ),
+                        None => format!("Iter({})", self.ty(ty, Precedence::Union)),
+                    };
+                    (src, Precedence::Prefix)
+                }
+                Cons::Fun(param, ret, effects) => {
+                    let param = self.ty(param, Precedence::Prefix);
+                    let src = match shown_effects(effects) {
+                        Some(effects) => {
+                            let ret = self.ty(ret, Precedence::Prefix);
+                            format!("{} -> {} ! {}", param, ret, self.record(effects))
+                        }
+                        None => format!("{} -> {}", param, self.ty(ret, Precedence::Fun)),
+                    };
+                    (src, Precedence::Fun)
+                }
+                Cons::Record(record) => (self.record(record), Precedence::Prefix),
+                Cons::Tuple(tuple) => (self.tuple(tuple), Precedence::Prefix),
+                Cons::RecordTuple(record_tuple) => {
+                    (self.record_tuple(record_tuple), Precedence::Prefix)
+                }
+                Cons::Union(union) => {
+                    let own = if union.fields.len() + union.rest.iter().count() > 1 {
+                        Precedence::Union
+                    } else {
+                        Precedence::Prefix
+                    };
+                    (self.union(union), own)
+                }
+            },
+        };
+        if own < precedence {
+            format!("({})", src)
+        } else {
+            src
+        }
+    }
+}
+impl Display for Type {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let mut printer = PrettyPrinter::default();
+        printer.count(self);
+        write!(fmt, "{}", printer.ty(self, Precedence::Union))
+    }
+}
+impl Display for Cons {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{}", Type::Cons(self.clone()))
+    }
+}
+impl Display for Scheme {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let mut printer = PrettyPrinter::default();
+        printer.count(&self.ty);
+        for constraint in self.constraints.iter() {
+            for ty in constraint.types() {
+                printer.count(ty);
+            }
+        }
+        let ty = printer.ty(&self.ty, Precedence::Union);
+        // written the way the container is used
+        let constraints: Vec<_> = self
+            .constraints
+            .iter()
+            .map(|constraint| match constraint {
+                Constraint::Iterable {
+                    container, elem, ..
+                } => format!(
+                    "for {} in {}",
+                    printer.ty(elem, Precedence::Union),
+                    printer.ty(container, Precedence::Union),
+                ),
+                Constraint::Indexable {
+                    container,
+                    index,
+                    elem,
+                } => format!(
+                    "{}[{}]: {}",
+                    printer.ty(container, Precedence::Union),
+                    printer.ty(index, Precedence::Union),
+                    printer.ty(elem, Precedence::Union),
+                ),
+                Constraint::Hashable { ty } => {
+                    format!("#[{}]", printer.ty(ty, Precedence::Union))
+                }
+            })
+            .collect();
+        let mut for_all: Vec<_> = self
+            .for_all
+            .iter()
+            .filter_map(|var| printer.names.get(&var.var).map(|index| (*index, var.kind)))
+            .collect();
+        if !for_all.is_empty() {
+            for_all.sort_unstable();
+            let for_all: Vec<_> = for_all
+                .into_iter()
+                .map(|(index, kind)| match kind {
+                    Kind::Type => letter_name(index),
+                    Kind::MutType => format!(":{}", letter_name(index)),
+                })
+                .collect();
+            write!(fmt, ":({}) ", for_all.join(", "))?;
+        }
+        write!(fmt, "{}", ty)?;
+        if !constraints.is_empty() {
+            write!(fmt, " where {}", constraints.join(", "))?;
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::test::infer_ty;
+
+    #[test]
+    fn primitive() {
+        assert_eq!(infer_ty("10"), Ok("Num".to_string()));
+        assert_eq!(infer_ty("true"), Ok("Bool".to_string()));
+        assert_eq!(infer_ty("()"), Ok("()".to_string()));
+        assert_eq!(infer_ty("[10]"), Ok("[Num]".to_string()));
+    }
+    #[test]
+    fn record() {
+        assert_eq!(
+            infer_ty("(foo = 10, bar = true)"),
+            Ok("{bar: Bool, foo: Num}".to_string())
+        );
+        assert_eq!(infer_ty("(10, true)"), Ok("(Num, Bool)".to_string()));
+    }
+    #[test]
+    fn union() {
+        assert_eq!(infer_ty("@val 10"), Ok("@val Num | *_".to_string()));
+        assert_eq!(infer_ty("@none"), Ok("@none | *_".to_string()));
+    }
+    #[test]
+    fn rename() {
+        assert_eq!(infer_ty("(foo) => foo"), Ok("(foo: a) -> a".to_string()));
+        assert_eq!(
+            infer_ty("(arr) => &arr^[0]"),
+            Ok("(arr: &:a [b]) -> &:a b".to_string())
+        );
+        assert_eq!(
+            infer_ty("(foo) => (bar = 10, *foo)"),
+            Ok("(foo: a) -> {bar: Num, *a}".to_string()),
+        );
+    }
+    #[test]
+    fn precedence() {
+        assert_eq!(
+            infer_ty("[(foo) => foo]"),
+            Ok("[(foo: a) -> a]".to_string())
+        );
+        assert_eq!(
+            infer_ty("() => () => 10"),
+            Ok("() -> () -> Num".to_string())
+        );
+        assert_eq!(
+            infer_ty("@val @val 10"),
+            Ok("@val (@val Num | *_) | *_".to_string())
+        );
+    }
+}
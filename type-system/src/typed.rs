@@ -0,0 +1,322 @@
+use crate::ty::{Subs, Substitutable, Type, TypeError};
+use hir::{
+    expr::{
+        Arg, Assign, Binary, Block, Bound, Break, Call, Condition, ControlFlow, Element, Entry,
+        Expr, Field, FieldAccess, For, Fun, Handle, Handler, If, Index, Jump, Loop, Match,
+        MatchArm, MatchCondition, Module, PlaceExpr, Range, Record, RecordWithSplat, Slice, Tag,
+        Tuple, TupleWithSplat, Unary, While, Yield,
+    },
+    pattern::{
+        AsPattern, ListPattern, ListWithRest, NewTypePattern, Pattern, PatternBound, RangePattern,
+        RecordPattern, TaggedPattern, Var,
+    },
+    statement::{Declare, FunDeclare, NewType, Statement},
+};
+
+// Applies the final substitution to every type annotation of the typed tree
+
+impl<T> Substitutable for [T]
+where
+    T: Substitutable,
+{
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        for item in self.iter_mut() {
+            item.substitute(subs)?;
+        }
+        Ok(())
+    }
+}
+impl<T> Substitutable for Box<T>
+where
+    T: Substitutable + ?Sized,
+{
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        T::substitute(self, subs)
+    }
+}
+impl<T> Substitutable for Option<T>
+where
+    T: Substitutable,
+{
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Some(item) => item.substitute(subs),
+            None => Ok(()),
+        }
+    }
+}
+impl Substitutable for Statement<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::Declare(Declare {
+                visibility: _,
+                pattern,
+                expr,
+                else_part,
+            }) => {
+                pattern.substitute(subs)?;
+                expr.substitute(subs)?;
+                else_part.substitute(subs)
+            }
+            Self::FunDeclare(FunDeclare {
+                visibility: _,
+                ident: _,
+                fun,
+                ty,
+            }) => {
+                fun.substitute(subs)?;
+                ty.substitute(subs)
+            }
+            Self::NewType(NewType {
+                visibility: _,
+                ident: _,
+                fields: _,
+                ty,
+            }) => ty.substitute(subs),
+            Self::Expr(expr) => expr.substitute(subs),
+        }
+    }
+}
+impl Substitutable for Var<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        self.ty.substitute(subs)
+    }
+}
+impl Substitutable for Pattern<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::True | Self::False | Self::UInt(_) | Self::Int(_) | Self::Ignore => Ok(()),
+            Self::Var(var) => var.substitute(subs),
+            Self::Record(RecordPattern { fields, rest })
+            | Self::NewType(NewTypePattern {
+                name: _,
+                fields: RecordPattern { fields, rest },
+            }) => {
+                for pattern in fields.values_mut() {
+                    pattern.substitute(subs)?;
+                }
+                rest.substitute(subs)
+            }
+            Self::Tuple(list) | Self::Array(list) => match list {
+                ListPattern::List(list) => list.substitute(subs),
+                ListPattern::ListWithRest(ListWithRest { left, rest, right }) => {
+                    left.substitute(subs)?;
+                    rest.substitute(subs)?;
+                    right.substitute(subs)
+                }
+            },
+            Self::Tag(TaggedPattern { tag: _, pattern }) => pattern.substitute(subs),
+            Self::Ref(pattern) => pattern.substitute(subs),
+            Self::Or(patterns) => patterns.substitute(subs),
+            Self::Range(RangePattern { left, right }) => {
+                for bound in [left, right].into_iter().flatten() {
+                    let PatternBound { kind: _, pattern } = bound;
+                    pattern.substitute(subs)?;
+                }
+                Ok(())
+            }
+            Self::As(AsPattern { pattern, var }) => {
+                pattern.substitute(subs)?;
+                var.substitute(subs)
+            }
+            Self::Equal(expr) => expr.substitute(subs),
+        }
+    }
+}
+impl Substitutable for Fun<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        self.param.substitute(subs)?;
+        self.body.substitute(subs)
+    }
+}
+impl Substitutable for Arg<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::Unit => Ok(()),
+            Self::Splat(expr) => expr.substitute(subs),
+            Self::Record(record) => record.substitute(subs),
+            Self::Tuple(tuple) => tuple.substitute(subs),
+        }
+    }
+}
+impl Substitutable for Block<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        self.statement.substitute(subs)?;
+        self.expr.substitute(subs)
+    }
+}
+impl Substitutable for Range<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        for bound in [&mut self.left, &mut self.right].into_iter().flatten() {
+            let Bound { kind: _, expr } = bound;
+            expr.substitute(subs)?;
+        }
+        Ok(())
+    }
+}
+impl Substitutable for Field<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        self.expr.substitute(subs)
+    }
+}
+impl Substitutable for Record<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::Record(fields) => fields.substitute(subs),
+            Self::RecordWithSplat(RecordWithSplat { left, splat, right }) => {
+                left.substitute(subs)?;
+                splat.substitute(subs)?;
+                right.substitute(subs)
+            }
+        }
+    }
+}
+impl Substitutable for Tuple<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::Tuple(exprs) => exprs.substitute(subs),
+            Self::TupleWithSplat(TupleWithSplat { left, splat, right }) => {
+                left.substitute(subs)?;
+                splat.substitute(subs)?;
+                right.substitute(subs)
+            }
+        }
+    }
+}
+impl Substitutable for PlaceExpr<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::Var(_) => Ok(()),
+            Self::FieldAccess(FieldAccess { expr, name: _ }) => expr.substitute(subs),
+            Self::Index(Index { expr, index }) => {
+                expr.substitute(subs)?;
+                index.substitute(subs)
+            }
+            Self::Slice(Slice { expr, range }) => {
+                expr.substitute(subs)?;
+                range.substitute(subs)
+            }
+            Self::Deref(expr) | Self::Len(expr) => expr.substitute(subs),
+        }
+    }
+}
+impl Substitutable for Condition<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::Bool(expr) => expr.substitute(subs),
+            Self::Match(MatchCondition { expr, pattern }) => {
+                expr.substitute(subs)?;
+                pattern.substitute(subs)
+            }
+        }
+    }
+}
+impl Substitutable for ControlFlow<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::Block(block) | Self::Loop(Loop { body: block, .. }) => block.substitute(subs),
+            Self::If(If {
+                condition,
+                body,
+                else_part,
+            }) => {
+                condition.substitute(subs)?;
+                body.substitute(subs)?;
+                else_part.substitute(subs)
+            }
+            Self::For(For {
+                pattern,
+                expr,
+                body,
+                ..
+            }) => {
+                pattern.substitute(subs)?;
+                expr.substitute(subs)?;
+                body.substitute(subs)
+            }
+            Self::While(While {
+                condition, body, ..
+            }) => {
+                condition.substitute(subs)?;
+                body.substitute(subs)
+            }
+            Self::Match(Match { expr, arm }) => {
+                expr.substitute(subs)?;
+                for MatchArm { pattern, expr } in arm.iter_mut() {
+                    pattern.substitute(subs)?;
+                    expr.substitute(subs)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+impl Substitutable for Expr<Type> {
+    fn substitute(&mut self, subs: &Subs) -> Result<(), TypeError> {
+        match self {
+            Self::Literal(_) | Self::Unit => Ok(()),
+            Self::Tag(Tag { tag: _, expr }) => expr.substitute(subs),
+            Self::Assign(assigns) => {
+                for Assign { place, expr } in assigns.iter_mut() {
+                    place.substitute(subs)?;
+                    expr.substitute(subs)?;
+                }
+                Ok(())
+            }
+            Self::Array(elements) => {
+                for Element { expr, kind: _ } in elements.iter_mut() {
+                    expr.substitute(subs)?;
+                }
+                Ok(())
+            }
+            Self::Dict(entries) => {
+                for Entry { key, value } in entries.iter_mut() {
+                    key.substitute(subs)?;
+                    value.substitute(subs)?;
+                }
+                Ok(())
+            }
+            Self::Set(elements) => elements.substitute(subs),
+            Self::ArrayRange(range) => range.substitute(subs),
+            Self::Splat(expr) => expr.substitute(subs),
+            Self::Record(record) => record.substitute(subs),
+            Self::Tuple(tuple) => tuple.substitute(subs),
+            Self::Unary(Unary { kind: _, expr }) => expr.substitute(subs),
+            Self::Binary(Binary {
+                kind: _,
+                left,
+                right,
+            }) => {
+                left.substitute(subs)?;
+                right.substitute(subs)
+            }
+            Self::Place(place) => place.substitute(subs),
+            Self::Call(Call { expr, arg }) => {
+                expr.substitute(subs)?;
+                arg.substitute(subs)
+            }
+            Self::ControlFlow(control_flow) => control_flow.substitute(subs),
+            Self::Fun(fun) => fun.substitute(subs),
+            Self::Jump(Jump::Break(Break { expr, .. }) | Jump::Return(expr)) => {
+                expr.substitute(subs)
+            }
+            Self::Jump(Jump::Continue(_)) => Ok(()),
+            Self::Yield(Yield { effect: _, arg }) => arg.substitute(subs),
+            Self::Handle(Handle { expr, handler }) => {
+                expr.substitute(subs)?;
+                for Handler {
+                    effect: _,
+                    param,
+                    body,
+                } in handler.iter_mut()
+                {
+                    param.substitute(subs)?;
+                    body.substitute(subs)?;
+                }
+                Ok(())
+            }
+            Self::Module(Module::File(_)) => Ok(()),
+            Self::Module(Module::Inline(statements)) => statements.substitute(subs),
+        }
+    }
+}